@@ -0,0 +1,136 @@
+// Copyright 2018-2021 TON Labs LTD.
+//
+// Licensed under the SOFTWARE EVALUATION License (the "License"); you may not
+// use this file except in compliance with the License.
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific TON DEV software governing permissions and
+// limitations under the License.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::OnceLock;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+
+use tvm_types::Result;
+use tvm_types::UInt256;
+use tvm_types::sha256_digest;
+
+use crate::contract::Contract;
+
+/// Capacity of the global cache
+pub const DEFAULT_ABI_CACHE_CAPACITY: usize = 128;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct AbiCacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub entries: usize,
+}
+
+impl AbiCacheStats {
+    /// Part of lookups served from the cache, 0 if there were no lookups
+    pub fn hit_rate(&self) -> f64 {
+        let total = self.hits + self.misses;
+        if total == 0 { 0.0 } else { self.hits as f64 / total as f64 }
+    }
+}
+
+struct CacheEntry {
+    contract: Arc<Contract>,
+    last_used: u64,
+}
+
+#[derive(Default)]
+struct CacheState {
+    entries: HashMap<UInt256, CacheEntry>,
+    tick: u64,
+}
+
+/// LRU cache of parsed ABIs keyed by hash of the ABI JSON
+pub struct AbiCache {
+    capacity: usize,
+    state: Mutex<CacheState>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl AbiCache {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            state: Default::default(),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    /// Cache used by the `json_abi` functions
+    pub fn global() -> &'static AbiCache {
+        static CACHE: OnceLock<AbiCache> = OnceLock::new();
+        CACHE.get_or_init(|| AbiCache::new(DEFAULT_ABI_CACHE_CAPACITY))
+    }
+
+    /// Returns parsed ABI, parsing and caching it on the first use
+    pub fn load(&self, abi: &str) -> Result<Arc<Contract>> {
+        let hash = UInt256::from(sha256_digest(abi.as_bytes()));
+        if let Some(contract) = self.get(&hash) {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+            return Ok(contract);
+        }
+        self.misses.fetch_add(1, Ordering::Relaxed);
+        // parse outside of the lock, concurrent misses of the same ABI are harmless
+        let contract = Arc::new(Contract::load(abi.as_bytes())?);
+        self.insert(hash, contract.clone());
+        Ok(contract)
+    }
+
+    pub fn stats(&self) -> AbiCacheStats {
+        AbiCacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            entries: self.state.lock().unwrap().entries.len(),
+        }
+    }
+
+    pub fn clear(&self) {
+        self.state.lock().unwrap().entries.clear();
+        self.hits.store(0, Ordering::Relaxed);
+        self.misses.store(0, Ordering::Relaxed);
+    }
+
+    fn get(&self, hash: &UInt256) -> Option<Arc<Contract>> {
+        let mut state = self.state.lock().unwrap();
+        state.tick += 1;
+        let tick = state.tick;
+        state.entries.get_mut(hash).map(|entry| {
+            entry.last_used = tick;
+            entry.contract.clone()
+        })
+    }
+
+    fn insert(&self, hash: UInt256, contract: Arc<Contract>) {
+        let mut state = self.state.lock().unwrap();
+        if state.entries.len() >= self.capacity && !state.entries.contains_key(&hash) {
+            let oldest = state
+                .entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(hash, _)| hash.clone());
+            if let Some(oldest) = oldest {
+                state.entries.remove(&oldest);
+            }
+        }
+        state.tick += 1;
+        let last_used = state.tick;
+        state.entries.insert(hash, CacheEntry { contract, last_used });
+    }
+}
+
+#[cfg(test)]
+#[path = "tests/test_abi_cache.rs"]
+mod tests;
@@ -0,0 +1,224 @@
+// Copyright 2018-2021 TON Labs LTD.
+//
+// Licensed under the SOFTWARE EVALUATION License (the "License"); you may not
+// use this file except in compliance with the License.
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific TON DEV software governing permissions and
+// limitations under the License.
+
+//! Round-trip conformance checker: encodes random valid values of every
+//! function input and output of the ABI and checks that decoding gives the
+//! same values back.
+
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+
+use num_bigint::BigInt;
+use num_bigint::BigUint;
+use rand::Rng;
+use tvm_block::Grams;
+use tvm_block::MsgAddrStd;
+use tvm_block::MsgAddress;
+use tvm_types::AccountId;
+use tvm_types::BuilderData;
+use tvm_types::Result;
+use tvm_types::SliceData;
+use tvm_types::UInt256;
+
+use crate::Contract;
+use crate::Function;
+use crate::Int;
+use crate::Param;
+use crate::ParamType;
+use crate::Token;
+use crate::TokenValue;
+use crate::Uint;
+
+/// Maximum number of elements generated for arrays and maps
+const MAX_COLLECTION_LEN: usize = 3;
+/// Maximum length of generated bytes and strings
+const MAX_BYTES_LEN: usize = 64;
+
+/// Values which didn't survive encoding and decoding
+#[derive(Debug, Clone, PartialEq)]
+pub struct RoundTripFailure {
+    pub function: String,
+    /// `true` if function outputs were checked, `false` for inputs
+    pub output: bool,
+    pub tokens: Vec<Token>,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RoundTripReport {
+    /// Number of performed round trips
+    pub checked: usize,
+    pub failures: Vec<RoundTripFailure>,
+}
+
+impl RoundTripReport {
+    pub fn is_ok(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
+
+/// Performs `iterations` round trips of random inputs and outputs for every
+/// function of the ABI
+pub fn check_round_trip<R: Rng + ?Sized>(
+    abi: &str,
+    iterations: usize,
+    rng: &mut R,
+) -> Result<RoundTripReport> {
+    let contract = Contract::load(abi.as_bytes())?;
+    let mut functions = contract.functions().values().collect::<Vec<_>>();
+    functions.sort_by(|a, b| a.name.cmp(&b.name));
+
+    let mut report = RoundTripReport::default();
+    for function in functions {
+        for _ in 0..iterations {
+            for output in [false, true] {
+                let params =
+                    if output { function.output_params() } else { function.input_params() };
+                let tokens = random_tokens(params, rng);
+                report.checked += 1;
+                if let Err(reason) = round_trip(function, &tokens, output) {
+                    report.failures.push(RoundTripFailure {
+                        function: function.name.clone(),
+                        output,
+                        tokens,
+                        reason,
+                    });
+                }
+            }
+        }
+    }
+    Ok(report)
+}
+
+fn round_trip(
+    function: &Function,
+    tokens: &[Token],
+    output: bool,
+) -> std::result::Result<(), String> {
+    let encoded = if output {
+        function.encode_internal_output(0, tokens)
+    } else {
+        function.encode_input(&HashMap::new(), tokens, true, None, None)
+    };
+    let decoded = encoded
+        .and_then(SliceData::load_builder)
+        .map_err(|err| format!("encoding failed: {}", err))?;
+    let decoded = if output {
+        function.decode_output(decoded, true, false)
+    } else {
+        function.decode_input(decoded, true, false)
+    }
+    .map_err(|err| format!("decoding failed: {}", err))?;
+    if decoded != tokens {
+        return Err(format!("decoded values differ: {:?}", decoded));
+    }
+    Ok(())
+}
+
+/// Generates random values for the parameters
+pub fn random_tokens<R: Rng + ?Sized>(params: &[Param], rng: &mut R) -> Vec<Token> {
+    params
+        .iter()
+        .map(|param| Token::new(&param.name, random_token_value(&param.kind, rng)))
+        .collect()
+}
+
+/// Generates random value of the type, respecting its range
+pub fn random_token_value<R: Rng + ?Sized>(kind: &ParamType, rng: &mut R) -> TokenValue {
+    match kind {
+        ParamType::Uint(size) => {
+            TokenValue::Uint(Uint { number: random_biguint(*size, rng), size: *size })
+        }
+        ParamType::Int(size) => {
+            let number = BigInt::from(random_biguint(*size, rng)) - (BigInt::from(1) << (size - 1));
+            TokenValue::Int(Int { number, size: *size })
+        }
+        ParamType::VarUint(size) => {
+            let len = rng.gen_range(0..*size);
+            TokenValue::VarUint(*size, random_biguint(len * 8, rng))
+        }
+        ParamType::VarInt(size) => {
+            let len = rng.gen_range(0..*size);
+            TokenValue::VarInt(*size, BigInt::from_signed_bytes_be(&random_bytes(len, rng)))
+        }
+        ParamType::Bool => TokenValue::Bool(rng.gen()),
+        ParamType::Tuple(params) => TokenValue::Tuple(random_tokens(params, rng)),
+        ParamType::Array(inner) => {
+            let len = rng.gen_range(0..=MAX_COLLECTION_LEN);
+            TokenValue::Array(
+                inner.as_ref().clone(),
+                (0..len).map(|_| random_token_value(inner, rng)).collect(),
+            )
+        }
+        ParamType::FixedArray(inner, size) => TokenValue::FixedArray(
+            inner.as_ref().clone(),
+            (0..*size).map(|_| random_token_value(inner, rng)).collect(),
+        ),
+        ParamType::Cell => {
+            let data = random_bytes(rng.gen_range(0..=MAX_BYTES_LEN), rng);
+            let bits = data.len() * 8;
+            let cell = BuilderData::with_raw(data, bits).and_then(|builder| builder.into_cell());
+            TokenValue::Cell(cell.unwrap_or_default())
+        }
+        ParamType::Map(key, value) => {
+            let mut map = BTreeMap::new();
+            for _ in 0..rng.gen_range(0..=MAX_COLLECTION_LEN) {
+                let key = random_token_value(key, rng);
+                // map keys are stored in the same form as they are decoded
+                if let Some(key) = serde_json::to_value(&key).ok().and_then(|key| match key {
+                    serde_json::Value::String(key) => Some(key),
+                    _ => None,
+                }) {
+                    map.insert(key, random_token_value(value, rng));
+                }
+            }
+            TokenValue::Map(key.as_ref().clone(), value.as_ref().clone(), map)
+        }
+        ParamType::Address => TokenValue::Address(random_address(rng)),
+        ParamType::Bytes => TokenValue::Bytes(random_bytes(rng.gen_range(0..=MAX_BYTES_LEN), rng)),
+        ParamType::FixedBytes(size) => TokenValue::FixedBytes(random_bytes(*size, rng)),
+        ParamType::String => {
+            let len = rng.gen_range(0..=MAX_BYTES_LEN);
+            TokenValue::String(
+                (0..len).map(|_| rng.sample(rand::distributions::Alphanumeric) as char).collect(),
+            )
+        }
+        ParamType::Token => TokenValue::Token(Grams::from(rng.gen::<u64>())),
+        ParamType::Time => TokenValue::Time(rng.gen()),
+        ParamType::Expire => TokenValue::Expire(rng.gen()),
+        ParamType::PublicKey => TokenValue::PublicKey(rng.gen::<bool>().then(|| rng.gen())),
+        ParamType::Optional(inner) => TokenValue::Optional(
+            inner.as_ref().clone(),
+            rng.gen::<bool>().then(|| Box::new(random_token_value(inner, rng))),
+        ),
+        ParamType::Ref(inner) => TokenValue::Ref(Box::new(random_token_value(inner, rng))),
+    }
+}
+
+fn random_bytes<R: Rng + ?Sized>(len: usize, rng: &mut R) -> Vec<u8> {
+    (0..len).map(|_| rng.gen()).collect()
+}
+
+/// Random number of `bits` length
+fn random_biguint<R: Rng + ?Sized>(bits: usize, rng: &mut R) -> BigUint {
+    let bytes = random_bytes(bits.div_ceil(8), rng);
+    BigUint::from_bytes_be(&bytes) >> (bytes.len() * 8 - bits)
+}
+
+fn random_address<R: Rng + ?Sized>(rng: &mut R) -> MsgAddress {
+    let workchain_id = if rng.gen() { 0 } else { -1 };
+    let address = AccountId::from(UInt256::from(rng.gen::<[u8; 32]>()));
+    MsgAddress::AddrStd(MsgAddrStd::with_address(None, workchain_id, address))
+}
+
+#[cfg(test)]
+#[path = "tests/test_conformance.rs"]
+mod tests;
@@ -398,6 +398,11 @@ impl Contract {
         &self.fields
     }
 
+    /// Returns names of storage fields to be supplied on deploy
+    pub fn init_fields(&self) -> &HashSet<String> {
+        &self.init_fields
+    }
+
     /// Returns version
     pub fn version(&self) -> &AbiVersion {
         &self.abi_version
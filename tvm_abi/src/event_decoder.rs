@@ -0,0 +1,105 @@
+// Copyright 2018-2021 TON Labs LTD.
+//
+// Licensed under the SOFTWARE EVALUATION License (the "License"); you may not
+// use this file except in compliance with the License.
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific TON DEV software governing permissions and
+// limitations under the License.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use tvm_types::Result;
+use tvm_types::SliceData;
+
+use crate::AbiCache;
+use crate::Contract;
+use crate::Event;
+use crate::Token;
+use crate::TokenValue;
+use crate::error::AbiError;
+
+/// Output of `EventDecoder::decode_into`. Reusing the same value between
+/// calls keeps its buffers allocated.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DecodedEvent {
+    pub id: u32,
+    pub name: String,
+    pub tokens: Vec<Token>,
+}
+
+/// Decoder of events of one contract, intended for bulk decoding. Events are
+/// looked up by id in a map, and decoded values are written into
+/// caller-provided buffers.
+pub struct EventDecoder {
+    contract: Arc<Contract>,
+    events: HashMap<u32, String>,
+    allow_partial: bool,
+}
+
+impl EventDecoder {
+    pub fn new(contract: Arc<Contract>) -> Self {
+        let events =
+            contract.events().values().map(|event| (event.get_id(), event.name.clone())).collect();
+        Self { contract, events, allow_partial: false }
+    }
+
+    pub fn load(abi: &str) -> Result<Self> {
+        Ok(Self::new(AbiCache::global().load(abi)?))
+    }
+
+    /// Allows bodies with the data left after the last event parameter
+    pub fn with_allow_partial(mut self, allow_partial: bool) -> Self {
+        self.allow_partial = allow_partial;
+        self
+    }
+
+    pub fn contract(&self) -> &Contract {
+        &self.contract
+    }
+
+    /// Returns event the body belongs to
+    pub fn event(&self, body: &SliceData) -> Result<&Event> {
+        let id = Event::decode_id(body.clone())?;
+        self.event_by_id(id)
+    }
+
+    /// Decodes event body into `out`, replacing its previous content
+    pub fn decode_into(&self, mut body: SliceData, out: &mut DecodedEvent) -> Result<()> {
+        let id = body.get_next_u32()?;
+        let event = self.event_by_id(id)?;
+        out.id = id;
+        out.name.clear();
+        out.name.push_str(&event.name);
+        out.tokens.clear();
+        TokenValue::decode_params_into(
+            &event.inputs,
+            body.into(),
+            &event.abi_version,
+            self.allow_partial,
+            true,
+            &mut out.tokens,
+        )?;
+        Ok(())
+    }
+
+    pub fn decode(&self, body: SliceData) -> Result<DecodedEvent> {
+        let mut out = DecodedEvent::default();
+        self.decode_into(body, &mut out)?;
+        Ok(out)
+    }
+
+    fn event_by_id(&self, id: u32) -> Result<&Event> {
+        self.events
+            .get(&id)
+            .and_then(|name| self.contract.events().get(name))
+            .ok_or_else(|| AbiError::InvalidFunctionId { id }.into())
+    }
+}
+
+#[cfg(test)]
+#[path = "tests/test_event_decoder.rs"]
+mod tests;
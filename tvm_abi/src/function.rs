@@ -210,6 +210,23 @@ impl Function {
         .map(|(tokens, _)| tokens)
     }
 
+    /// Decodes single input parameter found by `path` of parameter names,
+    /// without decoding the rest of the call.
+    pub fn decode_input_field(
+        &self,
+        data: SliceData,
+        internal: bool,
+        path: &[&str],
+    ) -> Result<TokenValue> {
+        let (_, id, cursor) = Self::decode_header(&self.abi_version, data, &self.header, internal)?;
+
+        if id != self.get_input_id() {
+            Err(AbiError::WrongId { id })?
+        }
+
+        TokenValue::decode_field(self.input_params(), path, cursor, &self.abi_version, true)
+    }
+
     /// Decodes function id from contract answer
     pub fn decode_input_id(
         abi_version: &AbiVersion,
@@ -19,11 +19,12 @@ use tvm_types::Ed25519PrivateKey;
 use tvm_types::Result;
 use tvm_types::SliceData;
 
+use crate::AbiCache;
 use crate::PublicKeyData;
 use crate::SignatureData;
-use crate::contract::Contract;
 use crate::error::AbiError;
 use crate::token::Detokenizer;
+use crate::token::Token;
 use crate::token::TokenValue;
 use crate::token::Tokenizer;
 
@@ -38,7 +39,7 @@ pub fn encode_function_call(
     sign_key: Option<&Ed25519PrivateKey>,
     address: Option<&str>,
 ) -> Result<BuilderData> {
-    let contract = Contract::load(abi.as_bytes())?;
+    let contract = AbiCache::global().load(abi)?;
 
     let function = contract.function(function)?;
 
@@ -75,7 +76,7 @@ pub fn prepare_function_call_for_sign(
     parameters: &str,
     address: Option<&str>,
 ) -> Result<(BuilderData, Vec<u8>)> {
-    let contract = Contract::load(abi.as_bytes())?;
+    let contract = AbiCache::global().load(abi)?;
 
     let function = contract.function(function)?;
 
@@ -102,7 +103,7 @@ pub fn add_sign_to_function_call(
     public_key: Option<&PublicKeyData>,
     function_call: SliceData,
 ) -> Result<BuilderData> {
-    let contract = Contract::load(abi.as_bytes())?;
+    let contract = AbiCache::global().load(abi)?;
     contract.add_sign_to_encoded_input(signature, public_key, function_call)
 }
 
@@ -114,7 +115,7 @@ pub fn decode_function_response(
     internal: bool,
     allow_partial: bool,
 ) -> Result<String> {
-    let contract = Contract::load(abi.as_bytes())?;
+    let contract = AbiCache::global().load(abi)?;
 
     let function = contract.function(function)?;
 
@@ -136,7 +137,7 @@ pub fn decode_unknown_function_response(
     internal: bool,
     allow_partial: bool,
 ) -> Result<DecodedMessage> {
-    let contract = Contract::load(abi.as_bytes())?;
+    let contract = AbiCache::global().load(abi)?;
 
     let result = contract.decode_output(response, internal, allow_partial)?;
 
@@ -153,7 +154,7 @@ pub fn decode_unknown_function_call(
     internal: bool,
     allow_partial: bool,
 ) -> Result<DecodedMessage> {
-    let contract = Contract::load(abi.as_bytes())?;
+    let contract = AbiCache::global().load(abi)?;
 
     let result = contract.decode_input(response, internal, allow_partial)?;
 
@@ -162,9 +163,30 @@ pub fn decode_unknown_function_call(
     Ok(DecodedMessage { function_name: result.function_name, params: input })
 }
 
+/// Decodes single input parameter of the function call. `field_path` is the
+/// parameter name, with names of tuple components separated by dots, e.g.
+/// `transfer.amount`. Returns JSON value of the parameter.
+pub fn decode_field(
+    abi: &str,
+    function: &str,
+    body: SliceData,
+    internal: bool,
+    field_path: &str,
+) -> Result<String> {
+    let contract = AbiCache::global().load(abi)?;
+    let function = contract.function(function)?;
+
+    let path: Vec<&str> = field_path.split('.').collect();
+    let value = function.decode_input_field(body, internal, &path)?;
+    let token = Token { name: path[path.len() - 1].to_owned(), value };
+
+    let mut json = Detokenizer::detokenize_to_json_value(&[token])?;
+    Ok(json[path[path.len() - 1]].take().to_string())
+}
+
 /// Changes initial values for public contract variables
 pub fn update_contract_data(abi: &str, parameters: &str, data: SliceData) -> Result<SliceData> {
-    let contract = Contract::load(abi.as_bytes())?;
+    let contract = AbiCache::global().load(abi)?;
 
     let data_json: serde_json::Value = serde_json::from_str(parameters)?;
 
@@ -177,14 +199,14 @@ pub fn update_contract_data(abi: &str, parameters: &str, data: SliceData) -> Res
 
 /// Decode initial values of public contract variables
 pub fn decode_contract_data(abi: &str, data: SliceData, allow_partial: bool) -> Result<String> {
-    let contract = Contract::load(abi.as_bytes())?;
+    let contract = AbiCache::global().load(abi)?;
 
     Detokenizer::detokenize(&contract.decode_data(data, allow_partial)?)
 }
 
 /// Decode account storage fields
 pub fn decode_storage_fields(abi: &str, data: SliceData, allow_partial: bool) -> Result<String> {
-    let contract = Contract::load(abi.as_bytes())?;
+    let contract = AbiCache::global().load(abi)?;
 
     let decoded = contract.decode_storage_fields(data, allow_partial)?;
 
@@ -197,7 +219,7 @@ pub fn get_signature_data(
     cursor: SliceData,
     address: Option<&str>,
 ) -> Result<(Vec<u8>, Vec<u8>)> {
-    let contract = Contract::load(abi.as_bytes())?;
+    let contract = AbiCache::global().load(abi)?;
     let address = address.map(MsgAddressInt::from_str).transpose()?;
     contract.get_signature_data(cursor, address)
 }
@@ -205,7 +227,7 @@ pub fn get_signature_data(
 /// Encodes `parameters` for given `function` of contract described by `abi`
 /// into `BuilderData` which can be used as message body for calling contract
 pub fn encode_storage_fields(abi: &str, init_fields: Option<&str>) -> Result<BuilderData> {
-    let contract = Contract::load(abi.as_bytes())?;
+    let contract = AbiCache::global().load(abi)?;
 
     let init_fields = if let Some(init_fields) = init_fields {
         let v: Value =
@@ -9,9 +9,12 @@
 // See the License for the specific TON DEV software governing permissions and
 // limitations under the License.
 
+pub mod abi_cache;
+pub mod conformance;
 pub mod contract;
 pub mod error;
 pub mod event;
+pub mod event_decoder;
 pub mod function;
 pub mod int;
 pub mod json_abi;
@@ -19,12 +22,15 @@ pub mod param;
 pub mod param_type;
 pub mod token;
 
+pub use abi_cache::AbiCache;
 pub use contract::Contract;
 pub use contract::DataItem;
 pub use contract::PublicKeyData;
 pub use contract::SignatureData;
 pub use error::*;
 pub use event::Event;
+pub use event_decoder::DecodedEvent;
+pub use event_decoder::EventDecoder;
 pub use function::Function;
 pub use int::Int;
 pub use int::Uint;
@@ -0,0 +1,40 @@
+// Copyright 2018-2021 TON Labs LTD.
+//
+// Licensed under the SOFTWARE EVALUATION License (the "License"); you may not
+// use this file except in compliance with the License.
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific TON DEV software governing permissions and
+// limitations under the License.
+
+use super::*;
+
+fn abi(function: &str) -> String {
+    format!(
+        r#"{{"version":"2.4","functions":[{{"name":"{}","inputs":[],"outputs":[]}}],"events":[]}}"#,
+        function
+    )
+}
+
+#[test]
+fn test_lru_eviction() {
+    let cache = AbiCache::new(2);
+    let first = cache.load(&abi("a")).unwrap();
+    assert!(Arc::ptr_eq(&first, &cache.load(&abi("a")).unwrap()));
+    cache.load(&abi("b")).unwrap();
+    cache.load(&abi("a")).unwrap();
+    // "b" is the least recently used
+    cache.load(&abi("c")).unwrap();
+    assert_eq!(cache.stats(), AbiCacheStats { hits: 2, misses: 3, entries: 2 });
+
+    assert!(Arc::ptr_eq(&first, &cache.load(&abi("a")).unwrap()));
+    cache.load(&abi("b")).unwrap();
+    assert_eq!(cache.stats(), AbiCacheStats { hits: 3, misses: 4, entries: 2 });
+    assert!((cache.stats().hit_rate() - 3.0 / 7.0).abs() < f64::EPSILON);
+
+    assert!(cache.load("invalid").is_err());
+    cache.clear();
+    assert_eq!(cache.stats(), AbiCacheStats::default());
+}
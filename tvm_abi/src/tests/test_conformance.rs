@@ -0,0 +1,81 @@
+// Copyright 2018-2021 TON Labs LTD.
+//
+// Licensed under the SOFTWARE EVALUATION License (the "License"); you may not
+// use this file except in compliance with the License.
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific TON DEV software governing permissions and
+// limitations under the License.
+
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+
+use super::*;
+
+const ABI: &str = r#"{
+    "version": "2.4",
+    "header": ["time", "expire"],
+    "functions": [
+        {
+            "name": "numbers",
+            "inputs": [
+                {"name":"a","type":"uint8"},
+                {"name":"b","type":"int256"},
+                {"name":"c","type":"varuint16"},
+                {"name":"d","type":"varint32"},
+                {"name":"e","type":"bool"}
+            ],
+            "outputs": [
+                {"name":"value","type":"uint128"},
+                {"name":"values","type":"int32[]"}
+            ]
+        },
+        {
+            "name": "structs",
+            "inputs": [
+                {"name":"s","type":"tuple","components":[
+                    {"name":"owner","type":"address"},
+                    {"name":"name","type":"string"},
+                    {"name":"data","type":"bytes"}
+                ]},
+                {"name":"m","type":"map(uint32,address)"},
+                {"name":"o","type":"optional(uint64)"},
+                {"name":"r","type":"ref(cell)"},
+                {"name":"f","type":"fixedbytes4"}
+            ],
+            "outputs": [
+                {"name":"fixed","type":"uint16[3]"},
+                {"name":"amount","type":"varuint32"}
+            ]
+        }
+    ],
+    "events": [],
+    "fields": []
+}"#;
+
+#[test]
+fn test_round_trip() {
+    let mut rng = StdRng::seed_from_u64(0);
+    let report = check_round_trip(ABI, 20, &mut rng).unwrap();
+    assert_eq!(report.checked, 2 * 20 * 2);
+    assert!(report.is_ok(), "{:?}", report.failures);
+}
+
+#[test]
+fn test_random_value_ranges() {
+    let mut rng = StdRng::seed_from_u64(1);
+    for _ in 0..100 {
+        match random_token_value(&ParamType::Int(8), &mut rng) {
+            TokenValue::Int(int) => {
+                assert!(int.number >= BigInt::from(-128) && int.number < BigInt::from(128))
+            }
+            value => panic!("unexpected value {:?}", value),
+        }
+        match random_token_value(&ParamType::Uint(3), &mut rng) {
+            TokenValue::Uint(uint) => assert!(uint.number < BigUint::from(8u32)),
+            value => panic!("unexpected value {:?}", value),
+        }
+    }
+}
@@ -0,0 +1,55 @@
+// Copyright 2018-2021 TON Labs LTD.
+//
+// Licensed under the SOFTWARE EVALUATION License (the "License"); you may not
+// use this file except in compliance with the License.
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific TON DEV software governing permissions and
+// limitations under the License.
+
+use tvm_block::Serializable;
+
+use super::*;
+use crate::Uint;
+
+const ABI: &str = r#"{
+    "version": "2.4",
+    "functions": [],
+    "events": [
+        {"name":"Transfer","inputs":[{"name":"amount","type":"uint128"},{"name":"comment","type":"string"}]},
+        {"name":"Paused","inputs":[]}
+    ]
+}"#;
+
+fn event_body(decoder: &EventDecoder, name: &str, tokens: &[Token]) -> SliceData {
+    let event = decoder.contract().event(name).unwrap();
+    let id = event.get_id().write_to_new_cell().unwrap();
+    let builder =
+        TokenValue::pack_values_into_chain(tokens, vec![id.into()], &event.abi_version).unwrap();
+    SliceData::load_builder(builder).unwrap()
+}
+
+#[test]
+fn test_decode_into() {
+    let decoder = EventDecoder::load(ABI).unwrap();
+    let transfer = vec![
+        Token::new("amount", TokenValue::Uint(Uint::new(1000, 128))),
+        Token::new("comment", TokenValue::String("hello".to_owned())),
+    ];
+
+    let mut out = DecodedEvent::default();
+    decoder.decode_into(event_body(&decoder, "Transfer", &transfer), &mut out).unwrap();
+    assert_eq!(out.name, "Transfer");
+    assert_eq!(out.tokens, transfer);
+    assert_eq!(decoder.event(&event_body(&decoder, "Paused", &[])).unwrap().name, "Paused");
+
+    decoder.decode_into(event_body(&decoder, "Paused", &[]), &mut out).unwrap();
+    assert_eq!(out.name, "Paused");
+    assert!(out.tokens.is_empty());
+    assert!(out.tokens.capacity() >= 2, "buffer should be reused");
+
+    let unknown = SliceData::new(vec![0xde, 0xad, 0xbe, 0xef, 0x80]);
+    assert!(decoder.decode(unknown).is_err());
+}
@@ -14,6 +14,7 @@ use tvm_types::Ed25519PublicKey;
 use tvm_types::dictionary::HashmapE;
 use tvm_types::ed25519_generate_private_key;
 
+use crate::Contract;
 use crate::json_abi::*;
 
 const WALLET_ABI: &str = r#"{
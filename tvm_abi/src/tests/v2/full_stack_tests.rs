@@ -18,6 +18,7 @@ use tvm_types::dictionary::HashmapE;
 use tvm_types::ed25519_generate_private_key;
 use tvm_types::ed25519_verify;
 
+use crate::Contract;
 use crate::json_abi::*;
 
 const WALLET_ABI: &str = r#"{
@@ -767,3 +768,70 @@ fn test_wrong_storage_layout() {
         .is_ok()
     );
 }
+
+const FIELDS_ABI: &str = r#"{
+    "ABI version": 2,
+    "version": "2.3",
+    "header": ["time"],
+    "functions": [
+        {
+            "name": "transfer",
+            "inputs": [
+                {"name":"a","type":"uint256"},
+                {"name":"b","type":"int256"},
+                {"name":"c","type":"uint256"},
+                {"name":"flag","type":"bool"},
+                {"name":"comment","type":"bytes"},
+                {"name":"transfer","type":"tuple","components":[
+                    {"name":"amount","type":"uint128"},
+                    {"name":"dest","type":"address"}
+                ]},
+                {"name":"d","type":"uint256"}
+            ],
+            "outputs": []
+        }
+    ],
+    "events": [],
+    "data": []
+}"#;
+
+#[test]
+fn test_decode_field() {
+    let params = json!({
+        "a": "1",
+        "b": "-2",
+        "c": "3",
+        "flag": true,
+        "comment": "616263",
+        "transfer": {
+            "amount": "1000000000",
+            "dest": "0:1111111111111111111111111111111111111111111111111111111111111111",
+        },
+        "d": "4",
+    })
+    .to_string();
+
+    for internal in [false, true] {
+        let header = (!internal).then_some(r#"{"time": 1}"#);
+        let body =
+            encode_function_call(FIELDS_ABI, "transfer", header, &params, internal, None, None)
+                .unwrap();
+        let body = SliceData::load_builder(body).unwrap();
+
+        let decoded: serde_json::Value = serde_json::from_str(
+            &decode_unknown_function_call(FIELDS_ABI, body.clone(), internal, false)
+                .unwrap()
+                .params,
+        )
+        .unwrap();
+
+        for path in ["a", "b", "c", "flag", "comment", "transfer", "transfer.amount", "d"] {
+            let field = decode_field(FIELDS_ABI, "transfer", body.clone(), internal, path).unwrap();
+            let expected = path.split('.').fold(&decoded, |value, name| &value[name]);
+            assert_eq!(serde_json::from_str::<serde_json::Value>(&field).unwrap(), *expected);
+        }
+
+        assert!(decode_field(FIELDS_ABI, "transfer", body.clone(), internal, "e").is_err());
+        assert!(decode_field(FIELDS_ABI, "transfer", body, internal, "a.amount").is_err());
+    }
+}
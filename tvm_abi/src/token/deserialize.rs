@@ -539,15 +539,34 @@ impl TokenValue {
 
     pub fn decode_params_with_cursor(
         params: &[Param],
-        mut cursor: Cursor,
+        cursor: Cursor,
         abi_version: &AbiVersion,
         allow_partial: bool,
         last: bool,
     ) -> Result<(Vec<Token>, Cursor)> {
-        let mut tokens = vec![];
+        let mut tokens = Vec::with_capacity(params.len());
+        let cursor = Self::decode_params_into(
+            params,
+            cursor,
+            abi_version,
+            allow_partial,
+            last,
+            &mut tokens,
+        )?;
+        Ok((tokens, cursor))
+    }
 
+    /// Decodes provided params appending tokens to `tokens`, so the caller
+    /// can reuse its buffer
+    pub fn decode_params_into(
+        params: &[Param],
+        mut cursor: Cursor,
+        abi_version: &AbiVersion,
+        allow_partial: bool,
+        last: bool,
+        tokens: &mut Vec<Token>,
+    ) -> Result<Cursor> {
         for param in params {
-            // println!("{:?}", param);
             let last = Some(param) == params.last() && last;
             let (token_value, new_cursor) =
                 Self::read_from(&param.kind, cursor, last, abi_version, allow_partial)?;
@@ -556,7 +575,52 @@ impl TokenValue {
             tokens.push(Token { name: param.name.clone(), value: token_value });
         }
 
-        Ok((tokens, cursor))
+        Ok(cursor)
+    }
+
+    /// Decodes the single parameter found by `path` of parameter names,
+    /// descending into tuples. Integer and bool parameters before it are
+    /// skipped without decoding, parameters after it are not read at all.
+    pub fn decode_field(
+        params: &[Param],
+        path: &[&str],
+        mut cursor: Cursor,
+        abi_version: &AbiVersion,
+        last: bool,
+    ) -> Result<Self> {
+        let Some((name, path)) = path.split_first() else {
+            fail!(AbiError::InvalidName { name: String::new() })
+        };
+        let index = params
+            .iter()
+            .position(|param| param.name == *name)
+            .ok_or_else(|| AbiError::InvalidName { name: name.to_string() })?;
+        for param in &params[..index] {
+            cursor = Self::skip(&param.kind, cursor, abi_version)?;
+        }
+        let param = &params[index];
+        let last = last && index + 1 == params.len();
+        match (&param.kind, path.first()) {
+            (_, None) => Ok(Self::read_from(&param.kind, cursor, last, abi_version, true)?.0),
+            (ParamType::Tuple(tuple_params), Some(_)) => {
+                Self::decode_field(tuple_params, path, cursor, abi_version, last)
+            }
+            (_, Some(name)) => fail!(AbiError::InvalidName { name: name.to_string() }),
+        }
+    }
+
+    /// Moves the cursor over the parameter which is not the last one
+    fn skip(param_type: &ParamType, cursor: Cursor, abi_version: &AbiVersion) -> Result<Cursor> {
+        let bits = match param_type {
+            ParamType::Uint(size) | ParamType::Int(size) => *size,
+            ParamType::Bool => 1,
+            _ => return Ok(Self::read_from(param_type, cursor, false, abi_version, true)?.1),
+        };
+        let mut slice = find_next_bits(cursor.slice.clone(), bits)?;
+        slice.move_by(bits)?;
+        let mut cursor = Self::check_layout(param_type, cursor, &slice, abi_version, false)?;
+        cursor.slice = slice;
+        Ok(cursor)
     }
 }
 
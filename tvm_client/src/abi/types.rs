@@ -39,7 +39,9 @@ impl Abi {
     }
 
     pub fn abi(&self) -> ClientResult<tvm_abi::Contract> {
-        tvm_abi::Contract::load(self.json_string()?.as_bytes()).map_err(Error::invalid_json)
+        let contract =
+            tvm_abi::AbiCache::global().load(&self.json_string()?).map_err(Error::invalid_json)?;
+        Ok(contract.as_ref().clone())
     }
 }
 
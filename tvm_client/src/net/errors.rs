@@ -34,6 +34,7 @@ pub enum ErrorCode {
     SendMessageFailed = 621,
     NotFound = 622,
     AllAttemptsFailed = 623,
+    InvalidShardEndpoints = 624,
 }
 
 pub struct Error;
@@ -278,6 +279,10 @@ impl Error {
     pub fn all_attempts_failed() -> ClientError {
         error(ErrorCode::AllAttemptsFailed, "All attempts failed".to_string())
     }
+
+    pub fn invalid_shard_endpoints<E: Display>(err: E) -> ClientError {
+        error(ErrorCode::InvalidShardEndpoints, format!("Invalid shard endpoints: {}", err))
+    }
 }
 
 #[cfg(test)]
@@ -74,6 +74,7 @@ pub use types::BLOCKS_COLLECTION;
 pub use types::MESSAGES_COLLECTION;
 pub use types::NetworkConfig;
 pub use types::NetworkQueriesProtocol;
+pub use types::ShardEndpoints;
 pub use types::TRANSACTIONS_COLLECTION;
 
 use crate::client::ClientContext;
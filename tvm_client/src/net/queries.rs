@@ -10,6 +10,7 @@
 // limitations under the License.
 
 use serde_json::Value;
+use tvm_block::MsgAddressInt;
 
 use super::Error;
 use crate::client::ClientContext;
@@ -71,6 +72,25 @@ pub async fn query(
     Ok(ResultOfQuery { result: deserialize_result(result, server_link).await? })
 }
 
+/// Performs query on the endpoint serving the shard of the account if shard
+/// endpoints are configured for it and reachable, otherwise on the query
+/// endpoint.
+pub(crate) async fn query_account(
+    context: std::sync::Arc<ClientContext>,
+    address: &MsgAddressInt,
+    params: ParamsOfQuery,
+) -> ClientResult<ResultOfQuery> {
+    let server_link = context.get_server_link()?;
+    let query = GraphQLQuery {
+        query: params.query,
+        variables: params.variables,
+        is_batch: false,
+        timeout: None,
+    };
+    let result = server_link.query_account(&query, address).await;
+    Ok(ResultOfQuery { result: deserialize_result(result, server_link).await? })
+}
+
 //------------------------------------------------------------------------------- query_collection
 
 #[derive(Serialize, Deserialize, ApiType, Default, Clone)]
@@ -28,6 +28,9 @@ use serde_json::Value;
 use tokio::sync::Mutex;
 use tokio::sync::RwLock;
 use tokio::sync::watch;
+use tvm_block::MsgAddressInt;
+use tvm_client_processing::send_error::SendError;
+use tvm_sdk::ShardMap;
 use tvm_types::UInt256;
 use tvm_types::base64_encode;
 
@@ -95,6 +98,7 @@ pub(crate) struct NetworkState {
     bk_send_message_endpoint: RwLock<Option<String>>,
     bm_license_contract: RwLock<Option<String>>,
     bm_token: RwLock<Option<Value>>,
+    shard_endpoints: ShardMap<Vec<String>>,
 }
 
 async fn query_by_url(
@@ -116,6 +120,7 @@ impl NetworkState {
         config: NetworkConfig,
         endpoint_addresses: Vec<String>,
         bm_send_message_endpoint: String,
+        shard_endpoints: ShardMap<Vec<String>>,
     ) -> Self {
         let (sender, receiver) = watch::channel(false);
         let regulation =
@@ -136,6 +141,7 @@ impl NetworkState {
             bk_send_message_endpoint: RwLock::new(None),
             bm_license_contract: RwLock::new(None),
             bm_token: RwLock::new(None),
+            shard_endpoints,
         }
     }
 
@@ -352,7 +358,6 @@ impl NetworkState {
         lock.insert(address, ResolvedEndpoint { endpoint, time_added: self.client_env.now_ms() });
     }
 
-    #[allow(dead_code)]
     pub async fn get_resolved_endpoint(&self, address: &str) -> Option<Arc<Endpoint>> {
         let lock = self.resolved_endpoints.read().await;
         lock.get(address).and_then(|endpoint| {
@@ -364,6 +369,48 @@ impl NetworkState {
         })
     }
 
+    /// Returns endpoint serving the shard of the account, `None` if no shard
+    /// endpoints are configured for the account or none of them resolves.
+    /// Websocket queries go through the link to the query endpoint, so
+    /// nothing is resolved for them.
+    pub async fn get_account_endpoint(&self, address: &MsgAddressInt) -> Option<Arc<Endpoint>> {
+        if let NetworkQueriesProtocol::WS = self.config.queries_protocol {
+            return None;
+        }
+        let urls = self.shard_urls(address)?;
+        for url in urls {
+            if let Some(endpoint) = self.get_resolved_endpoint(url).await {
+                return Some(endpoint);
+            }
+        }
+        for url in urls {
+            match self.resolve_endpoint(url).await {
+                Ok(endpoint) => return Some(endpoint),
+                Err(err) => log::warn!("Shard endpoint {} is not resolved: {}", url, err.message),
+            }
+        }
+        None
+    }
+
+    /// Returns send endpoint of the block manager serving the shard of the
+    /// destination account, `None` if no shard endpoints are configured for
+    /// the account
+    pub fn get_account_send_endpoint(&self, address: &MsgAddressInt) -> Option<String> {
+        let url = self.shard_urls(address)?.first()?;
+        construct_bm_send_message_endpoint(url, false).ok()
+    }
+
+    fn shard_urls(&self, address: &MsgAddressInt) -> Option<&Vec<String>> {
+        match self.shard_endpoints.find(address) {
+            Ok(Some((_, urls))) if !urls.is_empty() => Some(urls),
+            Ok(_) => None,
+            Err(err) => {
+                log::warn!("Shard of {} is not found: {}", address, err);
+                None
+            }
+        }
+    }
+
     pub async fn select_send_message_endpoint(&self) -> String {
         let guarded_bk_endpoint = self.bk_send_message_endpoint.read().await;
         if let Some(bk_endpoint) = guarded_bk_endpoint.as_ref() {
@@ -504,12 +551,19 @@ impl ServerLink {
         let endpoint_addresses = replace_endpoints(endpoint_addresses);
         let bm_send_message_endpoint =
             construct_bm_send_message_endpoint(&endpoint_addresses[0], false)?;
+        let mut shard_endpoints = ShardMap::new();
+        for shard in config.shard_endpoints.iter().flatten() {
+            shard_endpoints
+                .insert_str(&shard.shard, replace_endpoints(shard.endpoints.clone()))
+                .map_err(Error::invalid_shard_endpoints)?;
+        }
 
         let state = Arc::new(NetworkState::new(
             client_env.clone(),
             config.clone(),
             endpoint_addresses,
             bm_send_message_endpoint,
+            shard_endpoints,
         ));
 
         Ok(ServerLink {
@@ -639,18 +693,6 @@ impl ServerLink {
         query: &GraphQLQuery,
         endpoint: Option<&Endpoint>,
     ) -> ClientResult<Value> {
-        let request = json!({
-            "query": query.query,
-            "variables": query.variables,
-        })
-        .to_string();
-
-        let mut headers = HashMap::new();
-        headers.insert("content-type".to_owned(), "application/json".to_owned());
-        for (name, value) in Endpoint::http_headers(&self.config) {
-            headers.insert(name, value);
-        }
-
         let mut current_endpoint: Option<Arc<Endpoint>>;
         let start = self.client_env.now_ms();
         loop {
@@ -660,33 +702,7 @@ impl ServerLink {
                 current_endpoint = Some(self.state.get_query_endpoint().await?.clone());
                 current_endpoint.as_ref().unwrap()
             };
-            let result = self
-                .client_env
-                .fetch(
-                    &endpoint.query_url,
-                    FetchMethod::Post,
-                    Some(headers.clone()),
-                    Some(request.clone()),
-                    query.timeout.unwrap_or(self.config.query_timeout),
-                )
-                .await;
-            let result = match result {
-                Err(err) => Err(err),
-                Ok(response) => {
-                    self.state.reset_resume_timeout();
-                    if response.status == 401 {
-                        Err(Error::unauthorized(&response))
-                    } else {
-                        match response.body_as_json(false) {
-                            Err(err) => Err(err),
-                            Ok(value) => match Error::try_extract_graphql_error(&value) {
-                                Some(err) => Err(err),
-                                None => Ok(value),
-                            },
-                        }
-                    }
-                }
-            };
+            let result = self.fetch_graphql(query, endpoint).await;
 
             if let Err(err) = &result {
                 if crate::client::Error::is_network_error(err) {
@@ -712,6 +728,63 @@ impl ServerLink {
         }
     }
 
+    // Performs single attempt of the query on the endpoint
+    async fn fetch_graphql(
+        &self,
+        query: &GraphQLQuery,
+        endpoint: &Endpoint,
+    ) -> ClientResult<Value> {
+        let request = json!({
+            "query": query.query,
+            "variables": query.variables,
+        })
+        .to_string();
+
+        let mut headers = HashMap::new();
+        headers.insert("content-type".to_owned(), "application/json".to_owned());
+        for (name, value) in Endpoint::http_headers(&self.config) {
+            headers.insert(name, value);
+        }
+
+        let response = self
+            .client_env
+            .fetch(
+                &endpoint.query_url,
+                FetchMethod::Post,
+                Some(headers),
+                Some(request),
+                query.timeout.unwrap_or(self.config.query_timeout),
+            )
+            .await?;
+        self.state.reset_resume_timeout();
+        if response.status == 401 {
+            return Err(Error::unauthorized(&response));
+        }
+        let value = response.body_as_json(false)?;
+        match Error::try_extract_graphql_error(&value) {
+            Some(err) => Err(err),
+            None => Ok(value),
+        }
+    }
+
+    /// Performs query on the endpoint serving the shard of the account. Falls
+    /// back to the query endpoint if there is none or it is unreachable.
+    pub(crate) async fn query_account(
+        &self,
+        query: &GraphQLQuery,
+        address: &MsgAddressInt,
+    ) -> ClientResult<Value> {
+        if let Some(endpoint) = self.state.get_account_endpoint(address).await {
+            match self.fetch_graphql(query, &endpoint).await {
+                Err(err) if crate::client::Error::is_network_error(&err) => {
+                    log::warn!("Shard endpoint {} failed: {}", endpoint.query_url, err.message);
+                }
+                result => return result,
+            }
+        }
+        self.query(query, None).await
+    }
+
     pub(crate) async fn query_http(&self, request: String, endpoint: &str) -> ClientResult<Value> {
         let mut headers = HashMap::new();
         headers.insert("content-type".to_owned(), "application/json".to_owned());
@@ -891,12 +964,14 @@ impl ServerLink {
             .remove(0))
     }
 
-    // Sends message to blockchain
+    // Sends message to blockchain. Message to the account of a shard with
+    // configured endpoints is sent to the block manager of the shard first.
     pub async fn send_message(
         &self,
         msg_id: &str,
         msg_body: &[u8],
         thread_id: ThreadIdentifier,
+        dst: Option<&MsgAddressInt>,
     ) -> ClientResult<Value> {
         let mut attempts = 0;
 
@@ -910,9 +985,20 @@ impl ServerLink {
             bm_token: network_state.get_bm_token().await,
         };
 
-        let mut endpoint = network_state.select_send_message_endpoint().await;
+        let shard_endpoint = dst.and_then(|dst| network_state.get_account_send_endpoint(dst));
+        let mut endpoint = match &shard_endpoint {
+            Some(endpoint) => endpoint.clone(),
+            None => network_state.select_send_message_endpoint().await,
+        };
         let query = json!([message]).to_string();
         let mut result = self.query_http(query, &endpoint).await;
+        if let (Some(shard_endpoint), Err(err)) = (&shard_endpoint, &result) {
+            if crate::client::Error::is_network_error(err) {
+                log::warn!("Shard endpoint {} failed: {}", shard_endpoint, err.message);
+                endpoint = network_state.select_send_message_endpoint().await;
+                result = self.query_http(json!([message]).to_string(), &endpoint).await;
+            }
+        }
         while attempts < self.config.message_retries_count {
             attempts += 1;
             if let Ok(ref data) = result {
@@ -933,19 +1019,12 @@ impl ServerLink {
                 message.bm_token = network_state.get_bm_token().await;
             }
 
-            let Some(ext) = err.data.get("node_error").and_then(|e| e.get("extensions")) else {
-                return result;
-            };
-
-            let Some(code) = ext.get("code").and_then(Value::as_str) else {
-                return result;
-            };
-
-            if !["WRONG_PRODUCER", "THREAD_MISMATCH", "TOKEN_EXPIRED"].contains(&code) {
+            let send_error = SendError::from_error(err.clone().into());
+            if !send_error.is_redirect() {
                 return result;
             }
 
-            if code == "TOKEN_EXPIRED" {
+            if send_error == SendError::TokenExpired {
                 endpoint = network_state.get_bm_send_message_endpoint().await;
                 network_state.update_bk_send_message_endpoint(None).await;
             }
@@ -318,6 +318,23 @@ pub struct NetworkConfig {
 
     /// Access key to GraphQL API (Project secret)
     pub access_key: Option<String>,
+
+    /// Endpoints serving particular shards. Account queries and messages are
+    /// routed to the endpoints of the most specific shard containing the
+    /// account, messages to the block manager of the first endpoint. Other
+    /// queries, accounts of shards missing here and requests failed on the
+    /// shard endpoints use `endpoints`.
+    pub shard_endpoints: Option<Vec<ShardEndpoints>>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ApiType, Default, PartialEq)]
+pub struct ShardEndpoints {
+    /// Shard in `workchain:prefix` form with tagged hex prefix, e.g.
+    /// `0:8000000000000000`.
+    pub shard: String,
+
+    /// Endpoints serving the shard.
+    pub endpoints: Vec<String>,
 }
 
 impl NetworkConfig {
@@ -356,6 +373,7 @@ impl Default for NetworkConfig {
             next_remp_status_timeout: default_next_remp_status_timeout(),
             signature_id: None,
             access_key: None,
+            shard_endpoints: None,
         }
     }
 }
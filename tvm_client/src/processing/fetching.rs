@@ -146,8 +146,9 @@ pub(crate) async fn fetch_account(
     address: &MsgAddressInt,
     result: &str,
 ) -> ClientResult<Value> {
-    let mut result = crate::net::query(
+    let mut result = crate::net::queries::query_account(
         context,
+        address,
         crate::net::ParamsOfQuery {
             query: format!("query account($address:String!){{blockchain{{account(address:$address){{info{{{}}}}}}}}}", result),
             variables: Some(json!({
@@ -22,6 +22,7 @@ mod message_monitor_sdk_services;
 pub(crate) mod parsing;
 pub(crate) mod process_message;
 mod remp;
+mod send_batch;
 pub(crate) mod send_message;
 mod send_messages;
 mod thread_identifier;
@@ -46,6 +47,7 @@ pub use message_monitor::monitor_messages_api;
 pub(crate) use message_monitor_sdk_services::SdkServices;
 pub use process_message::ParamsOfProcessMessage;
 pub use process_message::process_message;
+pub use send_batch::BatchSender;
 pub use send_message::ParamsOfSendMessage;
 pub use send_message::ResultOfSendMessage;
 pub use send_message::send_message;
@@ -63,6 +65,12 @@ pub use tvm_client_processing::MessageMonitoringTransactionCompute;
 pub use tvm_client_processing::MonitorFetchWaitMode;
 pub use tvm_client_processing::MonitoredMessage;
 pub use tvm_client_processing::MonitoringQueueInfo;
+pub use tvm_client_processing::batch_send::BatchMessage;
+pub use tvm_client_processing::batch_send::BatchSendPolicy;
+pub use tvm_client_processing::batch_send::BatchSendResult;
+pub use tvm_client_processing::batch_send::MessagePriority;
+pub use tvm_client_processing::batch_send::SendOutcome;
+pub use tvm_client_processing::rebroadcast::RebroadcastPolicy;
 pub use types::DecodedOutput;
 pub use types::ProcessingEvent;
 pub use types::ProcessingResponseType;
@@ -0,0 +1,109 @@
+// Copyright 2018-2021 TON Labs LTD.
+//
+// Licensed under the SOFTWARE EVALUATION License (the "License"); you may not
+// use this file except in compliance with the License.
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific TON DEV software governing permissions and
+// limitations under the License.
+//
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use futures::Stream;
+use tvm_block::Message;
+use tvm_client_processing::batch_send;
+use tvm_client_processing::batch_send::BatchMessage;
+use tvm_client_processing::batch_send::BatchSendPolicy;
+use tvm_client_processing::batch_send::BatchSendResult;
+use tvm_client_processing::batch_send::MessageSender;
+use tvm_client_processing::send_error::SendError;
+
+use super::ThreadIdentifier;
+use crate::boc::internal::deserialize_object_from_boc;
+use crate::boc::internal::serialize_cell_to_bytes;
+use crate::client::ClientContext;
+use crate::error::ClientResult;
+use crate::net::ParamsOfQueryCollection;
+use crate::net::TRANSACTIONS_COLLECTION;
+use crate::net::query_collection;
+
+/// Sends message batches through the network link of the client context.
+pub struct BatchSender {
+    context: Arc<ClientContext>,
+}
+
+impl BatchSender {
+    pub fn new(context: Arc<ClientContext>) -> Self {
+        Self { context }
+    }
+
+    /// Sends messages with at most `concurrency` sends in flight, retrying,
+    /// rate limiting and rebroadcasting them according to `policy`. Yields
+    /// the outcome of each message as soon as it is resolved, so results may
+    /// come out of batch order. With rebroadcasting enabled the stream ends
+    /// when the transactions of all sent messages are seen or they expire.
+    pub fn send_batch<'a>(
+        &'a self,
+        messages: Vec<BatchMessage>,
+        concurrency: usize,
+        policy: &'a BatchSendPolicy,
+    ) -> impl Stream<Item = BatchSendResult> + 'a {
+        batch_send::send_batch(self, messages, concurrency, policy)
+    }
+
+    async fn send_message(&self, boc: &str) -> ClientResult<()> {
+        let message = deserialize_object_from_boc::<Message>(&self.context, boc, "message")?;
+        let id = message.cell.repr_hash().as_hex_string();
+        let body = serialize_cell_to_bytes(&message.cell, "message")?;
+        self.context
+            .get_server_link()?
+            .send_message(&id, &body, ThreadIdentifier::default(), message.object.dst_ref())
+            .await?;
+        Ok(())
+    }
+
+    async fn has_transaction(&self, boc: &str) -> ClientResult<bool> {
+        let message = deserialize_object_from_boc::<Message>(&self.context, boc, "message")?;
+        let transactions = query_collection(
+            self.context.clone(),
+            ParamsOfQueryCollection {
+                collection: TRANSACTIONS_COLLECTION.into(),
+                filter: Some(json!({
+                    "in_msg": { "eq": message.cell.repr_hash().as_hex_string() }
+                })),
+                result: "id".into(),
+                limit: Some(1),
+                ..Default::default()
+            },
+        )
+        .await?;
+        Ok(!transactions.result.is_empty())
+    }
+}
+
+#[async_trait]
+impl MessageSender for BatchSender {
+    async fn send(&self, boc: &str) -> Result<(), SendError> {
+        self.send_message(boc).await.map_err(|err| SendError::from_error(err.into()))
+    }
+
+    // Network errors keep the message rebroadcast
+    async fn is_processed(&self, boc: &str) -> bool {
+        self.has_transaction(boc).await.unwrap_or_else(|err| {
+            log::debug!("Transaction of the rebroadcast message is not checked: {}", err);
+            false
+        })
+    }
+
+    async fn sleep(&self, ms: u64) {
+        let _ = self.context.set_timer(ms).await;
+    }
+
+    fn now_ms(&self) -> u64 {
+        self.context.now_ms()
+    }
+}
@@ -0,0 +1,216 @@
+// Copyright 2018-2021 TON Labs LTD.
+//
+// Licensed under the SOFTWARE EVALUATION License (the "License"); you may not
+// use this file except in compliance with the License.
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific TON DEV software governing permissions and
+// limitations under the License.
+
+use std::sync::Arc;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+
+use futures::Stream;
+use futures::StreamExt;
+use serde_json::Value;
+use tokio::sync::Mutex;
+
+use crate::rebroadcast;
+use crate::rebroadcast::RebroadcastPolicy;
+use crate::rebroadcast::RebroadcastSchedule;
+use crate::send_error::SendError;
+
+/// Transport used by `send_batch` to deliver a single message.
+#[async_trait]
+pub trait MessageSender: Send + Sync {
+    async fn send(&self, boc: &str) -> Result<(), SendError>;
+
+    /// Whether the transaction processing the message is seen, its
+    /// rebroadcasting stops then. Senders not tracking transactions
+    /// rebroadcast messages until they expire.
+    async fn is_processed(&self, _boc: &str) -> bool {
+        false
+    }
+
+    async fn sleep(&self, ms: u64);
+    fn now_ms(&self) -> u64;
+}
+
+#[derive(Serialize, Deserialize, Default, Debug, Clone, PartialEq)]
+pub struct BatchMessage {
+    /// BOC of the message, that must be sent to the blockchain.
+    pub boc: String,
+
+    /// Expiration time of the message.
+    /// Must be specified as a UNIX timestamp in seconds.
+    pub wait_until: u32,
+
+    /// User defined data associated with this message.
+    pub user_data: Option<Value>,
+
+    /// Priority class of the message.
+    #[serde(default)]
+    pub priority: MessagePriority,
+}
+
+#[derive(Serialize, Deserialize, Default, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MessagePriority {
+    Low,
+    #[default]
+    Normal,
+    High,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct BatchSendPolicy {
+    /// Number of resend attempts after the first failed one.
+    pub retries: u32,
+
+    /// Delay between attempts in milliseconds.
+    pub retry_delay_ms: u64,
+
+    /// Maximum number of send attempts per second over the whole batch.
+    /// Unlimited if not specified.
+    pub max_per_second: Option<u32>,
+
+    /// Resending of sent messages until they are processed or expire.
+    /// Sent messages are not rebroadcast if not specified.
+    #[serde(default)]
+    pub rebroadcast: Option<RebroadcastPolicy>,
+}
+
+impl Default for BatchSendPolicy {
+    fn default() -> Self {
+        Self { retries: 3, retry_delay_ms: 1000, max_per_second: None, rebroadcast: None }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "type")]
+pub enum SendOutcome {
+    /// Message was accepted by the endpoint.
+    Sent { attempts: u32 },
+    /// Message was rejected with not retriable error, or all attempts failed.
+    /// `error` is the last error received.
+    Rejected { attempts: u32, error: SendError },
+    /// Message expired before it was accepted.
+    Expired { attempts: u32 },
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct BatchSendResult {
+    /// Index of the message in the batch.
+    pub index: usize,
+    pub user_data: Option<Value>,
+    pub outcome: SendOutcome,
+}
+
+/// Sends messages with at most `concurrency` sends in flight. Results are
+/// yielded as soon as each message is resolved, so they may come out of
+/// batch order. With the rebroadcast policy sent messages are resent on
+/// schedule, and the stream ends when each of them is processed or expires.
+pub fn send_batch<'a>(
+    sender: &'a dyn MessageSender,
+    messages: Vec<BatchMessage>,
+    concurrency: usize,
+    policy: &'a BatchSendPolicy,
+) -> impl Stream<Item = BatchSendResult> + 'a {
+    let limiter = RateLimiter::new(policy.max_per_second);
+    let schedule =
+        policy.rebroadcast.clone().map(|x| Arc::new(Mutex::new(RebroadcastSchedule::new(x))));
+    let sending = Arc::new(AtomicUsize::new(messages.len()));
+    let results = {
+        let schedule = schedule.clone();
+        let sending = sending.clone();
+        futures::stream::iter(messages.into_iter().enumerate())
+            .map(move |(index, message)| {
+                let limiter = limiter.clone();
+                let schedule = schedule.clone();
+                let sending = sending.clone();
+                async move {
+                    let outcome = send_one(sender, &message, policy, &limiter).await;
+                    if let (Some(schedule), SendOutcome::Sent { .. }) = (schedule, &outcome) {
+                        schedule.lock().await.schedule(
+                            index.to_string(),
+                            &message,
+                            sender.now_ms(),
+                        );
+                    }
+                    sending.fetch_sub(1, Ordering::SeqCst);
+                    BatchSendResult { index, user_data: message.user_data, outcome }
+                }
+            })
+            .buffer_unordered(concurrency.max(1))
+    };
+    match schedule {
+        None => results.left_stream(),
+        Some(schedule) => {
+            let rebroadcasting = futures::stream::once(async move {
+                rebroadcast::rebroadcast_scheduled(sender, &schedule, &sending).await
+            })
+            .filter_map(|()| futures::future::ready(None));
+            futures::stream::select(results, rebroadcasting).right_stream()
+        }
+    }
+}
+
+async fn send_one(
+    sender: &dyn MessageSender,
+    message: &BatchMessage,
+    policy: &BatchSendPolicy,
+    limiter: &RateLimiter,
+) -> SendOutcome {
+    let mut attempts = 0;
+    loop {
+        limiter.acquire(sender).await;
+        if sender.now_ms() / 1000 >= message.wait_until as u64 {
+            return SendOutcome::Expired { attempts };
+        }
+        attempts += 1;
+        match sender.send(&message.boc).await {
+            Ok(()) | Err(SendError::AlreadyExists) => return SendOutcome::Sent { attempts },
+            Err(SendError::Expired) => return SendOutcome::Expired { attempts },
+            Err(error) if !error.is_retriable() || attempts > policy.retries => {
+                return SendOutcome::Rejected { attempts, error };
+            }
+            Err(error) => {
+                log::debug!("Batch send attempt {} failed: {}", attempts, error);
+                sender.sleep(policy.retry_delay_ms).await;
+            }
+        }
+    }
+}
+
+/// Spreads send attempts evenly over time. Each attempt reserves the next
+/// free slot and waits for it.
+#[derive(Clone)]
+struct RateLimiter {
+    interval_ms: Option<u64>,
+    next_slot_ms: Arc<Mutex<u64>>,
+}
+
+impl RateLimiter {
+    fn new(max_per_second: Option<u32>) -> Self {
+        Self {
+            interval_ms: max_per_second.map(|rate| 1000 / rate.max(1) as u64),
+            next_slot_ms: Default::default(),
+        }
+    }
+
+    async fn acquire(&self, sender: &dyn MessageSender) {
+        let Some(interval_ms) = self.interval_ms else { return };
+        let now = sender.now_ms();
+        let slot = {
+            let mut next_slot = self.next_slot_ms.lock().await;
+            let slot = (*next_slot).max(now);
+            *next_slot = slot + interval_ms;
+            slot
+        };
+        if slot > now {
+            sender.sleep(slot - now).await;
+        }
+    }
+}
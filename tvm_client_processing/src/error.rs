@@ -13,6 +13,14 @@ impl Error {
     pub fn invalid_boc<E: Display>(err: E) -> Self {
         Self::with_code_message(201, format!("Invalid BOC: {}", err))
     }
+
+    pub fn sink_failed<E: Display>(err: E) -> Self {
+        Self::with_code_message(518, format!("Event sink failed: {}", err))
+    }
+
+    pub fn journal_failed<E: Display>(err: E) -> Self {
+        Self::with_code_message(519, format!("Event journal failed: {}", err))
+    }
 }
 
 pub type Result<T> = std::result::Result<T, Error>;
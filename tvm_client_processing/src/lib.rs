@@ -18,11 +18,17 @@ extern crate async_trait;
 #[macro_use]
 extern crate api_derive;
 
+pub mod batch_send;
 mod error;
 mod message_monitor;
+pub mod rebroadcast;
 mod sdk_services;
+pub mod send_error;
+pub mod sink;
 #[cfg(test)]
 mod tests;
+#[cfg(feature = "webhook")]
+pub mod webhook;
 
 pub use error::Error;
 pub use error::Result;
@@ -0,0 +1,199 @@
+// Copyright 2018-2021 TON Labs LTD.
+//
+// Licensed under the SOFTWARE EVALUATION License (the "License"); you may not
+// use this file except in compliance with the License.
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific TON DEV software governing permissions and
+// limitations under the License.
+
+use std::collections::HashMap;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+
+use tokio::sync::Mutex;
+
+use crate::batch_send::BatchMessage;
+use crate::batch_send::MessagePriority;
+use crate::batch_send::MessageSender;
+use crate::send_error::SendError;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RebroadcastPolicy {
+    /// Resend interval in milliseconds for each priority class. Messages of
+    /// classes missing here are not rebroadcast.
+    pub intervals_ms: HashMap<MessagePriority, u64>,
+}
+
+impl Default for RebroadcastPolicy {
+    fn default() -> Self {
+        Self {
+            intervals_ms: HashMap::from([
+                (MessagePriority::High, 2000),
+                (MessagePriority::Normal, 5000),
+            ]),
+        }
+    }
+}
+
+struct ScheduledMessage {
+    boc: String,
+    wait_until: u32,
+    interval_ms: u64,
+    next_send_ms: u64,
+}
+
+/// Keeps sent messages and resends the same BOC with the interval of its
+/// priority class until the message expires, its transaction is seen or it
+/// is cancelled. `send_batch` keeps one for the batch when its policy has
+/// rebroadcasting enabled.
+pub struct RebroadcastSchedule {
+    policy: RebroadcastPolicy,
+    messages: HashMap<String, ScheduledMessage>,
+}
+
+impl RebroadcastSchedule {
+    pub fn new(policy: RebroadcastPolicy) -> Self {
+        Self { policy, messages: HashMap::new() }
+    }
+
+    /// Schedules rebroadcasting of the message sent at `now_ms` under the
+    /// key (usually message hash). Returns `false` if its priority class is
+    /// not rebroadcast.
+    pub fn schedule(&mut self, key: String, message: &BatchMessage, now_ms: u64) -> bool {
+        let Some(interval_ms) = self.policy.intervals_ms.get(&message.priority).copied() else {
+            return false;
+        };
+        self.messages.insert(
+            key,
+            ScheduledMessage {
+                boc: message.boc.clone(),
+                wait_until: message.wait_until,
+                interval_ms,
+                next_send_ms: now_ms + interval_ms,
+            },
+        );
+        true
+    }
+
+    pub fn cancel(&mut self, key: &str) -> bool {
+        self.messages.remove(key).is_some()
+    }
+
+    pub fn len(&self) -> usize {
+        self.messages.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.messages.is_empty()
+    }
+
+    /// Time of the closest resend, `None` if nothing is scheduled.
+    pub fn next_send_ms(&self) -> Option<u64> {
+        self.messages.values().map(|x| x.next_send_ms).min()
+    }
+
+    /// Removes expired messages and returns key and BOC of the messages to be
+    /// resent at `now_ms`, moving their next resend time forward.
+    pub fn take_due(&mut self, now_ms: u64) -> Vec<(String, String)> {
+        self.messages.retain(|_, x| (x.wait_until as u64) * 1000 > now_ms);
+        let mut due = Vec::new();
+        for (key, message) in self.messages.iter_mut() {
+            if message.next_send_ms <= now_ms {
+                message.next_send_ms = now_ms + message.interval_ms;
+                due.push((key.clone(), message.boc.clone()));
+            }
+        }
+        due
+    }
+
+    /// Resends due messages. Messages with the transactions seen and messages
+    /// rejected with not retriable errors are removed from the schedule, the
+    /// rejected ones are returned.
+    pub async fn rebroadcast_due(
+        &mut self,
+        sender: &dyn MessageSender,
+    ) -> Vec<(String, SendError)> {
+        let mut rejected = Vec::new();
+        for (key, boc) in self.take_due(sender.now_ms()) {
+            match resend(sender, &key, &boc).await {
+                Resend::Scheduled => {}
+                Resend::Processed => {
+                    self.messages.remove(&key);
+                }
+                Resend::Rejected(error) => {
+                    self.messages.remove(&key);
+                    rejected.push((key, error));
+                }
+            }
+        }
+        rejected
+    }
+
+    // Shortest resend interval, used to wait for messages to be scheduled
+    fn min_interval_ms(&self) -> u64 {
+        self.policy.intervals_ms.values().copied().min().unwrap_or(DEFAULT_WAIT_MS).max(1)
+    }
+}
+
+const DEFAULT_WAIT_MS: u64 = 1000;
+
+enum Resend {
+    Scheduled,
+    Processed,
+    Rejected(SendError),
+}
+
+async fn resend(sender: &dyn MessageSender, key: &str, boc: &str) -> Resend {
+    if sender.is_processed(boc).await {
+        return Resend::Processed;
+    }
+    match sender.send(boc).await {
+        Ok(()) | Err(SendError::AlreadyExists) => Resend::Scheduled,
+        Err(error) if error.is_retriable() => {
+            log::debug!("Rebroadcast of {} failed: {}", key, error);
+            Resend::Scheduled
+        }
+        Err(error) => Resend::Rejected(error),
+    }
+}
+
+/// Rebroadcasts messages of the schedule shared with the sending of a batch
+/// until each of them is processed or expires and no more messages are
+/// `sending`. The schedule is not locked while messages are resent.
+pub(crate) async fn rebroadcast_scheduled(
+    sender: &dyn MessageSender,
+    schedule: &Mutex<RebroadcastSchedule>,
+    sending: &AtomicUsize,
+) {
+    loop {
+        let wait_ms = {
+            let schedule = schedule.lock().await;
+            if schedule.is_empty() && sending.load(Ordering::SeqCst) == 0 {
+                return;
+            }
+            match schedule.next_send_ms() {
+                Some(next_send_ms) => next_send_ms.saturating_sub(sender.now_ms()),
+                None => schedule.min_interval_ms(),
+            }
+        };
+        if wait_ms > 0 {
+            sender.sleep(wait_ms).await;
+        }
+        let due = schedule.lock().await.take_due(sender.now_ms());
+        for (key, boc) in due {
+            match resend(sender, &key, &boc).await {
+                Resend::Scheduled => {}
+                Resend::Processed => {
+                    schedule.lock().await.cancel(&key);
+                }
+                Resend::Rejected(error) => {
+                    log::warn!("Rebroadcast of {} rejected: {}", key, error);
+                    schedule.lock().await.cancel(&key);
+                }
+            }
+        }
+    }
+}
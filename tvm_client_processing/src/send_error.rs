@@ -0,0 +1,113 @@
+// Copyright 2018-2021 TON Labs LTD.
+//
+// Licensed under the SOFTWARE EVALUATION License (the "License"); you may not
+// use this file except in compliance with the License.
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific TON DEV software governing permissions and
+// limitations under the License.
+
+use std::fmt::Display;
+
+use serde_json::Value;
+use tvm_sdk::NodeRejection;
+
+use crate::error::Error;
+
+/// Reason why the endpoint didn't accept a message.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "type")]
+pub enum SendError {
+    /// Message with the same hash was already received by the node.
+    AlreadyExists,
+    /// Destination account doesn't exist.
+    AccountNotFound,
+    /// Endpoint rate limit or message queue limit is exceeded.
+    LimitExceeded,
+    /// Message must be resent to the active block producer.
+    WrongProducer,
+    /// Message must be resent with the other thread identifier.
+    ThreadMismatch,
+    /// Block manager token must be refreshed.
+    TokenExpired,
+    /// Message expired before it was accepted.
+    Expired,
+    /// Endpoint wasn't reached or didn't return a node error.
+    Transport { error: Error },
+    /// Any other node error.
+    Rejected { code: String, message: String },
+}
+
+impl SendError {
+    /// Maps node error code and message, classified by
+    /// `tvm_sdk::NodeRejection` as in the SDK transports
+    pub fn from_node_error(code: &str, message: &str) -> Self {
+        match NodeRejection::classify(code, message) {
+            NodeRejection::AlreadyExists => Self::AlreadyExists,
+            NodeRejection::AccountNotFound => Self::AccountNotFound,
+            NodeRejection::LimitExceeded => Self::LimitExceeded,
+            NodeRejection::WrongProducer => Self::WrongProducer,
+            NodeRejection::ThreadMismatch => Self::ThreadMismatch,
+            NodeRejection::TokenExpired => Self::TokenExpired,
+            NodeRejection::Expired => Self::Expired,
+            NodeRejection::Other => {
+                Self::Rejected { code: code.to_string(), message: message.to_string() }
+            }
+        }
+    }
+
+    /// Maps error returned by the send request. Errors without
+    /// `data.node_error` are treated as transport errors.
+    pub fn from_error(error: Error) -> Self {
+        let Some(extensions) = error.data.get("node_error").and_then(|x| x.get("extensions"))
+        else {
+            return Self::Transport { error };
+        };
+        let code = extensions.get("code").and_then(Value::as_str).unwrap_or_default();
+        let message =
+            extensions.get("message").and_then(Value::as_str).unwrap_or(error.message.as_str());
+        Self::from_node_error(code, message)
+    }
+
+    /// Message may be accepted if sent again.
+    pub fn is_retriable(&self) -> bool {
+        matches!(
+            self,
+            Self::LimitExceeded
+                | Self::WrongProducer
+                | Self::ThreadMismatch
+                | Self::TokenExpired
+                | Self::Transport { .. }
+        )
+    }
+
+    /// Message must be resent to another endpoint or with updated
+    /// producer data.
+    pub fn is_redirect(&self) -> bool {
+        matches!(self, Self::WrongProducer | Self::ThreadMismatch | Self::TokenExpired)
+    }
+}
+
+impl Display for SendError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::AlreadyExists => write!(f, "message already exists"),
+            Self::AccountNotFound => write!(f, "account not found"),
+            Self::LimitExceeded => write!(f, "limit exceeded"),
+            Self::WrongProducer => write!(f, "wrong producer"),
+            Self::ThreadMismatch => write!(f, "thread mismatch"),
+            Self::TokenExpired => write!(f, "token expired"),
+            Self::Expired => write!(f, "message expired"),
+            Self::Transport { error } => write!(f, "transport error: {}", error.message),
+            Self::Rejected { code, message } => write!(f, "{}: {}", code, message),
+        }
+    }
+}
+
+impl From<Error> for SendError {
+    fn from(error: Error) -> Self {
+        Self::from_error(error)
+    }
+}
@@ -0,0 +1,275 @@
+// Copyright 2018-2021 TON Labs LTD.
+//
+// Licensed under the SOFTWARE EVALUATION License (the "License"); you may not
+// use this file except in compliance with the License.
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific TON DEV software governing permissions and
+// limitations under the License.
+
+use std::collections::VecDeque;
+use std::path::Path;
+
+use serde_json::Value;
+use tvm_sdk::Journal;
+
+use crate::MessageMonitoringResult;
+use crate::MessageMonitoringStatus;
+use crate::error::Error;
+
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MonitorEventKind {
+    /// Message was included into finalized block and its transaction
+    /// succeeded.
+    Finalization,
+    /// Message was included into finalized block but its transaction was
+    /// aborted.
+    Failure,
+    /// Message was not processed until its `wait_until` time.
+    Expiration,
+    /// Message was not processed until its `wait_until` time and the
+    /// emulation of its transaction failed, `data.error` contains the
+    /// reason.
+    Rejection,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct MonitorEvent {
+    /// Kind of the event.
+    pub event: MonitorEventKind,
+    /// Hash of the message the event relates to. Used as a message key by
+    /// sinks supporting partitioning.
+    pub key: String,
+    /// Time of the event, UNIX timestamp in milliseconds.
+    pub timestamp_ms: u64,
+    /// Event details.
+    pub data: Value,
+}
+
+impl MonitorEvent {
+    /// Converts resolved monitoring result into event. Returns `None` for
+    /// intermediate statuses.
+    pub fn from_monitoring_result(
+        result: &MessageMonitoringResult,
+        timestamp_ms: u64,
+    ) -> crate::Result<Option<Self>> {
+        let aborted = result.transaction.as_ref().is_some_and(|transaction| transaction.aborted);
+        let event = match result.status {
+            MessageMonitoringStatus::Finalized if aborted => MonitorEventKind::Failure,
+            MessageMonitoringStatus::Finalized => MonitorEventKind::Finalization,
+            MessageMonitoringStatus::Timeout if result.error.is_some() => {
+                MonitorEventKind::Rejection
+            }
+            MessageMonitoringStatus::Timeout => MonitorEventKind::Expiration,
+            MessageMonitoringStatus::Reserved => return Ok(None),
+        };
+        let data = serde_json::to_value(result).map_err(Error::sink_failed)?;
+        Ok(Some(Self { event, key: result.hash.clone(), timestamp_ms, data }))
+    }
+
+    pub fn to_json_bytes(&self) -> crate::Result<Vec<u8>> {
+        serde_json::to_vec(self).map_err(Error::sink_failed)
+    }
+}
+
+/// Destination for monitoring events. `publish` must return only after the
+/// event has been accepted by the destination, so that callers can retry
+/// failed events and get at-least-once delivery.
+#[async_trait]
+pub trait EventSink: Send + Sync {
+    async fn publish(&self, event: &MonitorEvent) -> crate::Result<()>;
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum JournalRecord {
+    Event { seq: u64, event: MonitorEvent },
+    Ack { seq: u64 },
+}
+
+/// Events waiting for delivery to a sink, persisted in the journal. An event
+/// is journaled before it is published and acknowledged only after the sink
+/// has accepted it, so events survive restarts and are delivered at least
+/// once: a crash between publishing and acknowledging publishes the event
+/// again, consumers deduplicate by `key` and `event`.
+pub struct EventQueue {
+    journal: Journal<JournalRecord>,
+    next_seq: u64,
+    pending: VecDeque<(u64, MonitorEvent)>,
+}
+
+impl EventQueue {
+    /// Opens the journal, creating it if needed, and restores not
+    /// acknowledged events
+    pub fn open(path: impl AsRef<Path>) -> crate::Result<Self> {
+        let (journal, records) = Journal::open(path).map_err(Error::journal_failed)?;
+        let mut pending = VecDeque::new();
+        for record in records {
+            match record {
+                JournalRecord::Event { seq, event } => pending.push_back((seq, event)),
+                JournalRecord::Ack { seq } => pending.retain(|(pending, _)| *pending != seq),
+            }
+        }
+        let next_seq = pending.back().map_or(0, |(seq, _)| seq + 1);
+        let mut queue = Self { journal, next_seq, pending };
+        queue.compact()?;
+        Ok(queue)
+    }
+
+    pub fn path(&self) -> &Path {
+        self.journal.path()
+    }
+
+    /// Events not acknowledged yet, in publishing order
+    pub fn pending(&self) -> impl Iterator<Item = &MonitorEvent> {
+        self.pending.iter().map(|(_, event)| event)
+    }
+
+    /// Journals the event for delivery
+    pub fn push(&mut self, event: MonitorEvent) -> crate::Result<()> {
+        let seq = self.next_seq;
+        let record = JournalRecord::Event { seq, event: event.clone() };
+        self.journal.append(&record).map_err(Error::journal_failed)?;
+        self.next_seq += 1;
+        self.pending.push_back((seq, event));
+        Ok(())
+    }
+
+    /// Publishes pending events one by one in order, acknowledging each
+    /// accepted one. Stops at the first failure, the failed event and the
+    /// rest stay pending. Returns the number of published events.
+    pub async fn deliver(&mut self, sink: &dyn EventSink) -> crate::Result<usize> {
+        let mut published = 0;
+        while let Some((seq, event)) = self.pending.front() {
+            sink.publish(event).await?;
+            let seq = *seq;
+            self.journal.append(&JournalRecord::Ack { seq }).map_err(Error::journal_failed)?;
+            self.pending.pop_front();
+            published += 1;
+        }
+        if published != 0 {
+            self.compact()?;
+        }
+        Ok(published)
+    }
+
+    // Rewrites the journal with the pending events only
+    fn compact(&mut self) -> crate::Result<()> {
+        let records: Vec<_> = self
+            .pending
+            .iter()
+            .map(|(seq, event)| JournalRecord::Event { seq: *seq, event: event.clone() })
+            .collect();
+        self.journal.compact(&records).map_err(Error::journal_failed)
+    }
+}
+
+/// Journals resolved results fetched from `MessageMonitor` and delivers all
+/// pending events of the queue to the sink. A delivery failure is returned
+/// after the results are journaled, so the caller retries with
+/// `EventQueue::deliver`, also after a restart.
+pub async fn publish_monitoring_results(
+    sink: &dyn EventSink,
+    queue: &mut EventQueue,
+    results: &[MessageMonitoringResult],
+    timestamp_ms: u64,
+) -> crate::Result<()> {
+    for result in results {
+        if let Some(event) = MonitorEvent::from_monitoring_result(result, timestamp_ms)? {
+            queue.push(event)?;
+        }
+    }
+    queue.deliver(sink).await?;
+    Ok(())
+}
+
+#[cfg(feature = "nats")]
+pub mod nats {
+    use super::*;
+
+    /// Publishes events to NATS JetStream subject. Publishing waits for the
+    /// stream acknowledgement.
+    pub struct NatsSink {
+        context: async_nats::jetstream::Context,
+        subject: String,
+    }
+
+    impl NatsSink {
+        pub async fn connect(server: &str, subject: &str) -> crate::Result<Self> {
+            let client = async_nats::connect(server).await.map_err(Error::sink_failed)?;
+            Ok(Self::with_client(client, subject))
+        }
+
+        pub fn with_client(client: async_nats::Client, subject: &str) -> Self {
+            Self { context: async_nats::jetstream::new(client), subject: subject.to_owned() }
+        }
+    }
+
+    #[async_trait]
+    impl EventSink for NatsSink {
+        async fn publish(&self, event: &MonitorEvent) -> crate::Result<()> {
+            let ack = self
+                .context
+                .publish(self.subject.clone(), event.to_json_bytes()?.into())
+                .await
+                .map_err(Error::sink_failed)?;
+            ack.await.map_err(Error::sink_failed)?;
+            Ok(())
+        }
+    }
+}
+
+#[cfg(feature = "kafka")]
+pub mod kafka {
+    use std::time::Duration;
+
+    use rdkafka::ClientConfig;
+    use rdkafka::producer::FutureProducer;
+    use rdkafka::producer::FutureRecord;
+
+    use super::*;
+
+    /// Publishes events to Kafka topic keyed by message hash. Publishing waits
+    /// for the delivery report.
+    pub struct KafkaSink {
+        producer: FutureProducer,
+        topic: String,
+        queue_timeout: Duration,
+    }
+
+    impl KafkaSink {
+        pub fn new(brokers: &str, topic: &str, queue_timeout: Duration) -> crate::Result<Self> {
+            let producer = ClientConfig::new()
+                .set("bootstrap.servers", brokers)
+                .set("enable.idempotence", "true")
+                .set("acks", "all")
+                .create()
+                .map_err(Error::sink_failed)?;
+            Ok(Self::with_producer(producer, topic, queue_timeout))
+        }
+
+        pub fn with_producer(
+            producer: FutureProducer,
+            topic: &str,
+            queue_timeout: Duration,
+        ) -> Self {
+            Self { producer, topic: topic.to_owned(), queue_timeout }
+        }
+    }
+
+    #[async_trait]
+    impl EventSink for KafkaSink {
+        async fn publish(&self, event: &MonitorEvent) -> crate::Result<()> {
+            let payload = event.to_json_bytes()?;
+            let record = FutureRecord::to(&self.topic).key(&event.key).payload(&payload);
+            self.producer
+                .send(record, self.queue_timeout)
+                .await
+                .map_err(|(err, _)| Error::sink_failed(err))?;
+            Ok(())
+        }
+    }
+}
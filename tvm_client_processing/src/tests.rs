@@ -1,20 +1,38 @@
+use std::io::Write;
+use std::path::PathBuf;
 use std::sync::Arc;
+use std::sync::Mutex;
 use std::sync::RwLock;
 use std::time::Duration;
 
+use futures::StreamExt;
 use tokio::time::sleep;
 use tvm_block::MsgAddrStd;
 use tvm_types::AccountId;
 use tvm_types::UInt256;
 
 use crate::MonitoredMessage;
+use crate::batch_send::BatchMessage;
+use crate::batch_send::BatchSendPolicy;
+use crate::batch_send::MessagePriority;
+use crate::batch_send::MessageSender;
+use crate::batch_send::SendOutcome;
+use crate::batch_send::send_batch;
 use crate::message_monitor::MessageMonitor;
 use crate::message_monitor::MessageMonitoringParams;
 use crate::message_monitor::MessageMonitoringResult;
 use crate::message_monitor::MessageMonitoringStatus;
 use crate::message_monitor::MessageMonitoringTransaction;
 use crate::message_monitor::MonitorFetchWaitMode;
+use crate::rebroadcast::RebroadcastPolicy;
+use crate::rebroadcast::RebroadcastSchedule;
 use crate::sdk_services::MockSdkServices;
+use crate::send_error::SendError;
+use crate::sink::EventQueue;
+use crate::sink::EventSink;
+use crate::sink::MonitorEvent;
+use crate::sink::MonitorEventKind;
+use crate::sink::publish_monitoring_results;
 
 #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
 async fn test_fetch() {
@@ -204,6 +222,288 @@ async fn test_buffering() {
     );
 }
 
+struct FailingSink {
+    fail_on: usize,
+    published: RwLock<Vec<MonitorEvent>>,
+}
+
+#[async_trait]
+impl EventSink for FailingSink {
+    async fn publish(&self, event: &MonitorEvent) -> crate::Result<()> {
+        let mut published = self.published.write().unwrap();
+        if published.len() == self.fail_on {
+            return Err(crate::Error::sink_failed("unavailable"));
+        }
+        published.push(event.clone());
+        Ok(())
+    }
+}
+
+fn journal_path(name: &str) -> PathBuf {
+    let path =
+        std::env::temp_dir().join(format!("tvm-events-{}-{}.jsonl", name, std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    path
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_publish_monitoring_results() {
+    let path = journal_path("publish");
+    let mut queue = EventQueue::open(&path).unwrap();
+    let sink = FailingSink { fail_on: 2, published: RwLock::new(vec![]) };
+    let mut aborted = msg_res(4, MessageMonitoringStatus::Finalized);
+    aborted.transaction.as_mut().unwrap().aborted = true;
+    let mut rejected = msg_res(5, MessageMonitoringStatus::Timeout);
+    rejected.error = Some("exit code 60".to_owned());
+    let results = vec![
+        msg_res(1, MessageMonitoringStatus::Finalized),
+        msg_res(2, MessageMonitoringStatus::Reserved),
+        msg_res(3, MessageMonitoringStatus::Timeout),
+        aborted,
+        rejected,
+    ];
+    publish_monitoring_results(&sink, &mut queue, &results, 1000).await.unwrap_err();
+    {
+        let published = sink.published.read().unwrap();
+        assert_eq!(published.len(), 2);
+        assert_eq!(published[0].event, MonitorEventKind::Finalization);
+        assert_eq!(published[0].key, hash(1));
+        assert_eq!(published[1].event, MonitorEventKind::Expiration);
+        assert_eq!(published[1].key, hash(3));
+    }
+    drop(queue);
+
+    // not acknowledged events are delivered after restart
+    let mut queue = EventQueue::open(&path).unwrap();
+    let pending = queue.pending().map(|event| (event.event.clone(), event.key.clone()));
+    assert_eq!(
+        pending.collect::<Vec<_>>(),
+        vec![(MonitorEventKind::Failure, hash(4)), (MonitorEventKind::Rejection, hash(5))]
+    );
+    let sink = FailingSink { fail_on: usize::MAX, published: RwLock::new(vec![]) };
+    assert_eq!(queue.deliver(&sink).await.unwrap(), 2);
+    drop(queue);
+    assert_eq!(EventQueue::open(&path).unwrap().pending().count(), 0);
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_event_queue_drops_incomplete_record() {
+    let path = journal_path("torn");
+    let mut queue = EventQueue::open(&path).unwrap();
+    for n in 1..=2 {
+        let result = msg_res(n, MessageMonitoringStatus::Finalized);
+        queue.push(MonitorEvent::from_monitoring_result(&result, 1000).unwrap().unwrap()).unwrap();
+    }
+    drop(queue);
+    let mut file = std::fs::OpenOptions::new().append(true).open(&path).unwrap();
+    file.write_all(br#"{"ack":{"seq":"#).unwrap();
+    drop(file);
+
+    let queue = EventQueue::open(&path).unwrap();
+    assert_eq!(
+        queue.pending().map(|event| event.key.clone()).collect::<Vec<_>>(),
+        [hash(1), hash(2)]
+    );
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[cfg(feature = "webhook")]
+#[test]
+fn test_webhook_config_debug_redacts_secret() {
+    let config = crate::webhook::WebhookConfig {
+        url: "https://example.com/hook".to_owned(),
+        secret: "top-secret".to_owned(),
+        timeout_ms: Some(1000),
+    };
+    let debug = format!("{:?}", config);
+    assert!(debug.contains("https://example.com/hook"));
+    assert!(!debug.contains("top-secret"));
+}
+
+/// Fails the first `failures` attempts of every message with `error`, sees
+/// the transaction of the message after `processed_after` attempts. Time is
+/// virtual and advances only on sleep, which yields to other tasks.
+struct FlakySender {
+    failures: usize,
+    error: SendError,
+    processed_after: usize,
+    attempts: Mutex<Vec<String>>,
+    now_ms: Mutex<u64>,
+}
+
+#[async_trait]
+impl MessageSender for FlakySender {
+    async fn send(&self, boc: &str) -> Result<(), SendError> {
+        let mut attempts = self.attempts.lock().unwrap();
+        attempts.push(boc.to_string());
+        if attempts.iter().filter(|x| *x == boc).count() <= self.failures {
+            return Err(self.error.clone());
+        }
+        Ok(())
+    }
+
+    async fn is_processed(&self, boc: &str) -> bool {
+        self.attempts(boc) >= self.processed_after
+    }
+
+    async fn sleep(&self, ms: u64) {
+        *self.now_ms.lock().unwrap() += ms;
+        tokio::task::yield_now().await;
+    }
+
+    fn now_ms(&self) -> u64 {
+        *self.now_ms.lock().unwrap()
+    }
+}
+
+impl FlakySender {
+    fn attempts(&self, boc: &str) -> usize {
+        self.attempts.lock().unwrap().iter().filter(|x| *x == boc).count()
+    }
+}
+
+fn flaky_sender(failures: usize, error: SendError) -> FlakySender {
+    FlakySender {
+        failures,
+        error,
+        processed_after: usize::MAX,
+        attempts: Mutex::new(vec![]),
+        now_ms: Mutex::new(0),
+    }
+}
+
+fn batch_msg(boc: &str, wait_until: u32) -> BatchMessage {
+    BatchMessage {
+        boc: boc.to_string(),
+        wait_until,
+        user_data: Some(boc.into()),
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn test_send_batch() {
+    let sender = flaky_sender(1, SendError::LimitExceeded);
+    let policy = BatchSendPolicy { retries: 1, retry_delay_ms: 1000, ..Default::default() };
+    let messages = vec![batch_msg("a", 10), batch_msg("b", 2), batch_msg("c", 0)];
+    let mut results = send_batch(&sender, messages, 2, &policy).collect::<Vec<_>>().await;
+    results.sort_by_key(|x| x.index);
+
+    assert_eq!(results[0].outcome, SendOutcome::Sent { attempts: 2 });
+    assert_eq!(results[0].user_data, Some("a".into()));
+    assert_eq!(results[1].outcome, SendOutcome::Expired { attempts: 1 });
+    assert_eq!(results[2].outcome, SendOutcome::Expired { attempts: 0 });
+
+    let sender = flaky_sender(5, SendError::LimitExceeded);
+    let results =
+        send_batch(&sender, vec![batch_msg("a", 10)], 1, &policy).collect::<Vec<_>>().await;
+    assert_eq!(
+        results[0].outcome,
+        SendOutcome::Rejected { attempts: 2, error: SendError::LimitExceeded }
+    );
+
+    let sender = flaky_sender(5, SendError::AccountNotFound);
+    let results =
+        send_batch(&sender, vec![batch_msg("a", 10)], 1, &policy).collect::<Vec<_>>().await;
+    assert_eq!(
+        results[0].outcome,
+        SendOutcome::Rejected { attempts: 1, error: SendError::AccountNotFound }
+    );
+
+    let sender = flaky_sender(5, SendError::AlreadyExists);
+    let results =
+        send_batch(&sender, vec![batch_msg("a", 10)], 1, &policy).collect::<Vec<_>>().await;
+    assert_eq!(results[0].outcome, SendOutcome::Sent { attempts: 1 });
+}
+
+#[tokio::test]
+async fn test_rebroadcast_schedule() {
+    let mut schedule = RebroadcastSchedule::new(RebroadcastPolicy::default());
+    let high = BatchMessage { priority: MessagePriority::High, ..batch_msg("a", 10) };
+    let low = BatchMessage { priority: MessagePriority::Low, ..batch_msg("b", 10) };
+    assert!(schedule.schedule("a".to_string(), &high, 0));
+    assert!(schedule.schedule("c".to_string(), &batch_msg("c", 10), 0));
+    assert!(!schedule.schedule("b".to_string(), &low, 0));
+    assert_eq!(schedule.next_send_ms(), Some(2000));
+
+    assert_eq!(schedule.take_due(1999), vec![]);
+    assert_eq!(schedule.take_due(2000), vec![("a".to_string(), "a".to_string())]);
+    let mut due = schedule.take_due(5000);
+    due.sort();
+    assert_eq!(due, vec![("a".to_string(), "a".to_string()), ("c".to_string(), "c".to_string())]);
+
+    assert!(schedule.cancel("c"));
+    assert_eq!(schedule.take_due(10000), vec![]);
+    assert!(schedule.is_empty(), "expired messages should be removed");
+
+    let sender = flaky_sender(5, SendError::AccountNotFound);
+    schedule.schedule("a".to_string(), &high, 0);
+    *sender.now_ms.lock().unwrap() = 2000;
+    let rejected = schedule.rebroadcast_due(&sender).await;
+    assert_eq!(rejected, vec![("a".to_string(), SendError::AccountNotFound)]);
+    assert!(schedule.is_empty());
+}
+
+#[tokio::test]
+async fn test_send_batch_rebroadcast() {
+    let policy =
+        BatchSendPolicy { rebroadcast: Some(RebroadcastPolicy::default()), ..Default::default() };
+    let high = BatchMessage { priority: MessagePriority::High, ..batch_msg("a", 10) };
+    let low = BatchMessage { priority: MessagePriority::Low, ..batch_msg("b", 10) };
+
+    // resent every 2 s until it expires at 10 s, low priority is sent once
+    let sender = flaky_sender(0, SendError::LimitExceeded);
+    let results =
+        send_batch(&sender, vec![high.clone(), low], 2, &policy).collect::<Vec<_>>().await;
+    assert!(results.iter().all(|x| x.outcome == SendOutcome::Sent { attempts: 1 }));
+    assert_eq!(sender.attempts("a"), 5);
+    assert_eq!(sender.attempts("b"), 1);
+    assert_eq!(sender.now_ms(), 10000);
+
+    // stops once the transaction is seen
+    let sender = FlakySender { processed_after: 3, ..flaky_sender(0, SendError::LimitExceeded) };
+    send_batch(&sender, vec![high], 1, &policy).collect::<Vec<_>>().await;
+    assert_eq!(sender.attempts("a"), 3);
+    assert_eq!(sender.now_ms(), 6000);
+}
+
+#[test]
+fn test_send_error_mapping() {
+    assert_eq!(SendError::from_node_error("WRONG_PRODUCER", ""), SendError::WrongProducer);
+    assert_eq!(
+        SendError::from_node_error("", "Message already exists in the pool"),
+        SendError::AlreadyExists
+    );
+    assert_eq!(
+        SendError::from_node_error("ERROR", "Account not found: 0:00"),
+        SendError::AccountNotFound
+    );
+    assert!(SendError::from_node_error("", "Rate limit exceeded").is_retriable());
+    assert!(!SendError::from_node_error("OTHER", "Invalid message").is_retriable());
+
+    let mut error = crate::Error::with_code_message(1, "Send message failed".to_string());
+    assert!(matches!(SendError::from_error(error.clone()), SendError::Transport { .. }));
+    error.data["node_error"]["extensions"]["code"] = "TOKEN_EXPIRED".into();
+    let error = SendError::from_error(error);
+    assert!(error.is_redirect() && error.is_retriable());
+}
+
+#[tokio::test]
+async fn test_send_batch_rate_limit() {
+    let sender = flaky_sender(0, SendError::LimitExceeded);
+    let policy = BatchSendPolicy {
+        retries: 0,
+        retry_delay_ms: 0,
+        max_per_second: Some(4),
+        rebroadcast: None,
+    };
+    let messages = (0..8).map(|i| batch_msg(&i.to_string(), 100)).collect();
+    let results = send_batch(&sender, messages, 8, &policy).collect::<Vec<_>>().await;
+    assert!(results.iter().all(|x| x.outcome == SendOutcome::Sent { attempts: 1 }));
+    assert!(sender.now_ms() >= 1750);
+}
+
 fn hash(n: usize) -> String {
     UInt256::from_be_bytes(&n.to_be_bytes()).as_hex_string()
 }
@@ -0,0 +1,99 @@
+// Copyright 2018-2021 TON Labs LTD.
+//
+// Licensed under the SOFTWARE EVALUATION License (the "License"); you may not
+// use this file except in compliance with the License.
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific TON DEV software governing permissions and
+// limitations under the License.
+
+use std::fmt;
+use std::time::Duration;
+
+use hmac::Hmac;
+use hmac::Mac;
+use sha2::Sha256;
+
+use crate::error::Error;
+use crate::sink::EventSink;
+use crate::sink::MonitorEvent;
+
+/// HTTP header carrying hex encoded HMAC-SHA256 of the request body
+pub const SIGNATURE_HEADER: &str = "X-Tvm-Signature";
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct WebhookConfig {
+    /// Endpoint receiving `POST` requests with JSON payloads.
+    pub url: String,
+    /// Secret used to sign payloads with HMAC-SHA256.
+    pub secret: String,
+    /// Request timeout in milliseconds.
+    pub timeout_ms: Option<u64>,
+}
+
+// The secret is redacted so the config can be logged
+impl fmt::Debug for WebhookConfig {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("WebhookConfig")
+            .field("url", &self.url)
+            .field("secret", &"<redacted>")
+            .field("timeout_ms", &self.timeout_ms)
+            .finish()
+    }
+}
+
+/// Delivers monitoring events to external HTTP endpoint as signed JSON.
+pub struct WebhookSink {
+    config: WebhookConfig,
+    client: reqwest::Client,
+}
+
+impl WebhookSink {
+    pub fn new(config: WebhookConfig) -> crate::Result<Self> {
+        let mut builder = reqwest::Client::builder();
+        if let Some(timeout_ms) = config.timeout_ms {
+            builder = builder.timeout(Duration::from_millis(timeout_ms));
+        }
+        let client = builder.build().map_err(Error::sink_failed)?;
+        Ok(Self { config, client })
+    }
+
+    /// Calculates hex encoded HMAC-SHA256 signature of the payload
+    pub fn sign(&self, payload: &[u8]) -> String {
+        sign_payload(self.config.secret.as_bytes(), payload)
+    }
+}
+
+#[async_trait]
+impl EventSink for WebhookSink {
+    async fn publish(&self, event: &MonitorEvent) -> crate::Result<()> {
+        let body = event.to_json_bytes()?;
+        let response = self
+            .client
+            .post(&self.config.url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .header(SIGNATURE_HEADER, self.sign(&body))
+            .body(body)
+            .send()
+            .await
+            .map_err(Error::sink_failed)?;
+        if !response.status().is_success() {
+            return Err(Error::sink_failed(format!(
+                "endpoint responded with status {}",
+                response.status()
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Calculates hex encoded HMAC-SHA256 signature of the payload. Receivers use
+/// it to verify the `X-Tvm-Signature` header.
+pub fn sign_payload(secret: &[u8], payload: &[u8]) -> String {
+    // HMAC accepts keys of any length, so `new_from_slice` can't fail here
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret).unwrap();
+    mac.update(payload);
+    hex::encode(mac.finalize().into_bytes())
+}
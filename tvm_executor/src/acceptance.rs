@@ -0,0 +1,139 @@
+// Copyright (C) 2019-2023 EverX. All Rights Reserved.
+//
+// Licensed under the SOFTWARE EVALUATION License (the "License"); you may not
+// use this file except in compliance with the License.
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific TON DEV software governing permissions and
+// limitations under the License.
+
+use std::cmp::min;
+
+use tvm_block::Account;
+use tvm_block::Deserializable;
+use tvm_block::Message;
+use tvm_block::Serializable;
+use tvm_types::HashmapType;
+use tvm_types::Result;
+use tvm_types::SliceData;
+use tvm_types::fail;
+use tvm_vm::boolean;
+use tvm_vm::error::tvm_exception_or_custom_code;
+use tvm_vm::executor::gas::gas_state::Gas;
+use tvm_vm::int;
+use tvm_vm::smart_contract_info::SmartContractInfo;
+use tvm_vm::stack::Stack;
+use tvm_vm::stack::StackItem;
+use tvm_vm::stack::integer::IntegerData;
+
+use crate::ExecuteParams;
+use crate::blockchain_config::BlockchainConfig;
+use crate::vmsetup::VMSetup;
+use crate::vmsetup::VMSetupContext;
+
+/// Result of the external message acceptance check
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct AcceptanceCheck {
+    /// Contract executed ACCEPT (or SETGASLIMIT) within the gas credit
+    pub accepted: bool,
+    pub exit_code: i32,
+    pub gas_used: i64,
+}
+
+/// Checks whether the account would accept the external inbound message.
+/// Unlike the full transaction, the contract runs with the gas credit only:
+/// execution after ACCEPT is limited by the credit too, so the check is cheap
+/// and can be used to filter messages before simulation or broadcast.
+pub fn will_accept(
+    account_boc: &[u8],
+    message: &Message,
+    config: &BlockchainConfig,
+    params: &ExecuteParams,
+) -> Result<AcceptanceCheck> {
+    if !message.is_inbound_external() {
+        fail!("Acceptance can be checked only for external inbound messages")
+    }
+    let account = Account::construct_from_bytes(account_boc)?;
+    let Some(address) = message.dst_ref().or_else(|| account.get_addr()) else {
+        fail!("Message has no destination address")
+    };
+
+    // uninit account may be deployed by the message
+    let state_init = message.state_init();
+    let code = account.get_code().or_else(|| state_init.and_then(|init| init.code.clone()));
+    let data = account.get_data().or_else(|| state_init.and_then(|init| init.data.clone()));
+    let Some(code) = code else {
+        return Ok(AcceptanceCheck { accepted: false, exit_code: -13, gas_used: 0 });
+    };
+
+    let balance = account.balance().cloned().unwrap_or_default();
+    let gas_config = config.get_gas_config(address.is_masterchain());
+    let gas_credit = min(
+        (1 << (3 * 8)) - 1, // because gas_credit is stored as VarUInteger3
+        min(gas_config.gas_credit, gas_config.calc_gas(balance.grams.as_u128())),
+    ) as i64;
+    if gas_credit == 0 {
+        return Ok(AcceptanceCheck { accepted: false, exit_code: 0, gas_used: 0 });
+    }
+    // gas_limit_max equal to credit bounds execution after ACCEPT
+    let gas = Gas::new(0, gas_credit, gas_credit, gas_config.get_real_gas_price() as i64);
+
+    let smc_info = SmartContractInfo {
+        capabilities: config.raw_config().capabilities(),
+        myself: SliceData::load_builder(address.write_to_new_cell()?)?,
+        block_lt: params.block_lt,
+        unix_time: params.block_unixtime,
+        seq_no: params.seq_no,
+        balance: balance.clone(),
+        config_params: config.raw_config().config_params.data().cloned(),
+        ..Default::default()
+    };
+    let mut stack = Stack::new();
+    stack
+        .push(int!(balance.grams.as_u128()))
+        .push(int!(0))
+        .push(StackItem::Cell(message.serialize()?))
+        .push(StackItem::Slice(message.body().unwrap_or_default()))
+        .push(boolean!(true));
+
+    let mut libs = vec![];
+    if let Some(state_init) = state_init {
+        libs.push(state_init.libraries().inner());
+    }
+    libs.push(account.libraries().inner());
+    libs.push(params.state_libs.clone());
+
+    let mut vm = VMSetup::with_context(
+        SliceData::load_cell(code)?,
+        VMSetupContext {
+            capabilities: config.capabilites(),
+            block_version: params.block_version,
+            #[cfg(feature = "signature_with_id")]
+            signature_id: params.signature_id,
+        },
+    )
+    .set_smart_contract_info(smc_info)?
+    .set_stack(stack)
+    .set_data(data.unwrap_or_default())?
+    .set_libraries(libs)
+    .set_gas(gas)
+    .set_debug(params.debug)
+    .create();
+
+    let exit_code = match vm.execute() {
+        Ok(exit_code) => exit_code,
+        Err(err) => tvm_exception_or_custom_code(&err),
+    };
+    let gas = vm.get_gas();
+    Ok(AcceptanceCheck {
+        accepted: gas.get_gas_credit() == 0,
+        exit_code,
+        gas_used: gas.get_gas_used(),
+    })
+}
+
+#[cfg(test)]
+#[path = "tests/test_acceptance.rs"]
+mod tests;
@@ -11,12 +11,18 @@
 
 #![cfg_attr(feature = "ci_run", deny(warnings))]
 
+pub mod acceptance;
+pub use acceptance::*;
+
 pub mod transaction_executor;
 pub use transaction_executor::*;
 
 pub mod ordinary_transaction;
 pub use ordinary_transaction::OrdinaryTransactionExecutor;
 
+pub mod storage_transaction;
+pub use storage_transaction::StorageTransactionExecutor;
+
 pub mod tick_tock_transaction;
 pub use tick_tock_transaction::TickTockTransactionExecutor;
 
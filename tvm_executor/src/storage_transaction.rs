@@ -0,0 +1,107 @@
+// Copyright (C) 2019-2022 TON Labs. All Rights Reserved.
+//
+// Licensed under the SOFTWARE EVALUATION License (the "License"); you may not
+// use this file except in compliance with the License.
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific TON DEV software governing permissions and
+// limitations under the License.
+
+use std::sync::atomic::Ordering;
+
+use tvm_block::Account;
+use tvm_block::MASTERCHAIN_ID;
+use tvm_block::Message;
+use tvm_block::Transaction;
+use tvm_block::TransactionDescr;
+use tvm_types::Result;
+use tvm_types::error;
+use tvm_types::fail;
+use tvm_vm::stack::Stack;
+
+use crate::ExecuteParams;
+use crate::TransactionExecutor;
+use crate::blockchain_config::BlockchainConfig;
+use crate::error::ExecutorError;
+
+/// Executor of storage transactions, which only collect storage fees of the
+/// account without running its code
+pub struct StorageTransactionExecutor {
+    pub config: BlockchainConfig,
+}
+
+impl StorageTransactionExecutor {
+    pub fn new(config: BlockchainConfig) -> Self {
+        Self { config }
+    }
+}
+
+impl TransactionExecutor for StorageTransactionExecutor {
+    /// Create end execute storage transaction for account
+    fn execute_with_params(
+        &self,
+        in_msg: Option<&Message>,
+        account: &mut Account,
+        params: ExecuteParams,
+        minted_shell: &mut u128,
+    ) -> Result<Transaction> {
+        if in_msg.is_some() {
+            fail!("Storage transaction must not have input message")
+        }
+        let (account_id, account_address) = match (account.get_id(), account.get_addr()) {
+            (Some(account_id), Some(address)) => (account_id, address.clone()),
+            _ => fail!("Storage transaction requires existing account"),
+        };
+        log::debug!(target: "executor", "storage transation account {:x}", account_id);
+        let is_masterchain = account_address.workchain_id() == MASTERCHAIN_ID;
+        let is_special = self.config.is_special_account(&account_address)?;
+        let mut acc_balance = account.balance().cloned().unwrap_or_default();
+        let lt = std::cmp::max(
+            account.last_tr_time().unwrap_or_default(),
+            params.last_tr_lt.load(Ordering::Relaxed),
+        );
+        let mut tr = Transaction::with_address_and_status(account_id, account.status());
+        tr.set_logical_time(lt);
+        tr.set_now(params.block_unixtime);
+        let is_due = account.due_payment().is_some_and(|due| due.as_u128() != 0);
+        let storage = match self.storage_phase(
+            account,
+            &mut acc_balance,
+            &mut tr,
+            is_masterchain,
+            is_special,
+            params.available_credit,
+            minted_shell,
+            is_due,
+        ) {
+            Ok(storage_ph) => storage_ph,
+            Err(e) => fail!(ExecutorError::TrExecutorError(format!(
+                "cannot create storage phase of a new transaction for \
+                         smart contract for reason {}",
+                e
+            ))),
+        };
+        account.set_last_paid(if !is_special { params.block_unixtime } else { 0 });
+        tr.set_end_status(account.status());
+        account.set_balance(acc_balance);
+        params.last_tr_lt.store(lt, Ordering::Relaxed);
+        let lt = self.add_messages(&mut tr, vec![], params.last_tr_lt)?;
+        account.set_last_tr_time(lt);
+        tr.write_description(&TransactionDescr::Storage(storage))?;
+        Ok(tr)
+    }
+
+    fn ordinary_transaction(&self) -> bool {
+        false
+    }
+
+    fn config(&self) -> &BlockchainConfig {
+        &self.config
+    }
+
+    fn build_stack(&self, _in_msg: Option<&Message>, _account: &Account) -> Stack {
+        Stack::new()
+    }
+}
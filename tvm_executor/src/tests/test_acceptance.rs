@@ -0,0 +1,105 @@
+// Copyright (C) 2019-2023 EverX. All Rights Reserved.
+//
+// Licensed under the SOFTWARE EVALUATION License (the "License"); you may not
+// use this file except in compliance with the License.
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific TON DEV software governing permissions and
+// limitations under the License.
+
+use tvm_block::ConfigParams;
+use tvm_block::CurrencyCollection;
+use tvm_block::ExternalInboundMessageHeader;
+use tvm_block::MsgAddressInt;
+use tvm_block::StateInit;
+use tvm_types::BuilderData;
+use tvm_types::Cell;
+
+use super::*;
+
+const ACCEPT: [u8; 2] = [0xF8, 0x00];
+const THROW_60: [u8; 2] = [0xF2, 0x3C];
+const BALANCE: u64 = 1_000_000_000;
+
+fn config() -> BlockchainConfig {
+    let config = ConfigParams::construct_from_bytes(include_bytes!("data/config.boc")).unwrap();
+    BlockchainConfig::with_config(config).unwrap()
+}
+
+fn code(opcodes: &[u8]) -> Cell {
+    BuilderData::with_raw(opcodes.to_vec(), opcodes.len() * 8).unwrap().into_cell().unwrap()
+}
+
+fn address() -> MsgAddressInt {
+    MsgAddressInt::with_standart(None, 0, [0x11; 32].into()).unwrap()
+}
+
+fn message(state_init: Option<StateInit>) -> Message {
+    let mut message = Message::with_ext_in_header(ExternalInboundMessageHeader {
+        dst: address(),
+        ..Default::default()
+    });
+    if let Some(state_init) = state_init {
+        message.set_state_init(state_init);
+    }
+    message
+}
+
+fn state_init(opcodes: &[u8]) -> StateInit {
+    StateInit { code: Some(code(opcodes)), data: Some(Cell::default()), ..Default::default() }
+}
+
+fn active_account(opcodes: &[u8]) -> Vec<u8> {
+    Account::active_by_init_code_hash(
+        address(),
+        CurrencyCollection::with_grams(BALANCE),
+        0,
+        state_init(opcodes),
+        false,
+    )
+    .unwrap()
+    .write_to_bytes()
+    .unwrap()
+}
+
+#[test]
+fn test_contract_accepting() {
+    let check =
+        will_accept(&active_account(&ACCEPT), &message(None), &config(), &ExecuteParams::default())
+            .unwrap();
+    assert!(check.accepted);
+    assert_eq!(check.exit_code, 0);
+    assert!(check.gas_used > 0);
+}
+
+#[test]
+fn test_contract_throwing_before_accept() {
+    let opcodes = [THROW_60, ACCEPT].concat();
+    let check = will_accept(
+        &active_account(&opcodes),
+        &message(None),
+        &config(),
+        &ExecuteParams::default(),
+    )
+    .unwrap();
+    assert!(!check.accepted);
+    assert_eq!(check.exit_code, 60);
+}
+
+#[test]
+fn test_uninit_account_deployed_by_message() {
+    let account = Account::uninit(address(), 0, 0, CurrencyCollection::with_grams(BALANCE));
+    let account = account.write_to_bytes().unwrap();
+    let config = config();
+    let params = ExecuteParams::default();
+
+    let check = will_accept(&account, &message(Some(state_init(&ACCEPT))), &config, &params);
+    assert!(check.unwrap().accepted);
+
+    // without state init there is no code to run
+    let check = will_accept(&account, &message(None), &config, &params).unwrap();
+    assert!(!check.accepted);
+    assert_eq!(check.exit_code, -13);
+}
@@ -0,0 +1,42 @@
+use std::hint::black_box;
+use std::io::Cursor;
+
+use criterion::Criterion;
+use criterion::criterion_group;
+use criterion::criterion_main;
+use tvm_block::MsgAddressExt;
+use tvm_sdk::Contract;
+use tvm_sdk::ContractImage;
+
+const WALLET_TVC: &[u8] = include_bytes!("../../tvm_client/src/tests/contracts/abi_v2/Wallet.tvc");
+
+fn bench_deploy_message(c: &mut Criterion) {
+    let image = ContractImage::from_state_init(&mut Cursor::new(WALLET_TVC)).unwrap();
+    let mut g = c.benchmark_group("deploy-message");
+    g.bench_function("cloned-image", |b| {
+        b.iter(|| {
+            let message = Contract::construct_deploy_message_no_constructor(
+                image.clone(),
+                0,
+                MsgAddressExt::default(),
+            )
+            .unwrap();
+            black_box(Contract::serialize_message(&message).unwrap());
+        })
+    });
+    g.bench_function("borrowed-image", |b| {
+        b.iter(|| {
+            let message = Contract::construct_deploy_message_no_constructor_ref(
+                &image,
+                0,
+                MsgAddressExt::default(),
+            )
+            .unwrap();
+            black_box(Contract::serialize_message(&message).unwrap());
+        })
+    });
+    g.finish();
+}
+
+criterion_group!(benches, bench_deploy_message);
+criterion_main!(benches);
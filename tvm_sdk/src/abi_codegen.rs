@@ -0,0 +1,273 @@
+// Copyright 2018-2021 TON Labs LTD.
+//
+// Licensed under the SOFTWARE EVALUATION License (the "License"); you may not
+// use this file except in compliance with the License.
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific TON DEV software governing permissions and
+// limitations under the License.
+
+//! Generation of typed Rust bindings from contract ABI JSON.
+//!
+//! For every function the generated code has `<Name>Input` and
+//! `<Name>Output` structs and a `<name>` wrapper building `FunctionCallSet`
+//! from typed input, for every event a `<Name>Event` struct and a variant of
+//! the `Event` enum. Structs mirror JSON accepted and produced by `json_abi`,
+//! so outputs and events decoded with it can be read with
+//! `serde_json::from_str`. Integers, addresses, cells and bytes are kept as
+//! strings in the same format `json_abi` uses.
+//!
+//! The generated code uses `serde` (with `derive`), `serde_json` and
+//! `tvm_sdk`. It is intended to be produced in a build script:
+//!
+//! ```ignore
+//! tvm_sdk::abi_codegen::generate_file("Wallet.abi.json", out_dir.join("wallet.rs"))?;
+//! // in the crate:
+//! mod wallet { include!(concat!(env!("OUT_DIR"), "/wallet.rs")); }
+//! ```
+
+use std::fmt::Write;
+use std::path::Path;
+
+use tvm_abi::Param;
+use tvm_abi::ParamType;
+use tvm_types::Result;
+use tvm_types::error;
+
+use crate::AbiContract;
+
+const DERIVE: &str =
+    "#[derive(Clone, Debug, Default, PartialEq, serde::Serialize, serde::Deserialize)]";
+
+const KEYWORDS: &[&str] = &[
+    "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum", "extern",
+    "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub",
+    "ref", "return", "self", "Self", "static", "struct", "super", "trait", "true", "type",
+    "unsafe", "use", "where", "while", "abstract", "become", "box", "do", "final", "gen", "macro",
+    "override", "priv", "try", "typeof", "unsized", "virtual", "yield",
+];
+
+/// Generates bindings for the ABI given as JSON
+pub fn generate(abi: &str) -> Result<String> {
+    let contract = AbiContract::load(abi.as_bytes())?;
+    let mut generator = Generator { out: String::new() };
+    generator.header(abi)?;
+
+    let mut functions = contract.functions().values().collect::<Vec<_>>();
+    functions.sort_by(|a, b| a.name.cmp(&b.name));
+    generator.params_struct("Header", contract.header(), true)?;
+    for function in functions {
+        let name = pascal_case(&function.name);
+        generator.params_struct(&format!("{}Input", name), &function.inputs, false)?;
+        generator.params_struct(&format!("{}Output", name), &function.outputs, false)?;
+        generator.call_wrapper(&function.name, &name)?;
+    }
+
+    let mut events = contract.events().values().collect::<Vec<_>>();
+    events.sort_by(|a, b| a.name.cmp(&b.name));
+    for event in &events {
+        generator.params_struct(
+            &format!("{}Event", pascal_case(&event.name)),
+            &event.inputs,
+            false,
+        )?;
+    }
+    let events = events.iter().map(|event| event.name.as_str()).collect::<Vec<_>>();
+    generator.events_enum(&events)?;
+    Ok(generator.out)
+}
+
+/// Generates bindings for the ABI file and writes them to `out_path`, for use
+/// in build scripts
+pub fn generate_file(abi_path: impl AsRef<Path>, out_path: impl AsRef<Path>) -> Result<()> {
+    let abi_path = abi_path.as_ref();
+    let abi = std::fs::read_to_string(abi_path)
+        .map_err(|e| error!("Can't read ABI from {}: {}", abi_path.display(), e))?;
+    let code = generate(&abi)?;
+    let out_path = out_path.as_ref();
+    std::fs::write(out_path, code)
+        .map_err(|e| error!("Can't write bindings to {}: {}", out_path.display(), e))?;
+    Ok(())
+}
+
+struct Generator {
+    out: String,
+}
+
+impl Generator {
+    fn header(&mut self, abi: &str) -> Result<()> {
+        let mut hashes = "#".to_string();
+        while abi.contains(&format!("\"{}", hashes)) {
+            hashes.push('#');
+        }
+        writeln!(self.out, "// Generated by tvm_sdk::abi_codegen, do not edit.")?;
+        writeln!(self.out)?;
+        writeln!(self.out, "pub const ABI: &str = r{0}\"{1}\"{0};", hashes, abi)?;
+        Ok(())
+    }
+
+    /// Writes struct with fields for the params, and before it structs of
+    /// the nested tuples
+    fn params_struct(&mut self, name: &str, params: &[Param], optional: bool) -> Result<()> {
+        let mut code = String::new();
+        writeln!(code)?;
+        writeln!(code, "{}", DERIVE)?;
+        if params.is_empty() {
+            writeln!(code, "pub struct {} {{}}", name)?;
+            self.out.push_str(&code);
+            return Ok(());
+        }
+        writeln!(code, "pub struct {} {{", name)?;
+        for param in params {
+            let field = snake_case(&param.name);
+            let mut kind =
+                self.rust_type(&param.kind, &format!("{}{}", name, pascal_case(&param.name)))?;
+            if field != param.name {
+                writeln!(code, "    #[serde(rename = \"{}\")]", param.name)?;
+            }
+            if optional {
+                writeln!(code, "    #[serde(default, skip_serializing_if = \"Option::is_none\")]")?;
+                kind = format!("Option<{}>", kind);
+            }
+            writeln!(code, "    pub {}: {},", field, kind)?;
+        }
+        writeln!(code, "}}")?;
+        self.out.push_str(&code);
+        Ok(())
+    }
+
+    fn rust_type(&mut self, kind: &ParamType, tuple_name: &str) -> Result<String> {
+        Ok(match kind {
+            ParamType::Bool => "bool".to_string(),
+            ParamType::Tuple(params) => {
+                self.params_struct(tuple_name, params, false)?;
+                tuple_name.to_string()
+            }
+            ParamType::Array(item) | ParamType::FixedArray(item, _) => {
+                format!("Vec<{}>", self.rust_type(item, tuple_name)?)
+            }
+            ParamType::Map(_, value) => {
+                format!(
+                    "std::collections::BTreeMap<String, {}>",
+                    self.rust_type(value, tuple_name)?
+                )
+            }
+            ParamType::Optional(inner) => format!("Option<{}>", self.rust_type(inner, tuple_name)?),
+            ParamType::Ref(inner) => self.rust_type(inner, tuple_name)?,
+            ParamType::Uint(_)
+            | ParamType::Int(_)
+            | ParamType::VarUint(_)
+            | ParamType::VarInt(_)
+            | ParamType::Cell
+            | ParamType::Address
+            | ParamType::Bytes
+            | ParamType::FixedBytes(_)
+            | ParamType::String
+            | ParamType::Token
+            | ParamType::Time
+            | ParamType::Expire
+            | ParamType::PublicKey => "String".to_string(),
+        })
+    }
+
+    fn call_wrapper(&mut self, function: &str, name: &str) -> Result<()> {
+        writeln!(self.out)?;
+        writeln!(self.out, "/// Call set of `{}` function", function)?;
+        writeln!(
+            self.out,
+            "pub fn {}(input: &{}Input, header: Option<&Header>) -> serde_json::Result<tvm_sdk::FunctionCallSet> {{",
+            snake_case(function),
+            name
+        )?;
+        writeln!(self.out, "    Ok(tvm_sdk::FunctionCallSet {{")?;
+        writeln!(self.out, "        func: \"{}\".to_string(),", function)?;
+        writeln!(self.out, "        header: header.map(serde_json::to_string).transpose()?,")?;
+        writeln!(self.out, "        input: serde_json::to_string(input)?,")?;
+        writeln!(self.out, "        abi: ABI.to_string(),")?;
+        writeln!(self.out, "    }})")?;
+        writeln!(self.out, "}}")?;
+        Ok(())
+    }
+
+    fn events_enum(&mut self, events: &[&str]) -> Result<()> {
+        if events.is_empty() {
+            return Ok(());
+        }
+        writeln!(self.out)?;
+        writeln!(self.out, "#[derive(Clone, Debug, PartialEq)]")?;
+        writeln!(self.out, "pub enum Event {{")?;
+        for event in events {
+            let name = pascal_case(event);
+            writeln!(self.out, "    {}({}Event),", name, name)?;
+        }
+        writeln!(self.out, "}}")?;
+        writeln!(self.out)?;
+        writeln!(self.out, "impl Event {{")?;
+        writeln!(
+            self.out,
+            "    /// Reads event from its name and parameters decoded to JSON, `None` for unknown names"
+        )?;
+        writeln!(
+            self.out,
+            "    pub fn from_json(name: &str, params: &str) -> serde_json::Result<Option<Self>> {{"
+        )?;
+        writeln!(self.out, "        Ok(match name {{")?;
+        for event in events {
+            writeln!(
+                self.out,
+                "            \"{}\" => Some(Self::{}(serde_json::from_str(params)?)),",
+                event,
+                pascal_case(event)
+            )?;
+        }
+        writeln!(self.out, "            _ => None,")?;
+        writeln!(self.out, "        }})")?;
+        writeln!(self.out, "    }}")?;
+        writeln!(self.out, "}}")?;
+        Ok(())
+    }
+}
+
+fn snake_case(name: &str) -> String {
+    let mut result = String::with_capacity(name.len() + 4);
+    let mut prev_lower = false;
+    for c in name.chars() {
+        if c.is_ascii_uppercase() {
+            if prev_lower {
+                result.push('_');
+            }
+            result.push(c.to_ascii_lowercase());
+            prev_lower = false;
+        } else if c.is_ascii_alphanumeric() {
+            result.push(c);
+            prev_lower = true;
+        } else {
+            result.push('_');
+            prev_lower = false;
+        }
+    }
+    if result.is_empty() || result.starts_with(|c: char| c.is_ascii_digit()) {
+        result.insert(0, '_');
+    }
+    if KEYWORDS.contains(&result.as_str()) {
+        result.push('_');
+    }
+    result
+}
+
+fn pascal_case(name: &str) -> String {
+    let mut result = String::with_capacity(name.len());
+    for part in name.split(|c: char| !c.is_ascii_alphanumeric()) {
+        let mut chars = part.chars();
+        if let Some(first) = chars.next() {
+            result.push(first.to_ascii_uppercase());
+            result.extend(chars);
+        }
+    }
+    if result.is_empty() || result.starts_with(|c: char| c.is_ascii_digit()) {
+        result.insert(0, 'T');
+    }
+    result
+}
@@ -0,0 +1,166 @@
+// Copyright 2018-2021 TON Labs LTD.
+//
+// Licensed under the SOFTWARE EVALUATION License (the "License"); you may not
+// use this file except in compliance with the License.
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific TON DEV software governing permissions and
+// limitations under the License.
+
+use std::collections::BTreeMap;
+
+use tvm_abi::AbiCache;
+use tvm_block::Message as TvmMessage;
+use tvm_types::Result;
+use tvm_types::SliceData;
+
+use crate::AbiContract;
+use crate::AbiFunction;
+use crate::Contract;
+
+/// Report of how the ABI matches messages observed on-chain
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct AbiCoverage {
+    /// Number of messages checked
+    pub messages: u64,
+    /// Messages without body, e.g. plain transfers
+    pub without_body: u64,
+    /// Bounced internal messages, they are not checked against the ABI
+    pub bounced: u64,
+    /// Bodies too short to read the function id
+    pub malformed: u64,
+    /// Number of calls of each function, from inbound messages
+    pub calls: BTreeMap<String, u64>,
+    /// Number of responses of each function, from external outbound messages
+    pub responses: BTreeMap<String, u64>,
+    /// Number of emissions of each event
+    pub events: BTreeMap<String, u64>,
+    /// Number of bodies with the id of the function or event which failed to
+    /// decode, indicating changed parameters
+    pub decode_failures: BTreeMap<String, u64>,
+    /// Number of bodies with each id not present in the ABI, as `0x` prefixed
+    /// hex
+    pub unknown_ids: BTreeMap<String, u64>,
+    /// Functions of the ABI never called
+    pub not_called: Vec<String>,
+    /// Events of the ABI never emitted
+    pub not_emitted: Vec<String>,
+}
+
+impl AbiCoverage {
+    /// Checks messages of the contract against its ABI. Inbound messages are
+    /// decoded as function calls and external outbound ones as responses or
+    /// events.
+    pub fn collect<'a>(
+        abi: &str,
+        messages: impl IntoIterator<Item = &'a TvmMessage>,
+    ) -> Result<Self> {
+        let contract = AbiCache::global().load(abi)?;
+        let mut coverage = Self::default();
+        for message in messages {
+            coverage.add_message(&contract, message);
+        }
+
+        let mut not_called: Vec<String> = contract
+            .functions()
+            .keys()
+            .filter(|name| !coverage.calls.contains_key(*name))
+            .cloned()
+            .collect();
+        not_called.sort();
+        coverage.not_called = not_called;
+
+        let mut not_emitted: Vec<String> = contract
+            .events()
+            .keys()
+            .filter(|name| !coverage.events.contains_key(*name))
+            .cloned()
+            .collect();
+        not_emitted.sort();
+        coverage.not_emitted = not_emitted;
+
+        Ok(coverage)
+    }
+
+    /// Checks bodies of messages from the serialized messages
+    pub fn collect_from_bytes(abi: &str, messages: &[Vec<u8>]) -> Result<Self> {
+        let messages =
+            messages.iter().map(Contract::deserialize_message).collect::<Result<Vec<_>>>()?;
+        Self::collect(abi, &messages)
+    }
+
+    fn add_message(&mut self, contract: &AbiContract, message: &TvmMessage) {
+        self.messages += 1;
+        let Some(body) = message.body() else {
+            self.without_body += 1;
+            return;
+        };
+        if let Some(header) = message.int_header() {
+            if header.bounced {
+                self.bounced += 1;
+            } else {
+                self.add_call(contract, body, true);
+            }
+        } else if message.is_inbound_external() {
+            self.add_call(contract, body, false);
+        } else {
+            self.add_output(contract, body);
+        }
+    }
+
+    fn add_call(&mut self, contract: &AbiContract, body: SliceData, internal: bool) {
+        let id = match AbiFunction::decode_input_id(
+            contract.version(),
+            body.clone(),
+            contract.header(),
+            internal,
+        ) {
+            Ok(id) => id,
+            Err(_) => {
+                self.malformed += 1;
+                return;
+            }
+        };
+        match contract.function_by_id(id, true) {
+            Ok(function) => {
+                let counter = match function.decode_input(body, internal, false) {
+                    Ok(_) => &mut self.calls,
+                    Err(_) => &mut self.decode_failures,
+                };
+                *counter.entry(function.name.clone()).or_default() += 1;
+            }
+            Err(_) => self.add_unknown(id),
+        }
+    }
+
+    fn add_output(&mut self, contract: &AbiContract, body: SliceData) {
+        let id = match AbiFunction::decode_output_id(body.clone()) {
+            Ok(id) => id,
+            Err(_) => {
+                self.malformed += 1;
+                return;
+            }
+        };
+        if let Ok(function) = contract.function_by_id(id, false) {
+            let counter = match function.decode_output(body, false, false) {
+                Ok(_) => &mut self.responses,
+                Err(_) => &mut self.decode_failures,
+            };
+            *counter.entry(function.name.clone()).or_default() += 1;
+        } else if let Ok(event) = contract.event_by_id(id) {
+            let counter = match event.decode_input(body, false) {
+                Ok(_) => &mut self.events,
+                Err(_) => &mut self.decode_failures,
+            };
+            *counter.entry(event.name.clone()).or_default() += 1;
+        } else {
+            self.add_unknown(id);
+        }
+    }
+
+    fn add_unknown(&mut self, id: u32) {
+        *self.unknown_ids.entry(format!("0x{:08x}", id)).or_default() += 1;
+    }
+}
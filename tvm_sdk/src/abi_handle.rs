@@ -0,0 +1,88 @@
+// Copyright 2018-2021 TON Labs LTD.
+//
+// Licensed under the SOFTWARE EVALUATION License (the "License"); you may not
+// use this file except in compliance with the License.
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific TON DEV software governing permissions and
+// limitations under the License.
+
+use std::sync::Arc;
+
+use tvm_abi::AbiCache;
+use tvm_types::Result;
+
+use crate::AbiContract;
+use crate::FunctionCallSet;
+
+/// ABI accepted by `Contract` functions. JSON strings are parsed through the
+/// global `AbiCache`, which still hashes the whole string on each call, while
+/// `AbiHandle` holds the parsed contract.
+pub trait AbiSource {
+    fn abi_contract(&self) -> Result<Arc<AbiContract>>;
+}
+
+impl AbiSource for str {
+    fn abi_contract(&self) -> Result<Arc<AbiContract>> {
+        AbiCache::global().load(self)
+    }
+}
+
+impl AbiSource for String {
+    fn abi_contract(&self) -> Result<Arc<AbiContract>> {
+        AbiCache::global().load(self)
+    }
+}
+
+impl<T: AbiSource + ?Sized> AbiSource for &T {
+    fn abi_contract(&self) -> Result<Arc<AbiContract>> {
+        (**self).abi_contract()
+    }
+}
+
+/// ABI parsed once, for services decoding many messages of the same contract.
+/// Cloning is cheap.
+#[derive(Clone)]
+pub struct AbiHandle {
+    json: Arc<str>,
+    contract: Arc<AbiContract>,
+}
+
+impl AbiHandle {
+    pub fn new(json: impl Into<String>) -> Result<Self> {
+        let json: String = json.into();
+        let contract = Arc::new(AbiContract::load(json.as_bytes())?);
+        Ok(Self { json: json.into(), contract })
+    }
+
+    pub fn json(&self) -> &str {
+        &self.json
+    }
+
+    pub fn contract(&self) -> &AbiContract {
+        &self.contract
+    }
+
+    /// Call of the function of this ABI
+    pub fn call(
+        &self,
+        func: impl Into<String>,
+        header: Option<String>,
+        input: impl Into<String>,
+    ) -> FunctionCallSet {
+        FunctionCallSet {
+            func: func.into(),
+            header,
+            input: input.into(),
+            abi: self.json.to_string(),
+        }
+    }
+}
+
+impl AbiSource for AbiHandle {
+    fn abi_contract(&self) -> Result<Arc<AbiContract>> {
+        Ok(self.contract.clone())
+    }
+}
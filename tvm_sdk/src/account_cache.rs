@@ -0,0 +1,192 @@
+// Copyright 2018-2021 TON Labs LTD.
+//
+// Licensed under the SOFTWARE EVALUATION License (the "License"); you may not
+// use this file except in compliance with the License.
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific TON DEV software governing permissions and
+// limitations under the License.
+
+use std::path::Path;
+use std::sync::Mutex;
+use std::sync::OnceLock;
+
+use tvm_block::Account;
+use tvm_block::Deserializable;
+use tvm_block::MsgAddressInt;
+#[cfg(feature = "transport")]
+use tvm_block::Serializable;
+use tvm_types::Result;
+use tvm_types::UInt256;
+use tvm_types::error;
+use tvm_types::fail;
+
+use crate::error::SdkError;
+#[cfg(feature = "transport")]
+use crate::flows::ContractEvent;
+#[cfg(feature = "transport")]
+use crate::flows::EventSubscription;
+use crate::proofs::AccountProof;
+use crate::proofs::TrustStore;
+use crate::storage::FileStorage;
+use crate::storage::KeyValueStorage;
+#[cfg(feature = "transport")]
+use crate::transport::Transport;
+
+/// Checks account state before it is accepted by `AccountCache`, e.g. against
+/// a proof obtained together with the state
+pub trait AccountUpdateVerifier: Send + Sync {
+    fn verify(&self, address: &MsgAddressInt, account_boc: &[u8]) -> Result<()>;
+}
+
+/// Persistent cache of last known account states, stored as BOCs in the
+/// key-value storage by account addresses. Updates with last transaction lt
+/// not greater than the cached one are ignored, so the cache can be fed from
+/// unordered sources such as subscriptions.
+///
+/// When the trust store is enabled by `trust_block`, only updates with
+/// proofs checked against one of the trusted blocks are accepted.
+pub struct AccountCache {
+    storage: Box<dyn KeyValueStorage>,
+    verifier: Option<Box<dyn AccountUpdateVerifier>>,
+    trust_store: OnceLock<TrustStore>,
+    update_lock: Mutex<()>,
+}
+
+impl AccountCache {
+    /// Cache storing states as BOC files in the directory
+    pub fn open(dir: impl AsRef<Path>) -> Result<Self> {
+        Ok(Self::with_storage(Box::new(FileStorage::open(dir)?)))
+    }
+
+    pub fn with_storage(storage: Box<dyn KeyValueStorage>) -> Self {
+        Self { storage, verifier: None, trust_store: OnceLock::new(), update_lock: Mutex::new(()) }
+    }
+
+    /// Accepts only updates confirmed by the verifier
+    pub fn with_verifier(mut self, verifier: Box<dyn AccountUpdateVerifier>) -> Self {
+        self.verifier = Some(verifier);
+        self
+    }
+
+    /// Adds the block root hash to the trust store, e.g. of a block checked
+    /// by `block_signatures`, and enables the store
+    pub fn trust_block(&self, block_id: UInt256) {
+        self.trust_store.get_or_init(TrustStore::new).trust_block(block_id);
+    }
+
+    /// Trust store checking the updates, `None` until it is enabled
+    pub fn trust_store(&self) -> Option<&TrustStore> {
+        self.trust_store.get()
+    }
+
+    /// Updates must come with proofs, see `trust_block`
+    pub fn requires_proofs(&self) -> bool {
+        self.trust_store.get().is_some()
+    }
+
+    /// Returns cached account BOC
+    pub fn get_boc(&self, address: &MsgAddressInt) -> Result<Option<Vec<u8>>> {
+        self.storage.get(&Self::key(address))
+    }
+
+    pub fn get(&self, address: &MsgAddressInt) -> Result<Option<Account>> {
+        self.get_boc(address)?.map(|boc| Account::construct_from_bytes(&boc)).transpose()
+    }
+
+    /// Stores account state if it is newer than the cached one. Returns `true`
+    /// if the cache was updated. Fails when the trust store is enabled.
+    pub fn update(&self, account_boc: &[u8]) -> Result<bool> {
+        if self.requires_proofs() {
+            fail!(SdkError::InvalidData {
+                msg: "Account update without proof is not accepted".to_owned()
+            })
+        }
+        self.store(account_boc)
+    }
+
+    /// Same as `update` but checks the proof against the trust store first,
+    /// if it is enabled
+    pub fn update_with_proof(&self, account_boc: &[u8], proof: &AccountProof) -> Result<bool> {
+        if let Some(trust_store) = self.trust_store() {
+            trust_store.verify_account(proof, account_boc)?;
+        }
+        self.store(account_boc)
+    }
+
+    pub fn remove(&self, address: &MsgAddressInt) -> Result<()> {
+        self.storage.remove(&Self::key(address))
+    }
+
+    fn store(&self, account_boc: &[u8]) -> Result<bool> {
+        let account = Account::construct_from_bytes(account_boc)?;
+        let Some(address) = account.get_addr() else {
+            fail!(SdkError::InvalidData { msg: "Can't cache account without address".to_owned() })
+        };
+        // concurrent updates must not replace a newer state by an older one
+        let _lock = self.update_lock.lock().unwrap();
+        if let Some(cached) = self.get(address)? {
+            if cached.last_tr_time().unwrap_or_default()
+                >= account.last_tr_time().unwrap_or_default()
+            {
+                return Ok(false);
+            }
+        }
+        if let Some(verifier) = &self.verifier {
+            verifier.verify(address, account_boc)?;
+        }
+        self.storage.put(&Self::key(address), account_boc)?;
+        Ok(true)
+    }
+
+    fn key(address: &MsgAddressInt) -> String {
+        format!("{}.boc", address)
+    }
+}
+
+#[cfg(feature = "transport")]
+impl AccountCache {
+    /// Fetches the account from the transport, with the proof if the trust
+    /// store is enabled, and updates the cache. Returns `true` if the cache
+    /// was updated, accounts not existing are left cached.
+    pub async fn refresh<T: Transport + ?Sized>(
+        &self,
+        transport: &T,
+        address: &MsgAddressInt,
+    ) -> Result<bool> {
+        if self.requires_proofs() {
+            match transport.query_account_with_proof(address).await? {
+                Some((account, proof)) => {
+                    self.update_with_proof(&account.write_to_bytes()?, &proof)
+                }
+                None => Ok(false),
+            }
+        } else {
+            match transport.query_account(address).await? {
+                Some(account) => self.update(&account.write_to_bytes()?),
+                None => Ok(false),
+            }
+        }
+    }
+
+    /// Polls the subscription and refreshes the account when it has
+    /// transactions newer than the cached state. Returns the events.
+    pub async fn refresh_on_events<T: Transport + ?Sized>(
+        &self,
+        subscription: &mut EventSubscription<'_, T>,
+    ) -> Result<Vec<ContractEvent>> {
+        let events = subscription.next_events().await?;
+        let address = subscription.address();
+        let cached_lt = self.get(address)?.and_then(|account| account.last_tr_time());
+        if cached_lt.unwrap_or_default() < subscription.last_lt() {
+            self.refresh(subscription.transport(), address).await?;
+        }
+        Ok(events)
+    }
+}
+
+#[cfg(test)]
+#[path = "tests/test_account_cache.rs"]
+mod tests;
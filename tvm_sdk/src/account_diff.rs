@@ -0,0 +1,137 @@
+// Copyright 2018-2021 TON Labs LTD.
+//
+// Licensed under the SOFTWARE EVALUATION License (the "License"); you may not
+// use this file except in compliance with the License.
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific TON DEV software governing permissions and
+// limitations under the License.
+
+use serde_json::Map;
+use serde_json::Value;
+use tvm_abi::token::Detokenizer;
+use tvm_block::Account;
+use tvm_block::Deserializable;
+use tvm_types::Cell;
+use tvm_types::Result;
+use tvm_types::SliceData;
+
+use crate::AbiContract;
+
+/// Change of a single decoded account data field between two account states
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct FieldChange {
+    /// Name of the data field as declared in ABI
+    pub name: String,
+    /// Field value before update, `None` if field was absent
+    pub old_value: Option<Value>,
+    /// Field value after update, `None` if field was removed
+    pub new_value: Option<Value>,
+}
+
+/// Tracks decoded data of a single account and reports field-level changes
+/// between consecutive account states. `flows::AccountSubscription` feeds it
+/// with the states polled from the transport.
+#[derive(Debug, Clone)]
+pub struct AccountDataDiffer {
+    abi: AbiContract,
+    last_data: Option<Map<String, Value>>,
+}
+
+impl AccountDataDiffer {
+    pub fn new(abi: &str) -> Result<Self> {
+        Ok(Self::with_abi(AbiContract::load(abi.as_bytes())?))
+    }
+
+    pub fn with_abi(abi: AbiContract) -> Self {
+        Self { abi, last_data: None }
+    }
+
+    /// Returns last known decoded account data
+    pub fn last_data(&self) -> Option<&Map<String, Value>> {
+        self.last_data.as_ref()
+    }
+
+    /// Decodes data of the account from its serialized state and returns
+    /// changed fields. The first update reports all fields as new ones.
+    pub fn update_from_account_bytes(&mut self, account: &[u8]) -> Result<Vec<FieldChange>> {
+        let account = Account::construct_from_bytes(account)?;
+        self.update_from_account(&account)
+    }
+
+    /// Decodes data of the account and returns changed fields. Account
+    /// without data (uninit, frozen or deleted) clears all fields.
+    pub fn update_from_account(&mut self, account: &Account) -> Result<Vec<FieldChange>> {
+        match account.get_data() {
+            Some(data) => self.update(data),
+            None => Ok(self.clear()),
+        }
+    }
+
+    /// Clears all fields, e.g. when the account is deleted, and returns them
+    /// as removed ones
+    pub fn clear(&mut self) -> Vec<FieldChange> {
+        self.apply(Map::new())
+    }
+
+    /// Decodes account data cell and returns changed fields
+    pub fn update(&mut self, data: Cell) -> Result<Vec<FieldChange>> {
+        let decoded = self.decode(data)?;
+        Ok(self.apply(decoded))
+    }
+
+    fn decode(&self, data: Cell) -> Result<Map<String, Value>> {
+        let data = SliceData::load_cell(data)?;
+        let tokens = if self.abi.fields().is_empty() {
+            self.abi.decode_data(data, true)?
+        } else {
+            self.abi.decode_storage_fields(data, true)?
+        };
+
+        match Detokenizer::detokenize_to_json_value(&tokens)? {
+            Value::Object(map) => Ok(map),
+            _ => Ok(Map::new()),
+        }
+    }
+
+    fn apply(&mut self, decoded: Map<String, Value>) -> Vec<FieldChange> {
+        let changes = diff_decoded_data(self.last_data.as_ref(), &decoded);
+        self.last_data = Some(decoded);
+        changes
+    }
+}
+
+/// Compares two sets of decoded data fields and returns changed ones in order
+/// of their appearance in the new data followed by removed fields
+pub fn diff_decoded_data(
+    old: Option<&Map<String, Value>>,
+    new: &Map<String, Value>,
+) -> Vec<FieldChange> {
+    let empty = Map::new();
+    let old = old.unwrap_or(&empty);
+
+    let mut changes = vec![];
+    for (name, new_value) in new {
+        let old_value = old.get(name);
+        if old_value != Some(new_value) {
+            changes.push(FieldChange {
+                name: name.clone(),
+                old_value: old_value.cloned(),
+                new_value: Some(new_value.clone()),
+            });
+        }
+    }
+    for (name, old_value) in old {
+        if !new.contains_key(name) {
+            changes.push(FieldChange {
+                name: name.clone(),
+                old_value: Some(old_value.clone()),
+                new_value: None,
+            });
+        }
+    }
+
+    changes
+}
@@ -0,0 +1,78 @@
+// Copyright 2018-2021 TON Labs LTD.
+//
+// Licensed under the SOFTWARE EVALUATION License (the "License"); you may not
+// use this file except in compliance with the License.
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific TON DEV software governing permissions and
+// limitations under the License.
+
+use tvm_block::Account;
+use tvm_block::AccountStatus;
+use tvm_types::Result;
+
+use crate::json_helper;
+use crate::types::grams_to_u64;
+
+/// Summary of the account state. Field names and formats follow the
+/// `accounts` collection of the GraphQL API.
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct AccountInfo {
+    /// Address, `None` for accounts not existing
+    pub id: Option<String>,
+    #[serde(with = "json_helper::account_status")]
+    pub acc_type: AccountStatus,
+    #[serde(with = "json_helper::uint")]
+    pub balance: u64,
+    #[serde(with = "json_helper::uint")]
+    pub last_trans_lt: u64,
+    pub last_paid: u32,
+    /// Storage fee debt, zero if there is none
+    #[serde(with = "json_helper::uint")]
+    pub due_payment: u64,
+    pub code_hash: Option<String>,
+    pub data_hash: Option<String>,
+}
+
+impl TryFrom<&Account> for AccountInfo {
+    type Error = tvm_types::Error;
+
+    fn try_from(account: &Account) -> Result<Self> {
+        Ok(Self {
+            id: account.get_addr().map(|address| address.to_string()),
+            acc_type: account.status(),
+            balance: match account.balance() {
+                Some(balance) => grams_to_u64(&balance.grams)?,
+                None => 0,
+            },
+            last_trans_lt: account.last_tr_time().unwrap_or_default(),
+            last_paid: account.last_paid(),
+            due_payment: match account.due_payment() {
+                Some(due_payment) => grams_to_u64(due_payment)?,
+                None => 0,
+            },
+            code_hash: account.get_code_hash().map(|hash| hash.as_hex_string()),
+            data_hash: account.get_data_hash().map(|hash| hash.as_hex_string()),
+        })
+    }
+}
+
+impl AccountInfo {
+    pub fn recommended_bounce(&self) -> bool {
+        recommended_bounce(&self.acc_type)
+    }
+}
+
+/// Bounce flag for value sent to the account in the state. Value sent with
+/// the flag to an uninitialized or not existing account comes back, losing
+/// the fees, and value sent without it to an active account is lost if the
+/// account fails to process the message. Frozen accounts return the value.
+pub fn recommended_bounce(status: &AccountStatus) -> bool {
+    match status {
+        AccountStatus::AccStateActive | AccountStatus::AccStateFrozen => true,
+        AccountStatus::AccStateUninit | AccountStatus::AccStateNonexist => false,
+    }
+}
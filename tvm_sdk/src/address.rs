@@ -0,0 +1,125 @@
+// Copyright 2018-2021 TON Labs LTD.
+//
+// Licensed under the SOFTWARE EVALUATION License (the "License"); you may not
+// use this file except in compliance with the License.
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific TON DEV software governing permissions and
+// limitations under the License.
+
+//! Conversion of addresses between the raw `workchain:hex` format and the
+//! user friendly base64 format. The base64 form is 36 bytes: flags tag,
+//! workchain id, 32 bytes of account id and CRC16 of them, with `+/` replaced
+//! by `-_` in the URL-safe variant.
+
+use std::str::FromStr;
+
+use tvm_block::MsgAddressInt;
+use tvm_types::Result;
+use tvm_types::SliceData;
+use tvm_types::base64_decode;
+use tvm_types::base64_encode;
+use tvm_types::error;
+use tvm_types::fail;
+
+use crate::error::SdkError;
+
+pub(crate) const CRC16: crc::Crc<u16> = crc::Crc::<u16>::new(&crc::CRC_16_XMODEM);
+
+const TAG_BOUNCEABLE: u8 = 0x11;
+const TAG_NON_BOUNCEABLE: u8 = 0x51;
+const TAG_TEST_ONLY: u8 = 0x80;
+
+/// Base64 address length in characters
+const BASE64_LEN: usize = 48;
+
+/// Representations of the same address
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct AddressFormats {
+    /// `workchain:hex`
+    pub raw: String,
+    pub bounceable: String,
+    pub non_bounceable: String,
+    pub bounceable_url: String,
+    pub non_bounceable_url: String,
+}
+
+impl AddressFormats {
+    /// Formats of the standard address. Addresses with anycast or not fitting
+    /// into 8-bit workchain id have no base64 form.
+    pub fn new(address: &MsgAddressInt) -> Result<Self> {
+        Ok(Self {
+            raw: address.to_string(),
+            bounceable: to_base64(address, true, false, false)?,
+            non_bounceable: to_base64(address, false, false, false)?,
+            bounceable_url: to_base64(address, true, false, true)?,
+            non_bounceable_url: to_base64(address, false, false, true)?,
+        })
+    }
+}
+
+/// Encodes the standard address in base64. `test` sets the test-only flag.
+pub fn to_base64(
+    address: &MsgAddressInt,
+    bounceable: bool,
+    test: bool,
+    url: bool,
+) -> Result<String> {
+    let MsgAddressInt::AddrStd(std) = address else {
+        fail!(SdkError::InvalidData {
+            msg: format!("Non-std address {} has no base64 form", address)
+        })
+    };
+    if std.anycast.is_some() {
+        fail!(SdkError::InvalidData {
+            msg: format!("Anycast address {} has no base64 form", address)
+        })
+    }
+    let mut tag = if bounceable { TAG_BOUNCEABLE } else { TAG_NON_BOUNCEABLE };
+    if test {
+        tag |= TAG_TEST_ONLY;
+    }
+    let mut data = vec![tag];
+    data.extend_from_slice(&std.workchain_id.to_be_bytes());
+    data.extend_from_slice(&std.address.get_bytestring(0));
+    data.extend_from_slice(&CRC16.checksum(&data).to_be_bytes());
+
+    let encoded = base64_encode(&data);
+    Ok(if url { encoded.replace('+', "-").replace('/', "_") } else { encoded })
+}
+
+/// Decodes base64 address in the standard or URL-safe alphabet. Bounceable
+/// and test-only flags are not returned.
+pub fn from_base64(address: &str) -> Result<MsgAddressInt> {
+    let invalid = |msg: &str| SdkError::InvalidData { msg: format!("{}: {}", msg, address) };
+    let data = base64_decode(address.replace('-', "+").replace('_', "/"))
+        .map_err(|_| invalid("Invalid base64 address"))?;
+    if data.len() != 36 {
+        fail!(invalid("Invalid base64 address length"))
+    }
+    if CRC16.checksum(&data[..34]).to_be_bytes() != data[34..] {
+        fail!(invalid("Address CRC mismatch"))
+    }
+    let tag = data[0] & !TAG_TEST_ONLY;
+    if tag != TAG_BOUNCEABLE && tag != TAG_NON_BOUNCEABLE {
+        fail!(invalid("Unknown address flags"))
+    }
+    MsgAddressInt::with_standart(
+        None,
+        data[1] as i8,
+        SliceData::from_raw(data[2..34].to_vec(), 256),
+    )
+}
+
+/// Parses address in any of the formats of `AddressFormats`
+pub fn parse_any(address: &str) -> Result<MsgAddressInt> {
+    if address.len() == BASE64_LEN && !address.contains(':') {
+        from_base64(address)
+    } else {
+        MsgAddressInt::from_str(address).map_err(|err| {
+            error!(SdkError::InvalidData { msg: format!("Invalid address {}: {}", address, err) })
+        })
+    }
+}
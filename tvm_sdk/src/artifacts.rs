@@ -0,0 +1,146 @@
+// Copyright 2018-2021 TON Labs LTD.
+//
+// Licensed under the SOFTWARE EVALUATION License (the "License"); you may not
+// use this file except in compliance with the License.
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific TON DEV software governing permissions and
+// limitations under the License.
+
+use std::path::Path;
+use std::path::PathBuf;
+
+use serde_json::Value;
+use tvm_block::MsgAddressExt;
+use tvm_block::MsgAddressInt;
+use tvm_types::Result;
+use tvm_types::ed25519_create_private_key;
+use tvm_types::error;
+use tvm_types::fail;
+
+use crate::Contract;
+use crate::ContractImage;
+use crate::FunctionCallSet;
+use crate::LocalSigner;
+use crate::SdkMessage;
+use crate::Signer;
+use crate::error::SdkError;
+use crate::tvc::Tvc;
+
+/// Compiled contract loaded by `ContractImage::from_artifacts`
+pub struct ContractArtifacts {
+    /// Image with the public key of the keys file, if any
+    pub image: ContractImage,
+    pub abi: String,
+    /// Signer with the keys file
+    pub signer: Option<LocalSigner>,
+}
+
+#[derive(Deserialize)]
+struct KeysFile {
+    public: Option<String>,
+    secret: String,
+}
+
+impl ContractImage {
+    /// Loads the contract compiled into `<name>.tvc` and `<name>.abi.json`,
+    /// with keys from `<name>.keys.json` if it exists. `path` is either the
+    /// .tvc file or the directory with the only .tvc file.
+    pub fn from_artifacts(path: impl AsRef<Path>) -> Result<ContractArtifacts> {
+        let path = path.as_ref();
+        let tvc = if path.is_dir() { find_tvc(path)? } else { path.to_path_buf() };
+        let abi = with_suffix(&tvc, ".abi.json");
+        let keys = with_suffix(&tvc, ".keys.json");
+        Self::from_artifact_paths(&tvc, &abi, keys.exists().then_some(keys.as_path()))
+    }
+
+    /// Loads the contract from the .tvc, ABI and optional keys files.
+    /// Keys file is JSON with `public` and `secret` keys in hex.
+    pub fn from_artifact_paths(
+        tvc: &Path,
+        abi: &Path,
+        keys: Option<&Path>,
+    ) -> Result<ContractArtifacts> {
+        let mut image = Tvc::read_from(tvc)?.as_contract_image()?;
+        let abi = std::fs::read_to_string(abi)
+            .map_err(|e| error!("Can't read ABI from {}: {}", abi.display(), e))?;
+        let signer = keys.map(read_keys).transpose()?;
+        if let Some(signer) = &signer {
+            image.set_public_key(&Signer::public_key(signer)?)?;
+        }
+        Ok(ContractArtifacts { image, abi, signer })
+    }
+}
+
+impl ContractArtifacts {
+    /// Call of the constructor with the input given as JSON object
+    pub fn constructor_call(&self, input: Value) -> FunctionCallSet {
+        self.call("constructor", input)
+    }
+
+    pub fn call(&self, function: &str, input: Value) -> FunctionCallSet {
+        FunctionCallSet {
+            func: function.to_owned(),
+            header: None,
+            input: input.to_string(),
+            abi: self.abi.clone(),
+        }
+    }
+
+    /// Address the contract is deployed at
+    pub fn address(&self, workchain_id: i32) -> MsgAddressInt {
+        self.image.msg_address(workchain_id)
+    }
+
+    /// Deploy message calling the constructor, signed with the keys file
+    pub fn deploy_message(&self, input: Value, workchain_id: i32) -> Result<SdkMessage> {
+        Contract::construct_deploy_message_json_ref(
+            &self.constructor_call(input),
+            &self.image,
+            self.signer.as_ref().map(|signer| signer as _),
+            workchain_id,
+            MsgAddressExt::AddrNone,
+        )
+    }
+}
+
+fn find_tvc(dir: &Path) -> Result<PathBuf> {
+    let mut found = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.extension().is_some_and(|extension| extension == "tvc") {
+            found.push(path);
+        }
+    }
+    match found.len() {
+        1 => Ok(found.remove(0)),
+        0 => fail!(SdkError::InvalidData { msg: format!("No .tvc file in {}", dir.display()) }),
+        _ => fail!(SdkError::InvalidData {
+            msg: format!("Several .tvc files in {}, the file must be given", dir.display())
+        }),
+    }
+}
+
+// `dir/name.tvc` -> `dir/name<suffix>`
+fn with_suffix(tvc: &Path, suffix: &str) -> PathBuf {
+    let stem = tvc.file_stem().unwrap_or_default().to_string_lossy();
+    tvc.with_file_name(format!("{}{}", stem, suffix))
+}
+
+fn read_keys(path: &Path) -> Result<LocalSigner> {
+    let keys: KeysFile = serde_json::from_str(
+        &std::fs::read_to_string(path)
+            .map_err(|e| error!("Can't read keys from {}: {}", path.display(), e))?,
+    )?;
+    let key = ed25519_create_private_key(&hex::decode(keys.secret.trim())?)?;
+    if let Some(public) = keys.public {
+        if hex::decode(public.trim())? != key.verifying_key() {
+            fail!(SdkError::InvalidData {
+                msg: format!("Public key doesn't match the secret one in {}", path.display())
+            })
+        }
+    }
+    Ok(LocalSigner::new(key))
+}
@@ -9,6 +9,12 @@
 // See the License for the specific TON DEV software governing permissions and
 // limitations under the License.
 
+use tvm_block::Block as TvmBlock;
+use tvm_block::Deserializable;
+use tvm_block::HashmapAugType;
+use tvm_types::Result;
+use tvm_types::UInt256;
+
 use crate::MessageId;
 use crate::TransactionId;
 use crate::contract::ShardDescr;
@@ -29,3 +35,87 @@ pub struct Block {
     pub shard_descr: ShardDescr,
     pub in_msg_descr: Vec<MsgDescr>,
 }
+
+/// Transactions of the account in the block, in order of logical time
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct AccountTransactions {
+    /// Full address with the workchain of the block
+    pub account: String,
+    pub transactions: Vec<TransactionId>,
+}
+
+/// Block decoded from BOC, see `Contract::parse_block`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ParsedBlock {
+    pub id: BlockId,
+    pub workchain_id: i32,
+    /// Shard prefix with the tag in hex, e.g. `8000000000000000`
+    pub shard: String,
+    pub seq_no: u32,
+    pub gen_utime: u32,
+    pub start_lt: u64,
+    pub end_lt: u64,
+    pub after_split: bool,
+    pub after_merge: bool,
+    pub key_block: bool,
+    pub account_blocks: Vec<AccountTransactions>,
+    /// Hashes of messages imported by the block
+    pub in_msgs: Vec<MessageId>,
+    /// Hashes of messages created or forwarded by the block
+    pub out_msgs: Vec<MessageId>,
+}
+
+impl ParsedBlock {
+    pub fn from_boc(data: &[u8]) -> Result<Self> {
+        let root = tvm_types::boc::read_single_root_boc(data)?;
+        let block = TvmBlock::construct_from_cell(root.clone())?;
+        let info = block.read_info()?;
+        let extra = block.read_extra()?;
+
+        let workchain_id = info.shard().workchain_id();
+        let mut account_blocks = Vec::new();
+        extra.read_account_blocks()?.iterate_objects(|account_block| {
+            let mut transactions = Vec::new();
+            account_block.transaction_iterate_full(|_, transaction, _| {
+                transactions.push(transaction.repr_hash().into());
+                Ok(true)
+            })?;
+            account_blocks.push(AccountTransactions {
+                account: format!("{}:{}", workchain_id, account_block.account_id().as_hex_string()),
+                transactions,
+            });
+            Ok(true)
+        })?;
+
+        let mut in_msgs = Vec::new();
+        extra.read_in_msg_descr()?.iterate_with_keys(|hash: UInt256, _| {
+            in_msgs.push(hash.into());
+            Ok(true)
+        })?;
+        let mut out_msgs = Vec::new();
+        extra.read_out_msg_descr()?.iterate_with_keys(|hash: UInt256, _| {
+            out_msgs.push(hash.into());
+            Ok(true)
+        })?;
+
+        Ok(Self {
+            id: root.repr_hash().into(),
+            workchain_id,
+            shard: info.shard().shard_prefix_as_str_with_tag(),
+            seq_no: info.seq_no(),
+            gen_utime: info.gen_utime().as_u32(),
+            start_lt: info.start_lt(),
+            end_lt: info.end_lt(),
+            after_split: info.after_split(),
+            after_merge: info.after_merge(),
+            key_block: info.key_block(),
+            account_blocks,
+            in_msgs,
+            out_msgs,
+        })
+    }
+
+    pub fn transaction_count(&self) -> usize {
+        self.account_blocks.iter().map(|account| account.transactions.len()).sum()
+    }
+}
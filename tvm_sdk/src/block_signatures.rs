@@ -0,0 +1,163 @@
+// Copyright 2018-2021 TON Labs LTD.
+//
+// Licensed under the SOFTWARE EVALUATION License (the "License"); you may not
+// use this file except in compliance with the License.
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific TON DEV software governing permissions and
+// limitations under the License.
+
+use std::collections::HashMap;
+use std::collections::HashSet;
+
+use tvm_block::Block;
+use tvm_block::BlockError;
+use tvm_block::BlockIdExt;
+use tvm_block::BlockProof;
+use tvm_block::BlockSignaturesPure;
+use tvm_block::CatchainConfig;
+use tvm_block::CryptoSignaturePair;
+use tvm_block::Deserializable;
+use tvm_block::MerkleProof;
+use tvm_block::ValidatorDescr;
+use tvm_block::ValidatorSet;
+use tvm_types::HashmapType;
+use tvm_types::Result;
+use tvm_types::UInt256;
+use tvm_types::error;
+use tvm_types::fail;
+
+use crate::error::SdkError;
+
+/// Result of the successful block signatures verification
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockSignaturesCheck {
+    pub block_id: BlockIdExt,
+    /// Number of signatures in the proof
+    pub signatures: u32,
+    /// Total weight of validators whose signatures were verified, each
+    /// validator is counted once
+    pub signed_weight: u64,
+    /// Total weight of the validator subset which produced the block
+    pub total_weight: u64,
+}
+
+/// Verifies signatures of the block proof (`BlockProof` BOC) against the
+/// validator set (config param 34) using default catchain config, i.e. for
+/// masterchain blocks the subset is the head of the validator list without
+/// shuffling. Use `verify_block_signatures_with_config` when config param 28
+/// of the network is known.
+pub fn verify_block_signatures(
+    block_proof_boc: &[u8],
+    validator_set: &ValidatorSet,
+) -> Result<BlockSignaturesCheck> {
+    verify_block_signatures_with_config(block_proof_boc, validator_set, &CatchainConfig::default())
+}
+
+/// Verifies signatures of the block proof (`BlockProof` BOC). Fails if the
+/// validator subset calculated from `validator_set` and `cc_config` doesn't
+/// match the one declared in the proof, if any signature is invalid or if
+/// valid signatures have no more than 2/3 of the subset weight. Repeated
+/// signatures of the same validator add its weight once.
+pub fn verify_block_signatures_with_config(
+    block_proof_boc: &[u8],
+    validator_set: &ValidatorSet,
+    cc_config: &CatchainConfig,
+) -> Result<BlockSignaturesCheck> {
+    let proof = BlockProof::construct_from_bytes(block_proof_boc)?;
+    let block_id = proof.proof_for.clone();
+    let signatures = proof.signatures.as_ref().ok_or_else(|| {
+        error!(SdkError::InvalidData { msg: format!("Proof for {} has no signatures", block_id) })
+    })?;
+
+    let merkle_proof = MerkleProof::construct_from_cell(proof.root.clone())?;
+    if merkle_proof.hash != block_id.root_hash {
+        fail!(SdkError::InvalidData {
+            msg: format!(
+                "Proof for {} contains Merkle proof of another block {:x}",
+                block_id, merkle_proof.hash
+            )
+        })
+    }
+    let gen_utime = merkle_proof.virtualize::<Block>()?.read_info()?.gen_utime();
+
+    let (subset, hash_short) = validator_set.calc_subset(
+        cc_config,
+        block_id.shard().shard_prefix_with_tag(),
+        block_id.shard().workchain_id(),
+        signatures.validator_info.catchain_seqno,
+        gen_utime,
+    )?;
+    if hash_short != signatures.validator_info.validator_list_hash_short {
+        fail!(SdkError::InvalidData {
+            msg: format!(
+                "Validator subset of proof for {} doesn't match validator set: \
+                    calculated hash {}, found {}",
+                block_id, hash_short, signatures.validator_info.validator_list_hash_short
+            )
+        })
+    }
+
+    let data = Block::build_data_for_sign(block_id.root_hash(), block_id.file_hash());
+    let signed_weight = signed_weight(&signatures.pure_signatures, &subset, &data)?;
+    let total_weight = subset.iter().map(|descr| descr.weight).sum::<u64>();
+    if signed_weight != signatures.pure_signatures.weight() {
+        fail!(SdkError::InvalidData {
+            msg: format!(
+                "Signatures weight mismatch in proof for {}: declared {}, calculated {}",
+                block_id,
+                signatures.pure_signatures.weight(),
+                signed_weight
+            )
+        })
+    }
+    if signed_weight as u128 * 3 <= total_weight as u128 * 2 {
+        fail!(SdkError::InvalidData {
+            msg: format!(
+                "Too small signatures weight in proof for {}: {} of {}",
+                block_id, signed_weight, total_weight
+            )
+        })
+    }
+
+    Ok(BlockSignaturesCheck {
+        block_id,
+        signatures: signatures.pure_signatures.count(),
+        signed_weight,
+        total_weight,
+    })
+}
+
+// Same as `BlockSignaturesPure::check_signatures` but doesn't count the
+// weight of the validator twice when its signature is repeated in the proof
+fn signed_weight(
+    signatures: &BlockSignaturesPure,
+    subset: &[ValidatorDescr],
+    data: &[u8],
+) -> Result<u64> {
+    let validators = subset
+        .iter()
+        .map(|descr| (descr.compute_node_id_short(), descr))
+        .collect::<HashMap<UInt256, &ValidatorDescr>>();
+    let mut signed = HashSet::new();
+    let mut weight = 0u64;
+    signatures.signatures().iterate_slices(|_, mut slice| {
+        let pair = CryptoSignaturePair::construct_from(&mut slice)?;
+        if let Some(descr) = validators.get(&pair.node_id_short) {
+            if !descr.verify_signature(data, &pair.sign) {
+                fail!(BlockError::BadSignature)
+            }
+            if signed.insert(pair.node_id_short) {
+                weight += descr.weight;
+            }
+        }
+        Ok(true)
+    })?;
+    Ok(weight)
+}
+
+#[cfg(test)]
+#[path = "tests/test_block_signatures.rs"]
+mod tests;
@@ -0,0 +1,98 @@
+// Copyright 2018-2021 TON Labs LTD.
+//
+// Licensed under the SOFTWARE EVALUATION License (the "License"); you may not
+// use this file except in compliance with the License.
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific TON DEV software governing permissions and
+// limitations under the License.
+
+use std::borrow::Cow;
+
+use tvm_types::Cell;
+use tvm_types::Result;
+use tvm_types::SliceData;
+use tvm_types::base64_decode;
+use tvm_types::error;
+use tvm_types::read_single_root_boc;
+use tvm_types::write_boc;
+
+use crate::error::SdkError;
+
+/// Bag of cells in any of the forms it is passed around in: serialized,
+/// encoded as base64 or hex text, or already deserialized to the root cell.
+///
+/// Byte slices and cells convert into it, so APIs taking it accept them as
+/// before. Text has to be wrapped in `Base64` or `Hex`, as the same string
+/// may be valid in both encodings.
+#[derive(Debug, Clone, PartialEq)]
+pub enum BocInput<'a> {
+    Bytes(&'a [u8]),
+    Base64(&'a str),
+    Hex(&'a str),
+    Cell(Cell),
+}
+
+impl BocInput<'_> {
+    /// Serialized bag of cells, borrowed if given as bytes
+    pub fn to_bytes(&self) -> Result<Cow<'_, [u8]>> {
+        match self {
+            BocInput::Bytes(bytes) => Ok(Cow::Borrowed(bytes)),
+            BocInput::Base64(text) => base64_decode(text.trim()).map(Cow::Owned).map_err(|err| {
+                error!(SdkError::InvalidData { msg: format!("Invalid base64 BOC: {}", err) })
+            }),
+            BocInput::Hex(text) => {
+                let text = text.trim();
+                let text = text.strip_prefix("0x").unwrap_or(text);
+                hex::decode(text).map(Cow::Owned).map_err(|err| {
+                    error!(SdkError::InvalidData { msg: format!("Invalid hex BOC: {}", err) })
+                })
+            }
+            BocInput::Cell(cell) => Ok(Cow::Owned(write_boc(cell)?)),
+        }
+    }
+
+    /// Root cell of the bag, which must have the single root
+    pub fn to_cell(&self) -> Result<Cell> {
+        match self {
+            BocInput::Cell(cell) => Ok(cell.clone()),
+            input => read_single_root_boc(input.to_bytes()?),
+        }
+    }
+
+    pub fn to_slice(&self) -> Result<SliceData> {
+        SliceData::load_cell(self.to_cell()?)
+    }
+}
+
+impl<'a> From<&'a [u8]> for BocInput<'a> {
+    fn from(bytes: &'a [u8]) -> Self {
+        BocInput::Bytes(bytes)
+    }
+}
+
+impl<'a> From<&'a Vec<u8>> for BocInput<'a> {
+    fn from(bytes: &'a Vec<u8>) -> Self {
+        BocInput::Bytes(bytes)
+    }
+}
+
+impl<'a, const N: usize> From<&'a [u8; N]> for BocInput<'a> {
+    fn from(bytes: &'a [u8; N]) -> Self {
+        BocInput::Bytes(bytes)
+    }
+}
+
+impl From<Cell> for BocInput<'_> {
+    fn from(cell: Cell) -> Self {
+        BocInput::Cell(cell)
+    }
+}
+
+impl From<&Cell> for BocInput<'_> {
+    fn from(cell: &Cell) -> Self {
+        BocInput::Cell(cell.clone())
+    }
+}
@@ -0,0 +1,112 @@
+// Copyright 2018-2021 TON Labs LTD.
+//
+// Licensed under the SOFTWARE EVALUATION License (the "License"); you may not
+// use this file except in compliance with the License.
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific TON DEV software governing permissions and
+// limitations under the License.
+
+use serde_json::Value;
+use tvm_block::MsgAddressInt;
+use tvm_types::Result;
+use tvm_types::UInt256;
+use tvm_types::sha256_digest;
+
+use crate::ExpirationPolicy;
+use crate::FunctionCallSet;
+
+const CALL_SET_PREFIX: &[u8] = b"\xff\xfftvm-call-set-v1";
+
+impl FunctionCallSet {
+    /// Canonical serialization of the call to the destination built with the
+    /// policy. JSON of the header, input and ABI is taken with sorted keys
+    /// and without whitespace, so equal calls give equal bytes whatever their
+    /// formatting. Missing and empty headers are equal. The policy is
+    /// represented by its TTL and public key rather than by the time and
+    /// expiration, which change with every message built.
+    pub fn canonical_bytes(
+        &self,
+        destination: Option<&MsgAddressInt>,
+        policy: Option<&ExpirationPolicy>,
+    ) -> Result<Vec<u8>> {
+        let header = match self.header.as_deref() {
+            Some(header) => canonical_json(header)?,
+            None => String::new(),
+        };
+        let header = if header == "{}" { String::new() } else { header };
+        let destination = destination.map(ToString::to_string).unwrap_or_default();
+
+        let mut bytes = CALL_SET_PREFIX.to_vec();
+        push_field(&mut bytes, self.func.as_bytes());
+        push_field(&mut bytes, header.as_bytes());
+        push_field(&mut bytes, canonical_json(&self.input)?.as_bytes());
+        push_field(&mut bytes, &sha256_digest(canonical_json(&self.abi)?.as_bytes()));
+        push_field(&mut bytes, destination.as_bytes());
+        match policy {
+            Some(policy) => {
+                bytes.push(1);
+                bytes.extend_from_slice(&policy.ttl().to_be_bytes());
+                push_field(&mut bytes, policy.public_key().map_or(&[], |key| &key[..]));
+            }
+            None => bytes.push(0),
+        }
+        Ok(bytes)
+    }
+
+    /// Hash of the canonical serialization, e.g. for whitelisting or
+    /// deduplication of signing requests
+    pub fn canonical_hash(
+        &self,
+        destination: Option<&MsgAddressInt>,
+        policy: Option<&ExpirationPolicy>,
+    ) -> Result<UInt256> {
+        let bytes = self.canonical_bytes(destination, policy)?;
+        Ok(UInt256::from(sha256_digest(&bytes)))
+    }
+}
+
+/// JSON with object keys sorted and without whitespace
+pub(crate) fn canonical_json(json: &str) -> Result<String> {
+    let value: Value = serde_json::from_str(json)?;
+    let mut canonical = String::new();
+    write_canonical(&value, &mut canonical);
+    Ok(canonical)
+}
+
+fn write_canonical(value: &Value, output: &mut String) {
+    match value {
+        Value::Object(map) => {
+            let mut entries: Vec<_> = map.iter().collect();
+            entries.sort_by_key(|(key, _)| *key);
+            output.push('{');
+            for (index, (key, value)) in entries.into_iter().enumerate() {
+                if index > 0 {
+                    output.push(',');
+                }
+                output.push_str(&Value::from(key.as_str()).to_string());
+                output.push(':');
+                write_canonical(value, output);
+            }
+            output.push('}');
+        }
+        Value::Array(items) => {
+            output.push('[');
+            for (index, item) in items.iter().enumerate() {
+                if index > 0 {
+                    output.push(',');
+                }
+                write_canonical(item, output);
+            }
+            output.push(']');
+        }
+        value => output.push_str(&value.to_string()),
+    }
+}
+
+fn push_field(bytes: &mut Vec<u8>, field: &[u8]) {
+    bytes.extend_from_slice(&(field.len() as u32).to_be_bytes());
+    bytes.extend_from_slice(field);
+}
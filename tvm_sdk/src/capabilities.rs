@@ -0,0 +1,66 @@
+// Copyright 2018-2021 TON Labs LTD.
+//
+// Licensed under the SOFTWARE EVALUATION License (the "License"); you may not
+// use this file except in compliance with the License.
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific TON DEV software governing permissions and
+// limitations under the License.
+
+use tvm_block::ConfigParams;
+use tvm_block::GlobalCapabilities;
+
+/// `GlobalCapabilities::CapSignatureWithId`, declared only with the
+/// `signature_with_id` feature of `tvm_block`
+const CAP_SIGNATURE_WITH_ID: u64 = 0x0000_0400_0000;
+
+/// Global version and capabilities of the network (config param 8)
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Capabilities {
+    version: u32,
+    capabilities: u64,
+}
+
+impl Capabilities {
+    pub fn new(version: u32, capabilities: u64) -> Self {
+        Self { version, capabilities }
+    }
+
+    /// Capabilities of the config, none if param 8 is absent
+    pub fn from_config(config: &ConfigParams) -> Self {
+        Self::new(config.global_version(), config.capabilities())
+    }
+
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+
+    pub fn bits(&self) -> u64 {
+        self.capabilities
+    }
+
+    pub fn supports(&self, capability: GlobalCapabilities) -> bool {
+        self.capabilities & capability as u64 != 0
+    }
+
+    /// Whether signed data is prefixed with the signature id
+    pub fn signature_with_id(&self) -> bool {
+        self.capabilities & CAP_SIGNATURE_WITH_ID != 0
+    }
+
+    /// Signature id of the network with the global id, `None` if signatures
+    /// have no id
+    pub fn signature_id(&self, global_id: i32) -> Option<i32> {
+        self.signature_with_id().then_some(global_id)
+    }
+
+    /// Data to sign prefixed with the big-endian signature id, if any
+    pub fn data_to_sign(&self, global_id: i32, data: &[u8]) -> Vec<u8> {
+        match self.signature_id(global_id) {
+            Some(signature_id) => [&signature_id.to_be_bytes()[..], data].concat(),
+            None => data.to_vec(),
+        }
+    }
+}
@@ -0,0 +1,184 @@
+// Copyright 2018-2021 TON Labs LTD.
+//
+// Licensed under the SOFTWARE EVALUATION License (the "License"); you may not
+// use this file except in compliance with the License.
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific TON DEV software governing permissions and
+// limitations under the License.
+
+use std::collections::BTreeMap;
+use std::collections::HashSet;
+
+use tvm_block::Serializable;
+use tvm_block::Transaction as TvmTransaction;
+use tvm_types::Result;
+
+use crate::MessageType;
+use crate::ParsedMessage;
+use crate::Transaction;
+
+/// Cost of a single transaction of the cascade, values in nanotokens
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct LegCost {
+    pub transaction_id: String,
+    /// Account paying the fees of the transaction
+    pub account: String,
+    pub lt: u64,
+    pub aborted: bool,
+    /// All fees taken from the account, including forwarding fees of
+    /// outbound messages
+    pub fees: u128,
+    /// Value of the inbound message
+    pub value_in: u128,
+    /// Total value of outbound messages
+    pub value_out: u128,
+    /// Value of outbound bounced messages, returned to their senders
+    pub value_bounced: u128,
+    /// `value_in - value_out - fees`
+    pub balance_change: i128,
+}
+
+/// Costs of the cascade summed up per account, values in nanotokens
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct AccountCost {
+    pub account: String,
+    pub transactions: usize,
+    pub fees: u128,
+    pub value_received: u128,
+    pub value_sent: u128,
+    /// Part of `value_received` returned by bounced messages
+    pub value_returned: u128,
+    /// Positive if the value is locked on the account, negative if it is
+    /// spent
+    pub balance_change: i128,
+}
+
+impl AccountCost {
+    /// Total the account paid for the operation: fees and value which was
+    /// not returned
+    pub fn charge(&self) -> u128 {
+        if self.balance_change < 0 { self.balance_change.unsigned_abs() } else { 0 }
+    }
+}
+
+/// Attribution of fees and value transfers of a cascade of transactions
+/// produced by a single operation, e.g. for charging users of multi-contract
+/// operations. Messages produced and consumed within the set move value
+/// between accounts, others bring value into the cascade or take it out.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct CascadeCost {
+    /// Legs ordered by logical time
+    pub legs: Vec<LegCost>,
+    /// Accounts ordered by address
+    pub accounts: Vec<AccountCost>,
+    pub total_fees: u128,
+    /// Value of internal messages coming from outside of the set
+    pub value_entered: u128,
+    /// Value of internal messages not consumed within the set, e.g. still in
+    /// flight or sent to accounts which transactions are not given
+    pub value_left: u128,
+}
+
+impl CascadeCost {
+    /// Builds the report from transactions of simulated or fetched cascade
+    pub fn from_transactions<'a>(
+        transactions: impl IntoIterator<Item = &'a TvmTransaction>,
+    ) -> Result<Self> {
+        let mut parsed = Vec::new();
+        for transaction in transactions {
+            parsed.push(Self::parse_transaction(transaction)?);
+        }
+        parsed.sort_by_key(|(leg, _, _)| leg.lt);
+
+        let mut produced = HashSet::new();
+        let mut consumed = HashSet::new();
+        for (_, in_msg, out_msgs) in &parsed {
+            produced.extend(out_msgs.iter().map(|message| message.id.to_string()));
+            if let Some(message) = in_msg {
+                consumed.insert(message.id.to_string());
+            }
+        }
+
+        let mut report = Self::default();
+        let mut accounts: BTreeMap<String, AccountCost> = BTreeMap::new();
+        for (leg, in_msg, out_msgs) in parsed {
+            if let Some(message) = in_msg.as_ref().filter(|message| is_internal(message)) {
+                if !produced.contains(&message.id.to_string()) {
+                    report.value_entered += message.value as u128;
+                }
+            }
+            for message in out_msgs.iter().filter(|message| is_internal(message)) {
+                if !consumed.contains(&message.id.to_string()) {
+                    report.value_left += message.value as u128;
+                }
+            }
+            let account = accounts.entry(leg.account.clone()).or_insert_with(|| AccountCost {
+                account: leg.account.clone(),
+                ..Default::default()
+            });
+            account.transactions += 1;
+            account.fees += leg.fees;
+            account.value_received += leg.value_in;
+            account.value_sent += leg.value_out;
+            if in_msg.as_ref().is_some_and(|message| message.bounced) {
+                account.value_returned += leg.value_in;
+            }
+            account.balance_change += leg.balance_change;
+            report.total_fees += leg.fees;
+            report.legs.push(leg);
+        }
+        report.accounts = accounts.into_values().collect();
+        Ok(report)
+    }
+
+    pub fn account(&self, address: &str) -> Option<&AccountCost> {
+        self.accounts.iter().find(|account| account.account == address)
+    }
+
+    fn parse_transaction(
+        transaction: &TvmTransaction,
+    ) -> Result<(LegCost, Option<ParsedMessage>, Vec<ParsedMessage>)> {
+        let in_msg = match transaction.read_in_msg()? {
+            Some(message) => Some(ParsedMessage::with_msg(&message, None)?),
+            None => None,
+        };
+        let mut out_msgs = Vec::new();
+        for index in 0..transaction.msg_count() {
+            if let Some(message) = transaction.get_out_msg(index)? {
+                out_msgs.push(ParsedMessage::with_msg(&message, None)?);
+            }
+        }
+        let account = in_msg
+            .as_ref()
+            .and_then(|message| message.dst.clone())
+            .unwrap_or_else(|| transaction.account_id().to_hex_string());
+
+        let fees = Transaction::try_from(transaction)?.calc_fees().account_fees as u128;
+        let value_in = in_msg.as_ref().map_or(0, |message| message.value as u128);
+        let value_out = out_msgs.iter().map(|message| message.value as u128).sum::<u128>();
+        let value_bounced = out_msgs
+            .iter()
+            .filter(|message| message.bounced)
+            .map(|message| message.value as u128)
+            .sum();
+        let leg = LegCost {
+            transaction_id: transaction.serialize()?.repr_hash().as_hex_string(),
+            account,
+            lt: transaction.logical_time(),
+            aborted: transaction.read_description()?.is_aborted(),
+            fees,
+            value_in,
+            value_out,
+            value_bounced,
+            balance_change: value_in as i128 - value_out as i128 - fees as i128,
+        };
+        Ok((leg, in_msg, out_msgs))
+    }
+}
+
+fn is_internal(message: &ParsedMessage) -> bool {
+    message.msg_type == MessageType::Internal
+}
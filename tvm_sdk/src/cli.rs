@@ -0,0 +1,260 @@
+// Copyright 2018-2021 TON Labs LTD.
+//
+// Licensed under the SOFTWARE EVALUATION License (the "License"); you may not
+// use this file except in compliance with the License.
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific TON DEV software governing permissions and
+// limitations under the License.
+
+//! Command line interface of a contract generated from its ABI.
+//!
+//! `AbiCli::command` builds a clap command tree with `call`, `run` and
+//! `deploy` subcommands holding a subcommand per function (constructor for
+//! `deploy`) with an argument per function input, and a `decode` subcommand
+//! for message bodies. `AbiCli::action` turns parsed arguments into call sets
+//! and decoded bodies, sending and running messages is left to the binary.
+//!
+//! Scalar inputs are taken as is, arrays, tuples and maps as JSON.
+
+use std::sync::Arc;
+
+use clap::Arg;
+use clap::ArgAction;
+use clap::ArgMatches;
+use clap::Command;
+use serde_json::Map;
+use serde_json::Value;
+use tvm_abi::Function;
+use tvm_abi::ParamType;
+use tvm_abi::json_abi::DecodedMessage;
+use tvm_block::MsgAddressInt;
+use tvm_types::Result;
+use tvm_types::base64_decode;
+use tvm_types::error;
+use tvm_types::fail;
+
+use crate::AbiContract;
+use crate::Contract;
+use crate::ContractImage;
+use crate::FunctionCallSet;
+
+const HEADER_ARG: &str = "header";
+const WORKCHAIN_ARG: &str = "workchain";
+const BODY_ARG: &str = "body";
+const INTERNAL_ARG: &str = "internal";
+const OUTPUT_ARG: &str = "output";
+
+/// Action requested by the command line
+pub enum CliAction {
+    /// Send external message calling the function
+    Call { address: Option<MsgAddressInt>, call_set: FunctionCallSet },
+    /// Run getter locally on the account state
+    Run { address: Option<MsgAddressInt>, call_set: FunctionCallSet },
+    /// Deploy contract image with the constructor call
+    Deploy { address: Option<MsgAddressInt>, workchain_id: i32, call_set: FunctionCallSet },
+    /// Decoded message body
+    Decode(DecodedMessage),
+}
+
+pub struct AbiCli {
+    name: String,
+    abi: String,
+    contract: Arc<AbiContract>,
+    address: Option<MsgAddressInt>,
+    image: Option<ContractImage>,
+}
+
+impl AbiCli {
+    pub fn new(name: impl Into<String>, abi: impl Into<String>) -> Result<Self> {
+        let abi = abi.into();
+        let contract = tvm_abi::AbiCache::global().load(&abi)?;
+        Ok(Self { name: name.into(), abi, contract, address: None, image: None })
+    }
+
+    /// Address used by `call` and `run`
+    pub fn with_address(mut self, address: MsgAddressInt) -> Self {
+        self.address = Some(address);
+        self
+    }
+
+    /// Image used by `deploy` to compute the address of the contract
+    pub fn with_image(mut self, image: ContractImage) -> Self {
+        self.image = Some(image);
+        self
+    }
+
+    pub fn command(&self) -> Command {
+        let mut call = Command::new("call")
+            .about("Calls contract function with external message")
+            .subcommand_required(true);
+        let mut run =
+            Command::new("run").about("Runs contract getter locally").subcommand_required(true);
+        for function in self.functions() {
+            if function.name == "constructor" {
+                continue;
+            }
+            call = call.subcommand(Self::function_command(function).arg(header_arg()));
+            run = run.subcommand(Self::function_command(function));
+        }
+
+        let mut deploy = Command::new("deploy").about("Deploys contract").arg(header_arg()).arg(
+            Arg::new(WORKCHAIN_ARG)
+                .long(WORKCHAIN_ARG)
+                .value_parser(clap::value_parser!(i32))
+                .default_value("0")
+                .help("Workchain of the contract"),
+        );
+        if let Ok(constructor) = self.contract.function("constructor") {
+            deploy = deploy.args(Self::input_args(constructor));
+        }
+
+        let decode = Command::new("decode")
+            .about("Decodes message body given as base64 BOC")
+            .arg(Arg::new(BODY_ARG).required(true))
+            .arg(
+                Arg::new(INTERNAL_ARG)
+                    .long(INTERNAL_ARG)
+                    .action(ArgAction::SetTrue)
+                    .help("Body of internal message"),
+            )
+            .arg(
+                Arg::new(OUTPUT_ARG)
+                    .long(OUTPUT_ARG)
+                    .action(ArgAction::SetTrue)
+                    .help("Body of function response instead of call"),
+            );
+
+        Command::new(self.name.clone())
+            .subcommand_required(true)
+            .subcommand(call)
+            .subcommand(run)
+            .subcommand(deploy)
+            .subcommand(decode)
+    }
+
+    /// Parses arguments matched by the tree from `command`
+    pub fn action(&self, matches: &ArgMatches) -> Result<CliAction> {
+        match matches.subcommand() {
+            Some(("call", matches)) => {
+                let (function, matches) = subcommand(matches)?;
+                let call_set = self.call_set(function, matches, true)?;
+                Ok(CliAction::Call { address: self.address.clone(), call_set })
+            }
+            Some(("run", matches)) => {
+                let (function, matches) = subcommand(matches)?;
+                let call_set = self.call_set(function, matches, false)?;
+                Ok(CliAction::Run { address: self.address.clone(), call_set })
+            }
+            Some(("deploy", matches)) => {
+                let workchain_id = *matches.get_one::<i32>(WORKCHAIN_ARG).unwrap_or(&0);
+                let call_set = self.call_set("constructor", matches, true)?;
+                let address = self.image.as_ref().map(|image| image.msg_address(workchain_id));
+                Ok(CliAction::Deploy { address, workchain_id, call_set })
+            }
+            Some(("decode", matches)) => {
+                let body = matches
+                    .get_one::<String>(BODY_ARG)
+                    .ok_or_else(|| error!("Message body is not set"))?;
+                let body = base64_decode(body)?;
+                let internal = matches.get_flag(INTERNAL_ARG);
+                let decoded = if matches.get_flag(OUTPUT_ARG) {
+                    Contract::decode_unknown_function_response_from_bytes_json(
+                        &self.abi, &body, internal, false,
+                    )?
+                } else {
+                    Contract::decode_unknown_function_call_from_bytes_json(
+                        &self.abi, &body, internal, false,
+                    )?
+                };
+                Ok(CliAction::Decode(decoded))
+            }
+            Some((command, _)) => fail!("Unknown command {}", command),
+            None => fail!("Command is not set"),
+        }
+    }
+
+    fn functions(&self) -> Vec<&Function> {
+        let mut functions = self.contract.functions().values().collect::<Vec<_>>();
+        functions.sort_by(|a, b| a.name.cmp(&b.name));
+        functions
+    }
+
+    fn function_command(function: &Function) -> Command {
+        Command::new(function.name.clone()).args(Self::input_args(function))
+    }
+
+    fn input_args(function: &Function) -> Vec<Arg> {
+        function
+            .inputs
+            .iter()
+            .map(|param| {
+                let arg = Arg::new(param.name.clone())
+                    .long(param.name.clone())
+                    .help(param.kind.type_signature());
+                match &param.kind {
+                    ParamType::Optional(_) => arg,
+                    ParamType::Bool => arg.required(true).value_parser(clap::value_parser!(bool)),
+                    _ => arg.required(true),
+                }
+            })
+            .collect()
+    }
+
+    fn call_set(
+        &self,
+        function: &str,
+        matches: &ArgMatches,
+        header: bool,
+    ) -> Result<FunctionCallSet> {
+        let function = self.contract.function(function)?;
+        let mut input = Map::new();
+        for param in &function.inputs {
+            let value = match &param.kind {
+                ParamType::Bool => {
+                    matches.get_one::<bool>(&param.name).map(|value| Value::Bool(*value))
+                }
+                kind => matches
+                    .get_one::<String>(&param.name)
+                    .map(|value| input_value(kind, value))
+                    .transpose()?,
+            };
+            if let Some(value) = value {
+                input.insert(param.name.clone(), value);
+            }
+        }
+        let header = match header {
+            true => matches.get_one::<String>(HEADER_ARG).cloned(),
+            false => None,
+        };
+        Ok(FunctionCallSet {
+            func: function.name.clone(),
+            header,
+            input: Value::Object(input).to_string(),
+            abi: self.abi.clone(),
+        })
+    }
+}
+
+fn header_arg() -> Arg {
+    Arg::new(HEADER_ARG).long(HEADER_ARG).help("Call header in JSON")
+}
+
+fn subcommand(matches: &ArgMatches) -> Result<(&str, &ArgMatches)> {
+    matches.subcommand().ok_or_else(|| error!("Function is not set"))
+}
+
+pub(crate) fn input_value(kind: &ParamType, value: &str) -> Result<Value> {
+    match kind {
+        ParamType::Optional(inner) | ParamType::Ref(inner) => input_value(inner, value),
+        ParamType::Bool => Ok(Value::Bool(value.parse()?)),
+        ParamType::Tuple(_)
+        | ParamType::Array(_)
+        | ParamType::FixedArray(_, _)
+        | ParamType::Map(_, _) => serde_json::from_str(value)
+            .map_err(|e| error!("Can't parse {} from {}: {}", kind, value, e)),
+        _ => Ok(Value::String(value.to_owned())),
+    }
+}
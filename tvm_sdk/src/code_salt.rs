@@ -0,0 +1,112 @@
+// Copyright 2018-2021 TON Labs LTD.
+//
+// Licensed under the SOFTWARE EVALUATION License (the "License"); you may not
+// use this file except in compliance with the License.
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific TON DEV software governing permissions and
+// limitations under the License.
+
+//! Salt of contract code. The salt is a reference of the function selector
+//! cell, its place depends on the selector compiled into the code.
+
+use tvm_types::BuilderData;
+use tvm_types::Cell;
+use tvm_types::Result;
+use tvm_types::SliceData;
+use tvm_types::error;
+use tvm_types::fail;
+
+use crate::error::SdkError;
+
+const OLD_CPP_SELECTOR_DATA: &[u8] = &[
+    0xff, 0x00, 0x20, 0xc1, 0x01, 0xf4, 0xa4, 0x20, 0x58, 0x92, 0xf4, 0xa0, 0xe0, 0x5f, 0x02, 0x8a,
+    0x20, 0xed, 0x53, 0xd9,
+];
+const OLD_SOL_SELECTOR_DATA: &[u8] = &[
+    0xff, 0x00, 0xf4, 0xa4, 0x20, 0x22, 0xc0, 0x01, 0x92, 0xf4, 0xa0, 0xe1, 0x8a, 0xed, 0x53, 0x58,
+    0x30, 0xf4, 0xa1,
+];
+const NEW_SELECTOR_DATA: &[u8] = &[
+    0x8a, 0xed, 0x53, 0x20, 0xe3, 0x03, 0x20, 0xc0, 0xff, 0xe3, 0x02, 0x20, 0xc0, 0xfe, 0xe3, 0x02,
+    0xf2, 0x0b,
+];
+const MYCODE_SELECTOR_DATA: &[u8] = &[0x8a, 0xdb, 0x35];
+
+fn invalid(msg: &str) -> tvm_types::Error {
+    error!(SdkError::InvalidData { msg: msg.to_owned() })
+}
+
+/// Salt of the code, `None` if the code has no salt
+pub(crate) fn get_code_salt(code: &Cell) -> Result<Option<Cell>> {
+    match code.data() {
+        OLD_CPP_SELECTOR_DATA => Ok(code.reference(2).ok()),
+        OLD_SOL_SELECTOR_DATA => Ok(None),
+        NEW_SELECTOR_DATA => new_selector_salt(code),
+        MYCODE_SELECTOR_DATA => new_selector_salt(&mycode_new_selector(code)?),
+        _ => fail!(invalid("Unknown function selector of contract code")),
+    }
+}
+
+/// Returns the code with the salt set or replaced
+pub(crate) fn set_code_salt(code: &Cell, salt: Cell) -> Result<Cell> {
+    match code.data() {
+        OLD_CPP_SELECTOR_DATA => {
+            let present = code.reference(2).is_ok();
+            set_salt(code, salt, present)
+        }
+        NEW_SELECTOR_DATA => set_new_selector_salt(code, salt),
+        MYCODE_SELECTOR_DATA => {
+            let new_selector = set_new_selector_salt(&mycode_new_selector(code)?, salt)?;
+            replace_reference(code, 1, new_selector)
+        }
+        OLD_SOL_SELECTOR_DATA => fail!(invalid("Contract code doesn't support salt")),
+        _ => fail!(invalid("Unknown function selector of contract code")),
+    }
+}
+
+fn mycode_new_selector(code: &Cell) -> Result<Cell> {
+    code.reference(1).map_err(|_| invalid("No new selector in mycode selector"))
+}
+
+fn private_selector(code: &Cell) -> Result<Cell> {
+    code.reference(0).map_err(|_| invalid("No private functions selector in new selector"))
+}
+
+fn new_selector_salt(code: &Cell) -> Result<Option<Cell>> {
+    let mut selector = SliceData::load_cell(private_selector(code)?)?;
+    if selector.get_next_bits(13).ok() != Some(vec![0xf4, 0xa0]) {
+        fail!(invalid("Invalid private functions selector"))
+    }
+    selector.get_dictionary_opt();
+    if selector.reference_opt(0).is_none() {
+        fail!(invalid("No compiler version in contract code"))
+    }
+    Ok(selector.reference_opt(1))
+}
+
+fn set_new_selector_salt(code: &Cell, salt: Cell) -> Result<Cell> {
+    let present = new_selector_salt(code)?.is_some();
+    let selector = set_salt(&private_selector(code)?, salt, present)?;
+    replace_reference(code, 0, selector)
+}
+
+fn set_salt(cell: &Cell, salt: Cell, replace_last: bool) -> Result<Cell> {
+    let mut builder = BuilderData::from_cell(cell)?;
+    if replace_last {
+        builder.replace_reference_cell(builder.references_used() - 1, salt);
+    } else {
+        builder
+            .checked_append_reference(salt)
+            .map_err(|_| invalid("No free reference for salt"))?;
+    }
+    builder.into_cell()
+}
+
+fn replace_reference(cell: &Cell, index: usize, reference: Cell) -> Result<Cell> {
+    let mut builder = BuilderData::from_cell(cell)?;
+    builder.replace_reference_cell(index, reference);
+    builder.into_cell()
+}
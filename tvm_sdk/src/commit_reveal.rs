@@ -0,0 +1,152 @@
+// Copyright 2018-2021 TON Labs LTD.
+//
+// Licensed under the SOFTWARE EVALUATION License (the "License"); you may not
+// use this file except in compliance with the License.
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific TON DEV software governing permissions and
+// limitations under the License.
+
+//! Commit-reveal calls, e.g. for votes or sealed bids.
+//!
+//! The contract stores the commitment and later checks the revealed values
+//! against it, usually as `tvm.hash(abi.encode(value, salt)) == commitment`.
+//! The commitment here is the representation hash of the cell chain with the
+//! input parameters of the reveal function packed in their order by the ABI
+//! version of the contract, so it matches the contract check if the reveal
+//! function takes the committed values and the salt in the order they are
+//! encoded by the contract.
+
+use serde_json::Value;
+use serde_json::json;
+use tvm_abi::AbiCache;
+use tvm_abi::ParamType;
+use tvm_abi::TokenValue;
+use tvm_abi::token::Tokenizer;
+use tvm_types::Result;
+use tvm_types::UInt256;
+use tvm_types::error;
+use tvm_types::fail;
+
+use crate::FunctionCallSet;
+use crate::error::SdkError;
+
+/// Commitment with the values it hides. Keep it secret until the reveal.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Commitment {
+    /// Hash put on-chain by the commit call, in hex
+    pub hash: String,
+    /// Input of the reveal call, including the salt
+    pub input: Value,
+}
+
+impl Commitment {
+    /// Commit call passing the hash as the `uint256` parameter
+    pub fn commit_call(&self, abi: &str, function: &str, param: &str) -> FunctionCallSet {
+        FunctionCallSet {
+            func: function.to_owned(),
+            header: None,
+            input: json!({ param: format!("0x{}", self.hash) }).to_string(),
+            abi: abi.to_owned(),
+        }
+    }
+}
+
+/// Commitments for the reveal function of the contract
+#[derive(Clone, Debug)]
+pub struct CommitReveal {
+    abi: String,
+    function: String,
+    salt_param: String,
+}
+
+impl CommitReveal {
+    /// Reveal function with the salt parameter named `salt`
+    pub fn new(abi: impl Into<String>, reveal_function: impl Into<String>) -> Self {
+        Self { abi: abi.into(), function: reveal_function.into(), salt_param: "salt".to_owned() }
+    }
+
+    pub fn with_salt_param(mut self, name: impl Into<String>) -> Self {
+        self.salt_param = name.into();
+        self
+    }
+
+    /// Commits to the values of reveal parameters other than the salt, given
+    /// as JSON object, with a random salt
+    pub fn commit(&self, values: &Value) -> Result<Commitment> {
+        let kind = self.param_kind(&self.salt_param)?;
+        let salt = random_value(&kind)?;
+        self.commit_with_salt(values, salt)
+    }
+
+    /// Commits to the values with the salt given as JSON value of the salt
+    /// parameter type
+    pub fn commit_with_salt(&self, values: &Value, salt: Value) -> Result<Commitment> {
+        let mut input = values.as_object().cloned().ok_or_else(|| {
+            error!(SdkError::InvalidData { msg: "Committed values must be JSON object".to_owned() })
+        })?;
+        if input.insert(self.salt_param.clone(), salt).is_some() {
+            fail!(SdkError::InvalidData {
+                msg: format!("Salt parameter {} is given among committed values", self.salt_param)
+            })
+        }
+        let input = Value::Object(input);
+        Ok(Commitment { hash: self.hash(&input)?.as_hex_string(), input })
+    }
+
+    /// Reveal call with the values and the salt of the commitment
+    pub fn reveal_call(&self, commitment: &Commitment) -> FunctionCallSet {
+        FunctionCallSet {
+            func: self.function.clone(),
+            header: None,
+            input: commitment.input.to_string(),
+            abi: self.abi.clone(),
+        }
+    }
+
+    /// Checks that the commitment hash matches its values, e.g. after the
+    /// commitment is restored from storage or the ABI is updated
+    pub fn verify(&self, commitment: &Commitment) -> Result<bool> {
+        Ok(self.hash(&commitment.input)?.as_hex_string() == commitment.hash)
+    }
+
+    fn hash(&self, input: &Value) -> Result<UInt256> {
+        let contract = AbiCache::global().load(&self.abi)?;
+        let params = contract.function(&self.function)?.input_params();
+        let tokens = Tokenizer::tokenize_all_params(params, input)?;
+        let builder = TokenValue::pack_values_into_chain(&tokens, Vec::new(), contract.version())?;
+        Ok(builder.into_cell()?.repr_hash())
+    }
+
+    fn param_kind(&self, name: &str) -> Result<ParamType> {
+        let contract = AbiCache::global().load(&self.abi)?;
+        let function = contract.function(&self.function)?;
+        match function.input_params().iter().find(|param| param.name == name) {
+            Some(param) => Ok(param.kind.clone()),
+            None => fail!(SdkError::InvalidData {
+                msg: format!("Function {} has no parameter {}", self.function, name)
+            }),
+        }
+    }
+}
+
+fn random_value(kind: &ParamType) -> Result<Value> {
+    let random = |len: usize| -> Result<Vec<u8>> {
+        let mut bytes = vec![0; len];
+        getrandom::getrandom(&mut bytes)
+            .map_err(|err| error!(SdkError::InternalError { msg: err.to_string() }))?;
+        Ok(bytes)
+    };
+    match kind {
+        ParamType::Uint(size) if size % 8 == 0 => {
+            Ok(Value::String(format!("0x{}", hex::encode(random(size / 8)?))))
+        }
+        ParamType::FixedBytes(size) => Ok(Value::String(hex::encode(random(*size)?))),
+        ParamType::Bytes => Ok(Value::String(hex::encode(random(32)?))),
+        _ => fail!(SdkError::InvalidData {
+            msg: format!("Random salt of type {} is not supported", kind)
+        }),
+    }
+}
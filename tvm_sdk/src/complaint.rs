@@ -0,0 +1,254 @@
+// Copyright 2018-2021 TON Labs LTD.
+//
+// Licensed under the SOFTWARE EVALUATION License (the "License"); you may not
+// use this file except in compliance with the License.
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific TON DEV software governing permissions and
+// limitations under the License.
+
+//! Validator complaints: messages registering complaints in the elector
+//! contract, validator votes for them and decoding of elector answers.
+
+use tvm_block::CurrencyCollection;
+use tvm_block::Deserializable;
+use tvm_block::Grams;
+use tvm_block::MsgAddressExt;
+use tvm_block::MsgAddressInt;
+use tvm_block::Serializable;
+use tvm_types::BuilderData;
+use tvm_types::Cell;
+use tvm_types::Ed25519PrivateKey;
+use tvm_types::IBitstring;
+use tvm_types::Result;
+use tvm_types::SliceData;
+use tvm_types::UInt256;
+use tvm_types::error;
+use tvm_types::fail;
+
+use crate::Contract;
+use crate::SdkMessage;
+use crate::error::SdkError;
+
+/// Operation code of the internal message registering complaint ("RgCp")
+pub const REGISTER_COMPLAINT_OP: u32 = 0x52674370;
+/// Operation code of the external message with complaint vote ("VtCp")
+pub const VOTE_COMPLAINT_OP: u32 = 0x56744370;
+/// Tag of the signed part of the complaint vote ("VtCP")
+pub const VOTE_COMPLAINT_SIGN_TAG: u32 = 0x56744350;
+/// Base of the elector answer tag for complaint registration. Answer tag is
+/// this value plus the error code, zero meaning success.
+pub const REGISTER_COMPLAINT_ANSWER: u32 = 0xf2676350;
+
+const VALIDATOR_COMPLAINT_TAG: u8 = 0xbc;
+
+/// Complaint against a validator of the past validation round
+/// ```text
+/// complaint#bc validator_pubkey:bits256 description:^ComplaintDescr
+///     created_at:uint32 severity:uint8 reward_addr:uint256
+///     paid:Grams suggested_fine:Grams suggested_fine_part:uint32
+///     = ValidatorComplaint;
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ValidatorComplaint {
+    pub validator_pubkey: UInt256,
+    /// `ComplaintDescr` cell with the proof of the validator misbehaviour
+    pub description: Cell,
+    pub created_at: u32,
+    pub severity: u8,
+    /// Account id in masterchain receiving the reward for the accepted
+    /// complaint
+    pub reward_addr: UInt256,
+    pub paid: Grams,
+    pub suggested_fine: Grams,
+    /// Fine part of the validator stake, in 1/2^32 units
+    pub suggested_fine_part: u32,
+}
+
+impl ValidatorComplaint {
+    /// Hash of the complaint, used by validators to vote for it. Elector
+    /// rewrites `created_at` and `paid` on registration, so votes must use
+    /// the complaint as stored in the elector data.
+    pub fn hash(&self) -> Result<UInt256> {
+        Ok(self.serialize()?.repr_hash())
+    }
+}
+
+impl Serializable for ValidatorComplaint {
+    fn write_to(&self, builder: &mut BuilderData) -> Result<()> {
+        builder.append_u8(VALIDATOR_COMPLAINT_TAG)?;
+        self.validator_pubkey.write_to(builder)?;
+        builder.checked_append_reference(self.description.clone())?;
+        builder.append_u32(self.created_at)?;
+        builder.append_u8(self.severity)?;
+        self.reward_addr.write_to(builder)?;
+        self.paid.write_to(builder)?;
+        self.suggested_fine.write_to(builder)?;
+        builder.append_u32(self.suggested_fine_part)?;
+        Ok(())
+    }
+}
+
+impl Deserializable for ValidatorComplaint {
+    fn read_from(&mut self, slice: &mut SliceData) -> Result<()> {
+        let tag = slice.get_next_byte()?;
+        if tag != VALIDATOR_COMPLAINT_TAG {
+            fail!(SdkError::InvalidData {
+                msg: format!("Invalid validator complaint tag {:x}", tag)
+            })
+        }
+        self.validator_pubkey = slice.get_next_hash()?;
+        self.description = slice.checked_drain_reference()?;
+        self.created_at = slice.get_next_u32()?;
+        self.severity = slice.get_next_byte()?;
+        self.reward_addr = slice.get_next_hash()?;
+        self.paid.read_from(slice)?;
+        self.suggested_fine.read_from(slice)?;
+        self.suggested_fine_part = slice.get_next_u32()?;
+        Ok(())
+    }
+}
+
+/// Builds body of the internal message to the elector registering complaint
+/// for the election `election_id`. Complaint price must be attached to the
+/// message.
+pub fn register_complaint_body(
+    query_id: u64,
+    election_id: u32,
+    complaint: &ValidatorComplaint,
+) -> Result<SliceData> {
+    let mut builder = BuilderData::new();
+    builder.append_u32(REGISTER_COMPLAINT_OP)?;
+    builder.append_u64(query_id)?;
+    builder.append_u32(election_id)?;
+    complaint.write_to(&mut builder)?;
+    SliceData::load_builder(builder)
+}
+
+/// Builds internal message registering complaint in the elector contract
+/// `elector_address`
+pub fn construct_register_complaint_message(
+    elector_address: MsgAddressInt,
+    src_address: Option<MsgAddressInt>,
+    value: CurrencyCollection,
+    query_id: u64,
+    election_id: u32,
+    complaint: &ValidatorComplaint,
+) -> Result<SdkMessage> {
+    let body = register_complaint_body(query_id, election_id, complaint)?;
+    Contract::construct_int_message_with_body(
+        elector_address,
+        src_address,
+        true,
+        true,
+        value,
+        Some(body),
+    )
+}
+
+/// Elector answer to the complaint registration
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RegisterComplaintAnswer {
+    pub query_id: u64,
+    /// Zero if the complaint was registered, elector error code otherwise
+    pub error_code: u32,
+}
+
+impl RegisterComplaintAnswer {
+    pub fn is_accepted(&self) -> bool {
+        self.error_code == 0
+    }
+
+    /// Decodes body of the elector answer. Returns `None` if the body is not
+    /// an answer to the complaint registration.
+    pub fn decode(mut body: SliceData) -> Result<Option<Self>> {
+        if body.remaining_bits() < 32 + 64 + 32 {
+            return Ok(None);
+        }
+        let tag = body.get_next_u32()?;
+        let query_id = body.get_next_u64()?;
+        let op = body.get_next_u32()?;
+        if op != REGISTER_COMPLAINT_OP || tag < REGISTER_COMPLAINT_ANSWER {
+            return Ok(None);
+        }
+        Ok(Some(Self { query_id, error_code: tag - REGISTER_COMPLAINT_ANSWER }))
+    }
+}
+
+/// Validator vote for the complaint. `validator_idx` is the index of the
+/// validator in the current validator set (config param 34).
+#[derive(Debug, Clone, PartialEq)]
+pub struct ComplaintVote {
+    pub validator_idx: u16,
+    pub election_id: u32,
+    pub complaint_hash: UInt256,
+}
+
+impl ComplaintVote {
+    pub fn new(validator_idx: u16, election_id: u32, complaint_hash: UInt256) -> Self {
+        Self { validator_idx, election_id, complaint_hash }
+    }
+
+    /// Data the validator signs with its key
+    pub fn data_to_sign(&self) -> Result<Vec<u8>> {
+        Ok(self.unsigned_body()?.data().to_vec())
+    }
+
+    /// Builds external message body with the signature of `data_to_sign`
+    pub fn signed_body(&self, signature: &[u8]) -> Result<SliceData> {
+        if signature.len() != 64 {
+            fail!(SdkError::InvalidData { msg: "Vote signature must be 64 bytes long".to_owned() })
+        }
+        let mut builder = BuilderData::new();
+        builder.append_u32(VOTE_COMPLAINT_OP)?;
+        builder.append_raw(signature, 512)?;
+        builder.append_builder(&self.unsigned_body()?)?;
+        SliceData::load_builder(builder)
+    }
+
+    pub fn sign(&self, key: &Ed25519PrivateKey) -> Result<SliceData> {
+        self.signed_body(&key.sign(&self.data_to_sign()?))
+    }
+
+    /// Decodes vote from the external message body. Returns the vote and
+    /// its signature, which is not checked.
+    pub fn decode(mut body: SliceData) -> Result<(Self, Vec<u8>)> {
+        if body.get_next_u32()? != VOTE_COMPLAINT_OP {
+            fail!(SdkError::InvalidData { msg: "Body is not a complaint vote".to_owned() })
+        }
+        let signature = body.get_next_bits(512)?;
+        if body.get_next_u32()? != VOTE_COMPLAINT_SIGN_TAG {
+            fail!(SdkError::InvalidData { msg: "Invalid complaint vote sign tag".to_owned() })
+        }
+        let validator_idx = body.get_next_u16()?;
+        let election_id = body.get_next_u32()?;
+        let complaint_hash = body.get_next_hash()?;
+        Ok((Self { validator_idx, election_id, complaint_hash }, signature))
+    }
+
+    fn unsigned_body(&self) -> Result<BuilderData> {
+        let mut builder = BuilderData::new();
+        builder.append_u32(VOTE_COMPLAINT_SIGN_TAG)?;
+        builder.append_u16(self.validator_idx)?;
+        builder.append_u32(self.election_id)?;
+        self.complaint_hash.write_to(&mut builder)?;
+        Ok(builder)
+    }
+}
+
+/// Builds external inbound message to the elector contract with the signed
+/// vote body returned by `ComplaintVote::signed_body`
+pub fn construct_complaint_vote_message(
+    elector_address: MsgAddressInt,
+    signed_body: SliceData,
+) -> Result<SdkMessage> {
+    let msg = Contract::create_ext_in_message(
+        elector_address.clone(),
+        MsgAddressExt::default(),
+        signed_body,
+    )?;
+    let (serialized_message, id) = Contract::serialize_message(&msg)?;
+    Ok(SdkMessage { id, serialized_message, message: msg, address: elector_address })
+}
@@ -0,0 +1,204 @@
+// Copyright 2018-2021 TON Labs LTD.
+//
+// Licensed under the SOFTWARE EVALUATION License (the "License"); you may not
+// use this file except in compliance with the License.
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific TON DEV software governing permissions and
+// limitations under the License.
+
+//! Interactive composing of external call messages.
+//!
+//! `Composer` asks for a function of the contract, its parameters and a
+//! signer from `Keystore`, and builds the message. Every parameter is checked
+//! against the ABI when entered and asked again if it doesn't fit. Sending of
+//! the message is left to the binary.
+
+use std::io::BufRead;
+use std::io::Write;
+use std::sync::Arc;
+
+use serde_json::Map;
+use serde_json::Value;
+use tvm_abi::Function;
+use tvm_abi::ParamType;
+use tvm_abi::token::Tokenizer;
+use tvm_block::MsgAddressExt;
+use tvm_block::MsgAddressInt;
+use tvm_types::Result;
+use tvm_types::fail;
+
+use crate::AbiContract;
+use crate::Contract;
+use crate::ExpirationPolicy;
+use crate::FunctionCallSet;
+use crate::SdkMessage;
+use crate::Signer;
+use crate::SigningOptions;
+use crate::cli::input_value;
+pub use crate::signer::Keystore;
+
+pub struct Composer<'a, R, W> {
+    abi: String,
+    contract: Arc<AbiContract>,
+    address: MsgAddressInt,
+    keystore: &'a Keystore,
+    policy: Option<ExpirationPolicy>,
+    signing: SigningOptions,
+    reader: R,
+    writer: W,
+}
+
+impl<'a, R: BufRead, W: Write> Composer<'a, R, W> {
+    pub fn new(
+        abi: impl Into<String>,
+        address: MsgAddressInt,
+        keystore: &'a Keystore,
+        reader: R,
+        writer: W,
+    ) -> Result<Self> {
+        let abi = abi.into();
+        let contract = tvm_abi::AbiCache::global().load(&abi)?;
+        Ok(Self {
+            abi,
+            contract,
+            address,
+            keystore,
+            policy: None,
+            signing: SigningOptions::default(),
+            reader,
+            writer,
+        })
+    }
+
+    /// Policy filling header of the composed messages
+    pub fn with_expiration(mut self, policy: ExpirationPolicy) -> Self {
+        self.policy = Some(policy);
+        self
+    }
+
+    /// Options of signing the composed messages, e.g. with the signature id
+    /// of the network
+    pub fn with_signing_options(mut self, options: SigningOptions) -> Self {
+        self.signing = options;
+        self
+    }
+
+    /// Asks for the function, parameters and signer and builds the message
+    pub fn compose(&mut self) -> Result<SdkMessage> {
+        let call = self.compose_call()?;
+        let signer = self.choose_signer()?;
+        match &self.policy {
+            Some(policy) => Contract::construct_call_ext_in_message_with_policy(
+                self.address.clone(),
+                MsgAddressExt::default(),
+                &call,
+                signer,
+                policy,
+                self.signing,
+            ),
+            None => Contract::construct_call_ext_in_message_with_options(
+                self.address.clone(),
+                MsgAddressExt::default(),
+                &call,
+                signer,
+                self.signing,
+            ),
+        }
+    }
+
+    /// Asks for the function and its parameters
+    pub fn compose_call(&mut self) -> Result<FunctionCallSet> {
+        let contract = self.contract.clone();
+        let mut functions = contract
+            .functions()
+            .values()
+            .filter(|function| function.name != "constructor")
+            .collect::<Vec<_>>();
+        if functions.is_empty() {
+            fail!("Contract has no functions to call")
+        }
+        functions.sort_by(|a, b| a.name.cmp(&b.name));
+        let names = functions.iter().map(|function| function.name.as_str()).collect::<Vec<_>>();
+        let function = functions[self.choose("Function", &names)?];
+        let input = self.read_inputs(function)?;
+        Ok(FunctionCallSet {
+            func: function.name.clone(),
+            header: None,
+            input: input.to_string(),
+            abi: self.abi.clone(),
+        })
+    }
+
+    fn read_inputs(&mut self, function: &Function) -> Result<Value> {
+        let mut input = Map::new();
+        for param in &function.inputs {
+            let optional = matches!(param.kind, ParamType::Optional(_));
+            loop {
+                let prompt = match optional {
+                    true => format!("{} ({}, empty to skip)", param.name, param.kind),
+                    false => format!("{} ({})", param.name, param.kind),
+                };
+                let line = self.read_line(&prompt)?;
+                let value = match (line.is_empty(), optional) {
+                    (true, true) => Ok(Value::Null),
+                    _ => input_value(&param.kind, &line),
+                };
+                let checked = value.and_then(|value| {
+                    Tokenizer::tokenize_parameter(&param.kind, &value, &param.name)?;
+                    Ok(value)
+                });
+                match checked {
+                    Ok(Value::Null) => break,
+                    Ok(value) => {
+                        input.insert(param.name.clone(), value);
+                        break;
+                    }
+                    Err(err) => writeln!(self.writer, "Invalid {}: {}", param.name, err)?,
+                }
+            }
+        }
+        Ok(Value::Object(input))
+    }
+
+    fn choose_signer(&mut self) -> Result<Option<&'a dyn Signer>> {
+        if self.keystore.is_empty() {
+            return Ok(None);
+        }
+        let keystore = self.keystore;
+        let mut names = vec!["none"];
+        names.extend(keystore.names());
+        Ok(match self.choose("Signer", &names)? {
+            0 => None,
+            index => Some(keystore.signers[index - 1].1.as_ref()),
+        })
+    }
+
+    fn choose(&mut self, title: &str, items: &[&str]) -> Result<usize> {
+        for (index, item) in items.iter().enumerate() {
+            writeln!(self.writer, "{}) {}", index + 1, item)?;
+        }
+        loop {
+            let line = self.read_line(title)?;
+            match line.parse::<usize>() {
+                Ok(index) if index >= 1 && index <= items.len() => return Ok(index - 1),
+                _ => match items.iter().position(|item| *item == line) {
+                    Some(index) => return Ok(index),
+                    None => writeln!(self.writer, "Enter number from 1 to {}", items.len())?,
+                },
+            }
+        }
+    }
+
+    fn read_line(&mut self, prompt: &str) -> Result<String> {
+        write!(self.writer, "{}: ", prompt)?;
+        self.writer.flush()?;
+        let mut line = String::new();
+        if self.reader.read_line(&mut line)? == 0 {
+            fail!("Input is closed")
+        }
+        Ok(line.trim().to_owned())
+    }
+}
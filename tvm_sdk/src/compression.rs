@@ -0,0 +1,101 @@
+// Copyright 2018-2021 TON Labs LTD.
+//
+// Licensed under the SOFTWARE EVALUATION License (the "License"); you may not
+// use this file except in compliance with the License.
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific TON DEV software governing permissions and
+// limitations under the License.
+
+//! Zstd compression of serialized messages and contract images. Compressed
+//! data is recognized by the zstd frame magic, so readers accept both plain
+//! and compressed BOCs.
+
+use std::io::Cursor;
+use std::io::Read;
+
+use tvm_types::Result;
+use tvm_types::error;
+use tvm_types::fail;
+
+use crate::Contract;
+use crate::ContractImage;
+use crate::SdkMessage;
+use crate::error::SdkError;
+
+pub const DEFAULT_COMPRESSION_LEVEL: i32 = 3;
+/// Limit of the decompressed size protecting from decompression bombs
+pub const MAX_DECOMPRESSED_SIZE: u64 = 64 * 1024 * 1024;
+
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xB5, 0x2F, 0xFD];
+
+pub fn is_compressed(data: &[u8]) -> bool {
+    data.starts_with(&ZSTD_MAGIC)
+}
+
+/// Compresses the data with the zstd level, 1 to 22, 0 for the default one
+pub fn compress(data: &[u8], level: i32) -> Result<Vec<u8>> {
+    let mut compressed = Vec::new();
+    zstd::stream::copy_encode(&mut Cursor::new(data), &mut compressed, level).map_err(|err| {
+        error!(SdkError::InternalError { msg: format!("Cannot compress data: {}", err) })
+    })?;
+    Ok(compressed)
+}
+
+/// Decompresses zstd data, fails if it is longer than `max_size` bytes
+pub fn decompress(data: &[u8], max_size: u64) -> Result<Vec<u8>> {
+    let invalid = |err: std::io::Error| {
+        error!(SdkError::InvalidData { msg: format!("Cannot decompress data: {}", err) })
+    };
+    let mut decompressed = Vec::new();
+    zstd::stream::Decoder::new(Cursor::new(data))
+        .map_err(invalid)?
+        .take(max_size.saturating_add(1))
+        .read_to_end(&mut decompressed)
+        .map_err(invalid)?;
+    if decompressed.len() as u64 > max_size {
+        fail!(SdkError::InvalidData {
+            msg: format!("Decompressed data is longer than {} bytes", max_size)
+        })
+    }
+    Ok(decompressed)
+}
+
+/// Decompresses the data if it is compressed, returns it as is otherwise
+pub fn decompress_maybe(data: &[u8], max_size: u64) -> Result<Vec<u8>> {
+    if is_compressed(data) { decompress(data, max_size) } else { Ok(data.to_vec()) }
+}
+
+impl SdkMessage {
+    /// Serialized message compressed with the zstd level
+    pub fn serialize_compressed(&self, level: i32) -> Result<Vec<u8>> {
+        compress(&self.serialized_message, level)
+    }
+
+    /// Reads the message serialized either plain or compressed. The message
+    /// must have the destination, i.e. be inbound external or internal.
+    pub fn deserialize_maybe_compressed(data: &[u8]) -> Result<Self> {
+        let serialized_message = decompress_maybe(data, MAX_DECOMPRESSED_SIZE)?;
+        let message = Contract::deserialize_message(&serialized_message)?;
+        let Some(address) = message.dst_ref().cloned() else {
+            fail!(SdkError::InvalidData { msg: "Wrong message type (extOut)".to_owned() })
+        };
+        let (serialized_message, id) = Contract::serialize_message(&message)?;
+        Ok(Self { id, serialized_message, message, address })
+    }
+}
+
+impl ContractImage {
+    /// Serialized state init compressed with the zstd level
+    pub fn serialize_compressed(&self, level: i32) -> Result<Vec<u8>> {
+        compress(&self.serialize()?, level)
+    }
+
+    /// Reads the state init serialized either plain or compressed
+    pub fn deserialize_maybe_compressed(data: &[u8]) -> Result<Self> {
+        let data = decompress_maybe(data, MAX_DECOMPRESSED_SIZE)?;
+        Self::from_state_init(&mut Cursor::new(data))
+    }
+}
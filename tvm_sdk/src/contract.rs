@@ -9,14 +9,24 @@
 // See the License for the specific TON DEV software governing permissions and
 // limitations under the License.
 
+use std::collections::HashMap;
 use std::io::Read;
 use std::io::Seek;
 
 use chrono::prelude::Utc;
 use serde_json::Value;
+use tvm_abi::AbiCache;
+use tvm_abi::DecodedEvent;
+use tvm_abi::EventDecoder;
+use tvm_abi::ParamType;
 use tvm_abi::PublicKeyData;
 use tvm_abi::json_abi::DecodedMessage;
+use tvm_abi::token::Detokenizer;
+use tvm_abi::token::Token;
+use tvm_abi::token::Tokenizer;
+use tvm_block::Account;
 use tvm_block::AccountIdPrefixFull;
+use tvm_block::AnycastInfo;
 use tvm_block::CurrencyCollection;
 use tvm_block::Deserializable;
 use tvm_block::ExternalInboundMessageHeader;
@@ -25,25 +35,52 @@ use tvm_block::InternalMessageHeader;
 use tvm_block::Message as TvmMessage;
 use tvm_block::MsgAddressExt;
 use tvm_block::MsgAddressInt;
+use tvm_block::Number5;
 use tvm_block::Serializable;
+use tvm_block::ShardHashes;
 use tvm_block::ShardIdent;
+use tvm_block::SimpleLib;
 use tvm_block::StateInit;
+use tvm_block::TickTock;
 use tvm_types::AccountId;
 use tvm_types::BocReader;
+use tvm_types::BuilderData;
+use tvm_types::Cell;
+use tvm_types::CellType;
 use tvm_types::Ed25519PrivateKey;
+use tvm_types::IBitstring;
 use tvm_types::Result;
 use tvm_types::SliceData;
+use tvm_types::UInt256;
 use tvm_types::error;
 use tvm_types::fail;
 
 use crate::AbiContract;
+use crate::AbiSource;
+use crate::AccountInfo;
+use crate::AsyncSigner;
+use crate::BocInput;
+use crate::Capabilities;
+use crate::EncryptedFields;
+use crate::ExpirationPolicy;
 use crate::MessageId;
+use crate::ParsedBlock;
+use crate::ParsedMessage;
+use crate::ShardChain;
+use crate::SignatureScheme;
+use crate::Signer;
+use crate::SigningKey;
+use crate::TransactionInfo;
+use crate::address::AddressFormats;
+use crate::call_set::canonical_json;
 use crate::error::SdkError;
 use crate::json_helper;
 
+const BOUNCED_PREFIX: u32 = 0xFFFF_FFFF;
+
 pub struct Contract {}
 
-#[derive(Clone, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct FunctionCallSet {
     pub func: String,
     pub header: Option<String>,
@@ -51,6 +88,10 @@ pub struct FunctionCallSet {
     pub abi: String,
 }
 
+// Serialized with the message as base64 of its BOC, the id is checked
+// against the BOC on deserialization
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(try_from = "SerializedSdkMessage", into = "SerializedSdkMessage")]
 pub struct SdkMessage {
     pub id: MessageId,
     pub serialized_message: Vec<u8>,
@@ -58,6 +99,126 @@ pub struct SdkMessage {
     pub address: MsgAddressInt,
 }
 
+#[derive(Serialize, Deserialize)]
+struct SerializedSdkMessage {
+    id: MessageId,
+    #[serde(with = "json_helper::base64_bytes")]
+    message: Vec<u8>,
+    #[serde(with = "json_helper::address")]
+    address: MsgAddressInt,
+}
+
+impl From<SdkMessage> for SerializedSdkMessage {
+    fn from(message: SdkMessage) -> Self {
+        Self { id: message.id, message: message.serialized_message, address: message.address }
+    }
+}
+
+impl TryFrom<SerializedSdkMessage> for SdkMessage {
+    type Error = tvm_types::Error;
+
+    fn try_from(serialized: SerializedSdkMessage) -> Result<Self> {
+        let message = Contract::deserialize_message(&serialized.message)?;
+        let id: MessageId = message.serialize()?.repr_hash().into();
+        if id != serialized.id {
+            fail!(SdkError::InvalidData {
+                msg: format!("Message id {} doesn't match its BOC {}", serialized.id, id)
+            })
+        }
+        Ok(Self {
+            id,
+            serialized_message: serialized.message,
+            message,
+            address: serialized.address,
+        })
+    }
+}
+
+/// Hash used as the message id
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MessageIdKind {
+    /// Representation hash of the whole message, used by nodes and explorers
+    #[default]
+    Repr,
+    /// Hash of the external inbound message with `addr_none` source, zero
+    /// import fee, no state init and the body in reference, used by some
+    /// endpoints for deduplication. Messages differing only in those fields
+    /// have the same normalized hash.
+    Normalized,
+}
+
+impl SdkMessage {
+    /// Normalized hash of the external inbound message, `None` for internal
+    /// ones
+    pub fn normalized_id(&self) -> Result<Option<MessageId>> {
+        match self.message.ext_in_header() {
+            Some(_) => Ok(Some(Contract::message_id(&self.message, MessageIdKind::Normalized)?)),
+            None => Ok(None),
+        }
+    }
+
+    pub fn id_of(&self, kind: MessageIdKind) -> Result<MessageId> {
+        match kind {
+            MessageIdKind::Repr => Ok(self.id.clone()),
+            MessageIdKind::Normalized => Contract::message_id(&self.message, kind),
+        }
+    }
+
+    /// Workchain and first 64 bits of the destination account id
+    pub fn account_prefix(&self) -> Result<AccountIdPrefixFull> {
+        AccountIdPrefixFull::prefix(&self.address)
+    }
+
+    /// Routing of the message in the workchain split into shards of
+    /// `split_depth` prefix bits, e.g. the depth of the destination
+    /// workchain known from its config
+    pub fn routing(&self, split_depth: u8) -> Result<MessageRouting> {
+        let prefix = self.account_prefix()?;
+        Ok(MessageRouting {
+            workchain_id: prefix.workchain_id,
+            account_prefix: prefix.prefix,
+            shard: ShardIdent::with_prefix_len(split_depth, prefix.workchain_id, prefix.prefix)?,
+        })
+    }
+
+    /// Whether the destination account is in the shard, in the format of
+    /// `Contract::check_shard_match`
+    pub fn matches_shard(&self, shard: &ShardDescr) -> Result<bool> {
+        let ident = ShardIdent::with_tagged_prefix(shard.workchain_id, shard.shard)?;
+        Ok(ident.contains_full_prefix(&self.account_prefix()?))
+    }
+}
+
+/// Shard of the message destination, to pick the endpoint or the validator
+/// group to send the message to
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MessageRouting {
+    pub workchain_id: i32,
+    /// First 64 bits of the destination account id
+    pub account_prefix: u64,
+    /// Shard of the split depth containing the destination
+    pub shard: ShardIdent,
+}
+
+pub struct RetargetedMessage {
+    pub message: SdkMessage,
+    /// Message body is signed together with the old destination address, so
+    /// it must be signed again for the new one
+    pub resign_required: bool,
+}
+
+/// Call recovered from the body of a bounced message. Bounced body keeps only
+/// the first 256 bits of the original one, so trailing parameters of the call
+/// are usually lost.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BouncedCall {
+    pub function_name: String,
+    /// Leading input parameters fitting into the bounced body, as JSON
+    pub params: String,
+    /// Whether all input parameters were decoded
+    pub complete: bool,
+}
+
 // The struct represents contract's image
 #[derive(Clone)]
 pub struct ContractImage {
@@ -65,6 +226,24 @@ pub struct ContractImage {
     id: AccountId,
 }
 
+// Serialized as base64 of the state init BOC
+impl serde::Serialize for ContractImage {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        let boc = self.serialize().map_err(serde::ser::Error::custom)?;
+        json_helper::base64_bytes::serialize(&boc, serializer)
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for ContractImage {
+    fn deserialize<D: serde::Deserializer<'de>>(d: D) -> std::result::Result<Self, D::Error> {
+        let boc = json_helper::base64_bytes::deserialize(d)?;
+        Self::from_state_init(&mut std::io::Cursor::new(boc)).map_err(serde::de::Error::custom)
+    }
+}
+
 impl ContractImage {
     // Creating contract image from code data and library bags of cells
     pub fn from_code_data_and_library<T>(
@@ -110,6 +289,46 @@ impl ContractImage {
         Ok(Self { state_init, id })
     }
 
+    /// Same as `from_state_init` but takes hashes stored in the BOC cells
+    /// without recomputing them. Speeds up loading of big states serialized
+    /// with hashes, must be used only for BOCs from a trusted source.
+    pub fn from_trusted_state_init<T>(state_init_bag: &mut T) -> Result<Self>
+    where
+        T: Read + Seek,
+    {
+        let cell = BocReader::new()
+            .set_verify_hashes(false)
+            .read(state_init_bag)?
+            .withdraw_single_root()?;
+        let state_init: StateInit = StateInit::construct_from_cell(cell)?;
+        let id = state_init.hash()?.into();
+
+        Ok(Self { state_init, id })
+    }
+
+    /// Same as `from_state_init` but for untrusted input: fails BOCs longer
+    /// than `max_size` bytes or declaring more than `max_cells` cells in the
+    /// header, before any cell is read. The input is not streamed: a BOC
+    /// within the limits is still read and its cells are built in memory.
+    pub fn from_state_init_limited<T>(
+        state_init_bag: &mut T,
+        max_cells: usize,
+        max_size: u64,
+    ) -> Result<Self>
+    where
+        T: Read + Seek,
+    {
+        let cell = BocReader::new()
+            .set_max_cells(max_cells)
+            .set_max_size(max_size)
+            .read(state_init_bag)?
+            .withdraw_single_root()?;
+        let state_init: StateInit = StateInit::construct_from_cell(cell)?;
+        let id = state_init.hash()?.into();
+
+        Ok(Self { state_init, id })
+    }
+
     pub fn from_state_init_and_key<T>(
         state_init_bag: &mut T,
         pub_key: &PublicKeyData,
@@ -130,6 +349,46 @@ impl ContractImage {
         Ok(Self { state_init, id })
     }
 
+    /// Same as `from_state_init` but takes the bag of cells in any form, e.g.
+    /// base64 of the TVC
+    pub fn from_boc<'a>(state_init: impl Into<BocInput<'a>>) -> Result<Self> {
+        Self::from_cell(state_init.into().to_cell()?)
+    }
+
+    pub fn from_boc_and_key<'a>(
+        state_init: impl Into<BocInput<'a>>,
+        pub_key: &PublicKeyData,
+    ) -> Result<Self> {
+        let mut result = Self::from_boc(state_init)?;
+        result.set_public_key(pub_key)?;
+
+        Ok(result)
+    }
+
+    /// Same as `from_code_data_and_library` but takes the bags of cells in any
+    /// form
+    pub fn from_code_data_and_library_boc<'a>(
+        code: impl Into<BocInput<'a>>,
+        data: Option<BocInput<'a>>,
+        library: Option<BocInput<'a>>,
+    ) -> Result<Self> {
+        let mut state_init = StateInit::default();
+
+        state_init.set_code(code.into().to_cell()?);
+
+        if let Some(data) = data {
+            state_init.set_data(data.to_cell()?);
+        }
+
+        if let Some(library) = library {
+            state_init.set_library(library.to_cell()?);
+        }
+
+        let id = AccountId::from(state_init.hash()?);
+
+        Ok(Self { state_init, id })
+    }
+
     pub fn get_public_key(&self) -> Result<Option<PublicKeyData>> {
         let Some(data) = self.state_init.data.clone() else {
             return Ok(None);
@@ -151,6 +410,82 @@ impl ContractImage {
         Ok(())
     }
 
+    // Returns salt of the contract code, `None` if the code has no salt
+    pub fn get_code_salt(&self) -> Result<Option<Cell>> {
+        match &self.state_init.code {
+            Some(code) => crate::code_salt::get_code_salt(code),
+            None => fail!(SdkError::InvalidData { msg: "State init has no code".to_owned() }),
+        }
+    }
+
+    // Sets or replaces salt of the contract code. The contract address changes
+    // with the code.
+    pub fn set_code_salt(&mut self, salt: Cell) -> Result<()> {
+        let Some(code) = &self.state_init.code else {
+            fail!(SdkError::InvalidData { msg: "State init has no code".to_owned() })
+        };
+        let code = crate::code_salt::set_code_salt(code, salt)?;
+        self.state_init.set_code(code);
+        self.id = self.state_init.hash()?.into();
+        Ok(())
+    }
+
+    // Sets split depth of the state init, `None` removes it
+    pub fn set_split_depth(&mut self, split_depth: Option<u32>) -> Result<()> {
+        self.state_init.split_depth = split_depth.map(Number5::new).transpose()?;
+        self.id = self.state_init.hash()?.into();
+        Ok(())
+    }
+
+    // Makes the contract special with tick transactions. Special flags are
+    // removed from the state init when both tick and tock are off.
+    pub fn set_tick(&mut self, tick: bool) -> Result<()> {
+        let tock = self.state_init.special.as_ref().is_some_and(|special| special.tock);
+        self.set_tick_tock(tick, tock)
+    }
+
+    // Makes the contract special with tock transactions. Special flags are
+    // removed from the state init when both tick and tock are off.
+    pub fn set_tock(&mut self, tock: bool) -> Result<()> {
+        let tick = self.state_init.special.as_ref().is_some_and(|special| special.tick);
+        self.set_tick_tock(tick, tock)
+    }
+
+    fn set_tick_tock(&mut self, tick: bool, tock: bool) -> Result<()> {
+        self.state_init.special = (tick || tock).then(|| TickTock::with_values(tick, tock));
+        self.id = self.state_init.hash()?.into();
+        Ok(())
+    }
+
+    // Adds library cell to the state init, keyed by its representation hash.
+    // Returns the hash.
+    pub fn add_library(&mut self, cell: Cell, public: bool) -> Result<UInt256> {
+        let hash = cell.repr_hash();
+        self.state_init.set_library_code(cell, public)?;
+        self.id = self.state_init.hash()?.into();
+        Ok(hash)
+    }
+
+    // Removes library with the hash, returns `false` if there is no such
+    // library
+    pub fn remove_library(&mut self, hash: &UInt256) -> Result<bool> {
+        if !self.state_init.library.remove(hash)? {
+            return Ok(false);
+        }
+        self.id = self.state_init.hash()?.into();
+        Ok(true)
+    }
+
+    // Libraries of the state init with their hashes
+    pub fn libraries(&self) -> Result<Vec<(UInt256, SimpleLib)>> {
+        let mut libraries = Vec::new();
+        self.state_init.library.iterate_with_keys(|hash: UInt256, library: SimpleLib| {
+            libraries.push((hash, library));
+            Ok(true)
+        })?;
+        Ok(libraries)
+    }
+
     pub fn get_serialized_code(&self) -> Result<Vec<u8>> {
         match &self.state_init.code {
             Some(cell) => tvm_types::boc::write_boc(cell),
@@ -178,17 +513,55 @@ impl ContractImage {
         self.state_init
     }
 
+    // Returns reference to future contract's state_init struct
+    pub fn state_init_ref(&self) -> &StateInit {
+        &self.state_init
+    }
+
     // Returns future contract's identifier
     pub fn account_id(&self) -> AccountId {
         self.id.clone()
     }
 
-    // Returns future contract's address
+    // Returns future contract's address in all formats
+    pub fn addresses(&self, workchain_id: i32) -> Result<AddressFormats> {
+        AddressFormats::new(&self.msg_address(workchain_id))
+    }
+
+    // Returns future contract's address. Panics if the address can't be built,
+    // see `try_msg_address`.
     pub fn msg_address(&self, workchain_id: i32) -> MsgAddressInt {
-        match workchain_id / 128 {
-            0 => MsgAddressInt::with_standart(None, workchain_id as i8, self.id.clone()).unwrap(),
-            _ => MsgAddressInt::with_variant(None, workchain_id, self.id.clone()).unwrap(),
+        self.try_msg_address(workchain_id, None).unwrap()
+    }
+
+    // Returns future contract's address, optionally with anycast info. Workchain
+    // ids fitting into 8 bits, including masterchain -1, give standard
+    // addresses, others give variable length ones. Anycast depth must be equal
+    // to the split depth of the contract state init.
+    pub fn try_msg_address(
+        &self,
+        workchain_id: i32,
+        anycast: Option<AnycastInfo>,
+    ) -> Result<MsgAddressInt> {
+        if let Some(anycast) = &anycast {
+            let depth = anycast.depth.as_usize();
+            if anycast.rewrite_pfx.remaining_bits() != depth {
+                fail!(SdkError::InvalidData {
+                    msg: format!("Anycast prefix length differs from its depth {}", depth)
+                })
+            }
+            match self.state_init.split_depth() {
+                Some(split_depth) if split_depth.as_usize() == depth => {}
+                split_depth => fail!(SdkError::InvalidData {
+                    msg: format!(
+                        "Anycast depth {} doesn't match split depth {:?} of the contract",
+                        depth,
+                        split_depth.map(|split_depth| split_depth.as_usize())
+                    )
+                }),
+            }
         }
+        address_in_workchain(anycast, workchain_id, self.id.clone())
     }
 
     /// Allows to change initial values for public contract variables
@@ -214,13 +587,127 @@ impl ContractImage {
 
         Ok(())
     }
+
+    /// Encodes initial data with the public key and the init params given as
+    /// JSON object in one step. All init params must be supplied: all data
+    /// items of ABI before 2.4, or storage fields marked as `init` of later
+    /// ones, where the public key goes to the `_pubkey` field. Missing and
+    /// unexpected params are reported together, the image is left unchanged on
+    /// error.
+    pub fn encode_init_data(
+        &mut self,
+        pubkey: Option<&PublicKeyData>,
+        init_params: &str,
+        abi: &str,
+    ) -> Result<()> {
+        let contract = AbiCache::global().load(abi)?;
+        let mut params = match serde_json::from_str(init_params)? {
+            Value::Object(params) => params,
+            Value::Null => serde_json::Map::new(),
+            _ => fail!(SdkError::InvalidData { msg: "Init params must be JSON object".to_owned() }),
+        };
+        let data_map = contract.data_map_supported();
+        let expected = if data_map {
+            contract.data().keys().map(String::as_str).collect::<Vec<_>>()
+        } else {
+            contract
+                .fields()
+                .iter()
+                .map(|field| field.name.as_str())
+                .filter(|name| contract.init_fields().contains(*name))
+                .collect()
+        };
+        if let (Some(pubkey), false) = (pubkey, data_map) {
+            if !contract.init_fields().contains("_pubkey") {
+                fail!(SdkError::InvalidData {
+                    msg: "ABI has no `_pubkey` init field for the public key".to_owned()
+                })
+            }
+            if params
+                .insert("_pubkey".to_owned(), format!("0x{}", hex::encode(pubkey)).into())
+                .is_some()
+            {
+                fail!(SdkError::InvalidData {
+                    msg: "Public key is given both as argument and `_pubkey` param".to_owned()
+                })
+            }
+        }
+        let mut missing =
+            expected.iter().copied().filter(|name| !params.contains_key(*name)).collect::<Vec<_>>();
+        let mut unexpected = params
+            .keys()
+            .map(String::as_str)
+            .filter(|name| !expected.contains(name))
+            .collect::<Vec<_>>();
+        if !missing.is_empty() || !unexpected.is_empty() {
+            missing.sort_unstable();
+            unexpected.sort_unstable();
+            let mut problems = Vec::new();
+            if !missing.is_empty() {
+                problems.push(format!("missing {}", missing.join(", ")));
+            }
+            if !unexpected.is_empty() {
+                problems.push(format!("unexpected {}", unexpected.join(", ")));
+            }
+            fail!(SdkError::InvalidData {
+                msg: format!("Invalid init params: {}", problems.join("; "))
+            })
+        }
+
+        let params = Value::Object(params).to_string();
+        let new_data = if data_map {
+            let mut data = tvm_abi::json_abi::update_contract_data(
+                abi,
+                &params,
+                SliceData::load_cell(self.state_init.data.clone().unwrap_or_default())?,
+            )?;
+            if let Some(pubkey) = pubkey {
+                data = AbiContract::insert_pubkey(data, pubkey)?;
+            }
+            data.into_cell()
+        } else {
+            tvm_abi::json_abi::encode_storage_fields(abi, Some(&params))?.into_cell()?
+        };
+
+        self.state_init.set_data(new_data);
+        self.id = self.state_init.hash()?.into();
+
+        Ok(())
+    }
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub struct MessageToSign {
+    #[serde(with = "json_helper::base64_bytes")]
     pub message: Vec<u8>,
+    #[serde(with = "json_helper::base64_bytes")]
     pub data_to_sign: Vec<u8>,
 }
 
+/// Options of signing external messages
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SigningOptions {
+    /// Signature id prepended to the data to sign, required by networks with
+    /// `CapSignatureWithId`
+    pub signature_id: Option<i32>,
+}
+
+impl SigningOptions {
+    /// Options of signing for the network with the capabilities and the
+    /// global id
+    pub fn for_network(capabilities: &Capabilities, global_id: i32) -> Self {
+        Self { signature_id: capabilities.signature_id(global_id) }
+    }
+
+    /// Data to sign prefixed with the big-endian signature id, if any
+    pub fn data_to_sign(&self, data: Vec<u8>) -> Vec<u8> {
+        match self.signature_id {
+            Some(signature_id) => [&signature_id.to_be_bytes()[..], &data].concat(),
+            None => data,
+        }
+    }
+}
+
 #[derive(Deserialize, Debug, Clone)]
 pub struct ShardDescr {
     pub workchain_id: i32,
@@ -231,81 +718,187 @@ pub struct ShardDescr {
 impl Contract {
     /// Decodes output parameters returned by contract function call
     pub fn decode_function_response_json(
-        abi: &str,
+        abi: &(impl AbiSource + ?Sized),
         function: &str,
         response: SliceData,
         internal: bool,
         allow_partial: bool,
     ) -> Result<String> {
-        tvm_abi::json_abi::decode_function_response(
-            abi,
-            function,
-            response,
-            internal,
-            allow_partial,
-        )
+        let contract = abi.abi_contract()?;
+        let tokens =
+            contract.function(function)?.decode_output(response, internal, allow_partial)?;
+
+        Detokenizer::detokenize(&tokens)
     }
 
     /// Decodes output parameters returned by contract function call from
     /// serialized message body
-    pub fn decode_function_response_from_bytes_json(
-        abi: &str,
+    pub fn decode_function_response_from_bytes_json<'a>(
+        abi: &(impl AbiSource + ?Sized),
         function: &str,
-        response: &[u8],
+        response: impl Into<BocInput<'a>>,
         internal: bool,
         allow_partial: bool,
     ) -> Result<String> {
-        let slice = Self::deserialize_tree_to_slice(response)?;
+        let slice = response.into().to_slice()?;
 
         Self::decode_function_response_json(abi, function, slice, internal, allow_partial)
     }
 
     /// Decodes output parameters returned by contract function call
     pub fn decode_unknown_function_response_json(
-        abi: &str,
+        abi: &(impl AbiSource + ?Sized),
         response: SliceData,
         internal: bool,
         allow_partial: bool,
     ) -> Result<DecodedMessage> {
-        tvm_abi::json_abi::decode_unknown_function_response(abi, response, internal, allow_partial)
+        let result = abi.abi_contract()?.decode_output(response, internal, allow_partial)?;
+
+        Ok(DecodedMessage {
+            function_name: result.function_name,
+            params: Detokenizer::detokenize(&result.tokens)?,
+        })
     }
 
     /// Decodes output parameters returned by contract function call from
     /// serialized message body
-    pub fn decode_unknown_function_response_from_bytes_json(
-        abi: &str,
-        response: &[u8],
+    pub fn decode_unknown_function_response_from_bytes_json<'a>(
+        abi: &(impl AbiSource + ?Sized),
+        response: impl Into<BocInput<'a>>,
         internal: bool,
         allow_partial: bool,
     ) -> Result<DecodedMessage> {
-        let slice = Self::deserialize_tree_to_slice(response)?;
+        let slice = response.into().to_slice()?;
 
         Self::decode_unknown_function_response_json(abi, slice, internal, allow_partial)
     }
 
     /// Decodes output parameters returned by contract function call
     pub fn decode_unknown_function_call_json(
-        abi: &str,
+        abi: &(impl AbiSource + ?Sized),
         response: SliceData,
         internal: bool,
         allow_partial: bool,
     ) -> Result<DecodedMessage> {
-        tvm_abi::json_abi::decode_unknown_function_call(abi, response, internal, allow_partial)
+        let result = abi.abi_contract()?.decode_input(response, internal, allow_partial)?;
+
+        Ok(DecodedMessage {
+            function_name: result.function_name,
+            params: Detokenizer::detokenize(&result.tokens)?,
+        })
     }
 
     /// Decodes output parameters returned by contract function call from
     /// serialized message body
-    pub fn decode_unknown_function_call_from_bytes_json(
-        abi: &str,
-        response: &[u8],
+    pub fn decode_unknown_function_call_from_bytes_json<'a>(
+        abi: &(impl AbiSource + ?Sized),
+        response: impl Into<BocInput<'a>>,
         internal: bool,
         allow_partial: bool,
     ) -> Result<DecodedMessage> {
-        let slice = Self::deserialize_tree_to_slice(response)?;
+        let slice = response.into().to_slice()?;
 
         Self::decode_unknown_function_call_json(abi, slice, internal, allow_partial)
     }
 
+    /// Decodes single input parameter of the function call from serialized
+    /// message body, without decoding other parameters. `field_path` is the
+    /// parameter name, with tuple components separated by dots.
+    pub fn decode_field_json(
+        abi: &(impl AbiSource + ?Sized),
+        function: &str,
+        body: &[u8],
+        internal: bool,
+        field_path: &str,
+    ) -> Result<String> {
+        let slice = Self::deserialize_tree_to_slice(body)?;
+        let contract = abi.abi_contract()?;
+
+        let path: Vec<&str> = field_path.split('.').collect();
+        let name = path[path.len() - 1];
+        let value = contract.function(function)?.decode_input_field(slice, internal, &path)?;
+        let mut json =
+            Detokenizer::detokenize_to_json_value(&[Token { name: name.to_owned(), value }])?;
+        Ok(json[name].take().to_string())
+    }
+
+    /// Decodes the original call from serialized bounced internal message.
+    /// Returns `None` if the message is not bounced.
+    pub fn decode_bounced_message(
+        abi: &(impl AbiSource + ?Sized),
+        message: &[u8],
+    ) -> Result<Option<BouncedCall>> {
+        let message = Self::deserialize_message(message)?;
+        if !message.int_header().is_some_and(|header| header.bounced) {
+            return Ok(None);
+        }
+        let Some(mut body) = message.body() else {
+            return Ok(None);
+        };
+        if body.get_next_u32().ok() != Some(BOUNCED_PREFIX) {
+            return Ok(None);
+        }
+
+        let contract = abi.abi_contract()?;
+        let function = contract.function_by_id(body.clone().get_next_u32()?, true)?;
+        for count in (0..=function.inputs.len()).rev() {
+            let mut partial = function.clone();
+            partial.inputs.truncate(count);
+            if let Ok(tokens) = partial.decode_input(body.clone(), true, true) {
+                return Ok(Some(BouncedCall {
+                    function_name: function.name.clone(),
+                    params: Detokenizer::detokenize(&tokens)?,
+                    complete: count == function.inputs.len(),
+                }));
+            }
+        }
+        fail!(SdkError::InvalidData {
+            msg: format!("Bounced call of {} doesn't match ABI", function.name)
+        })
+    }
+
+    /// Runs the get-method of the account locally and returns the resulting
+    /// stack as JSON array. See `executor::run_get_method` for the format of
+    /// stack items.
+    #[cfg(feature = "executor")]
+    pub fn run_get_method(
+        account_boc: &[u8],
+        method_name: &str,
+        stack_params: &[Value],
+    ) -> Result<Value> {
+        crate::executor::run_get_method(account_boc, method_name, stack_params).map(Value::Array)
+    }
+
+    /// Decodes events from serialized messages. Messages other than external
+    /// outbound ones and bodies not matching ABI events, e.g. function
+    /// responses, are skipped.
+    pub fn decode_events_json(
+        abi: &(impl AbiSource + ?Sized),
+        messages: &[Vec<u8>],
+    ) -> Result<Vec<DecodedMessage>> {
+        let decoder = EventDecoder::new(abi.abi_contract()?);
+        let mut event = DecodedEvent::default();
+        let mut events = Vec::new();
+        for message in messages {
+            let message = Self::deserialize_message(message)?;
+            if message.ext_out_header().is_none() {
+                continue;
+            }
+            let Some(body) = message.body() else {
+                continue;
+            };
+            if decoder.event(&body).is_err() {
+                continue;
+            }
+            decoder.decode_into(body, &mut event)?;
+            events.push(DecodedMessage {
+                function_name: event.name.clone(),
+                params: Detokenizer::detokenize(&event.tokens)?,
+            });
+        }
+        Ok(events)
+    }
+
     // ------- Call constructing functions -------
 
     // Packs given inputs by abi into an external inbound Message struct.
@@ -315,7 +908,124 @@ impl Contract {
         address: MsgAddressInt,
         src_address: MsgAddressExt,
         params: &FunctionCallSet,
-        key_pair: Option<&Ed25519PrivateKey>,
+        signer: Option<&dyn Signer>,
+    ) -> Result<SdkMessage> {
+        Self::construct_call_ext_in_message_with_options(
+            address,
+            src_address,
+            params,
+            signer,
+            SigningOptions::default(),
+        )
+    }
+
+    // Same as `construct_call_ext_in_message_json` but signs with the options,
+    // e.g. with the signature id of the network.
+    pub fn construct_call_ext_in_message_with_options(
+        address: MsgAddressInt,
+        src_address: MsgAddressExt,
+        params: &FunctionCallSet,
+        signer: Option<&dyn Signer>,
+        options: SigningOptions,
+    ) -> Result<SdkMessage> {
+        let Some(signer) = signer else {
+            return Self::construct_unsigned_call_ext_in_message(address, src_address, params);
+        };
+        let public_key = Self::header_public_key(signer)?;
+        let params = Self::set_header_public_key(params, public_key.as_ref())?;
+        let message = Self::get_call_message_bytes_for_signing_with_options(
+            address,
+            src_address,
+            &params,
+            options,
+        )?;
+        let signature = signer.sign(&message.data_to_sign)?;
+        Self::add_sign_to_message(
+            &params.abi,
+            &signature,
+            public_key.as_ref().map(|public_key| public_key.as_slice()),
+            &message.message,
+        )
+    }
+
+    // Same as `construct_call_ext_in_message_with_options` but the signature
+    // goes to the `bytes` parameter of the body instead of the ABI signature,
+    // e.g. for 96 bytes BLS signatures. The key signs the data the ABI
+    // signature would be made of, the hash of the body with the parameter
+    // empty, prepared by the options.
+    pub fn construct_call_ext_in_message_signed_in_body(
+        address: MsgAddressInt,
+        src_address: MsgAddressExt,
+        params: &FunctionCallSet,
+        key: &SigningKey,
+        param: &str,
+        options: SigningOptions,
+    ) -> Result<SdkMessage> {
+        let mut input: serde_json::Map<String, Value> = serde_json::from_str(&params.input)?;
+        input.insert(param.to_owned(), "".into());
+        let unsigned =
+            FunctionCallSet { input: Value::Object(input.clone()).to_string(), ..params.clone() };
+        let message = Self::get_call_message_bytes_for_signing_with_options(
+            address.clone(),
+            src_address.clone(),
+            &unsigned,
+            options,
+        )?;
+        input.insert(param.to_owned(), hex::encode(key.sign(&message.data_to_sign)?).into());
+        let signed = FunctionCallSet { input: Value::Object(input).to_string(), ..params.clone() };
+        Self::construct_unsigned_call_ext_in_message(address, src_address, &signed)
+    }
+
+    // Same as `construct_call_ext_in_message_json` but waits for the signer.
+    pub async fn construct_call_ext_in_message_async(
+        address: MsgAddressInt,
+        src_address: MsgAddressExt,
+        params: &FunctionCallSet,
+        signer: &dyn AsyncSigner,
+    ) -> Result<SdkMessage> {
+        Self::construct_call_ext_in_message_async_with_options(
+            address,
+            src_address,
+            params,
+            signer,
+            SigningOptions::default(),
+        )
+        .await
+    }
+
+    // Same as `construct_call_ext_in_message_async` but signs with the
+    // options.
+    pub async fn construct_call_ext_in_message_async_with_options(
+        address: MsgAddressInt,
+        src_address: MsgAddressExt,
+        params: &FunctionCallSet,
+        signer: &dyn AsyncSigner,
+        options: SigningOptions,
+    ) -> Result<SdkMessage> {
+        let public_key = match signer.scheme() {
+            SignatureScheme::Ed25519 => Some(signer.public_key().await?),
+            _ => None,
+        };
+        let params = Self::set_header_public_key(params, public_key.as_ref())?;
+        let message = Self::get_call_message_bytes_for_signing_with_options(
+            address,
+            src_address,
+            &params,
+            options,
+        )?;
+        let signature = signer.sign(&message.data_to_sign).await?;
+        Self::add_sign_to_message(
+            &params.abi,
+            &signature,
+            public_key.as_ref().map(|public_key| public_key.as_slice()),
+            &message.message,
+        )
+    }
+
+    fn construct_unsigned_call_ext_in_message(
+        address: MsgAddressInt,
+        src_address: MsgAddressExt,
+        params: &FunctionCallSet,
     ) -> Result<SdkMessage> {
         // pack params into bag of cells via ABI
         let msg_body = tvm_abi::encode_function_call(
@@ -324,7 +1034,7 @@ impl Contract {
             params.header.as_deref(),
             &params.input,
             false,
-            key_pair,
+            None,
             Some(&address.to_string()),
         )?;
         let msg = Self::create_ext_in_message(
@@ -336,6 +1046,89 @@ impl Contract {
         Ok(SdkMessage { id, serialized_message: body, message: msg, address })
     }
 
+    // Same as `construct_call_ext_in_message_with_options` but fills `time`,
+    // `expire` and `pubkey` header parameters by the policy.
+    pub fn construct_call_ext_in_message_with_policy(
+        address: MsgAddressInt,
+        src_address: MsgAddressExt,
+        params: &FunctionCallSet,
+        signer: Option<&dyn Signer>,
+        policy: &ExpirationPolicy,
+        options: SigningOptions,
+    ) -> Result<SdkMessage> {
+        let params = policy.apply(params)?;
+        Self::construct_call_ext_in_message_with_options(
+            address,
+            src_address,
+            &params,
+            signer,
+            options,
+        )
+    }
+
+    // Packs unsigned external calls of contracts with the same ABI, looking the
+    // ABI up once. Each call is given as destination address, function name
+    // and JSON input. Repeated identical calls are encoded once.
+    pub fn construct_call_batch(
+        abi: &(impl AbiSource + ?Sized),
+        src_address: MsgAddressExt,
+        calls: &[(MsgAddressInt, &str, &str)],
+    ) -> Result<Vec<SdkMessage>> {
+        let abi = abi.abi_contract()?;
+        let header = HashMap::new();
+        let mut encoded: HashMap<(String, &str, String), SdkMessage> = HashMap::new();
+        calls
+            .iter()
+            .map(|(address, func, input)| {
+                let key = (address.to_string(), *func, canonical_json(input)?);
+                if let Some(message) = encoded.get(&key) {
+                    return Ok(message.clone());
+                }
+                let function = abi.function(func)?;
+                let input: Value = serde_json::from_str(input)?;
+                let input = Tokenizer::tokenize_all_params(function.input_params(), &input)?;
+                let msg_body =
+                    function.encode_input(&header, &input, false, None, Some(address.clone()))?;
+                let msg = Self::create_ext_in_message(
+                    address.clone(),
+                    src_address.clone(),
+                    SliceData::load_cell(msg_body.into_cell()?)?,
+                )?;
+                let (body, id) = Self::serialize_message(&msg)?;
+                let message = SdkMessage {
+                    id,
+                    serialized_message: body,
+                    message: msg,
+                    address: address.clone(),
+                };
+                encoded.insert(key, message.clone());
+                Ok(message)
+            })
+            .collect()
+    }
+
+    // Packs given inputs by abi into a message body without message wrapper.
+    // Works with json representation of input and abi.
+    // Returns body's bag of cells. `address` is required to sign external call
+    // bodies of ABI versions binding signature to the destination address.
+    pub fn encode_body_only(
+        params: &FunctionCallSet,
+        internal: bool,
+        key_pair: Option<&Ed25519PrivateKey>,
+        address: Option<&MsgAddressInt>,
+    ) -> Result<Vec<u8>> {
+        let msg_body = tvm_abi::encode_function_call(
+            &params.abi,
+            &params.func,
+            params.header.as_deref(),
+            &params.input,
+            internal,
+            key_pair,
+            address.map(|address| address.to_string()).as_deref(),
+        )?;
+        tvm_types::boc::write_boc(&msg_body.into_cell()?)
+    }
+
     // Packs given inputs by abi into an internal Message struct.
     // Works with json representation of input and abi.
     // Returns message's bag of cells and identifier.
@@ -344,7 +1137,7 @@ impl Contract {
         src_address: Option<MsgAddressInt>,
         ihr_disabled: bool,
         bounce: bool,
-        value: CurrencyCollection,
+        value: impl Into<CurrencyCollection>,
         params: &FunctionCallSet,
     ) -> Result<SdkMessage> {
         // pack params into bag of cells via ABI
@@ -363,7 +1156,7 @@ impl Contract {
             src_address,
             ihr_disabled,
             bounce,
-            value,
+            value.into(),
             Some(SliceData::load_cell(msg_body.into_cell()?)?),
         )
     }
@@ -395,6 +1188,22 @@ impl Contract {
         dst_address: MsgAddressInt,
         src_address: MsgAddressExt,
         params: &FunctionCallSet,
+    ) -> Result<MessageToSign> {
+        Self::get_call_message_bytes_for_signing_with_options(
+            dst_address,
+            src_address,
+            params,
+            SigningOptions::default(),
+        )
+    }
+
+    // Same as `get_call_message_bytes_for_signing` but the data to sign is
+    // prepared by the options, e.g. prefixed with the signature id.
+    pub fn get_call_message_bytes_for_signing_with_options(
+        dst_address: MsgAddressInt,
+        src_address: MsgAddressExt,
+        params: &FunctionCallSet,
+        options: SigningOptions,
     ) -> Result<MessageToSign> {
         // pack params into bag of cells via ABI
         let (msg_body, data_to_sign) = tvm_abi::prepare_function_call_for_sign(
@@ -410,8 +1219,10 @@ impl Contract {
             SliceData::load_cell(msg_body.into_cell()?)?,
         )?;
 
-        Self::serialize_message(&msg)
-            .map(|(msg_data, _id)| MessageToSign { message: msg_data, data_to_sign })
+        Self::serialize_message(&msg).map(|(msg_data, _id)| MessageToSign {
+            message: msg_data,
+            data_to_sign: options.data_to_sign(data_to_sign),
+        })
     }
 
     // ------- Deploy constructing functions -------
@@ -422,7 +1233,120 @@ impl Contract {
     pub fn construct_deploy_message_json(
         params: &FunctionCallSet,
         image: ContractImage,
-        key_pair: Option<&Ed25519PrivateKey>,
+        signer: Option<&dyn Signer>,
+        workchain_id: i32,
+        src_address: MsgAddressExt,
+    ) -> Result<SdkMessage> {
+        Self::construct_deploy_message_json_ref(params, &image, signer, workchain_id, src_address)
+    }
+
+    // Same as `construct_deploy_message_json` but leaves the image to the
+    // caller.
+    pub fn construct_deploy_message_json_ref(
+        params: &FunctionCallSet,
+        image: &ContractImage,
+        signer: Option<&dyn Signer>,
+        workchain_id: i32,
+        src_address: MsgAddressExt,
+    ) -> Result<SdkMessage> {
+        Self::construct_deploy_message_with_options(
+            params,
+            image,
+            signer,
+            workchain_id,
+            src_address,
+            SigningOptions::default(),
+        )
+    }
+
+    // Same as `construct_deploy_message_json_ref` but signs with the options,
+    // e.g. with the signature id of the network.
+    pub fn construct_deploy_message_with_options(
+        params: &FunctionCallSet,
+        image: &ContractImage,
+        signer: Option<&dyn Signer>,
+        workchain_id: i32,
+        src_address: MsgAddressExt,
+        options: SigningOptions,
+    ) -> Result<SdkMessage> {
+        let Some(signer) = signer else {
+            return Self::construct_unsigned_deploy_message(
+                params,
+                image,
+                workchain_id,
+                src_address,
+            );
+        };
+        let public_key = Self::header_public_key(signer)?;
+        let params = Self::set_header_public_key(params, public_key.as_ref())?;
+        let message = Self::get_deploy_message_bytes_for_signing_with_options(
+            &params,
+            image,
+            workchain_id,
+            src_address,
+            options,
+        )?;
+        let signature = signer.sign(&message.data_to_sign)?;
+        Self::add_sign_to_message(
+            &params.abi,
+            &signature,
+            public_key.as_ref().map(|public_key| public_key.as_slice()),
+            &message.message,
+        )
+    }
+
+    // Same as `construct_deploy_message_json_ref` but waits for the signer.
+    pub async fn construct_deploy_message_async(
+        params: &FunctionCallSet,
+        image: &ContractImage,
+        signer: &dyn AsyncSigner,
+        workchain_id: i32,
+        src_address: MsgAddressExt,
+    ) -> Result<SdkMessage> {
+        Self::construct_deploy_message_async_with_options(
+            params,
+            image,
+            signer,
+            workchain_id,
+            src_address,
+            SigningOptions::default(),
+        )
+        .await
+    }
+
+    // Same as `construct_deploy_message_async` but signs with the options.
+    pub async fn construct_deploy_message_async_with_options(
+        params: &FunctionCallSet,
+        image: &ContractImage,
+        signer: &dyn AsyncSigner,
+        workchain_id: i32,
+        src_address: MsgAddressExt,
+        options: SigningOptions,
+    ) -> Result<SdkMessage> {
+        let public_key = match signer.scheme() {
+            SignatureScheme::Ed25519 => Some(signer.public_key().await?),
+            _ => None,
+        };
+        let params = Self::set_header_public_key(params, public_key.as_ref())?;
+        let message = Self::get_deploy_message_bytes_for_signing_with_options(
+            &params,
+            image,
+            workchain_id,
+            src_address,
+            options,
+        )?;
+        let signature = signer.sign(&message.data_to_sign).await?;
+        Self::add_sign_to_message(
+            &params.abi,
+            &signature,
+            public_key.as_ref().map(|public_key| public_key.as_slice()),
+            &message.message,
+        )
+    }
+
+    fn construct_unsigned_deploy_message(
+        params: &FunctionCallSet,
+        image: &ContractImage,
         workchain_id: i32,
         src_address: MsgAddressExt,
     ) -> Result<SdkMessage> {
@@ -432,7 +1356,7 @@ impl Contract {
             params.header.as_deref(),
             &params.input,
             false,
-            key_pair,
+            None,
             Some(&image.msg_address(workchain_id).to_string()),
         )?;
 
@@ -456,6 +1380,17 @@ impl Contract {
         body: Option<&[u8]>,
         workchain_id: i32,
         src_address: MsgAddressExt,
+    ) -> Result<TvmMessage> {
+        Self::construct_deploy_message_with_body_ref(&image, body, workchain_id, src_address)
+    }
+
+    // Same as `construct_deploy_message_with_body` but leaves the image to the
+    // caller.
+    pub fn construct_deploy_message_with_body_ref(
+        image: &ContractImage,
+        body: Option<&[u8]>,
+        workchain_id: i32,
+        src_address: MsgAddressExt,
     ) -> Result<TvmMessage> {
         let body_cell = match body {
             None => None,
@@ -471,6 +1406,16 @@ impl Contract {
         image: ContractImage,
         workchain_id: i32,
         src_address: MsgAddressExt,
+    ) -> Result<TvmMessage> {
+        Self::create_ext_deploy_message(None, &image, workchain_id, src_address)
+    }
+
+    // Same as `construct_deploy_message_no_constructor` but leaves the image to
+    // the caller.
+    pub fn construct_deploy_message_no_constructor_ref(
+        image: &ContractImage,
+        workchain_id: i32,
+        src_address: MsgAddressExt,
     ) -> Result<TvmMessage> {
         Self::create_ext_deploy_message(None, image, workchain_id, src_address)
     }
@@ -484,6 +1429,27 @@ impl Contract {
         ihr_disabled: bool,
         bounce: bool,
         value: CurrencyCollection,
+    ) -> Result<TvmMessage> {
+        Self::create_int_deploy_message(
+            src,
+            None,
+            &image,
+            workchain_id,
+            ihr_disabled,
+            bounce,
+            value,
+        )
+    }
+
+    // Same as `construct_int_deploy_message_no_constructor` but leaves the
+    // image to the caller.
+    pub fn construct_int_deploy_message_no_constructor_ref(
+        src: Option<MsgAddressInt>,
+        image: &ContractImage,
+        workchain_id: i32,
+        ihr_disabled: bool,
+        bounce: bool,
+        value: CurrencyCollection,
     ) -> Result<TvmMessage> {
         Self::create_int_deploy_message(src, None, image, workchain_id, ihr_disabled, bounce, value)
     }
@@ -496,6 +1462,35 @@ impl Contract {
         image: ContractImage,
         workchain_id: i32,
         src_address: MsgAddressExt,
+    ) -> Result<MessageToSign> {
+        Self::get_deploy_message_bytes_for_signing_ref(params, &image, workchain_id, src_address)
+    }
+
+    // Same as `get_deploy_message_bytes_for_signing` but leaves the image to
+    // the caller.
+    pub fn get_deploy_message_bytes_for_signing_ref(
+        params: &FunctionCallSet,
+        image: &ContractImage,
+        workchain_id: i32,
+        src_address: MsgAddressExt,
+    ) -> Result<MessageToSign> {
+        Self::get_deploy_message_bytes_for_signing_with_options(
+            params,
+            image,
+            workchain_id,
+            src_address,
+            SigningOptions::default(),
+        )
+    }
+
+    // Same as `get_deploy_message_bytes_for_signing_ref` but the data to sign
+    // is prepared by the options, e.g. prefixed with the signature id.
+    pub fn get_deploy_message_bytes_for_signing_with_options(
+        params: &FunctionCallSet,
+        image: &ContractImage,
+        workchain_id: i32,
+        src_address: MsgAddressExt,
+        options: SigningOptions,
     ) -> Result<MessageToSign> {
         let (msg_body, data_to_sign) = tvm_abi::prepare_function_call_for_sign(
             &params.abi,
@@ -507,8 +1502,10 @@ impl Contract {
 
         let cell = SliceData::load_cell(msg_body.into_cell()?)?;
         let msg = Self::create_ext_deploy_message(Some(cell), image, workchain_id, src_address)?;
-        Self::serialize_message(&msg)
-            .map(|(msg_data, _id)| MessageToSign { message: msg_data, data_to_sign })
+        Self::serialize_message(&msg).map(|(msg_data, _id)| MessageToSign {
+            message: msg_data,
+            data_to_sign: options.data_to_sign(data_to_sign),
+        })
     }
 
     // Packs given image and input into Message struct with internal header and
@@ -521,6 +1518,27 @@ impl Contract {
         ihr_disabled: bool,
         bounce: bool,
         value: CurrencyCollection,
+    ) -> Result<Vec<u8>> {
+        Self::get_int_deploy_message_bytes_ref(
+            src,
+            params,
+            &image,
+            workchain_id,
+            ihr_disabled,
+            bounce,
+            value,
+        )
+    }
+
+    // Same as `get_int_deploy_message_bytes` but leaves the image to the caller.
+    pub fn get_int_deploy_message_bytes_ref(
+        src: Option<MsgAddressInt>,
+        params: &FunctionCallSet,
+        image: &ContractImage,
+        workchain_id: i32,
+        ihr_disabled: bool,
+        bounce: bool,
+        value: CurrencyCollection,
     ) -> Result<Vec<u8>> {
         let msg_body = tvm_abi::encode_function_call(
             &params.abi,
@@ -549,13 +1567,13 @@ impl Contract {
     // Add sign to message, returned by `get_deploy_message_bytes_for_signing` or
     // `get_run_message_bytes_for_signing` function.
     // Returns serialized message and identifier.
-    pub fn add_sign_to_message(
-        abi: &str,
+    pub fn add_sign_to_message<'a>(
+        abi: &(impl AbiSource + ?Sized),
         signature: &[u8],
         public_key: Option<&[u8]>,
-        message: &[u8],
+        message: impl Into<BocInput<'a>>,
     ) -> Result<SdkMessage> {
-        let mut slice = Self::deserialize_tree_to_slice(message)?;
+        let mut slice = message.into().to_slice()?;
 
         let mut message: TvmMessage = TvmMessage::construct_from(&mut slice)?;
 
@@ -563,8 +1581,7 @@ impl Contract {
             .body()
             .ok_or(error!(SdkError::InvalidData { msg: "No message body".to_owned() }))?;
 
-        let signed_body = tvm_abi::add_sign_to_function_call(
-            abi,
+        let signed_body = abi.abi_contract()?.add_sign_to_encoded_input(
             signature.try_into()?,
             public_key.map(|slice| slice.try_into()).transpose()?,
             body,
@@ -617,7 +1634,7 @@ impl Contract {
         Ok(SdkMessage { id, address, serialized_message: body, message })
     }
 
-    fn create_ext_in_message(
+    pub(crate) fn create_ext_in_message(
         address: MsgAddressInt,
         src: MsgAddressExt,
         msg_body: SliceData,
@@ -658,7 +1675,7 @@ impl Contract {
 
     pub(crate) fn create_ext_deploy_message(
         msg_body: Option<SliceData>,
-        image: ContractImage,
+        image: &ContractImage,
         workchain_id: i32,
         src: MsgAddressExt,
     ) -> Result<TvmMessage> {
@@ -668,7 +1685,7 @@ impl Contract {
             ..Default::default()
         };
         let mut msg = TvmMessage::with_ext_in_header(msg_header);
-        msg.set_state_init(image.state_init());
+        msg.set_state_init(image.state_init.clone());
         if let Some(body) = msg_body {
             msg.set_body(body)
         }
@@ -679,7 +1696,7 @@ impl Contract {
     pub(crate) fn create_int_deploy_message(
         src: Option<MsgAddressInt>,
         msg_body: Option<SliceData>,
-        image: ContractImage,
+        image: &ContractImage,
         workchain_id: i32,
         ihr_disabled: bool,
         bounce: bool,
@@ -696,7 +1713,7 @@ impl Contract {
         msg_header.value = value;
 
         let mut msg = TvmMessage::with_int_header(msg_header);
-        msg.set_state_init(image.state_init());
+        msg.set_state_init(image.state_init.clone());
         if let Some(body) = msg_body {
             msg.set_body(body)
         }
@@ -705,8 +1722,46 @@ impl Contract {
     }
 
     pub fn serialize_message(msg: &TvmMessage) -> Result<(Vec<u8>, MessageId)> {
+        Self::serialize_message_with_id(msg, MessageIdKind::Repr)
+    }
+
+    // Same as `serialize_message` but returns the id of the kind
+    pub fn serialize_message_with_id(
+        msg: &TvmMessage,
+        kind: MessageIdKind,
+    ) -> Result<(Vec<u8>, MessageId)> {
         let cells = msg.write_to_new_cell()?.into_cell()?;
-        Ok((tvm_types::boc::write_boc(&cells)?, (&cells.repr_hash().as_slice()[..]).into()))
+        let id = match kind {
+            MessageIdKind::Repr => (&cells.repr_hash().as_slice()[..]).into(),
+            MessageIdKind::Normalized => Self::message_id(msg, kind)?,
+        };
+        Ok((tvm_types::boc::write_boc(&cells)?, id))
+    }
+
+    // Computes the message id of the kind. Normalized ids exist for external
+    // inbound messages only.
+    pub fn message_id(msg: &TvmMessage, kind: MessageIdKind) -> Result<MessageId> {
+        if kind == MessageIdKind::Repr {
+            return Ok(msg.serialize()?.repr_hash().into());
+        }
+        let Some(header) = msg.ext_in_header() else {
+            fail!(SdkError::InvalidData {
+                msg: "Normalized id exists for external inbound messages only".to_owned()
+            })
+        };
+        let header = ExternalInboundMessageHeader::new(MsgAddressExt::AddrNone, header.dst.clone());
+        let mut builder = header.write_to_new_cell()?;
+        builder.append_bit_zero()?;
+        match msg.body() {
+            Some(body) => {
+                builder.append_bit_one()?;
+                builder.checked_append_reference(body.into_cell())?;
+            }
+            None => {
+                builder.append_bit_zero()?;
+            }
+        }
+        Ok(builder.into_cell()?.repr_hash().into())
     }
 
     /// Deserializes tree of cells from byte array into `SliceData`
@@ -721,11 +1776,143 @@ impl Contract {
         }
     }
 
-    /// Deserializes TvmMessage from byte array
-    pub fn deserialize_message(message: &[u8]) -> Result<TvmMessage> {
-        TvmMessage::construct_from_bytes(message)
+    /// Deserializes TvmMessage from bag of cells
+    pub fn deserialize_message<'a>(message: impl Into<BocInput<'a>>) -> Result<TvmMessage> {
+        TvmMessage::construct_from_cell(message.into().to_cell()?)
+    }
+
+    // Deserializes message and decodes its header and, if ABI is given, body
+    pub fn parse_message_full(message: &[u8], abi: Option<&str>) -> Result<ParsedMessage> {
+        ParsedMessage::with_msg(&Self::deserialize_message(message)?, abi)
+    }
+
+    // Computes address of the contract with the code given by its hash and
+    // depth and with the data, without loading the code. The code is put into
+    // the state init as a pruned branch, which keeps the hash of the state
+    // init at level 0 equal to the one with the full code. Cell hashes depend
+    // on depths of the children, so the depth of the code is required.
+    pub fn address_from_code_hash(
+        code_hash: &UInt256,
+        code_depth: u16,
+        data: Cell,
+        workchain_id: i32,
+    ) -> Result<MsgAddressInt> {
+        let mut code = BuilderData::new();
+        code.set_type(CellType::PrunedBranch);
+        code.append_u8(u8::from(CellType::PrunedBranch))?;
+        code.append_u8(1)?;
+        code.append_raw(code_hash.as_slice(), 256)?;
+        code.append_u16(code_depth)?;
+        let state_init =
+            StateInit { code: Some(code.into_cell()?), data: Some(data), ..Default::default() };
+        let id = AccountId::from(state_init.serialize()?.hash(0));
+        address_in_workchain(None, workchain_id, id)
+    }
+
+    // Returns hash and depth of the code, for `address_from_code_hash`
+    pub fn code_hash_and_depth(code: &[u8]) -> Result<(UInt256, u16)> {
+        let code = tvm_types::boc::read_single_root_boc(code)?;
+        Ok((code.repr_hash(), code.repr_depth()))
+    }
+
+    // Decodes account state BOC into its summary
+    pub fn parse_account_boc(data: &[u8]) -> Result<AccountInfo> {
+        AccountInfo::try_from(&Account::construct_from_bytes(data)?)
+    }
+
+    // Decodes block BOC into its header, transactions and messages
+    pub fn parse_block(block: &[u8]) -> Result<ParsedBlock> {
+        ParsedBlock::from_boc(block)
+    }
+
+    // Decodes transaction BOC into its phases, fees and produced messages
+    pub fn parse_transaction(transaction: &[u8]) -> Result<TransactionInfo> {
+        TransactionInfo::from_boc(transaction)
     }
 
+    // Moves body and state init of the serialized message to the new
+    // destination. External bodies of ABI 2.3+ are signed together with the
+    // destination address: if `abi` is not provided, any external message
+    // with a body is reported as requiring re-signing.
+    pub fn retarget_message(
+        message: &[u8],
+        new_dst: MsgAddressInt,
+        abi: Option<&str>,
+    ) -> Result<RetargetedMessage> {
+        let mut msg = Self::deserialize_message(message)?;
+        if let Some(state_init) = msg.state_init() {
+            if state_init.hash()? != new_dst.address() {
+                fail!(SdkError::InvalidData {
+                    msg: "Message with state init can't be sent to other address".to_owned()
+                })
+            }
+        }
+
+        let resign_required = if let Some(header) = msg.ext_in_header_mut() {
+            header.dst = new_dst.clone();
+            match (msg.body(), abi) {
+                (None, _) => false,
+                (Some(body), Some(abi)) => {
+                    let abi = AbiCache::global().load(abi)?;
+                    abi.version() >= &tvm_abi::contract::ABI_VERSION_2_3
+                        && body.remaining_bits() > 0
+                        && body.get_bit(0)?
+                }
+                (Some(_), None) => true,
+            }
+        } else if let Some(header) = msg.int_header_mut() {
+            header.set_dst(new_dst.clone());
+            false
+        } else {
+            fail!(SdkError::InvalidData {
+                msg: "Only inbound messages can be retargeted".to_owned()
+            })
+        };
+
+        let (serialized_message, id) = Self::serialize_message(&msg)?;
+        Ok(RetargetedMessage {
+            message: SdkMessage { id, serialized_message, message: msg, address: new_dst },
+            resign_required,
+        })
+    }
+
+    // Signer key for `pubkey` header parameter, only Ed25519 keys fit it
+    fn header_public_key(signer: &dyn Signer) -> Result<Option<PublicKeyData>> {
+        match signer.scheme() {
+            SignatureScheme::Ed25519 => Ok(Some(signer.public_key()?)),
+            _ => Ok(None),
+        }
+    }
+
+    // Puts signer key into `pubkey` header parameter if the function has it
+    // and the call doesn't set it, as the key is a part of the signed data.
+    fn set_header_public_key(
+        params: &FunctionCallSet,
+        public_key: Option<&PublicKeyData>,
+    ) -> Result<FunctionCallSet> {
+        let Some(public_key) = public_key else {
+            return Ok(params.clone());
+        };
+        let contract = AbiCache::global().load(&params.abi)?;
+        let function = contract.function(&params.func)?;
+        let mut header: serde_json::Map<String, Value> = match params.header.as_deref() {
+            Some(header) => serde_json::from_str(header)?,
+            None => serde_json::Map::new(),
+        };
+        let declared = function
+            .header_params()
+            .iter()
+            .any(|param| param.name == "pubkey" && param.kind == ParamType::PublicKey);
+        if !declared || header.contains_key("pubkey") {
+            return Ok(params.clone());
+        }
+        header.insert("pubkey".to_owned(), hex::encode(public_key).into());
+        Ok(FunctionCallSet { header: Some(Value::Object(header).to_string()), ..params.clone() })
+    }
+
+    /// Local wall clock time in seconds. Message headers take the time from
+    /// `ExpirationPolicy` clock instead, which may be corrected by the
+    /// network time with `DriftCorrectedClock`.
     pub fn now() -> u32 {
         Utc::now().timestamp() as u32
     }
@@ -744,4 +1931,207 @@ impl Contract {
         }
         Ok(Value::Null)
     }
+
+    /// Finds the shard containing the address in the shard hashes tree
+    /// together with its ancestors, e.g. to follow the account during shard
+    /// split or merge
+    pub fn find_matching_shard_chain(
+        shard_hashes: &ShardHashes,
+        address: &MsgAddressInt,
+    ) -> Result<Option<ShardChain>> {
+        ShardChain::find(shard_hashes, address)
+    }
+}
+
+enum MessageBody {
+    Call(FunctionCallSet),
+    Raw(SliceData),
+}
+
+/// Builder of external inbound messages. Header parameters set with
+/// `expire_at` and `header_param` override ones given in the call header.
+#[derive(Default)]
+pub struct MessageBuilder<'a> {
+    dst: Option<MsgAddressInt>,
+    src: MsgAddressExt,
+    body: Option<MessageBody>,
+    header: serde_json::Map<String, Value>,
+    signer: Option<&'a dyn Signer>,
+    state_init: Option<StateInit>,
+    policy: Option<ExpirationPolicy>,
+    encryption: Option<EncryptedFields>,
+    signing: SigningOptions,
+}
+
+impl<'a> MessageBuilder<'a> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn dst(mut self, dst: MsgAddressInt) -> Self {
+        self.dst = Some(dst);
+        self
+    }
+
+    pub fn src(mut self, src: MsgAddressExt) -> Self {
+        self.src = src;
+        self
+    }
+
+    /// Body encoded from the function call by ABI
+    pub fn body(mut self, call: FunctionCallSet) -> Self {
+        self.body = Some(MessageBody::Call(call));
+        self
+    }
+
+    /// Body taken as is. Header parameters and signing can't be used with it.
+    pub fn raw_body(mut self, body: SliceData) -> Self {
+        self.body = Some(MessageBody::Raw(body));
+        self
+    }
+
+    /// Sets `expire` header parameter, UNIX timestamp in seconds
+    pub fn expire_at(self, expire_at: u32) -> Self {
+        self.header_param("expire", expire_at)
+    }
+
+    pub fn header_param(mut self, name: &str, value: impl Into<Value>) -> Self {
+        self.header.insert(name.to_owned(), value.into());
+        self
+    }
+
+    /// Fills header parameters not set otherwise by the policy
+    pub fn expiration(mut self, policy: ExpirationPolicy) -> Self {
+        self.policy = Some(policy);
+        self
+    }
+
+    /// Encrypts the fields of the call before encoding
+    pub fn encrypt_fields(mut self, fields: EncryptedFields) -> Self {
+        self.encryption = Some(fields);
+        self
+    }
+
+    /// Signs with the signer of any scheme fitting ABI signature, e.g.
+    /// `LocalSigner` or `SigningKey`
+    pub fn sign_with(mut self, signer: &'a dyn Signer) -> Self {
+        self.signer = Some(signer);
+        self
+    }
+
+    pub fn state_init(mut self, state_init: StateInit) -> Self {
+        self.state_init = Some(state_init);
+        self
+    }
+
+    /// Signs for the network with the capabilities and the global id, i.e.
+    /// with the signature id if the network requires it
+    pub fn network(mut self, capabilities: &Capabilities, global_id: i32) -> Self {
+        self.signing = SigningOptions::for_network(capabilities, global_id);
+        self
+    }
+
+    pub fn signing_options(mut self, options: SigningOptions) -> Self {
+        self.signing = options;
+        self
+    }
+
+    pub fn build(self) -> Result<SdkMessage> {
+        let Some(address) = self.dst else {
+            fail!(SdkError::InvalidData { msg: "Message destination is not set".to_owned() })
+        };
+        let body = match self.body {
+            Some(MessageBody::Call(call)) => {
+                let call = match &self.policy {
+                    Some(policy) => policy.apply(&call)?,
+                    None => call,
+                };
+                let call = match &self.encryption {
+                    Some(fields) => fields.encrypt_call(&call)?,
+                    None => call,
+                };
+                let mut header = match call.header.as_deref() {
+                    Some(header) => serde_json::from_str(header)?,
+                    None => serde_json::Map::new(),
+                };
+                header.extend(self.header);
+                let call =
+                    FunctionCallSet { header: Some(Value::Object(header).to_string()), ..call };
+                let body = match self.signer {
+                    Some(signer) => {
+                        let public_key = Contract::header_public_key(signer)?;
+                        let call = Contract::set_header_public_key(&call, public_key.as_ref())?;
+                        let (body, data_to_sign) = tvm_abi::prepare_function_call_for_sign(
+                            &call.abi,
+                            &call.func,
+                            call.header.as_deref(),
+                            &call.input,
+                            Some(&address.to_string()),
+                        )?;
+                        let data = self.signing.data_to_sign(data_to_sign);
+                        AbiCache::global().load(&call.abi)?.add_sign_to_encoded_input(
+                            &signer.sign(&data)?,
+                            public_key.as_ref(),
+                            SliceData::load_builder(body)?,
+                        )?
+                    }
+                    None => tvm_abi::encode_function_call(
+                        &call.abi,
+                        &call.func,
+                        call.header.as_deref(),
+                        &call.input,
+                        false,
+                        None,
+                        Some(&address.to_string()),
+                    )?,
+                };
+                Some(SliceData::load_cell(body.into_cell()?)?)
+            }
+            Some(MessageBody::Raw(body)) => {
+                if !self.header.is_empty()
+                    || self.signer.is_some()
+                    || self.policy.is_some()
+                    || self.encryption.is_some()
+                {
+                    fail!(SdkError::InvalidData {
+                        msg: "Header parameters, signing and encryption require ABI call body"
+                            .to_owned()
+                    })
+                }
+                Some(body)
+            }
+            None => None,
+        };
+
+        let mut msg = TvmMessage::with_ext_in_header(ExternalInboundMessageHeader {
+            src: self.src,
+            dst: address.clone(),
+            ..Default::default()
+        });
+        if let Some(state_init) = self.state_init {
+            msg.set_state_init(state_init);
+        }
+        if let Some(body) = body {
+            msg.set_body(body);
+        }
+        let (serialized_message, id) = Contract::serialize_message(&msg)?;
+        Ok(SdkMessage { id, serialized_message, message: msg, address })
+    }
 }
+
+// Workchain ids fitting into 8 bits give standard addresses, others give
+// variable length ones
+pub(crate) fn address_in_workchain(
+    anycast: Option<AnycastInfo>,
+    workchain_id: i32,
+    id: AccountId,
+) -> Result<MsgAddressInt> {
+    match i8::try_from(workchain_id) {
+        Ok(workchain_id) => MsgAddressInt::with_standart(anycast, workchain_id, id),
+        Err(_) => MsgAddressInt::with_variant(anycast, workchain_id, id),
+    }
+}
+
+#[cfg(test)]
+#[path = "tests/test_contract.rs"]
+mod tests;
@@ -0,0 +1,252 @@
+// Copyright 2018-2021 TON Labs LTD.
+//
+// Licensed under the SOFTWARE EVALUATION License (the "License"); you may not
+// use this file except in compliance with the License.
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific TON DEV software governing permissions and
+// limitations under the License.
+
+//! Deployed contract as a trait object, so application code can be written
+//! once against `dyn DeployedContract` and run either against the network,
+//! by `NetworkContract`, or against the local chain of tests, by
+//! `testing::LocalContract`.
+
+use tvm_abi::DecodedEvent;
+use tvm_abi::EventDecoder;
+use tvm_abi::json_abi::DecodedMessage;
+use tvm_abi::token::Detokenizer;
+use tvm_block::MsgAddressInt;
+use tvm_types::Result;
+
+use crate::AbiSource;
+use crate::FunctionCallSet;
+use crate::MessageType;
+use crate::Transaction;
+
+/// Processed call with the function response and events decoded from the
+/// external outbound messages of the transaction
+pub struct CallResult {
+    pub transaction: Transaction,
+    pub output: Option<DecodedMessage>,
+    pub events: Vec<DecodedMessage>,
+}
+
+/// Contract deployed at the address, called by its ABI. Inputs are JSON
+/// objects of function parameters.
+#[async_trait::async_trait]
+pub trait DeployedContract: Send + Sync {
+    fn address(&self) -> &MsgAddressInt;
+
+    fn abi(&self) -> &str;
+
+    /// Calls the function by external message and decodes the function
+    /// response and events of the transaction
+    async fn call(&self, func: &str, input: &str) -> Result<CallResult>;
+
+    /// Runs the function on the current state without a transaction and
+    /// returns its output, e.g. for getters. The state is not changed.
+    async fn getter(&self, func: &str, input: &str) -> Result<DecodedMessage>;
+}
+
+/// Call of the contract function by JSON input
+pub fn function_call(contract: &dyn DeployedContract, func: &str, input: &str) -> FunctionCallSet {
+    FunctionCallSet {
+        func: func.to_owned(),
+        header: None,
+        input: input.to_owned(),
+        abi: contract.abi().to_owned(),
+    }
+}
+
+/// Decodes function response and events from external outbound messages of
+/// the transaction. Messages not matching the ABI are skipped.
+pub fn decode_call_result(
+    abi: &(impl AbiSource + ?Sized),
+    transaction: Transaction,
+) -> Result<CallResult> {
+    let contract = abi.abi_contract()?;
+    let decoder = EventDecoder::new(contract.clone());
+    let mut event = DecodedEvent::default();
+    let mut output = None;
+    let mut events = Vec::new();
+    for message in &transaction.out_messages {
+        if message.msg_type() != MessageType::ExternalOutbound {
+            continue;
+        }
+        let Some(body) = message.body() else {
+            continue;
+        };
+        if decoder.event(&body).is_ok() {
+            decoder.decode_into(body, &mut event)?;
+            events.push(DecodedMessage {
+                function_name: event.name.clone(),
+                params: Detokenizer::detokenize(&event.tokens)?,
+            });
+        } else if let Ok(decoded) = contract.decode_output(body, false, false) {
+            output = Some(DecodedMessage {
+                function_name: decoded.function_name,
+                params: Detokenizer::detokenize(&decoded.tokens)?,
+            });
+        }
+    }
+    Ok(CallResult { transaction, output, events })
+}
+
+/// Runs the call on the account locally by unsigned external message built
+/// with the policy and decodes the function response
+#[cfg(feature = "executor")]
+pub fn run_getter(
+    account: &tvm_block::Account,
+    call: &FunctionCallSet,
+    policy: &crate::ExpirationPolicy,
+) -> Result<DecodedMessage> {
+    use tvm_types::error;
+    use tvm_types::fail;
+
+    use crate::error::SdkError;
+
+    let Some(address) = account.get_addr() else {
+        fail!(SdkError::InvalidData { msg: "Account has no address".to_owned() })
+    };
+    let message = crate::Contract::construct_call_ext_in_message_with_policy(
+        address.clone(),
+        tvm_block::MsgAddressExt::default(),
+        call,
+        None,
+        policy,
+        crate::SigningOptions::default(),
+    )?;
+    let contract = call.abi.abi_contract()?;
+    for message in crate::executor::run_tvm(account, &message.message)? {
+        if message.ext_out_header().is_none() {
+            continue;
+        }
+        let Some(body) = message.body() else {
+            continue;
+        };
+        if let Ok(decoded) = contract.decode_output(body, false, false) {
+            if decoded.function_name == call.func {
+                return Ok(DecodedMessage {
+                    function_name: decoded.function_name,
+                    params: Detokenizer::detokenize(&decoded.tokens)?,
+                });
+            }
+        }
+    }
+    fail!(SdkError::InvalidData { msg: format!("Function {} returned no output", call.func) })
+}
+
+#[cfg(all(feature = "transport", feature = "executor"))]
+pub use network::NetworkContract;
+
+#[cfg(all(feature = "transport", feature = "executor"))]
+mod network {
+    use std::sync::Arc;
+
+    use tvm_abi::json_abi::DecodedMessage;
+    use tvm_block::MsgAddressExt;
+    use tvm_block::MsgAddressInt;
+    use tvm_types::Result;
+    use tvm_types::error;
+    use tvm_types::fail;
+
+    use super::CallResult;
+    use super::DeployedContract;
+    use super::decode_call_result;
+    use super::function_call;
+    use super::run_getter;
+    use crate::Contract;
+    use crate::ExpirationPolicy;
+    use crate::Signer;
+    use crate::SigningOptions;
+    use crate::error::SdkError;
+    use crate::transport::Transport;
+
+    const DEFAULT_TIMEOUT_MS: u32 = 60_000;
+
+    /// Contract called over the transport. Getters are run locally on the
+    /// account state fetched by the transport.
+    pub struct NetworkContract<'a, T: Transport + ?Sized> {
+        transport: &'a T,
+        address: MsgAddressInt,
+        abi: String,
+        signer: Option<Arc<dyn Signer + Send + Sync>>,
+        policy: ExpirationPolicy,
+        timeout_ms: u32,
+        signing: SigningOptions,
+    }
+
+    impl<'a, T: Transport + ?Sized> NetworkContract<'a, T> {
+        pub fn new(transport: &'a T, address: MsgAddressInt, abi: impl Into<String>) -> Self {
+            Self {
+                transport,
+                address,
+                abi: abi.into(),
+                signer: None,
+                policy: ExpirationPolicy::new(DEFAULT_TIMEOUT_MS / 1000),
+                timeout_ms: DEFAULT_TIMEOUT_MS,
+                signing: SigningOptions::default(),
+            }
+        }
+
+        /// Signer of the calls, calls are unsigned without it
+        pub fn with_signer(mut self, signer: Arc<dyn Signer + Send + Sync>) -> Self {
+            self.signer = Some(signer);
+            self
+        }
+
+        pub fn with_expiration(mut self, policy: ExpirationPolicy) -> Self {
+            self.policy = policy;
+            self
+        }
+
+        /// Options of signing the calls, e.g. with the signature id of the
+        /// network
+        pub fn with_signing_options(mut self, options: SigningOptions) -> Self {
+            self.signing = options;
+            self
+        }
+
+        /// Time to wait for transactions, in milliseconds
+        pub fn with_timeout(mut self, timeout_ms: u32) -> Self {
+            self.timeout_ms = timeout_ms;
+            self
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl<T: Transport + ?Sized> DeployedContract for NetworkContract<'_, T> {
+        fn address(&self) -> &MsgAddressInt {
+            &self.address
+        }
+
+        fn abi(&self) -> &str {
+            &self.abi
+        }
+
+        async fn call(&self, func: &str, input: &str) -> Result<CallResult> {
+            let message = Contract::construct_call_ext_in_message_with_policy(
+                self.address.clone(),
+                MsgAddressExt::default(),
+                &function_call(self, func, input),
+                self.signer.as_deref().map(|signer| signer as &dyn Signer),
+                &self.policy,
+                self.signing,
+            )?;
+            let transaction = self.transport.process_message(&message, self.timeout_ms).await?;
+            decode_call_result(self.abi.as_str(), transaction)
+        }
+
+        async fn getter(&self, func: &str, input: &str) -> Result<DecodedMessage> {
+            let Some(account) = self.transport.query_account(&self.address).await? else {
+                fail!(SdkError::InvalidData {
+                    msg: format!("Account {} doesn't exist", self.address)
+                })
+            };
+            run_getter(&account, &function_call(self, func, input), &self.policy)
+        }
+    }
+}
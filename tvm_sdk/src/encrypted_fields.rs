@@ -0,0 +1,223 @@
+// Copyright 2018-2021 TON Labs LTD.
+//
+// Licensed under the SOFTWARE EVALUATION License (the "License"); you may not
+// use this file except in compliance with the License.
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific TON DEV software governing permissions and
+// limitations under the License.
+
+//! Call parameters encrypted to the key of the contract owner, e.g. bids of
+//! a sealed-bid auction.
+//!
+//! Encrypted parameters are declared as `bytes` in the ABI. The caller gives
+//! them as any JSON values, `EncryptedFields` serializes them to JSON and
+//! replaces with the ciphertext before encoding, and the owner gets the
+//! original values back from the decoded call.
+
+use std::sync::Arc;
+
+use hmac::Hmac;
+use hmac::Mac;
+use serde_json::Value;
+use sha2::Sha256;
+use tvm_abi::AbiCache;
+use tvm_abi::Param;
+use tvm_abi::ParamType;
+use tvm_abi::PublicKeyData;
+use tvm_types::AesCtr;
+use tvm_types::Ed25519PrivateKey;
+use tvm_types::Result;
+use tvm_types::ed25519_expand_private_key;
+use tvm_types::ed25519_generate_private_key;
+use tvm_types::error;
+use tvm_types::fail;
+use tvm_types::sha256_digest_slices;
+use tvm_types::sha512_digest;
+use tvm_types::x25519_shared_secret;
+
+use crate::DecodedBody;
+use crate::FunctionCallSet;
+use crate::error::SdkError;
+
+const PUBLIC_KEY_LEN: usize = 32;
+const TAG_LEN: usize = 16;
+const MAC_KEY_DOMAIN: &[u8] = b"tvm-sdk encrypted field mac";
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Encryption of a single call parameter. `field` is the parameter path, so
+/// implementations may use different keys for different parameters.
+pub trait FieldCipher: Send + Sync {
+    fn encrypt(&self, field: &str, plaintext: &[u8]) -> Result<Vec<u8>>;
+    fn decrypt(&self, field: &str, ciphertext: &[u8]) -> Result<Vec<u8>>;
+}
+
+/// Encrypts to the Ed25519 public key of the recipient, usually the contract
+/// public key, with an ephemeral key: X25519 key agreement, AES-256-CTR and
+/// HMAC-SHA256 of the encrypted data truncated to 16 bytes. Ciphertext is the
+/// ephemeral public key, the tag and the encrypted data. Only the owner of the
+/// private key decrypts it.
+pub struct SealedBoxCipher {
+    recipient: PublicKeyData,
+    key: Option<Ed25519PrivateKey>,
+}
+
+impl SealedBoxCipher {
+    /// Cipher of the sender, which encrypts only
+    pub fn for_recipient(recipient: PublicKeyData) -> Self {
+        Self { recipient, key: None }
+    }
+
+    /// Cipher of the owner, which both encrypts and decrypts
+    pub fn for_owner(key: Ed25519PrivateKey) -> Self {
+        Self { recipient: key.verifying_key(), key: Some(key) }
+    }
+
+    // Keys of AES-256-CTR and HMAC-SHA256 bound to the field
+    fn keys(secret: &[u8; 32], ephemeral: &[u8], field: &str) -> Result<(AesCtr, HmacSha256)> {
+        let keys = sha512_digest([secret, ephemeral, field.as_bytes()].concat());
+        let mac_key = sha256_digest_slices(&[MAC_KEY_DOMAIN, &keys]);
+        let mac = HmacSha256::new_from_slice(&mac_key)
+            .map_err(|err| error!(SdkError::InternalError { msg: err.to_string() }))?;
+        Ok((AesCtr::with_params(&keys[..32], &keys[32..48])?, mac))
+    }
+}
+
+impl FieldCipher for SealedBoxCipher {
+    fn encrypt(&self, field: &str, plaintext: &[u8]) -> Result<Vec<u8>> {
+        let ephemeral = ed25519_generate_private_key()?;
+        let secret = x25519_shared_secret(
+            &ed25519_expand_private_key(&ephemeral)?.to_bytes(),
+            &self.recipient,
+        )?;
+        let ephemeral = ephemeral.verifying_key();
+        let (mut aes, mut mac) = Self::keys(&secret, &ephemeral, field)?;
+        let mut data = plaintext.to_vec();
+        aes.apply_keystream(&mut data, 0..plaintext.len())?;
+        mac.update(&data);
+        let tag = mac.finalize().into_bytes();
+        Ok([&ephemeral[..], &tag[..TAG_LEN], &data].concat())
+    }
+
+    fn decrypt(&self, field: &str, ciphertext: &[u8]) -> Result<Vec<u8>> {
+        let Some(key) = &self.key else {
+            fail!(SdkError::InvalidData {
+                msg: "Decryption requires the private key of the owner".to_owned()
+            })
+        };
+        if ciphertext.len() < PUBLIC_KEY_LEN + TAG_LEN {
+            fail!(SdkError::InvalidData { msg: format!("Encrypted field {} is too short", field) })
+        }
+        let (ephemeral, rest) = ciphertext.split_at(PUBLIC_KEY_LEN);
+        let (tag, data) = rest.split_at(TAG_LEN);
+        let secret = x25519_shared_secret(&ed25519_expand_private_key(key)?.to_bytes(), ephemeral)?;
+        let (mut aes, mut mac) = Self::keys(&secret, ephemeral, field)?;
+        mac.update(data);
+        // constant time comparison
+        if mac.verify_truncated_left(tag).is_err() {
+            fail!(SdkError::InvalidData {
+                msg: format!("Encrypted field {} is corrupted or encrypted to another key", field)
+            })
+        }
+        let mut plaintext = data.to_vec();
+        aes.apply_keystream(&mut plaintext, 0..data.len())?;
+        Ok(plaintext)
+    }
+}
+
+/// Parameters of calls encrypted by the cipher. Parameters are given by
+/// names, with tuple components separated by dots, and are skipped in
+/// functions not having them.
+#[derive(Clone)]
+pub struct EncryptedFields {
+    fields: Vec<String>,
+    cipher: Arc<dyn FieldCipher>,
+}
+
+impl EncryptedFields {
+    pub fn new(cipher: Arc<dyn FieldCipher>) -> Self {
+        Self { fields: Vec::new(), cipher }
+    }
+
+    pub fn field(mut self, path: impl Into<String>) -> Self {
+        self.fields.push(path.into());
+        self
+    }
+
+    pub fn fields(&self) -> &[String] {
+        &self.fields
+    }
+
+    /// Returns the call with the fields replaced by their ciphertext
+    pub fn encrypt_call(&self, call: &FunctionCallSet) -> Result<FunctionCallSet> {
+        let contract = AbiCache::global().load(&call.abi)?;
+        let function = contract.function(&call.func)?;
+        let mut input: Value = serde_json::from_str(&call.input)?;
+        for field in &self.fields {
+            let Some(kind) = find_param(function.input_params(), field) else {
+                continue;
+            };
+            if *kind != ParamType::Bytes {
+                fail!(SdkError::InvalidData {
+                    msg: format!(
+                        "Encrypted field {} must be declared as bytes, not {}",
+                        field, kind
+                    )
+                })
+            }
+            let Some(value) = input.pointer_mut(&pointer(field)) else {
+                fail!(SdkError::InvalidData {
+                    msg: format!("Encrypted field {} is not set", field)
+                })
+            };
+            let ciphertext = self.cipher.encrypt(field, value.to_string().as_bytes())?;
+            *value = Value::String(hex::encode(ciphertext));
+        }
+        Ok(FunctionCallSet { input: input.to_string(), ..call.clone() })
+    }
+
+    /// Decrypts the fields of decoded parameters, given as JSON
+    pub fn decrypt_params(&self, params: &str) -> Result<String> {
+        let mut params: Value = serde_json::from_str(params)?;
+        for field in &self.fields {
+            let Some(value) = params.pointer_mut(&pointer(field)) else {
+                continue;
+            };
+            let ciphertext =
+                value.as_str().and_then(|value| hex::decode(value).ok()).ok_or_else(|| {
+                    error!(SdkError::InvalidData { msg: format!("Field {} is not bytes", field) })
+                })?;
+            *value = serde_json::from_slice(&self.cipher.decrypt(field, &ciphertext)?)?;
+        }
+        Ok(params.to_string())
+    }
+
+    /// Decrypts the fields of the call decoded from the message body
+    pub fn decrypt_body(&self, body: DecodedBody) -> Result<DecodedBody> {
+        Ok(DecodedBody { params: self.decrypt_params(&body.params)?, ..body })
+    }
+}
+
+fn find_param<'a>(params: &'a [Param], path: &str) -> Option<&'a ParamType> {
+    let (name, rest) = match path.split_once('.') {
+        Some((name, rest)) => (name, Some(rest)),
+        None => (path, None),
+    };
+    let param = params.iter().find(|param| param.name == name)?;
+    match (&param.kind, rest) {
+        (kind, None) => Some(kind),
+        (ParamType::Tuple(components), Some(rest)) => find_param(components, rest),
+        _ => None,
+    }
+}
+
+fn pointer(path: &str) -> String {
+    format!("/{}", path.replace('.', "/"))
+}
+
+#[cfg(test)]
+#[path = "tests/test_encrypted_fields.rs"]
+mod tests;
@@ -17,4 +17,38 @@ pub enum SdkError {
 
     #[error("Internal error: {}", msg)]
     InternalError { msg: String },
+
+    /// Transport found no transaction of the message within the timeout,
+    /// the message may still be processed until it expires
+    #[error("No transaction for message {} in {} ms", message_id, timeout_ms)]
+    WaitTimeout { message_id: String, timeout_ms: u32 },
+
+    /// Contract or node rejected the message with the exit code of the
+    /// compute phase
+    #[error("Message rejected with exit code {}: {}", exit_code, msg)]
+    MessageRejected { exit_code: i32, msg: String },
+
+    /// Node didn't accept the message, see `NodeRejection::of` for the
+    /// reason
+    #[error("Message rejected by the node with code {}: {}", code, msg)]
+    NodeRejected { code: String, msg: String },
+}
+
+impl SdkError {
+    /// Exit code reported by the error: `MessageRejected` of transports and,
+    /// with the `executor` feature, errors of the local executor
+    pub fn exit_code_of(err: &tvm_types::Error) -> Option<i32> {
+        if let Some(SdkError::MessageRejected { exit_code, .. }) = err.downcast_ref::<SdkError>() {
+            return Some(*exit_code);
+        }
+        #[cfg(feature = "executor")]
+        match err.downcast_ref::<tvm_executor::ExecutorError>() {
+            Some(tvm_executor::ExecutorError::NoAcceptError(exit_code, _)) => {
+                return Some(*exit_code);
+            }
+            Some(tvm_executor::ExecutorError::TvmExceptionCode(code)) => return Some(*code as i32),
+            _ => {}
+        }
+        None
+    }
 }
@@ -0,0 +1,787 @@
+// Copyright 2018-2021 TON Labs LTD.
+//
+// Licensed under the SOFTWARE EVALUATION License (the "License"); you may not
+// use this file except in compliance with the License.
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific TON DEV software governing permissions and
+// limitations under the License.
+
+//! Offline execution of messages against local account state, used to
+//! estimate fees and validate messages before broadcasting. Tick-tock and
+//! storage transactions can be executed as well, to test special accounts
+//! and storage fee collection. Get-methods and ABI getters are run on the
+//! account state directly, without a transaction.
+
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+
+use serde_json::Value;
+use serde_json::json;
+use tvm_block::Account;
+use tvm_block::Block;
+use tvm_block::CommonMsgInfo;
+use tvm_block::ConfigParams;
+use tvm_block::Deserializable;
+use tvm_block::Message as TvmMessage;
+use tvm_block::OutAction;
+use tvm_block::OutActions;
+use tvm_block::Serializable;
+use tvm_block::TrComputePhase;
+use tvm_block::Transaction as TvmTransaction;
+use tvm_block::TransactionDescr;
+use tvm_block::TransactionTickTock;
+use tvm_executor::BlockchainConfig;
+use tvm_executor::ExecuteParams;
+use tvm_executor::OrdinaryTransactionExecutor;
+use tvm_executor::StorageTransactionExecutor;
+use tvm_executor::TickTockTransactionExecutor;
+use tvm_executor::TransactionExecutor;
+use tvm_types::BuilderData;
+use tvm_types::Cell;
+use tvm_types::ExceptionCode;
+use tvm_types::Result;
+use tvm_types::SliceData;
+use tvm_types::UInt256;
+use tvm_types::base64_decode;
+use tvm_types::base64_encode;
+use tvm_types::error;
+use tvm_types::fail;
+use tvm_vm::executor::Engine;
+use tvm_vm::executor::EngineTraceInfo;
+use tvm_vm::executor::TraceCallback;
+use tvm_vm::executor::gas::gas_state::Gas;
+use tvm_vm::stack::Stack;
+use tvm_vm::stack::StackItem;
+use tvm_vm::stack::continuation::ContinuationData;
+use tvm_vm::stack::integer::IntegerData;
+use tvm_vm::stack::savelist::SaveList;
+
+use crate::AccountDataDiffer;
+use crate::Capabilities;
+use crate::FieldChange;
+use crate::ParsedMessage;
+use crate::SdkMessage;
+use crate::Transaction;
+use crate::TransactionFees;
+use crate::address::CRC16;
+use crate::error::SdkError;
+use crate::expiration::Clock;
+use crate::expiration::SystemClock;
+
+const GET_METHOD_GAS_LIMIT: i64 = 1_000_000_000;
+
+pub struct LocalExecutionResult {
+    pub transaction: TvmTransaction,
+    pub fees: TransactionFees,
+    pub gas_used: u64,
+    pub exit_code: Option<i32>,
+    pub aborted: bool,
+    /// BOC of the account after the transaction
+    pub account: Vec<u8>,
+    /// Set when the compute phase failed on a VM resource limit
+    pub exhausted: Option<ResourceExhausted>,
+}
+
+/// VM resource whose limit was hit
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VmResource {
+    Gas,
+    /// Stack overflow, e.g. too many values passed to a continuation
+    Stack,
+    /// Cell overflow: too much data, references or depth of the cell
+    Cell,
+}
+
+/// Executed VM instruction
+#[derive(Clone, Debug)]
+pub struct TraceStep {
+    pub step: u32,
+    pub cmd: String,
+    pub gas_used: i64,
+    pub gas_cmd: i64,
+    pub stack_depth: usize,
+}
+
+/// Diagnostic of the compute phase failed on a VM resource limit. Trace is
+/// collected only by the executor created `with_trace`.
+#[derive(Clone, Debug)]
+pub struct ResourceExhausted {
+    pub resource: VmResource,
+    pub exit_code: i32,
+    /// Limit of the resource, known for gas only. It is the gas limit at the
+    /// start of the compute phase, accepting contract gets it raised.
+    pub limit: Option<u64>,
+    /// Gas used or the deepest stack seen in the trace
+    pub reached: Option<u64>,
+    /// Last steps before the failure
+    pub trace: Vec<TraceStep>,
+}
+
+/// Out messages at the same position of two executions, `None` where the
+/// execution produced fewer messages
+pub struct OutMessageDiff {
+    pub index: usize,
+    pub a: Option<ParsedMessage>,
+    pub b: Option<ParsedMessage>,
+}
+
+/// Results of the same message executed with two versions of the account code
+pub struct ExecutionDiff {
+    pub a: LocalExecutionResult,
+    pub b: LocalExecutionResult,
+    /// Differing out messages
+    pub out_messages: Vec<OutMessageDiff>,
+}
+
+impl ExecutionDiff {
+    pub fn exit_code_differs(&self) -> bool {
+        self.a.exit_code != self.b.exit_code || self.a.aborted != self.b.aborted
+    }
+
+    /// Gas used by the version `b` minus gas used by the version `a`
+    pub fn gas_delta(&self) -> i64 {
+        self.b.gas_used as i64 - self.a.gas_used as i64
+    }
+
+    /// Whether account data after the executions differs
+    pub fn data_differs(&self) -> Result<bool> {
+        Ok(Self::data(&self.a)? != Self::data(&self.b)?)
+    }
+
+    /// Fields of account data decoded by the ABI with values after the
+    /// execution `a` as old ones and after `b` as new ones
+    pub fn data_changes(&self, abi: &str) -> Result<Vec<FieldChange>> {
+        let mut differ = AccountDataDiffer::new(abi)?;
+        differ.update_from_account_bytes(&self.a.account)?;
+        differ.update_from_account_bytes(&self.b.account)
+    }
+
+    /// Whether both versions behave the same way
+    pub fn is_same(&self) -> Result<bool> {
+        Ok(!self.exit_code_differs()
+            && self.a.gas_used == self.b.gas_used
+            && self.out_messages.is_empty()
+            && !self.data_differs()?)
+    }
+
+    fn data(result: &LocalExecutionResult) -> Result<Option<Cell>> {
+        Ok(Account::construct_from_bytes(&result.account)?.get_data())
+    }
+}
+
+/// Field of the replayed transaction differing from the recorded one
+#[derive(Clone, Debug, PartialEq)]
+pub struct ReplayDivergence {
+    pub field: String,
+    pub recorded: String,
+    pub replayed: String,
+}
+
+/// Result of the local replay of the recorded transaction
+pub struct ReplayReport {
+    pub replayed: LocalExecutionResult,
+    pub divergences: Vec<ReplayDivergence>,
+}
+
+impl ReplayReport {
+    /// Whether the replayed transaction matches the recorded one
+    pub fn matches(&self) -> bool {
+        self.divergences.is_empty()
+    }
+}
+
+/// Block context of the recorded transaction, which is not stored in the
+/// transaction itself but is needed to replay it
+#[derive(Clone)]
+pub struct ReplayContext {
+    /// Logical time of the block start. The transaction lt is derived from
+    /// it, the account last transaction lt and the inbound message lt.
+    pub block_lt: u64,
+    pub seq_no: u32,
+    /// Random seed of the block, the account random seed is derived from it
+    pub rand_seed: UInt256,
+    /// Config of the masterchain state the block refers to
+    pub config: BlockchainConfig,
+}
+
+impl ReplayContext {
+    /// Takes the block start lt, sequence number and random seed from the
+    /// block of the transaction
+    pub fn from_block(block: &Block, config: BlockchainConfig) -> Result<Self> {
+        let info = block.read_info()?;
+        Ok(Self {
+            block_lt: info.start_lt(),
+            seq_no: info.seq_no(),
+            rand_seed: block.read_extra()?.rand_seed().clone(),
+            config,
+        })
+    }
+}
+
+pub struct LocalExecutor {
+    config: BlockchainConfig,
+    trace_steps: usize,
+}
+
+impl LocalExecutor {
+    pub fn new(config: BlockchainConfig) -> Self {
+        Self { config, trace_steps: 0 }
+    }
+
+    /// Capabilities of the config, they define fees model and instructions
+    /// available to the executed code
+    pub fn capabilities(&self) -> Capabilities {
+        Capabilities::new(self.config.global_version(), self.config.capabilites())
+    }
+
+    /// Keeps the last `steps` VM instructions of each execution for
+    /// diagnostics of resource exhaustion. Tracing slows the execution down.
+    pub fn with_trace(mut self, steps: usize) -> Self {
+        self.trace_steps = steps;
+        self
+    }
+
+    pub fn with_config_params(config: ConfigParams) -> Result<Self> {
+        Ok(Self::new(BlockchainConfig::with_config(config)?))
+    }
+
+    /// Executes the message on the account given as BOC. The account itself
+    /// is not changed, its new state is returned in the result.
+    pub fn execute(
+        &self,
+        message: &SdkMessage,
+        account_boc: &[u8],
+        block_time: u32,
+        block_lt: u64,
+    ) -> Result<LocalExecutionResult> {
+        let executor = OrdinaryTransactionExecutor::new(self.config.clone());
+        self.run(&executor, Some(&message.message), account_boc, block_time, block_lt)
+    }
+
+    /// Executes tick or tock transaction of the special account given as BOC
+    pub fn execute_tick_tock(
+        &self,
+        tt: TransactionTickTock,
+        account_boc: &[u8],
+        block_time: u32,
+        block_lt: u64,
+    ) -> Result<LocalExecutionResult> {
+        let executor = TickTockTransactionExecutor::new(self.config.clone(), tt);
+        self.run(&executor, None, account_boc, block_time, block_lt)
+    }
+
+    /// Executes storage transaction, which only collects storage fees due by
+    /// `block_time`, on the account given as BOC
+    pub fn execute_storage(
+        &self,
+        account_boc: &[u8],
+        block_time: u32,
+        block_lt: u64,
+    ) -> Result<LocalExecutionResult> {
+        let executor = StorageTransactionExecutor::new(self.config.clone());
+        self.run(&executor, None, account_boc, block_time, block_lt)
+    }
+
+    /// Executes the message on the account given as BOC with code replaced
+    /// by `code_a` and by `code_b` and compares the results, e.g. to check
+    /// the contract upgrade before deploying it
+    pub fn diff_execution(
+        &self,
+        message: &SdkMessage,
+        account_boc: &[u8],
+        code_a: Cell,
+        code_b: Cell,
+        block_time: u32,
+        block_lt: u64,
+    ) -> Result<ExecutionDiff> {
+        let a =
+            self.execute(message, &Self::with_code(account_boc, code_a)?, block_time, block_lt)?;
+        let b =
+            self.execute(message, &Self::with_code(account_boc, code_b)?, block_time, block_lt)?;
+
+        let messages_a = Self::out_messages(&a.transaction)?;
+        let messages_b = Self::out_messages(&b.transaction)?;
+        let mut out_messages = Vec::new();
+        for index in 0..messages_a.len().max(messages_b.len()) {
+            let a = messages_a.get(index).cloned();
+            let b = messages_b.get(index).cloned();
+            if a.as_ref().map(|m| m.id.to_string()) != b.as_ref().map(|m| m.id.to_string()) {
+                out_messages.push(OutMessageDiff { index, a, b });
+            }
+        }
+        Ok(ExecutionDiff { a, b, out_messages })
+    }
+
+    /// Executes the inbound message of the recorded transaction again and
+    /// compares the result with it. `account_boc` is the account state right
+    /// before the transaction, the block start lt, random seed and config are
+    /// taken from `context` rather than from the executor.
+    ///
+    /// Not reproduced are masterchain state libraries (only the account
+    /// libraries are available), the thread dapp id and available credit, and
+    /// validator behavior modifiers, so the transaction depending on them
+    /// replays with divergences.
+    pub fn replay_transaction(
+        &self,
+        transaction: &TvmTransaction,
+        account_boc: &[u8],
+        context: &ReplayContext,
+    ) -> Result<ReplayReport> {
+        let state_update = transaction.read_state_update()?;
+        let account_hash = tvm_types::boc::read_single_root_boc(account_boc)?.repr_hash();
+        if account_hash != state_update.old_hash {
+            fail!(SdkError::InvalidData {
+                msg: format!(
+                    "Account state {:x} is not the state before the transaction, expected {:x}",
+                    account_hash, state_update.old_hash
+                )
+            })
+        }
+        let config = context.config.clone();
+        let params = ExecuteParams {
+            block_unixtime: transaction.now(),
+            block_lt: context.block_lt,
+            seq_no: context.seq_no,
+            last_tr_lt: Arc::new(AtomicU64::new(context.block_lt)),
+            seed_block: context.rand_seed.clone(),
+            ..ExecuteParams::default()
+        };
+        let replayed = match transaction.read_description()? {
+            TransactionDescr::Ordinary(_) => {
+                let Some(message) = transaction.read_in_msg()? else {
+                    fail!(SdkError::InvalidData {
+                        msg: "Ordinary transaction has no inbound message".to_owned()
+                    })
+                };
+                let executor = OrdinaryTransactionExecutor::new(config);
+                self.run_with_params(&executor, Some(&message), account_boc, params)?
+            }
+            TransactionDescr::TickTock(descr) => {
+                let executor = TickTockTransactionExecutor::new(config, descr.tt);
+                self.run_with_params(&executor, None, account_boc, params)?
+            }
+            TransactionDescr::Storage(_) => {
+                let executor = StorageTransactionExecutor::new(config);
+                self.run_with_params(&executor, None, account_boc, params)?
+            }
+            _ => fail!(SdkError::InvalidData { msg: "Unsupported transaction type".to_owned() }),
+        };
+
+        let recorded = Transaction::try_from(transaction)?;
+        let mut divergences = Vec::new();
+        let mut compare = |field: &str, recorded: String, replayed: String| {
+            if recorded != replayed {
+                divergences.push(ReplayDivergence { field: field.to_owned(), recorded, replayed });
+            }
+        };
+        compare(
+            "lt",
+            transaction.logical_time().to_string(),
+            replayed.transaction.logical_time().to_string(),
+        );
+        compare(
+            "exit_code",
+            format!("{:?}", recorded.compute.exit_code),
+            format!("{:?}", replayed.exit_code),
+        );
+        compare("aborted", recorded.is_aborted().to_string(), replayed.aborted.to_string());
+        compare("gas_used", recorded.compute.gas_used.to_string(), replayed.gas_used.to_string());
+        compare(
+            "total_fees",
+            recorded.total_fees.to_string(),
+            Transaction::try_from(&replayed.transaction)?.total_fees.to_string(),
+        );
+        let out_ids = |transaction: &TvmTransaction| -> Result<Vec<String>> {
+            Ok(Self::out_messages(transaction)?.into_iter().map(|m| m.id.to_string()).collect())
+        };
+        compare(
+            "out_messages",
+            out_ids(transaction)?.join(","),
+            out_ids(&replayed.transaction)?.join(","),
+        );
+        compare(
+            "new_state",
+            state_update.new_hash.as_hex_string(),
+            tvm_types::boc::read_single_root_boc(&replayed.account)?.repr_hash().as_hex_string(),
+        );
+        Ok(ReplayReport { replayed, divergences })
+    }
+
+    fn with_code(account_boc: &[u8], code: Cell) -> Result<Vec<u8>> {
+        let mut account = Account::construct_from_bytes(account_boc)?;
+        if !account.set_code(code) {
+            fail!(SdkError::InvalidData { msg: "Account is not active".to_owned() })
+        }
+        account.write_to_bytes()
+    }
+
+    fn out_messages(transaction: &TvmTransaction) -> Result<Vec<ParsedMessage>> {
+        let mut messages = Vec::new();
+        for index in 0..transaction.msg_count() {
+            if let Some(message) = transaction.get_out_msg(index)? {
+                messages.push(ParsedMessage::with_msg(&message, None)?);
+            }
+        }
+        Ok(messages)
+    }
+
+    fn run(
+        &self,
+        executor: &impl TransactionExecutor,
+        message: Option<&TvmMessage>,
+        account_boc: &[u8],
+        block_time: u32,
+        block_lt: u64,
+    ) -> Result<LocalExecutionResult> {
+        let params = ExecuteParams {
+            block_unixtime: block_time,
+            block_lt,
+            last_tr_lt: Arc::new(AtomicU64::new(block_lt)),
+            ..ExecuteParams::default()
+        };
+        self.run_with_params(executor, message, account_boc, params)
+    }
+
+    fn run_with_params(
+        &self,
+        executor: &impl TransactionExecutor,
+        message: Option<&TvmMessage>,
+        account_boc: &[u8],
+        mut params: ExecuteParams,
+    ) -> Result<LocalExecutionResult> {
+        let trace = Arc::new(Mutex::new(VecDeque::new()));
+        let max_stack_depth = Arc::new(AtomicUsize::new(0));
+        let trace_callback = (self.trace_steps > 0).then(|| {
+            let (trace, max_stack_depth) = (trace.clone(), max_stack_depth.clone());
+            let steps = self.trace_steps;
+            Arc::new(move |_: &Engine, info: &EngineTraceInfo| {
+                if !info.has_cmd() {
+                    return;
+                }
+                max_stack_depth.fetch_max(info.stack.depth(), Ordering::Relaxed);
+                let Ok(mut trace) = trace.lock() else {
+                    return;
+                };
+                if trace.len() == steps {
+                    trace.pop_front();
+                }
+                trace.push_back(TraceStep {
+                    step: info.step,
+                    cmd: info.cmd_str.clone(),
+                    gas_used: info.gas_used,
+                    gas_cmd: info.gas_cmd,
+                    stack_depth: info.stack.depth(),
+                });
+            }) as Arc<TraceCallback>
+        });
+        params.trace_callback = trace_callback;
+        let mut account_root = tvm_types::boc::read_single_root_boc(account_boc)?;
+        let (transaction, _) =
+            executor.execute_with_libs_and_params(message, &mut account_root, params)?;
+
+        let trace = trace.lock().map(|trace| trace.iter().cloned().collect()).unwrap_or_default();
+        let max_stack_depth = max_stack_depth.load(Ordering::Relaxed) as u64;
+        let exhausted = Self::resource_exhausted(&transaction, trace, max_stack_depth)?;
+        let sdk_transaction = Transaction::try_from(&transaction)?;
+        Ok(LocalExecutionResult {
+            fees: sdk_transaction.calc_fees(),
+            gas_used: sdk_transaction.compute.gas_used,
+            exit_code: sdk_transaction.compute.exit_code,
+            aborted: sdk_transaction.is_aborted(),
+            account: tvm_types::boc::write_boc(&account_root)?,
+            exhausted,
+            transaction,
+        })
+    }
+
+    fn resource_exhausted(
+        transaction: &TvmTransaction,
+        trace: Vec<TraceStep>,
+        max_stack_depth: u64,
+    ) -> Result<Option<ResourceExhausted>> {
+        let compute = match transaction.read_description()? {
+            TransactionDescr::Ordinary(descr) => descr.compute_ph,
+            TransactionDescr::TickTock(descr) => descr.compute_ph,
+            _ => return Ok(None),
+        };
+        let TrComputePhase::Vm(vm) = compute else {
+            return Ok(None);
+        };
+        let gas_limit =
+            vm.gas_limit.as_u64().max(vm.gas_credit.map_or(0, |credit| credit.as_u32() as u64));
+        let traced_depth = (max_stack_depth > 0).then_some(max_stack_depth);
+        let (resource, limit, reached) = match vm.exit_code {
+            code if code == !(ExceptionCode::OutOfGas as i32)
+                || code == ExceptionCode::OutOfGas as i32 =>
+            {
+                (VmResource::Gas, Some(gas_limit), Some(vm.gas_used.as_u64()))
+            }
+            code if code == ExceptionCode::StackOverflow as i32 => {
+                (VmResource::Stack, None, traced_depth)
+            }
+            code if code == ExceptionCode::CellOverflow as i32 => (VmResource::Cell, None, None),
+            _ => return Ok(None),
+        };
+        Ok(Some(ResourceExhausted { resource, exit_code: vm.exit_code, limit, reached, trace }))
+    }
+}
+
+/// Runs the get-method of the account given as BOC and returns the resulting
+/// stack. `method` is the method name or its numeric id.
+///
+/// Stack items are JSON values as in the client library: integers are
+/// decimal strings, or `0x` prefixed hex strings padded to 64 or 128 digits
+/// above `u128`, `null` is the null item, arrays are tuples
+/// and cells, slices, builders and continuations are objects like
+/// `{"type":"Cell","value":"<base64 BOC>"}`.
+pub fn run_get_method(account_boc: &[u8], method: &str, params: &[Value]) -> Result<Vec<Value>> {
+    let account = Account::construct_from_bytes(account_boc)?;
+    let (exit_code, engine) = execute_get_method(&account, method, params, GET_METHOD_GAS_LIMIT)?;
+    if exit_code != 0 && exit_code != 1 {
+        fail!(SdkError::InvalidData {
+            msg: format!("Get-method {} failed with exit code {}", method, exit_code)
+        })
+    }
+    engine.stack().iter().map(stack_item_to_json).collect()
+}
+
+/// Gas limits of `run_get_method_with_gas`. The get-method is run with the
+/// `initial` limit, which is multiplied by `factor` after each run out of gas
+/// until it reaches `cap`.
+#[derive(Clone, Debug)]
+pub struct GetMethodGas {
+    pub initial: i64,
+    pub cap: i64,
+    pub factor: i64,
+    /// Return the stack left by the run out of gas at the cap instead of
+    /// failing
+    pub partial: bool,
+}
+
+impl Default for GetMethodGas {
+    fn default() -> Self {
+        Self {
+            initial: GET_METHOD_GAS_LIMIT,
+            cap: 10 * GET_METHOD_GAS_LIMIT,
+            factor: 10,
+            partial: false,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct GetMethodOutput {
+    pub stack: Vec<Value>,
+    pub exit_code: i32,
+    pub gas_used: i64,
+    /// Gas limit of the last run
+    pub gas_limit: i64,
+    /// `false` if the get-method ran out of gas at the cap and the stack is
+    /// what it left
+    pub complete: bool,
+}
+
+/// Same as `run_get_method` but raises the gas limit for long get-methods
+pub fn run_get_method_with_gas(
+    account_boc: &[u8],
+    method: &str,
+    params: &[Value],
+    gas: &GetMethodGas,
+) -> Result<GetMethodOutput> {
+    let account = Account::construct_from_bytes(account_boc)?;
+    let mut gas_limit = gas.initial.clamp(1, gas.cap.max(1));
+    loop {
+        let (exit_code, engine) = execute_get_method(&account, method, params, gas_limit)?;
+        let out_of_gas = exit_code == ExceptionCode::OutOfGas as i32
+            || exit_code == !(ExceptionCode::OutOfGas as i32);
+        if out_of_gas && gas_limit < gas.cap {
+            gas_limit = gas_limit.saturating_mul(gas.factor.max(2)).min(gas.cap);
+            continue;
+        }
+        if exit_code != 0 && exit_code != 1 && !(out_of_gas && gas.partial) {
+            fail!(SdkError::InvalidData {
+                msg: format!(
+                    "Get-method {} failed with exit code {}, gas limit {}",
+                    method, exit_code, gas_limit
+                )
+            })
+        }
+        return Ok(GetMethodOutput {
+            stack: engine.stack().iter().map(stack_item_to_json).collect::<Result<_>>()?,
+            exit_code,
+            gas_used: engine.gas_used(),
+            gas_limit,
+            complete: !out_of_gas,
+        });
+    }
+}
+
+/// Runs the message on the account directly by the VM, without a
+/// transaction, and returns the messages sent by the contract. The account is
+/// not changed. Used to run ABI getters, which don't accept external messages
+/// and so can't be executed as transactions.
+pub fn run_tvm(account: &Account, message: &TvmMessage) -> Result<Vec<TvmMessage>> {
+    let selector = match message.header() {
+        CommonMsgInfo::IntMsgInfo(_) => 0,
+        CommonMsgInfo::ExtInMsgInfo(_) => -1,
+        CommonMsgInfo::ExtOutMsgInfo(_) => {
+            fail!(SdkError::InvalidData { msg: "Can't run external outbound message".to_owned() })
+        }
+    };
+    let value = message.get_value().map_or(0, |value| value.grams.as_u128());
+    let mut stack = Stack::new();
+    stack
+        .push(StackItem::int(account.balance().map_or(0, |balance| balance.grams.as_u128())))
+        .push(StackItem::int(value))
+        .push(StackItem::Cell(message.serialize()?))
+        .push(StackItem::Slice(message.body().unwrap_or_default()))
+        .push(StackItem::int(selector));
+
+    let (exit_code, engine) = execute_code(account, stack, GET_METHOD_GAS_LIMIT)?;
+    if exit_code != 0 && exit_code != 1 {
+        fail!(SdkError::InvalidData {
+            msg: format!("Contract execution failed with exit code {}", exit_code)
+        })
+    }
+    let mut actions = OutActions::construct_from_cell(engine.get_actions().as_cell()?.clone())?;
+    let mut messages = Vec::new();
+    for action in actions.iter_mut() {
+        if let OutAction::SendMsg { out_msg, .. } = std::mem::replace(action, OutAction::None) {
+            messages.push(out_msg);
+        }
+    }
+    // actions are stored in reverse order of sending
+    messages.reverse();
+    Ok(messages)
+}
+
+fn execute_get_method(
+    account: &Account,
+    method: &str,
+    params: &[Value],
+    gas_limit: i64,
+) -> Result<(i32, Engine)> {
+    let mut stack = Stack::new();
+    for param in params {
+        stack.push(stack_item_from_json(param)?);
+    }
+    stack.push(StackItem::int(method_id(method)));
+    execute_code(account, stack, gas_limit)
+}
+
+fn execute_code(account: &Account, stack: Stack, gas_limit: i64) -> Result<(i32, Engine)> {
+    let invalid = |msg: &str| SdkError::InvalidData { msg: msg.to_owned() };
+    let code = account.get_code().ok_or_else(|| invalid("Account has no code"))?;
+    let data = account.get_data().ok_or_else(|| invalid("Account has no data"))?;
+    let address = account.get_addr().ok_or_else(|| invalid("Account has no address"))?;
+
+    let config = BlockchainConfig::default();
+    let mut info =
+        tvm_vm::SmartContractInfo::with_myself(SliceData::load_cell(address.serialize()?)?);
+    info.unix_time = (SystemClock.now_ms() / 1000) as u32;
+    info.balance = account.balance().cloned().unwrap_or_default();
+    info.capabilities = config.capabilites();
+    if let Some(hash) = account.init_code_hash() {
+        info.set_init_code_hash(hash.clone());
+    }
+    info.set_mycode(code.clone());
+    let mut ctrls = SaveList::new();
+    ctrls.put(4, &mut StackItem::Cell(data))?;
+    ctrls.put(7, &mut info.into_temp_data_item())?;
+
+    let gas = Gas::new(gas_limit, 0, gas_limit, 10);
+    let mut engine = Engine::with_capabilities(config.capabilites()).setup_with_libraries(
+        SliceData::load_cell(code)?,
+        Some(ctrls),
+        Some(stack),
+        Some(gas),
+        vec![account.libraries().inner()],
+    );
+    let exit_code = match engine.execute() {
+        Ok(exit_code) => exit_code,
+        Err(err) => tvm_vm::error::tvm_exception_or_custom_code(&err),
+    };
+    Ok((exit_code, engine))
+}
+
+fn method_id(method: &str) -> u32 {
+    match method.parse() {
+        Ok(id) => id,
+        Err(_) => CRC16.checksum(method.as_bytes()) as u32 | 0x10000,
+    }
+}
+
+fn integer_to_string(value: &IntegerData) -> String {
+    let hex = value.to_str_radix(16);
+    if hex.starts_with('-') || hex.len() <= 32 {
+        value.to_str_radix(10)
+    } else if hex.len() <= 64 {
+        format!("0x{:0>64}", hex)
+    } else {
+        format!("0x{:0>128}", hex)
+    }
+}
+
+fn stack_item_to_json(item: &StackItem) -> Result<Value> {
+    let boc = |cell| -> Result<String> { Ok(base64_encode(tvm_types::boc::write_boc(&cell)?)) };
+    Ok(match item {
+        StackItem::None => Value::Null,
+        StackItem::Integer(value) => Value::String(integer_to_string(value)),
+        StackItem::Tuple(items) => {
+            Value::Array(items.iter().map(stack_item_to_json).collect::<Result<_>>()?)
+        }
+        StackItem::Cell(cell) => json!({ "type": "Cell", "value": boc(cell.clone())? }),
+        StackItem::Slice(slice) => {
+            json!({ "type": "Slice", "value": boc(slice.clone().into_cell())? })
+        }
+        StackItem::Builder(builder) => {
+            json!({ "type": "Builder", "value": boc(builder.as_ref().clone().into_cell()?)? })
+        }
+        StackItem::Continuation(cont) => {
+            json!({ "type": "Continuation", "value": boc(cont.code().clone().into_cell())? })
+        }
+    })
+}
+
+fn stack_item_from_json(value: &Value) -> Result<StackItem> {
+    let invalid = || error!(SdkError::InvalidData { msg: format!("Invalid stack item {}", value) });
+    Ok(match value {
+        Value::Null => StackItem::None,
+        Value::Bool(value) => StackItem::boolean(*value),
+        Value::Number(number) => StackItem::int(number.as_i64().ok_or_else(invalid)?),
+        Value::String(string) => {
+            let integer = match string.strip_prefix("0x") {
+                Some(hex) => IntegerData::from_str_radix(hex, 16),
+                None => IntegerData::from_str_radix(string, 10),
+            };
+            StackItem::integer(integer.map_err(|_| invalid())?)
+        }
+        Value::Array(items) => {
+            StackItem::tuple(items.iter().map(stack_item_from_json).collect::<Result<_>>()?)
+        }
+        Value::Object(object) => {
+            let boc = object.get("value").and_then(Value::as_str).ok_or_else(invalid)?;
+            let cell = tvm_types::boc::read_single_root_boc(base64_decode(boc)?)?;
+            match object.get("type").and_then(Value::as_str) {
+                Some("Cell") => StackItem::cell(cell),
+                Some("Slice") => StackItem::slice(SliceData::load_cell(cell)?),
+                Some("Builder") => StackItem::builder(BuilderData::from_cell(&cell)?),
+                Some("Continuation") => StackItem::continuation(ContinuationData::with_code(
+                    SliceData::load_cell(cell)?,
+                )),
+                _ => return Err(invalid()),
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+#[path = "tests/test_executor.rs"]
+mod tests;
@@ -0,0 +1,228 @@
+// Copyright 2018-2021 TON Labs LTD.
+//
+// Licensed under the SOFTWARE EVALUATION License (the "License"); you may not
+// use this file except in compliance with the License.
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific TON DEV software governing permissions and
+// limitations under the License.
+
+use std::sync::Arc;
+use std::sync::atomic::AtomicI64;
+use std::sync::atomic::Ordering;
+
+use chrono::prelude::Utc;
+use serde_json::Map;
+use serde_json::Value;
+use tvm_abi::AbiCache;
+use tvm_abi::ParamType;
+use tvm_abi::PublicKeyData;
+use tvm_types::Result;
+
+use crate::FunctionCallSet;
+
+/// Source of the current time for message headers
+pub trait Clock: Send + Sync {
+    /// UNIX time in milliseconds
+    fn now_ms(&self) -> u64;
+}
+
+/// System wall clock
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now_ms(&self) -> u64 {
+        Utc::now().timestamp_millis() as u64
+    }
+}
+
+/// Offset not learned yet
+const NO_OFFSET: i64 = i64::MIN;
+
+/// Clock corrected by the offset from the network time, for clients with
+/// skewed clocks whose messages are rejected as expired.
+///
+/// The offset is learned from network times observed by the client, e.g.
+/// `now` of its transactions or generation time of the latest block, and is
+/// smoothed, as these times lag behind the real time by the block latency.
+/// Clones share the offset. Reading and learning never block, so the clock
+/// may be used from async code.
+#[derive(Clone)]
+pub struct DriftCorrectedClock {
+    clock: Arc<dyn Clock>,
+    offset_ms: Arc<AtomicI64>,
+}
+
+impl DriftCorrectedClock {
+    /// Corrects the system clock
+    pub fn new() -> Self {
+        Self::with_clock(Arc::new(SystemClock))
+    }
+
+    pub fn with_clock(clock: Arc<dyn Clock>) -> Self {
+        Self { clock, offset_ms: Arc::new(AtomicI64::new(NO_OFFSET)) }
+    }
+
+    /// Learns the offset from the network time, UNIX time in milliseconds.
+    /// The first observation sets the offset, later ones move it by a
+    /// quarter of the difference.
+    pub fn observe_ms(&self, network_time_ms: u64) {
+        let sample = network_time_ms as i64 - self.clock.now_ms() as i64;
+        let _ = self.offset_ms.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |offset| {
+            Some(match offset {
+                NO_OFFSET => sample,
+                offset => offset + (sample - offset) / 4,
+            })
+        });
+    }
+
+    /// Learns the offset from the network time in seconds, e.g. `now` of the
+    /// transaction
+    pub fn observe(&self, network_time: u32) {
+        self.observe_ms(network_time as u64 * 1000)
+    }
+
+    /// Offset added to the clock time, `None` until the first observation
+    pub fn offset_ms(&self) -> Option<i64> {
+        match self.offset_ms.load(Ordering::Relaxed) {
+            NO_OFFSET => None,
+            offset => Some(offset),
+        }
+    }
+
+    /// Sets the offset, e.g. restored from the previous run
+    pub fn set_offset_ms(&self, offset_ms: i64) {
+        self.offset_ms.store(offset_ms.max(NO_OFFSET + 1), Ordering::Relaxed);
+    }
+}
+
+impl Default for DriftCorrectedClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock for DriftCorrectedClock {
+    fn now_ms(&self) -> u64 {
+        let now = self.clock.now_ms();
+        match self.offset_ms() {
+            Some(offset) => now.saturating_add_signed(offset),
+            None => now,
+        }
+    }
+}
+
+/// Fills `time`, `expire` and `pubkey` header parameters of external calls.
+///
+/// `time` is set to the clock time in milliseconds, used by contracts for
+/// replay protection, and `expire` to the clock time plus TTL in seconds.
+/// Only parameters declared in the ABI header are filled, values already set
+/// in the call header are kept.
+#[derive(Clone)]
+pub struct ExpirationPolicy {
+    ttl: u32,
+    clock: Arc<dyn Clock>,
+    public_key: Option<PublicKeyData>,
+}
+
+impl ExpirationPolicy {
+    /// Policy with TTL in seconds and the system clock
+    pub fn new(ttl: u32) -> Self {
+        Self { ttl, clock: Arc::new(SystemClock), public_key: None }
+    }
+
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Key put into `pubkey` header. Signed calls get the signing key there
+    /// without it, so it is needed for messages signed outside of the SDK.
+    pub fn with_public_key(mut self, public_key: PublicKeyData) -> Self {
+        self.public_key = Some(public_key);
+        self
+    }
+
+    pub fn ttl(&self) -> u32 {
+        self.ttl
+    }
+
+    pub fn public_key(&self) -> Option<&PublicKeyData> {
+        self.public_key.as_ref()
+    }
+
+    /// Clock time, UNIX time in seconds
+    pub fn now(&self) -> u32 {
+        (self.clock.now_ms() / 1000).min(u32::MAX as u64) as u32
+    }
+
+    /// Expiration time of the message built now, UNIX time in seconds
+    pub fn expire_at(&self) -> u32 {
+        self.header_values(self.clock.now_ms()).1
+    }
+
+    /// Returns the call with header parameters filled by the policy
+    pub fn apply(&self, call: &FunctionCallSet) -> Result<FunctionCallSet> {
+        Ok(self.apply_with_expire(call)?.0)
+    }
+
+    /// Same as `apply` but also returns the expiration time of the call,
+    /// `None` if the function has no `expire` header, i.e. the message
+    /// never expires
+    pub fn apply_with_expire(
+        &self,
+        call: &FunctionCallSet,
+    ) -> Result<(FunctionCallSet, Option<u32>)> {
+        let contract = AbiCache::global().load(&call.abi)?;
+        let function = contract.function(&call.func)?;
+        let mut header = match call.header.as_deref() {
+            Some(header) => serde_json::from_str(header)?,
+            None => Map::new(),
+        };
+        let (time, expire) = self.header_values(self.clock.now_ms());
+        let mut expire_at = None;
+        for param in function.header_params() {
+            if let Some(value) = header.get(&param.name) {
+                if param.kind == ParamType::Expire {
+                    expire_at = value_to_u32(value);
+                }
+                continue;
+            }
+            let value = match param.kind {
+                ParamType::Time => Value::from(time),
+                ParamType::Expire => {
+                    expire_at = Some(expire);
+                    Value::from(expire)
+                }
+                ParamType::PublicKey => match &self.public_key {
+                    Some(key) => Value::from(hex::encode(key)),
+                    None => continue,
+                },
+                _ => continue,
+            };
+            header.insert(param.name.clone(), value);
+        }
+        let call = FunctionCallSet {
+            header: (!header.is_empty()).then(|| Value::Object(header).to_string()),
+            ..call.clone()
+        };
+        Ok((call, expire_at))
+    }
+
+    fn header_values(&self, now_ms: u64) -> (u64, u32) {
+        let expire = (now_ms / 1000).saturating_add(self.ttl as u64);
+        (now_ms, expire.min(u32::MAX as u64) as u32)
+    }
+}
+
+// Header values are numbers or decimal strings
+fn value_to_u32(value: &Value) -> Option<u32> {
+    match value {
+        Value::Number(number) => number.as_u64()?.try_into().ok(),
+        Value::String(string) => string.parse().ok(),
+        _ => None,
+    }
+}
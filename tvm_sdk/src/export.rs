@@ -0,0 +1,451 @@
+// Copyright 2018-2021 TON Labs LTD.
+//
+// Licensed under the SOFTWARE EVALUATION License (the "License"); you may not
+// use this file except in compliance with the License.
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific TON DEV software governing permissions and
+// limitations under the License.
+
+//! Tabular export of transactions and decoded events. Rows are JSON objects
+//! keyed by column name, which are written as CSV (`export-csv` feature) or
+//! Parquet (`export-parquet` feature) according to `ExportSchema`. With the
+//! `transport` feature `ExportWriter` also exports account transactions and
+//! `EventSubscription` events straight from the transport.
+
+use serde_json::Map;
+use serde_json::Value;
+use tvm_abi::ParamType;
+use tvm_types::Result;
+use tvm_types::error;
+use tvm_types::fail;
+
+use crate::AbiContract;
+use crate::Transaction;
+use crate::error::SdkError;
+
+pub type ExportRow = Map<String, Value>;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ColumnType {
+    Bool,
+    /// Integer fitting into `i64`
+    Int,
+    /// Any other value. Non-string JSON values are written as JSON text
+    Text,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExportColumn {
+    pub name: String,
+    pub kind: ColumnType,
+}
+
+impl ExportColumn {
+    pub fn new(name: &str, kind: ColumnType) -> Self {
+        Self { name: name.to_owned(), kind }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExportSchema {
+    pub columns: Vec<ExportColumn>,
+}
+
+impl ExportSchema {
+    /// Schema of rows produced by `transaction_row`
+    pub fn transactions() -> Self {
+        let columns = [
+            ("id", ColumnType::Text),
+            ("now", ColumnType::Int),
+            ("aborted", ColumnType::Bool),
+            ("in_msg", ColumnType::Text),
+            ("out_msgs_count", ColumnType::Int),
+            ("exit_code", ColumnType::Int),
+            ("gas_used", ColumnType::Int),
+            ("gas_fees", ColumnType::Int),
+            ("storage_fees", ColumnType::Int),
+            ("fwd_fees", ColumnType::Int),
+            ("total_fees", ColumnType::Int),
+        ];
+        Self {
+            columns: columns.iter().map(|(name, kind)| ExportColumn::new(name, *kind)).collect(),
+        }
+    }
+
+    /// Schema of rows produced by `event_row` for the ABI event: the `event`
+    /// name column followed by a column per event parameter
+    pub fn event(abi: &AbiContract, event: &str) -> Result<Self> {
+        let event = abi.event(event)?;
+        let mut columns = vec![ExportColumn::new("event", ColumnType::Text)];
+        for param in event.input_params() {
+            columns.push(ExportColumn::new(&param.name, Self::column_type(&param.kind)));
+        }
+        Ok(Self { columns })
+    }
+
+    /// Keeps only listed columns in the given order
+    pub fn select(self, names: &[&str]) -> Result<Self> {
+        let mut columns = Vec::with_capacity(names.len());
+        for name in names {
+            match self.columns.iter().find(|column| column.name == *name) {
+                Some(column) => columns.push(column.clone()),
+                None => fail!(SdkError::InvalidData {
+                    msg: format!("Column {} is not present in export schema", name)
+                }),
+            }
+        }
+        Ok(Self { columns })
+    }
+
+    fn column_type(kind: &ParamType) -> ColumnType {
+        match kind {
+            ParamType::Bool => ColumnType::Bool,
+            ParamType::Uint(size) if *size < 64 => ColumnType::Int,
+            ParamType::Int(size) if *size <= 64 => ColumnType::Int,
+            ParamType::Expire => ColumnType::Int,
+            _ => ColumnType::Text,
+        }
+    }
+}
+
+pub fn transaction_row(transaction: &Transaction) -> ExportRow {
+    let fees = transaction.calc_fees();
+    let mut row = ExportRow::new();
+    row.insert("id".to_owned(), transaction.id.to_string().into());
+    row.insert("now".to_owned(), transaction.now.into());
+    row.insert("aborted".to_owned(), transaction.aborted.into());
+    row.insert("in_msg".to_owned(), transaction.in_msg.as_ref().map(|id| id.to_string()).into());
+    row.insert("out_msgs_count".to_owned(), transaction.out_msgs.len().into());
+    row.insert("exit_code".to_owned(), transaction.compute.exit_code.into());
+    row.insert("gas_used".to_owned(), transaction.compute.gas_used.into());
+    row.insert("gas_fees".to_owned(), fees.gas_fee.into());
+    row.insert("storage_fees".to_owned(), fees.storage_fee.into());
+    row.insert("fwd_fees".to_owned(), fees.total_fwd_fees.into());
+    row.insert("total_fees".to_owned(), transaction.total_fees.into());
+    row
+}
+
+/// Builds row from decoded event name and its JSON encoded parameters, as
+/// returned by `Contract::decode_unknown_function_response_json`
+pub fn event_row(event_name: &str, params: &str) -> Result<ExportRow> {
+    let mut row = match serde_json::from_str(params)? {
+        Value::Object(map) => map,
+        _ => fail!(SdkError::InvalidData { msg: "Event parameters must be an object".to_owned() }),
+    };
+    row.insert("event".to_owned(), event_name.into());
+    Ok(row)
+}
+
+#[cfg(any(feature = "export-csv", feature = "export-parquet"))]
+fn cell_int(value: &Value) -> Result<Option<i64>> {
+    let int = match value {
+        Value::Null => None,
+        Value::Number(number) => number.as_i64(),
+        Value::String(string) => match string.strip_prefix("0x") {
+            Some(hex) => i64::from_str_radix(hex, 16).ok(),
+            None => string.parse().ok(),
+        },
+        _ => None,
+    };
+    if int.is_none() && !value.is_null() {
+        fail!(SdkError::InvalidData { msg: format!("Value {} is not an integer", value) })
+    }
+    Ok(int)
+}
+
+#[cfg(any(feature = "export-csv", feature = "export-parquet"))]
+fn cell_bool(value: &Value) -> Result<Option<bool>> {
+    match value {
+        Value::Null => Ok(None),
+        Value::Bool(b) => Ok(Some(*b)),
+        _ => fail!(SdkError::InvalidData { msg: format!("Value {} is not a boolean", value) }),
+    }
+}
+
+#[cfg(any(feature = "export-csv", feature = "export-parquet"))]
+fn cell_text(value: &Value) -> Option<String> {
+    match value {
+        Value::Null => None,
+        Value::String(string) => Some(string.clone()),
+        other => Some(other.to_string()),
+    }
+}
+
+/// Rows buffered by `ExportWriter` before they are written as a Parquet row
+/// group
+#[cfg(feature = "export-parquet")]
+const PARQUET_ROW_GROUP_SIZE: usize = 10_000;
+
+/// Transactions requested per page by
+/// `ExportWriter::write_account_transactions`
+#[cfg(all(feature = "transport", any(feature = "export-csv", feature = "export-parquet")))]
+const EXPORT_PAGE_SIZE: u32 = 50;
+
+#[cfg(any(feature = "export-csv", feature = "export-parquet"))]
+enum ExportOutput<W: std::io::Write + Send> {
+    #[cfg(feature = "export-csv")]
+    Csv(csv::Writer<W>),
+    #[cfg(feature = "export-parquet")]
+    Parquet { writer: parquet::file::writer::SerializedFileWriter<W>, rows: Vec<ExportRow> },
+}
+
+/// Writes rows one by one, so transaction and event streams of any length
+/// are exported without collecting them first. CSV rows are written as they
+/// come, Parquet rows are written in row groups of `PARQUET_ROW_GROUP_SIZE`.
+/// The output is complete only after `finish`.
+#[cfg(any(feature = "export-csv", feature = "export-parquet"))]
+pub struct ExportWriter<W: std::io::Write + Send> {
+    schema: ExportSchema,
+    output: ExportOutput<W>,
+    rows: usize,
+}
+
+#[cfg(any(feature = "export-csv", feature = "export-parquet"))]
+impl ExportWriter<std::fs::File> {
+    /// Creates the file and the writer of the format given by its extension,
+    /// `csv` or `parquet`
+    pub fn create(schema: ExportSchema, path: impl AsRef<std::path::Path>) -> Result<Self> {
+        let path = path.as_ref();
+        match path.extension().and_then(|extension| extension.to_str()) {
+            #[cfg(feature = "export-csv")]
+            Some("csv") => Self::csv(schema, std::fs::File::create(path)?),
+            #[cfg(feature = "export-parquet")]
+            Some("parquet") => Self::parquet(schema, std::fs::File::create(path)?),
+            _ => fail!(SdkError::InvalidData {
+                msg: format!("Unsupported export file format: {}", path.display())
+            }),
+        }
+    }
+}
+
+#[cfg(any(feature = "export-csv", feature = "export-parquet"))]
+impl<W: std::io::Write + Send> ExportWriter<W> {
+    #[cfg(feature = "export-csv")]
+    pub fn csv(schema: ExportSchema, writer: W) -> Result<Self> {
+        let mut writer = csv::Writer::from_writer(writer);
+        writer.write_record(schema.columns.iter().map(|column| column.name.as_str()))?;
+        Ok(Self { schema, output: ExportOutput::Csv(writer), rows: 0 })
+    }
+
+    #[cfg(feature = "export-parquet")]
+    pub fn parquet(schema: ExportSchema, writer: W) -> Result<Self> {
+        use std::sync::Arc;
+
+        use parquet::basic::Compression;
+        use parquet::basic::ConvertedType;
+        use parquet::basic::Repetition;
+        use parquet::basic::Type as PhysicalType;
+        use parquet::file::properties::WriterProperties;
+        use parquet::file::writer::SerializedFileWriter;
+        use parquet::schema::types::Type;
+
+        let mut fields = Vec::with_capacity(schema.columns.len());
+        for column in &schema.columns {
+            let builder = match column.kind {
+                ColumnType::Bool => {
+                    Type::primitive_type_builder(&column.name, PhysicalType::BOOLEAN)
+                }
+                ColumnType::Int => Type::primitive_type_builder(&column.name, PhysicalType::INT64),
+                ColumnType::Text => {
+                    Type::primitive_type_builder(&column.name, PhysicalType::BYTE_ARRAY)
+                        .with_converted_type(ConvertedType::UTF8)
+                }
+            };
+            fields.push(Arc::new(builder.with_repetition(Repetition::OPTIONAL).build()?));
+        }
+        let parquet_schema = Type::group_type_builder("schema").with_fields(fields).build()?;
+        let props = WriterProperties::builder().set_compression(Compression::SNAPPY).build();
+        let writer = SerializedFileWriter::new(writer, Arc::new(parquet_schema), Arc::new(props))?;
+        Ok(Self { schema, output: ExportOutput::Parquet { writer, rows: Vec::new() }, rows: 0 })
+    }
+
+    pub fn schema(&self) -> &ExportSchema {
+        &self.schema
+    }
+
+    /// Number of rows written so far
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    pub fn write_row(&mut self, row: &ExportRow) -> Result<()> {
+        match &mut self.output {
+            #[cfg(feature = "export-csv")]
+            ExportOutput::Csv(writer) => {
+                let mut record = Vec::with_capacity(self.schema.columns.len());
+                for column in &self.schema.columns {
+                    let value = row.get(&column.name).unwrap_or(&Value::Null);
+                    record.push(match column.kind {
+                        ColumnType::Bool => cell_bool(value)?.map(|b| b.to_string()),
+                        ColumnType::Int => cell_int(value)?.map(|int| int.to_string()),
+                        ColumnType::Text => cell_text(value),
+                    });
+                }
+                writer.write_record(record.iter().map(|cell| cell.as_deref().unwrap_or("")))?;
+            }
+            #[cfg(feature = "export-parquet")]
+            ExportOutput::Parquet { writer, rows } => {
+                rows.push(row.clone());
+                if rows.len() >= PARQUET_ROW_GROUP_SIZE {
+                    write_row_group(writer, &self.schema, rows)?;
+                    rows.clear();
+                }
+            }
+        }
+        self.rows += 1;
+        Ok(())
+    }
+
+    /// Writes buffered rows and the file footer, flushes the output
+    pub fn finish(self) -> Result<()> {
+        match self.output {
+            #[cfg(feature = "export-csv")]
+            ExportOutput::Csv(mut writer) => writer.flush()?,
+            #[cfg(feature = "export-parquet")]
+            ExportOutput::Parquet { mut writer, rows } => {
+                if !rows.is_empty() {
+                    write_row_group(&mut writer, &self.schema, &rows)?;
+                }
+                writer.close()?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(all(feature = "transport", any(feature = "export-csv", feature = "export-parquet")))]
+impl<W: std::io::Write + Send> ExportWriter<W> {
+    /// Writes `transaction_row` of every account transaction with logical
+    /// time greater than `after_lt`, paging through the transport until there
+    /// are no more. Returns the logical time of the last written transaction,
+    /// to resume the export later, or `after_lt` if nothing was written.
+    pub async fn write_account_transactions<T: crate::transport::Transport + ?Sized>(
+        &mut self,
+        transport: &T,
+        address: &tvm_block::MsgAddressInt,
+        after_lt: u64,
+    ) -> Result<u64> {
+        let mut last_lt = after_lt;
+        loop {
+            let transactions =
+                transport.query_account_transactions(address, last_lt, EXPORT_PAGE_SIZE).await?;
+            for transaction in &transactions {
+                self.write_row(&transaction_row(&Transaction::try_from(transaction)?))?;
+                last_lt = last_lt.max(transaction.logical_time());
+            }
+            if transactions.len() < EXPORT_PAGE_SIZE as usize {
+                return Ok(last_lt);
+            }
+        }
+    }
+
+    /// Writes `event_row` of every event polled from the subscription until
+    /// it has no new transactions. Returns the number of written events, the
+    /// subscription's `last_lt` tells where to resume.
+    pub async fn write_events<T: crate::transport::Transport + ?Sized>(
+        &mut self,
+        subscription: &mut crate::flows::EventSubscription<'_, T>,
+    ) -> Result<usize> {
+        let mut written = 0;
+        loop {
+            let last_lt = subscription.last_lt();
+            for event in subscription.next_events().await? {
+                self.write_row(&event_row(&event.event.function_name, &event.event.params)?)?;
+                written += 1;
+            }
+            if subscription.last_lt() == last_lt {
+                return Ok(written);
+            }
+        }
+    }
+}
+
+#[cfg(feature = "export-csv")]
+pub fn write_csv<W: std::io::Write + Send>(
+    schema: &ExportSchema,
+    rows: &[ExportRow],
+    writer: W,
+) -> Result<()> {
+    let mut writer = ExportWriter::csv(schema.clone(), writer)?;
+    for row in rows {
+        writer.write_row(row)?;
+    }
+    writer.finish()
+}
+
+#[cfg(feature = "export-parquet")]
+pub fn write_parquet<W: std::io::Write + Send>(
+    schema: &ExportSchema,
+    rows: &[ExportRow],
+    writer: W,
+) -> Result<()> {
+    let mut writer = ExportWriter::parquet(schema.clone(), writer)?;
+    for row in rows {
+        writer.write_row(row)?;
+    }
+    writer.finish()
+}
+
+#[cfg(feature = "export-parquet")]
+fn write_row_group<W: std::io::Write + Send>(
+    writer: &mut parquet::file::writer::SerializedFileWriter<W>,
+    schema: &ExportSchema,
+    rows: &[ExportRow],
+) -> Result<()> {
+    use parquet::data_type::BoolType;
+    use parquet::data_type::ByteArray;
+    use parquet::data_type::ByteArrayType;
+    use parquet::data_type::Int64Type;
+
+    let mut row_group = writer.next_row_group()?;
+    for column in &schema.columns {
+        let Some(mut column_writer) = row_group.next_column()? else {
+            fail!(SdkError::InternalError { msg: "Parquet column count mismatch".to_owned() })
+        };
+        let values = rows.iter().map(|row| row.get(&column.name).unwrap_or(&Value::Null));
+        let mut def_levels = Vec::with_capacity(rows.len());
+        match column.kind {
+            ColumnType::Bool => {
+                let mut data = vec![];
+                for value in values {
+                    let cell = cell_bool(value)?;
+                    def_levels.push(cell.is_some() as i16);
+                    data.extend(cell);
+                }
+                column_writer.typed::<BoolType>().write_batch(&data, Some(&def_levels), None)?;
+            }
+            ColumnType::Int => {
+                let mut data = vec![];
+                for value in values {
+                    let cell = cell_int(value)?;
+                    def_levels.push(cell.is_some() as i16);
+                    data.extend(cell);
+                }
+                column_writer.typed::<Int64Type>().write_batch(&data, Some(&def_levels), None)?;
+            }
+            ColumnType::Text => {
+                let mut data = vec![];
+                for value in values {
+                    let cell = cell_text(value);
+                    def_levels.push(cell.is_some() as i16);
+                    data.extend(cell.map(|text| ByteArray::from(text.into_bytes())));
+                }
+                column_writer.typed::<ByteArrayType>().write_batch(
+                    &data,
+                    Some(&def_levels),
+                    None,
+                )?;
+            }
+        }
+        column_writer.close()?;
+    }
+    row_group.close()?;
+    Ok(())
+}
+
+#[cfg(all(test, any(feature = "export-csv", feature = "export-parquet")))]
+#[path = "tests/test_export.rs"]
+mod tests;
@@ -0,0 +1,496 @@
+// Copyright 2018-2021 TON Labs LTD.
+//
+// Licensed under the SOFTWARE EVALUATION License (the "License"); you may not
+// use this file except in compliance with the License.
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific TON DEV software governing permissions and
+// limitations under the License.
+
+//! Common end-to-end recipes on top of `Transport`: deploying a multisig
+//! wallet, transferring value with a text comment, deploying a contract and
+//! calling it, calling a contract with decoding of its response and events,
+//! and following events emitted by a contract and changes of its data.
+
+use serde_json::Map;
+use serde_json::Value;
+use serde_json::json;
+use tvm_abi::PublicKeyData;
+use tvm_abi::json_abi::DecodedMessage;
+use tvm_block::AccountStatus;
+use tvm_block::MsgAddressExt;
+use tvm_block::MsgAddressInt;
+use tvm_types::BuilderData;
+use tvm_types::Cell;
+use tvm_types::IBitstring;
+use tvm_types::Result;
+use tvm_types::error;
+use tvm_types::fail;
+
+use crate::AccountDataDiffer;
+use crate::Contract;
+use crate::ContractImage;
+use crate::ExpirationPolicy;
+use crate::FieldChange;
+use crate::FunctionCallSet;
+use crate::Signer;
+use crate::SigningOptions;
+use crate::Transaction;
+pub use crate::deployed_contract::CallResult;
+pub use crate::deployed_contract::decode_call_result;
+use crate::error::SdkError;
+use crate::multisig::Multisig;
+use crate::multisig::SubmitTransaction;
+use crate::recommended_bounce;
+use crate::transport::Transport;
+
+const DEFAULT_TIMEOUT_MS: u32 = 60_000;
+const EVENTS_PAGE_SIZE: u32 = 50;
+
+/// Multisig wallet to deploy. The wallet address must be funded before.
+pub struct WalletDeploy {
+    pub image: ContractImage,
+    pub abi: String,
+    pub owners: Vec<PublicKeyData>,
+    pub req_confirms: u8,
+    pub workchain_id: i32,
+}
+
+/// Value transfer from the multisig wallet
+#[derive(Clone, Debug)]
+pub struct Transfer {
+    pub dest: MsgAddressInt,
+    pub value: u128,
+    pub bounce: bool,
+    pub comment: Option<String>,
+}
+
+/// Handling of transfers with the bounce flag to destinations which are not
+/// active, see `recommended_bounce`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum UninitDestination {
+    /// Sends the transfer without the bounce flag, so it funds the account
+    #[default]
+    DisableBounce,
+    /// Fails the transfer, the caller must clear the flag explicitly
+    Reject,
+    /// Sends the transfer as is, without checking the destination
+    Unchecked,
+}
+
+/// Handling of deploys found racing with another deploy of the same
+/// contract: the message arrives after the account is deployed, its state
+/// init is ignored and the constructor call fails
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RedeployHandling {
+    /// Reports `DeployOutcome::AlreadyDeployed`
+    #[default]
+    Report,
+    /// Resends the constructor call without the state init, for contracts
+    /// which constructor may be called on the deployed account
+    ResendConstructor,
+}
+
+/// Contract deployed by the flow
+pub struct Deployed {
+    pub address: MsgAddressInt,
+    pub transaction: Transaction,
+}
+
+/// Result of `Flows::deploy_with_outcome`
+pub enum DeployOutcome {
+    /// Deployed by the message
+    Deployed(Deployed),
+    /// Deployed by another message, the constructor call is resent without
+    /// the state init and processed
+    ConstructorResent(Deployed),
+    /// Deployed by another message with the same code, the constructor is
+    /// not called by this flow
+    AlreadyDeployed { address: MsgAddressInt },
+}
+
+/// Contract deployed and called by `Flows::deploy_and_call`
+pub struct DeployedCall {
+    pub deployed: Deployed,
+    pub call: CallResult,
+}
+
+/// Event decoded by `EventSubscription`
+pub struct ContractEvent {
+    /// Logical time of the transaction which emitted the event
+    pub transaction_lt: u64,
+    pub event: DecodedMessage,
+}
+
+/// Events emitted by the contract, polled from its transactions in the
+/// logical time order. Created by `Flows::subscribe_events`.
+pub struct EventSubscription<'a, T: Transport + ?Sized> {
+    transport: &'a T,
+    address: MsgAddressInt,
+    abi: String,
+    last_lt: u64,
+}
+
+impl<'a, T: Transport + ?Sized> EventSubscription<'a, T> {
+    pub fn address(&self) -> &MsgAddressInt {
+        &self.address
+    }
+
+    pub(crate) fn transport(&self) -> &'a T {
+        self.transport
+    }
+
+    /// Logical time of the last transaction seen, to resume the
+    /// subscription after restart
+    pub fn last_lt(&self) -> u64 {
+        self.last_lt
+    }
+
+    /// Fetches transactions after the last seen one and decodes events of
+    /// their external outbound messages. Returns no events if there are no
+    /// new transactions, messages not matching the ABI are skipped.
+    pub async fn next_events(&mut self) -> Result<Vec<ContractEvent>> {
+        let transactions = self
+            .transport
+            .query_account_transactions(&self.address, self.last_lt, EVENTS_PAGE_SIZE)
+            .await?;
+        let mut events = Vec::new();
+        for transaction in transactions {
+            let transaction_lt = transaction.logical_time();
+            let result =
+                decode_call_result(self.abi.as_str(), Transaction::try_from(&transaction)?)?;
+            events.extend(
+                result.events.into_iter().map(|event| ContractEvent { transaction_lt, event }),
+            );
+            self.last_lt = self.last_lt.max(transaction_lt);
+        }
+        Ok(events)
+    }
+}
+
+/// Changes of decoded data fields of the account, polled from the account
+/// state. Created by `Flows::subscribe_account`.
+pub struct AccountSubscription<'a, T: Transport + ?Sized> {
+    transport: &'a T,
+    address: MsgAddressInt,
+    differ: AccountDataDiffer,
+    last_lt: Option<u64>,
+}
+
+impl<T: Transport + ?Sized> AccountSubscription<'_, T> {
+    /// Last known decoded account data
+    pub fn last_data(&self) -> Option<&Map<String, Value>> {
+        self.differ.last_data()
+    }
+
+    /// Fetches the account and returns fields changed since the previous
+    /// call. The first call reports all fields as new ones, the account
+    /// without data (not deployed, frozen or deleted) has no fields. The
+    /// data is decoded only when the account has new transactions.
+    pub async fn next_changes(&mut self) -> Result<Vec<FieldChange>> {
+        let account = self.transport.query_account(&self.address).await?;
+        let last_lt = account.as_ref().and_then(|account| account.last_tr_time());
+        if self.differ.last_data().is_some() && last_lt == self.last_lt {
+            return Ok(Vec::new());
+        }
+        let changes = match account {
+            Some(account) => self.differ.update_from_account(&account)?,
+            None => self.differ.clear(),
+        };
+        self.last_lt = last_lt;
+        Ok(changes)
+    }
+}
+
+/// Runs the flows over the transport. Each message is sent once and waited
+/// for up to the timeout, its headers are filled by the expiration policy.
+pub struct Flows<'a, T: Transport + ?Sized> {
+    transport: &'a T,
+    policy: ExpirationPolicy,
+    timeout_ms: u32,
+    uninit_destination: UninitDestination,
+    redeploy: RedeployHandling,
+    signing: SigningOptions,
+}
+
+impl<'a, T: Transport + ?Sized> Flows<'a, T> {
+    pub fn new(transport: &'a T) -> Self {
+        Self {
+            transport,
+            policy: ExpirationPolicy::new(DEFAULT_TIMEOUT_MS / 1000),
+            timeout_ms: DEFAULT_TIMEOUT_MS,
+            uninit_destination: UninitDestination::default(),
+            redeploy: RedeployHandling::default(),
+            signing: SigningOptions::default(),
+        }
+    }
+
+    pub fn with_expiration(mut self, policy: ExpirationPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Options of signing the messages, e.g. with the signature id of the
+    /// network
+    pub fn with_signing_options(mut self, options: SigningOptions) -> Self {
+        self.signing = options;
+        self
+    }
+
+    /// Time to wait for each transaction, in milliseconds
+    pub fn with_timeout(mut self, timeout_ms: u32) -> Self {
+        self.timeout_ms = timeout_ms;
+        self
+    }
+
+    pub fn with_uninit_destination(mut self, handling: UninitDestination) -> Self {
+        self.uninit_destination = handling;
+        self
+    }
+
+    pub fn with_redeploy(mut self, handling: RedeployHandling) -> Self {
+        self.redeploy = handling;
+        self
+    }
+
+    /// Deploys multisig wallet with the custodians
+    pub async fn deploy_wallet(
+        &self,
+        wallet: &WalletDeploy,
+        signer: &dyn Signer,
+    ) -> Result<Deployed> {
+        let owners: Vec<String> =
+            wallet.owners.iter().map(|key| format!("0x{}", hex::encode(key))).collect();
+        let constructor = FunctionCallSet {
+            func: "constructor".to_owned(),
+            header: None,
+            input: json!({ "owners": owners, "reqConfirms": wallet.req_confirms }).to_string(),
+            abi: wallet.abi.clone(),
+        };
+        self.deploy(&wallet.image, &constructor, Some(signer), wallet.workchain_id).await
+    }
+
+    /// Transfer as it is sent by `transfer`: with the bounce flag cleared if
+    /// the destination is not active and the flows are configured so
+    pub async fn prepare_transfer(&self, transfer: &Transfer) -> Result<Transfer> {
+        if !transfer.bounce || self.uninit_destination == UninitDestination::Unchecked {
+            return Ok(transfer.clone());
+        }
+        let status = match self.transport.query_account(&transfer.dest).await? {
+            Some(account) => account.status(),
+            None => AccountStatus::AccStateNonexist,
+        };
+        if recommended_bounce(&status) {
+            return Ok(transfer.clone());
+        }
+        match self.uninit_destination {
+            UninitDestination::Reject => fail!(SdkError::InvalidData {
+                msg: format!(
+                    "Destination {} is not active, transfer with bounce flag would be \
+                     returned. Send it without the flag to fund the account.",
+                    transfer.dest
+                )
+            }),
+            _ => Ok(Transfer { bounce: false, ..transfer.clone() }),
+        }
+    }
+
+    /// Sends value with optional text comment from the multisig wallet. The
+    /// signer must be a custodian.
+    pub async fn transfer(
+        &self,
+        wallet: &Multisig,
+        transfer: &Transfer,
+        signer: &dyn Signer,
+    ) -> Result<CallResult> {
+        let transfer = &self.prepare_transfer(transfer).await?;
+        let payload = transfer.comment.as_deref().map(comment_payload).transpose()?;
+        let call = wallet.submit_transaction_call(
+            &SubmitTransaction {
+                dest: transfer.dest.clone(),
+                value: transfer.value,
+                bounce: transfer.bounce,
+                all_balance: false,
+                payload,
+            },
+            None,
+            None,
+        )?;
+        self.call(wallet.address(), &call, Some(signer)).await
+    }
+
+    /// Deploys the image with the constructor call. Fails if the contract
+    /// is found deployed by another message, see `deploy_with_outcome`.
+    pub async fn deploy(
+        &self,
+        image: &ContractImage,
+        constructor: &FunctionCallSet,
+        signer: Option<&dyn Signer>,
+        workchain_id: i32,
+    ) -> Result<Deployed> {
+        match self.deploy_with_outcome(image, constructor, signer, workchain_id).await? {
+            DeployOutcome::Deployed(deployed) | DeployOutcome::ConstructorResent(deployed) => {
+                Ok(deployed)
+            }
+            DeployOutcome::AlreadyDeployed { address } => fail!(SdkError::InvalidData {
+                msg: format!("Contract {} is already deployed by another message", address)
+            }),
+        }
+    }
+
+    /// Deploys the image with the constructor call. If the deploy fails and
+    /// the account turns out to be active with the code of the image, the
+    /// deploy raced with another one and is handled as configured by
+    /// `with_redeploy`.
+    pub async fn deploy_with_outcome(
+        &self,
+        image: &ContractImage,
+        constructor: &FunctionCallSet,
+        signer: Option<&dyn Signer>,
+        workchain_id: i32,
+    ) -> Result<DeployOutcome> {
+        let applied = self.policy.apply(constructor)?;
+        let message = Contract::construct_deploy_message_with_options(
+            &applied,
+            image,
+            signer,
+            workchain_id,
+            MsgAddressExt::default(),
+            self.signing,
+        )?;
+        let address = message.address.clone();
+        let err = match self.transport.process_message(&message, self.timeout_ms).await {
+            Ok(transaction) if !transaction.is_aborted() => {
+                return Ok(DeployOutcome::Deployed(Deployed { address, transaction }));
+            }
+            Ok(_) => {
+                error!(SdkError::InvalidData { msg: format!("Deploy of {} is aborted", address) })
+            }
+            Err(err) => err,
+        };
+        if !self.is_deployed_with(&address, image).await? {
+            return Err(err);
+        }
+        if self.redeploy == RedeployHandling::ResendConstructor {
+            if let Ok(call) = self.call(&address, constructor, signer).await {
+                if !call.transaction.is_aborted() {
+                    let transaction = call.transaction;
+                    return Ok(DeployOutcome::ConstructorResent(Deployed { address, transaction }));
+                }
+            }
+        }
+        Ok(DeployOutcome::AlreadyDeployed { address })
+    }
+
+    // Whether the account is active with the code of the image
+    async fn is_deployed_with(
+        &self,
+        address: &MsgAddressInt,
+        image: &ContractImage,
+    ) -> Result<bool> {
+        let Some(account) = self.transport.query_account(address).await? else {
+            return Ok(false);
+        };
+        let code_hash = image.state_init_ref().code.as_ref().map(|code| code.repr_hash());
+        Ok(account.status() == AccountStatus::AccStateActive
+            && account.get_code().map(|code| code.repr_hash()) == code_hash)
+    }
+
+    /// Deploys the image and calls the deployed contract, e.g. deploys token
+    /// root and mints tokens
+    pub async fn deploy_and_call(
+        &self,
+        image: &ContractImage,
+        constructor: &FunctionCallSet,
+        call: &FunctionCallSet,
+        signer: Option<&dyn Signer>,
+        workchain_id: i32,
+    ) -> Result<DeployedCall> {
+        let deployed = self.deploy(image, constructor, signer, workchain_id).await?;
+        let call = self.call(&deployed.address, call, signer).await?;
+        Ok(DeployedCall { deployed, call })
+    }
+
+    /// Calls the contract and decodes function response and events emitted
+    /// by the transaction with the ABI of the call
+    pub async fn call(
+        &self,
+        address: &MsgAddressInt,
+        call: &FunctionCallSet,
+        signer: Option<&dyn Signer>,
+    ) -> Result<CallResult> {
+        let message = Contract::construct_call_ext_in_message_with_policy(
+            address.clone(),
+            MsgAddressExt::default(),
+            call,
+            signer,
+            &self.policy,
+            self.signing,
+        )?;
+        let transaction = self.transport.process_message(&message, self.timeout_ms).await?;
+        decode_call_result(call.abi.as_str(), transaction)
+    }
+
+    /// Subscribes to events of the contract emitted by transactions with
+    /// logical time greater than `after_lt`, zero for all of them. The
+    /// transport must list account transactions.
+    pub fn subscribe_events(
+        &self,
+        address: &MsgAddressInt,
+        abi: &str,
+        after_lt: u64,
+    ) -> EventSubscription<'a, T> {
+        EventSubscription {
+            transport: self.transport,
+            address: address.clone(),
+            abi: abi.to_owned(),
+            last_lt: after_lt,
+        }
+    }
+
+    /// Subscribes to changes of the account data decoded with the ABI, which
+    /// must declare the storage `fields` or the `data` section.
+    pub fn subscribe_account(
+        &self,
+        address: &MsgAddressInt,
+        abi: &str,
+    ) -> Result<AccountSubscription<'a, T>> {
+        Ok(AccountSubscription {
+            transport: self.transport,
+            address: address.clone(),
+            differ: AccountDataDiffer::new(abi)?,
+            last_lt: None,
+        })
+    }
+}
+
+/// Payload of the transfer with text comment: zero 32-bit op followed by
+/// UTF-8 text, continued in references when it doesn't fit into a cell
+pub fn comment_payload(comment: &str) -> Result<Cell> {
+    // the first cell also holds the op
+    let first_len = 127 - 4;
+    let bytes = comment.as_bytes();
+    let split = bytes.len().min(first_len);
+    let mut chunks = vec![&bytes[..split]];
+    chunks.extend(bytes[split..].chunks(127));
+
+    let mut tail: Option<Cell> = None;
+    for (index, chunk) in chunks.iter().enumerate().rev() {
+        let mut builder = BuilderData::new();
+        if index == 0 {
+            builder.append_u32(0)?;
+        }
+        builder.append_raw(chunk, chunk.len() * 8)?;
+        if let Some(tail) = tail.take() {
+            builder.checked_append_reference(tail)?;
+        }
+        tail = Some(builder.into_cell()?);
+    }
+    Ok(tail.unwrap_or_default())
+}
+
+#[cfg(test)]
+#[path = "tests/test_flows.rs"]
+mod tests;
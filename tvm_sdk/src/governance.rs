@@ -0,0 +1,220 @@
+// Copyright 2018-2021 TON Labs LTD.
+//
+// Licensed under the SOFTWARE EVALUATION License (the "License"); you may not
+// use this file except in compliance with the License.
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific TON DEV software governing permissions and
+// limitations under the License.
+
+//! Messages of the masterchain configuration contract voting: new config
+//! parameter proposals and validator votes for them.
+
+use tvm_block::ConfigParamEnum;
+use tvm_block::CurrencyCollection;
+use tvm_block::MsgAddressExt;
+use tvm_block::MsgAddressInt;
+use tvm_block::Serializable;
+use tvm_types::BuilderData;
+use tvm_types::Cell;
+use tvm_types::Ed25519PrivateKey;
+use tvm_types::IBitstring;
+use tvm_types::Result;
+use tvm_types::SliceData;
+use tvm_types::UInt256;
+use tvm_types::error;
+use tvm_types::fail;
+
+use crate::Contract;
+use crate::SdkMessage;
+use crate::error::SdkError;
+
+/// Operation code of the internal message registering new proposal ("nVPR")
+pub const CONFIG_PROPOSAL_OP: u32 = 0x6e565052;
+/// Operation code of the external message with validator vote ("Vote")
+pub const CONFIG_VOTE_OP: u32 = 0x566f7465;
+
+const CONFIG_PROPOSAL_TAG: u8 = 0xf3;
+
+/// Proposed change of a single configuration parameter
+/// ```text
+/// cfg_proposal#f3 param_id:int32 param_value:(Maybe ^Cell)
+///     if_hash_equal:(Maybe uint256) = ConfigProposal;
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigProposal {
+    pub param_id: i32,
+    /// New parameter value, `None` removes the parameter
+    pub param_value: Option<Cell>,
+    /// Apply proposal only if the hash of current parameter value is equal
+    pub if_hash_equal: Option<UInt256>,
+}
+
+impl ConfigProposal {
+    pub fn new(param_id: i32, param_value: Option<Cell>) -> Self {
+        Self { param_id, param_value, if_hash_equal: None }
+    }
+
+    /// Creates proposal setting parameter to the given value
+    pub fn with_config_param(param: &ConfigParamEnum) -> Result<Self> {
+        let mut builder = BuilderData::new();
+        let index = param.write_to_cell(&mut builder)?;
+        let value = builder.references().first().cloned().ok_or_else(|| {
+            error!(SdkError::InvalidData { msg: format!("Config param {} is empty", index) })
+        })?;
+        Ok(Self::new(index as i32, Some(value)))
+    }
+
+    /// Binds proposal to the current value of the parameter, so that it is
+    /// rejected if the parameter changes before the proposal is accepted
+    pub fn if_current_value(mut self, current: Option<&Cell>) -> Self {
+        self.if_hash_equal = Some(current.map(|cell| cell.repr_hash()).unwrap_or_default());
+        self
+    }
+
+    /// Hash of the proposed parameter value cell
+    pub fn value_hash(&self) -> Option<UInt256> {
+        self.param_value.as_ref().map(|cell| cell.repr_hash())
+    }
+
+    /// Hash of the proposal, used by validators to vote for it
+    pub fn hash(&self) -> Result<UInt256> {
+        Ok(self.serialize()?.repr_hash())
+    }
+
+    pub fn parse(cell: Cell) -> Result<Self> {
+        let mut slice = SliceData::load_cell(cell)?;
+        if slice.get_next_byte()? != CONFIG_PROPOSAL_TAG {
+            fail!(SdkError::InvalidData { msg: "Invalid config proposal tag".to_owned() })
+        }
+        let param_id = slice.get_next_i32()?;
+        let param_value =
+            if slice.get_next_bit()? { Some(slice.checked_drain_reference()?) } else { None };
+        let if_hash_equal = if slice.get_next_bit()? { Some(slice.get_next_hash()?) } else { None };
+        Ok(Self { param_id, param_value, if_hash_equal })
+    }
+}
+
+impl Serializable for ConfigProposal {
+    fn write_to(&self, builder: &mut BuilderData) -> Result<()> {
+        builder.append_u8(CONFIG_PROPOSAL_TAG)?;
+        builder.append_i32(self.param_id)?;
+        match &self.param_value {
+            Some(value) => {
+                builder.append_bit_one()?;
+                builder.checked_append_reference(value.clone())?;
+            }
+            None => {
+                builder.append_bit_zero()?;
+            }
+        }
+        match &self.if_hash_equal {
+            Some(hash) => {
+                builder.append_bit_one()?;
+                hash.write_to(builder)?;
+            }
+            None => {
+                builder.append_bit_zero()?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Builds body of the internal message to the configuration contract which
+/// registers new proposal. Proposal price must be attached to the message.
+pub fn config_proposal_body(
+    query_id: u64,
+    expire_at: u32,
+    critical: bool,
+    proposal: &ConfigProposal,
+) -> Result<SliceData> {
+    let mut builder = BuilderData::new();
+    builder.append_u32(CONFIG_PROPOSAL_OP)?;
+    builder.append_u64(query_id)?;
+    builder.append_u32(expire_at)?;
+    builder.checked_append_reference(proposal.serialize()?)?;
+    builder.append_bit_bool(critical)?;
+    SliceData::load_builder(builder)
+}
+
+/// Builds internal message registering new proposal in the configuration
+/// contract `config_address`
+pub fn construct_config_proposal_message(
+    config_address: MsgAddressInt,
+    src_address: Option<MsgAddressInt>,
+    value: CurrencyCollection,
+    query_id: u64,
+    expire_at: u32,
+    critical: bool,
+    proposal: &ConfigProposal,
+) -> Result<SdkMessage> {
+    let body = config_proposal_body(query_id, expire_at, critical, proposal)?;
+    Contract::construct_int_message_with_body(
+        config_address,
+        src_address,
+        true,
+        true,
+        value,
+        Some(body),
+    )
+}
+
+/// Validator vote for the proposal. `validator_idx` is the index of the
+/// validator in the current validator set (config param 34).
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigVote {
+    pub validator_idx: u16,
+    pub proposal_hash: UInt256,
+}
+
+impl ConfigVote {
+    pub fn new(validator_idx: u16, proposal_hash: UInt256) -> Self {
+        Self { validator_idx, proposal_hash }
+    }
+
+    /// Data the validator signs with its key
+    pub fn data_to_sign(&self) -> Result<Vec<u8>> {
+        Ok(self.unsigned_body()?.data().to_vec())
+    }
+
+    /// Builds external message body with the signature of `data_to_sign`
+    pub fn signed_body(&self, signature: &[u8]) -> Result<SliceData> {
+        if signature.len() != 64 {
+            fail!(SdkError::InvalidData { msg: "Vote signature must be 64 bytes long".to_owned() })
+        }
+        let mut builder = BuilderData::new();
+        builder.append_raw(signature, 512)?;
+        builder.append_builder(&self.unsigned_body()?)?;
+        SliceData::load_builder(builder)
+    }
+
+    pub fn sign(&self, key: &Ed25519PrivateKey) -> Result<SliceData> {
+        self.signed_body(&key.sign(&self.data_to_sign()?))
+    }
+
+    fn unsigned_body(&self) -> Result<BuilderData> {
+        let mut builder = BuilderData::new();
+        builder.append_u32(CONFIG_VOTE_OP)?;
+        builder.append_u16(self.validator_idx)?;
+        self.proposal_hash.write_to(&mut builder)?;
+        Ok(builder)
+    }
+}
+
+/// Builds external inbound message to the configuration contract with the
+/// signed vote body returned by `ConfigVote::signed_body`
+pub fn construct_config_vote_message(
+    config_address: MsgAddressInt,
+    signed_body: SliceData,
+) -> Result<SdkMessage> {
+    let msg = Contract::create_ext_in_message(
+        config_address.clone(),
+        MsgAddressExt::default(),
+        signed_body,
+    )?;
+    let (serialized_message, id) = Contract::serialize_message(&msg)?;
+    Ok(SdkMessage { id, serialized_message, message: msg, address: config_address })
+}
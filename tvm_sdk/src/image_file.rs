@@ -0,0 +1,143 @@
+// Copyright 2018-2021 TON Labs LTD.
+//
+// Licensed under the SOFTWARE EVALUATION License (the "License"); you may not
+// use this file except in compliance with the License.
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific TON DEV software governing permissions and
+// limitations under the License.
+
+//! Async loading of contract images from files. Big files are mapped into
+//! memory instead of being read, parsed images are cached by file hash.
+
+use std::collections::HashMap;
+use std::ops::Deref;
+use std::path::Path;
+use std::sync::Mutex;
+use std::sync::OnceLock;
+
+use memmap2::Mmap;
+use tokio::io::AsyncReadExt;
+use tvm_types::Result;
+use tvm_types::UInt256;
+use tvm_types::error;
+use tvm_types::sha256_digest;
+
+use crate::ContractImage;
+use crate::tvc::Tvc;
+
+/// Files of this size and bigger are mapped into memory
+pub const MMAP_THRESHOLD: u64 = 1 << 20;
+
+/// Count of images kept by the file cache
+pub const IMAGE_CACHE_CAPACITY: usize = 64;
+
+enum FileData {
+    Mapped(Mmap),
+    Read(Vec<u8>),
+}
+
+impl Deref for FileData {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match self {
+            FileData::Mapped(map) => map,
+            FileData::Read(data) => data,
+        }
+    }
+}
+
+#[derive(Default)]
+struct ImageCache {
+    images: HashMap<(UInt256, bool), (ContractImage, u64)>,
+    tick: u64,
+}
+
+impl ImageCache {
+    fn get(&mut self, key: &(UInt256, bool)) -> Option<ContractImage> {
+        self.tick += 1;
+        let tick = self.tick;
+        self.images.get_mut(key).map(|(image, last_used)| {
+            *last_used = tick;
+            image.clone()
+        })
+    }
+
+    fn insert(&mut self, key: (UInt256, bool), image: ContractImage) {
+        if self.images.len() >= IMAGE_CACHE_CAPACITY && !self.images.contains_key(&key) {
+            let oldest = self
+                .images
+                .iter()
+                .min_by_key(|(_, (_, last_used))| *last_used)
+                .map(|(key, _)| key.clone());
+            if let Some(oldest) = oldest {
+                self.images.remove(&oldest);
+            }
+        }
+        self.tick += 1;
+        self.images.insert(key, (image, self.tick));
+    }
+}
+
+fn cache() -> &'static Mutex<ImageCache> {
+    static CACHE: OnceLock<Mutex<ImageCache>> = OnceLock::new();
+    CACHE.get_or_init(Default::default)
+}
+
+impl ContractImage {
+    /// Loads image from the file with state init BOC
+    pub async fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+        load(path.as_ref(), false).await
+    }
+
+    /// Loads image from the TVC file. Files with plain state init BOC are
+    /// accepted too, as they are produced by older compilers.
+    pub async fn from_tvc_file(path: impl AsRef<Path>) -> Result<Self> {
+        load(path.as_ref(), true).await
+    }
+
+    /// Drops images cached by `from_file` and `from_tvc_file`
+    pub fn clear_file_cache() {
+        cache().lock().unwrap().images.clear();
+    }
+}
+
+async fn load(path: &Path, tvc: bool) -> Result<ContractImage> {
+    let data = read_file(path)
+        .await
+        .map_err(|e| error!("Can't read contract image from {}: {}", path.display(), e))?;
+    tokio::task::spawn_blocking(move || {
+        let key = (UInt256::from(sha256_digest(&*data)), tvc);
+        if let Some(image) = cache().lock().unwrap().get(&key) {
+            return Ok(image);
+        }
+        let image = parse(&data, tvc)?;
+        cache().lock().unwrap().insert(key, image.clone());
+        Ok(image)
+    })
+    .await?
+}
+
+async fn read_file(path: &Path) -> std::io::Result<FileData> {
+    let mut file = tokio::fs::File::open(path).await?;
+    if file.metadata().await?.len() >= MMAP_THRESHOLD {
+        let file = file.into_std().await;
+        // SAFETY: the map is dropped right after parsing, files of images are
+        // not expected to be changed while being loaded
+        let map = unsafe { Mmap::map(&file)? };
+        return Ok(FileData::Mapped(map));
+    }
+    let mut data = Vec::new();
+    file.read_to_end(&mut data).await?;
+    Ok(FileData::Read(data))
+}
+
+fn parse(data: &[u8], tvc: bool) -> Result<ContractImage> {
+    if tvc {
+        return Tvc::from_bytes(data)?.as_contract_image();
+    }
+    ContractImage::from_cell(tvm_types::boc::read_single_root_boc(data)?)
+}
@@ -0,0 +1,242 @@
+// Copyright 2018-2021 TON Labs LTD.
+//
+// Licensed under the SOFTWARE EVALUATION License (the "License"); you may not
+// use this file except in compliance with the License.
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific TON DEV software governing permissions and
+// limitations under the License.
+
+//! Intents signed by users and executed by sponsoring relayers.
+//!
+//! The user signs `Intent` off-chain, the relayer checks it with
+//! `IntentRelay::validate` and sends the internal message built by
+//! `IntentRelay::materialize` from own wallet, paying for the processing.
+//! The message calls `executeIntent` of the user account, which checks the
+//! signature against the intent hash and calls the target with the payload.
+//!
+//! The intent hash is the representation hash of the cell
+//!
+//! ```text
+//! intent#494e5431 valid_until:uint32 nonce:uint64 value:uint64
+//!     addresses:^[account:MsgAddressInt target:MsgAddressInt
+//!                 relayer:MsgAddressIntOrNone]
+//!     payload:^Cell = Intent;
+//! ```
+//!
+//! so the account contract can rebuild and check it on-chain. `payload` is
+//! the internal call body of the target function.
+
+use serde_json::json;
+use tvm_abi::PublicKeyData;
+use tvm_abi::SignatureData;
+use tvm_block::CurrencyCollection;
+use tvm_block::MsgAddressInt;
+use tvm_block::MsgAddressIntOrNone;
+use tvm_block::Serializable;
+use tvm_types::BuilderData;
+use tvm_types::Cell;
+use tvm_types::IBitstring;
+use tvm_types::Result;
+use tvm_types::UInt256;
+use tvm_types::base64_encode;
+use tvm_types::ed25519_verify;
+use tvm_types::error;
+use tvm_types::fail;
+
+use crate::Contract;
+use crate::FunctionCallSet;
+use crate::SdkMessage;
+use crate::Signer;
+use crate::error::SdkError;
+use crate::json_helper;
+
+const INTENT_TAG: u32 = 0x494e5431;
+
+/// Call of the target function authorized by the account owner
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Intent {
+    /// Account executing the intent, its owner signs it
+    #[serde(with = "json_helper::address")]
+    pub account: MsgAddressInt,
+    #[serde(with = "json_helper::address")]
+    pub target: MsgAddressInt,
+    pub function: String,
+    /// Function parameters as JSON
+    pub params: String,
+    pub constraints: IntentConstraints,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct IntentConstraints {
+    /// UNIX time in seconds the intent can't be executed after
+    pub valid_until: u32,
+    /// Number used once, the account rejects intents with used numbers
+    pub nonce: u64,
+    /// Value sent by the account to the target
+    #[serde(with = "json_helper::uint")]
+    pub value: u64,
+    /// The only relayer allowed to execute the intent, any if not set
+    #[serde(default, with = "json_helper::opt_address")]
+    pub relayer: Option<MsgAddressInt>,
+}
+
+impl Intent {
+    /// Internal call body of the target function
+    pub fn payload(&self, target_abi: &str) -> Result<Cell> {
+        tvm_abi::encode_function_call(
+            target_abi,
+            &self.function,
+            None,
+            &self.params,
+            true,
+            None,
+            Some(&self.target.to_string()),
+        )?
+        .into_cell()
+    }
+
+    /// Cell hashed and signed for the intent
+    pub fn to_cell(&self, target_abi: &str) -> Result<Cell> {
+        let mut addresses = BuilderData::new();
+        self.account.write_to(&mut addresses)?;
+        self.target.write_to(&mut addresses)?;
+        match &self.constraints.relayer {
+            Some(relayer) => MsgAddressIntOrNone::Some(relayer.clone()),
+            None => MsgAddressIntOrNone::None,
+        }
+        .write_to(&mut addresses)?;
+
+        let mut builder = BuilderData::new();
+        builder
+            .append_u32(INTENT_TAG)?
+            .append_u32(self.constraints.valid_until)?
+            .append_u64(self.constraints.nonce)?
+            .append_u64(self.constraints.value)?;
+        builder.checked_append_reference(addresses.into_cell()?)?;
+        builder.checked_append_reference(self.payload(target_abi)?)?;
+        builder.into_cell()
+    }
+
+    pub fn hash(&self, target_abi: &str) -> Result<UInt256> {
+        Ok(self.to_cell(target_abi)?.repr_hash())
+    }
+
+    pub fn sign(self, signer: &dyn Signer, target_abi: &str) -> Result<SignedIntent> {
+        let hash = self.hash(target_abi)?;
+        Ok(SignedIntent {
+            public_key: signer.public_key()?,
+            signature: signer.sign(hash.as_slice())?,
+            intent: self,
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct SignedIntent {
+    pub intent: Intent,
+    #[serde(with = "json_helper::hex_array")]
+    pub public_key: PublicKeyData,
+    #[serde(with = "json_helper::hex_array")]
+    pub signature: SignatureData,
+}
+
+impl SignedIntent {
+    /// Checks the signature and that the intent is not expired at `now`.
+    /// Checking that the key belongs to the account is left to the caller.
+    pub fn verify(&self, target_abi: &str, now: u32) -> Result<()> {
+        if now > self.intent.constraints.valid_until {
+            fail!(SdkError::InvalidData {
+                msg: format!("Intent expired at {}", self.intent.constraints.valid_until)
+            })
+        }
+        let hash = self.intent.hash(target_abi)?;
+        ed25519_verify(&self.public_key, hash.as_slice(), &self.signature).map_err(|err| {
+            SdkError::InvalidData { msg: format!("Invalid intent signature: {}", err) }
+        })?;
+        Ok(())
+    }
+}
+
+/// Relayer side of intents executed by accounts with the given ABI
+#[derive(Clone, Debug)]
+pub struct IntentRelay {
+    account_abi: String,
+    function: String,
+}
+
+impl IntentRelay {
+    pub fn new(account_abi: impl Into<String>) -> Self {
+        Self { account_abi: account_abi.into(), function: "executeIntent".to_owned() }
+    }
+
+    pub fn with_function(mut self, function: impl Into<String>) -> Self {
+        self.function = function.into();
+        self
+    }
+
+    /// Checks the intent before spending on its execution by `relayer`
+    pub fn validate(
+        &self,
+        signed: &SignedIntent,
+        target_abi: &str,
+        relayer: &MsgAddressInt,
+        now: u32,
+    ) -> Result<()> {
+        if let Some(allowed) = &signed.intent.constraints.relayer {
+            if allowed != relayer {
+                fail!(SdkError::InvalidData {
+                    msg: format!("Intent can be executed only by {}", allowed)
+                })
+            }
+        }
+        let contract = tvm_abi::AbiCache::global().load(&self.account_abi)?;
+        contract
+            .function(&self.function)
+            .map_err(|err| error!("Account doesn't support intents: {}", err))?;
+        signed.verify(target_abi, now)
+    }
+
+    /// Call of the account executing the intent
+    pub fn materialize(&self, signed: &SignedIntent, target_abi: &str) -> Result<FunctionCallSet> {
+        let intent = &signed.intent;
+        let payload = tvm_types::boc::write_boc(&intent.payload(target_abi)?)?;
+        let input = json!({
+            "target": intent.target.to_string(),
+            "value": intent.constraints.value.to_string(),
+            "validUntil": intent.constraints.valid_until,
+            "nonce": intent.constraints.nonce.to_string(),
+            "relayer": intent.constraints.relayer.as_ref().map(ToString::to_string),
+            "payload": base64_encode(payload),
+            "signature": hex::encode(signed.signature),
+        });
+        Ok(FunctionCallSet {
+            func: self.function.clone(),
+            header: None,
+            input: input.to_string(),
+            abi: self.account_abi.clone(),
+        })
+    }
+
+    /// Internal message from the relayer wallet executing the intent. `value`
+    /// pays for the processing by the account.
+    pub fn materialize_message(
+        &self,
+        signed: &SignedIntent,
+        target_abi: &str,
+        relayer: MsgAddressInt,
+        value: u64,
+    ) -> Result<SdkMessage> {
+        let call = self.materialize(signed, target_abi)?;
+        Contract::construct_call_int_message_json(
+            signed.intent.account.clone(),
+            Some(relayer),
+            true,
+            true,
+            CurrencyCollection::with_grams(value),
+            &call,
+        )
+    }
+}
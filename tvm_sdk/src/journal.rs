@@ -0,0 +1,125 @@
+// Copyright 2018-2021 TON Labs LTD.
+//
+// Licensed under the SOFTWARE EVALUATION License (the "License"); you may not
+// use this file except in compliance with the License.
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific TON DEV software governing permissions and
+// limitations under the License.
+
+use std::fs::File;
+use std::fs::OpenOptions;
+use std::io::Read;
+use std::io::Write;
+use std::marker::PhantomData;
+use std::path::Path;
+use std::path::PathBuf;
+
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use tvm_types::Result;
+use tvm_types::error;
+use tvm_types::fail;
+
+use crate::error::SdkError;
+
+/// Append-only JSON lines file of state changes, shared by the persistent
+/// queues. Every record is synced before the change is applied in memory.
+/// The state is rebuilt by replaying the records and is written anew by
+/// `compact`, to a temporary file renamed over the journal, so a crash
+/// leaves either the old or the new journal.
+pub struct Journal<R> {
+    path: PathBuf,
+    file: File,
+    records: PhantomData<R>,
+}
+
+impl<R: Serialize + DeserializeOwned> Journal<R> {
+    /// Opens the journal, creating it if needed, and returns its records.
+    /// The last line not terminated by a newline is left by a crash in the
+    /// middle of appending and is dropped. Other broken lines fail.
+    pub fn open(path: impl AsRef<Path>) -> Result<(Self, Vec<R>)> {
+        let path = path.as_ref().to_path_buf();
+        let mut text = String::new();
+        if path.exists() {
+            File::open(&path)?.read_to_string(&mut text)?;
+        }
+        let complete = match text.rfind('\n') {
+            Some(end) => &text[..end + 1],
+            None => "",
+        };
+        let mut records = Vec::new();
+        for (index, line) in complete.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            records.push(serde_json::from_str(line).map_err(|err| {
+                error!(SdkError::InvalidData {
+                    msg: format!(
+                        "Broken line {} of journal {}: {}",
+                        index + 1,
+                        path.display(),
+                        err
+                    )
+                })
+            })?);
+        }
+        if complete.len() != text.len() {
+            log::warn!("Dropped incomplete last record of journal {}", path.display());
+        }
+        let file = Self::create(&path, &Self::lines(&records)?)?;
+        Ok((Self { path, file, records: PhantomData }, records))
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn append(&mut self, record: &R) -> Result<()> {
+        self.file.write_all(&Self::line(record)?)?;
+        self.file.sync_data()?;
+        Ok(())
+    }
+
+    /// Replaces the journal by the records
+    pub fn compact<'a>(&mut self, records: impl IntoIterator<Item = &'a R>) -> Result<()>
+    where
+        R: 'a,
+    {
+        self.file = Self::create(&self.path, &Self::lines(records)?)?;
+        Ok(())
+    }
+
+    fn create(path: &Path, content: &[u8]) -> Result<File> {
+        let Some(name) = path.file_name() else {
+            fail!(SdkError::InvalidData { msg: format!("Invalid journal path {}", path.display()) })
+        };
+        let mut tmp_name = name.to_os_string();
+        tmp_name.push(".tmp");
+        let tmp = path.with_file_name(tmp_name);
+        let mut file = File::create(&tmp)?;
+        file.write_all(content)?;
+        file.sync_all()?;
+        std::fs::rename(&tmp, path)?;
+        Ok(OpenOptions::new().append(true).open(path)?)
+    }
+
+    fn lines<'a>(records: impl IntoIterator<Item = &'a R>) -> Result<Vec<u8>>
+    where
+        R: 'a,
+    {
+        let mut lines = Vec::new();
+        for record in records {
+            lines.extend(Self::line(record)?);
+        }
+        Ok(lines)
+    }
+
+    fn line(record: &R) -> Result<Vec<u8>> {
+        let mut line = serde_json::to_vec(record)?;
+        line.push(b'\n');
+        Ok(line)
+    }
+}
@@ -122,6 +122,27 @@ pub mod opt_cell {
     }
 }
 
+pub mod base64_bytes {
+    use tvm_types::base64_encode;
+
+    use super::*;
+
+    pub fn deserialize<'de, D>(d: D) -> Result<Vec<u8>, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let b64 = d.deserialize_string(StringVisitor)?;
+        base64_decode(&b64).map_err(|err| D::Error::custom(format!("error decode base64: {}", err)))
+    }
+
+    pub fn serialize<S>(value: &[u8], serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(&base64_encode(value))
+    }
+}
+
 pub fn deserialize_tree_of_cells_from_base64<'de, D>(b64: &str) -> Result<Cell, D::Error>
 where
     D: serde::Deserializer<'de>,
@@ -154,6 +175,57 @@ pub mod address {
     }
 }
 
+pub mod opt_address {
+    use super::*;
+
+    pub fn deserialize<'de, D>(d: D) -> Result<Option<MsgAddressInt>, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let string = d.deserialize_option(StringVisitor)?;
+        if "null" == string {
+            return Ok(None);
+        }
+
+        MsgAddressInt::from_str(&string)
+            .map(Some)
+            .map_err(|err| D::Error::custom(format!("Address parsing error: {}", err)))
+    }
+
+    pub fn serialize<S>(value: &Option<MsgAddressInt>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        match value {
+            Some(address) => serializer.serialize_str(&address.to_string()),
+            None => serializer.serialize_none(),
+        }
+    }
+}
+
+pub mod hex_array {
+    use super::*;
+
+    pub fn deserialize<'de, D, const N: usize>(d: D) -> Result<[u8; N], D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let string = d.deserialize_string(StringVisitor)?;
+        let bytes = hex::decode(&string)
+            .map_err(|err| D::Error::custom(format!("error decode hex: {}", err)))?;
+        bytes.try_into().map_err(|bytes: Vec<u8>| {
+            D::Error::custom(format!("Expected {} bytes, got {}", N, bytes.len()))
+        })
+    }
+
+    pub fn serialize<S, const N: usize>(value: &[u8; N], serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(&hex::encode(value))
+    }
+}
+
 pub mod uint {
     use super::*;
 
@@ -25,15 +25,64 @@ mod error;
 pub use error::SdkError;
 
 mod contract;
+pub use contract::BouncedCall;
 pub use contract::Contract;
 pub use contract::ContractImage;
 pub use contract::FunctionCallSet;
+pub use contract::MessageBuilder;
+pub use contract::MessageIdKind;
+pub use contract::MessageRouting;
+pub use contract::MessageToSign;
+pub use contract::RetargetedMessage;
 pub use contract::SdkMessage;
+pub use contract::ShardDescr;
+pub use contract::SigningOptions;
+
+mod boc_input;
+pub use boc_input::BocInput;
+
+mod call_set;
+
+mod code_salt;
+
+mod abi_handle;
+pub use abi_handle::AbiHandle;
+pub use abi_handle::AbiSource;
+
+mod capabilities;
+pub use capabilities::Capabilities;
+
+mod encrypted_fields;
+pub use encrypted_fields::EncryptedFields;
+pub use encrypted_fields::FieldCipher;
+pub use encrypted_fields::SealedBoxCipher;
+
+mod expiration;
+pub use expiration::Clock;
+pub use expiration::DriftCorrectedClock;
+pub use expiration::ExpirationPolicy;
+pub use expiration::SystemClock;
+
+mod network_clock;
+pub use network_clock::NetworkClock;
+
+mod signer;
+pub use signer::AsyncSigner;
+pub use signer::Keystore;
+pub use signer::LocalSigner;
+pub use signer::SignatureScheme;
+pub use signer::Signer;
+pub use signer::SigningKey;
 
 mod message;
+pub use message::DecodedBody;
 pub use message::Message;
 pub use message::MessageId;
 pub use message::MessageType;
+pub use message::ParsedMessage;
+
+mod message_value;
+pub use message_value::MessageValue;
 
 mod transaction;
 pub use transaction::Transaction;
@@ -41,10 +90,134 @@ pub use transaction::TransactionFees;
 pub use transaction::TransactionId;
 
 mod block;
+pub use block::AccountTransactions;
 pub use block::Block;
 pub use block::MsgDescr;
+pub use block::ParsedBlock;
 
 pub mod types;
 pub use types::BlockId;
 
+pub mod abi_codegen;
+pub mod address;
+pub mod export;
 pub mod json_helper;
+
+mod cascade_cost;
+pub use cascade_cost::AccountCost;
+pub use cascade_cost::CascadeCost;
+pub use cascade_cost::LegCost;
+
+mod message_graph;
+pub use message_graph::GraphEdge;
+pub use message_graph::GraphNode;
+pub use message_graph::MessageGraph;
+pub use message_graph::NodeKind;
+
+mod stats;
+pub use stats::AccountStats;
+pub use stats::TimeWindow;
+
+mod account_diff;
+pub use account_diff::AccountDataDiffer;
+pub use account_diff::FieldChange;
+pub use account_diff::diff_decoded_data;
+
+mod abi_coverage;
+pub use abi_coverage::AbiCoverage;
+
+mod account_info;
+pub use account_info::AccountInfo;
+pub use account_info::recommended_bounce;
+
+mod transaction_info;
+pub use transaction_info::ActionPhaseInfo;
+pub use transaction_info::BouncePhaseInfo;
+pub use transaction_info::ComputePhaseInfo;
+pub use transaction_info::CreditPhaseInfo;
+pub use transaction_info::StoragePhaseInfo;
+pub use transaction_info::TransactionInfo;
+
+mod storage;
+pub use storage::FileStorage;
+pub use storage::KeyValueStorage;
+pub use storage::MemoryStorage;
+
+mod account_cache;
+pub use account_cache::AccountCache;
+pub use account_cache::AccountUpdateVerifier;
+
+mod snapshot;
+pub use snapshot::ChainSnapshot;
+
+mod outbox;
+pub use outbox::FailedAttempt;
+pub use outbox::Outbox;
+pub use outbox::OutboxEntry;
+pub use outbox::OutboxPreview;
+
+mod journal;
+pub use journal::Journal;
+
+mod node_rejection;
+pub use node_rejection::NodeRejection;
+
+mod scheduler;
+pub use scheduler::MessageScheduler;
+pub use scheduler::ScheduleCondition;
+pub use scheduler::ScheduledMessage;
+
+mod shard_state;
+pub use shard_state::ShardState;
+
+mod shard_map;
+pub use shard_map::ShardChain;
+pub use shard_map::ShardMap;
+pub use shard_map::ShardMatcher;
+
+#[cfg(feature = "tvc")]
+mod artifacts;
+#[cfg(feature = "tvc")]
+pub use artifacts::ContractArtifacts;
+
+pub mod block_signatures;
+#[cfg(feature = "cli")]
+pub mod cli;
+pub mod commit_reveal;
+pub mod complaint;
+#[cfg(feature = "cli")]
+pub mod composer;
+#[cfg(feature = "compression")]
+pub mod compression;
+pub mod deployed_contract;
+#[cfg(feature = "executor")]
+pub mod executor;
+#[cfg(feature = "transport")]
+pub mod flows;
+pub mod governance;
+#[cfg(feature = "fs")]
+pub mod image_file;
+pub mod intent;
+#[cfg(feature = "transport")]
+pub mod middleware;
+pub mod multisig;
+#[cfg(feature = "transport")]
+pub mod networks;
+pub mod proofs;
+pub mod replay;
+#[cfg(feature = "transport")]
+pub mod retry;
+#[cfg(feature = "transport")]
+pub mod seqno;
+pub mod session;
+pub mod signed_data;
+pub mod staking;
+#[cfg(feature = "testing")]
+pub mod testing;
+#[cfg(feature = "transport")]
+pub mod topup;
+pub mod tracing;
+#[cfg(feature = "transport")]
+pub mod transport;
+#[cfg(feature = "tvc")]
+pub mod tvc;
@@ -12,6 +12,8 @@
 use tvm_block::CommonMsgInfo;
 use tvm_block::GetRepresentationHash;
 use tvm_block::Message as TvmMessage;
+use tvm_block::MsgAddressExt;
+use tvm_block::StateInit;
 use tvm_types::Cell;
 use tvm_types::Result;
 use tvm_types::SliceData;
@@ -20,7 +22,7 @@ use crate::json_helper;
 use crate::types::StringId;
 use crate::types::grams_to_u64;
 
-#[derive(Deserialize, Debug, PartialEq, Clone, Default)]
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone, Default)]
 pub enum MessageType {
     Internal,
     ExternalInbound,
@@ -76,3 +78,105 @@ impl Message {
         self.msg_type.clone()
     }
 }
+
+/// Message body decoded by ABI
+#[derive(Debug, Clone, PartialEq)]
+pub struct DecodedBody {
+    pub function_name: String,
+    /// Parameters as JSON
+    pub params: String,
+    /// Body is a function output (answer or event) rather than a call
+    pub output: bool,
+}
+
+/// Message with header fields, state init and body decoded by ABI
+#[derive(Debug, Clone)]
+pub struct ParsedMessage {
+    pub id: MessageId,
+    pub msg_type: MessageType,
+    pub src: Option<String>,
+    pub dst: Option<String>,
+    pub value: u64,
+    pub bounce: bool,
+    pub bounced: bool,
+    pub created_lt: u64,
+    pub created_at: u32,
+    pub body: Option<Cell>,
+    pub state_init: Option<StateInit>,
+    /// `None` without ABI or if the body doesn't match it
+    pub decoded: Option<DecodedBody>,
+}
+
+impl ParsedMessage {
+    pub fn with_msg(tvm_msg: &TvmMessage, abi: Option<&str>) -> Result<Self> {
+        let message = Message::with_msg(tvm_msg)?;
+        let (src, dst) = match tvm_msg.header() {
+            CommonMsgInfo::IntMsgInfo(header) => {
+                (tvm_msg.src_ref().map(ToString::to_string), Some(header.dst.to_string()))
+            }
+            CommonMsgInfo::ExtInMsgInfo(header) => {
+                (ext_address(&header.src), Some(header.dst.to_string()))
+            }
+            CommonMsgInfo::ExtOutMsgInfo(header) => {
+                (tvm_msg.src_ref().map(ToString::to_string), ext_address(&header.dst))
+            }
+        };
+        let (bounce, bounced) = match tvm_msg.int_header() {
+            Some(header) => (header.bounce, header.bounced),
+            None => (false, false),
+        };
+        let (created_at, created_lt) = tvm_msg.at_and_lt().unwrap_or_default();
+        let decoded = match (abi, tvm_msg.body()) {
+            (Some(abi), Some(body)) => decode_body(abi, body, &message.msg_type),
+            _ => None,
+        };
+        Ok(Self {
+            id: message.id,
+            msg_type: message.msg_type,
+            src,
+            dst,
+            value: message.value,
+            bounce,
+            bounced,
+            created_lt,
+            created_at,
+            body: message.body,
+            state_init: tvm_msg.state_init().cloned(),
+            decoded,
+        })
+    }
+}
+
+fn ext_address(address: &MsgAddressExt) -> Option<String> {
+    match address {
+        MsgAddressExt::AddrNone => None,
+        address => Some(address.to_string()),
+    }
+}
+
+// Internal bodies are tried as calls first, then as answers
+pub(crate) fn decode_body(
+    abi: &str,
+    body: SliceData,
+    msg_type: &MessageType,
+) -> Option<DecodedBody> {
+    let internal = *msg_type == MessageType::Internal;
+    let call = match msg_type {
+        MessageType::ExternalOutbound => None,
+        _ => {
+            tvm_abi::json_abi::decode_unknown_function_call(abi, body.clone(), internal, false).ok()
+        }
+    };
+    let (decoded, output) = match call {
+        Some(decoded) => (decoded, false),
+        None => match msg_type {
+            MessageType::ExternalInbound => return None,
+            _ => (
+                tvm_abi::json_abi::decode_unknown_function_response(abi, body, internal, false)
+                    .ok()?,
+                true,
+            ),
+        },
+    };
+    Some(DecodedBody { function_name: decoded.function_name, params: decoded.params, output })
+}
@@ -0,0 +1,275 @@
+// Copyright 2018-2021 TON Labs LTD.
+//
+// Licensed under the SOFTWARE EVALUATION License (the "License"); you may not
+// use this file except in compliance with the License.
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific TON DEV software governing permissions and
+// limitations under the License.
+
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use serde_json::Value;
+use tvm_block::Serializable;
+use tvm_block::Transaction as TvmTransaction;
+use tvm_types::Result;
+use tvm_types::SliceData;
+
+use crate::MessageType;
+use crate::ParsedMessage;
+use crate::message::decode_body;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum NodeKind {
+    Account,
+    Transaction,
+    /// Source of external inbound or destination of external outbound
+    /// messages
+    External,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct GraphNode {
+    pub id: String,
+    pub kind: NodeKind,
+    pub label: String,
+    /// Account of the transaction node
+    pub account: Option<String>,
+    pub lt: Option<u64>,
+    pub aborted: bool,
+}
+
+/// Message between nodes
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct GraphEdge {
+    pub from: String,
+    pub to: String,
+    pub message_id: String,
+    pub msg_type: MessageType,
+    /// In nanotokens
+    pub value: u64,
+    pub bounced: bool,
+    /// Function decoded by the ABI of the destination or the source
+    pub function: Option<String>,
+}
+
+/// Graph of transactions connected by messages, e.g. a cascade of
+/// transactions produced by a single external message. Messages produced and
+/// consumed within the set connect transactions, others connect transactions
+/// with the accounts and external nodes.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct MessageGraph {
+    pub nodes: Vec<GraphNode>,
+    pub edges: Vec<GraphEdge>,
+}
+
+struct TransactionMessages {
+    node: GraphNode,
+    in_msg: Option<ParsedMessage>,
+    out_msgs: Vec<ParsedMessage>,
+}
+
+impl MessageGraph {
+    /// Builds the graph from transactions of simulated or fetched cascade.
+    /// `abis` maps account addresses to their ABIs for decoding of function
+    /// names.
+    pub fn from_transactions<'a>(
+        transactions: impl IntoIterator<Item = &'a TvmTransaction>,
+        abis: &HashMap<String, String>,
+    ) -> Result<Self> {
+        let mut parsed = Vec::new();
+        for transaction in transactions {
+            parsed.push(Self::parse_transaction(transaction)?);
+        }
+        parsed.sort_by_key(|transaction| transaction.node.lt);
+
+        let mut producers = HashMap::new();
+        let mut consumers = HashMap::new();
+        for transaction in &parsed {
+            for message in &transaction.out_msgs {
+                producers.insert(message.id.to_string(), transaction.node.id.clone());
+            }
+            if let Some(message) = &transaction.in_msg {
+                consumers.insert(message.id.to_string(), transaction.node.id.clone());
+            }
+        }
+
+        let mut graph = Self::default();
+        let mut extra_nodes = BTreeMap::new();
+        for transaction in &parsed {
+            if let Some(message) = &transaction.in_msg {
+                let from = match producers.get(&message.id.to_string()) {
+                    Some(producer) => producer.clone(),
+                    None => Self::endpoint(&mut extra_nodes, message, message.src.as_deref()),
+                };
+                graph.edges.push(Self::edge(from, transaction.node.id.clone(), message, abis));
+            }
+            for message in &transaction.out_msgs {
+                if consumers.contains_key(&message.id.to_string()) {
+                    continue;
+                }
+                let to = Self::endpoint(&mut extra_nodes, message, message.dst.as_deref());
+                graph.edges.push(Self::edge(transaction.node.id.clone(), to, message, abis));
+            }
+        }
+        graph.nodes = parsed.into_iter().map(|transaction| transaction.node).collect();
+        graph.nodes.extend(extra_nodes.into_values());
+        Ok(graph)
+    }
+
+    /// Graphviz DOT with transactions grouped by accounts
+    pub fn to_dot(&self) -> String {
+        let mut accounts: BTreeMap<&str, Vec<&GraphNode>> = BTreeMap::new();
+        let mut dot = String::from("digraph messages {\n    rankdir=LR;\n    node [shape=box];\n");
+        for node in &self.nodes {
+            match (&node.kind, &node.account) {
+                (NodeKind::Transaction, Some(account)) => {
+                    accounts.entry(account).or_default().push(node)
+                }
+                _ => {
+                    let style = match node.kind {
+                        NodeKind::External => ", shape=ellipse, style=dashed",
+                        _ => "",
+                    };
+                    let _ = writeln!(
+                        dot,
+                        "    {} [label={}{}];",
+                        dot_id(&node.id),
+                        dot_id(&node.label),
+                        style
+                    );
+                }
+            }
+        }
+        for (index, (account, transactions)) in accounts.into_iter().enumerate() {
+            let _ = writeln!(dot, "    subgraph cluster_{} {{", index);
+            let _ = writeln!(dot, "        label={};", dot_id(account));
+            for node in transactions {
+                let style = if node.aborted { ", color=red" } else { "" };
+                let _ = writeln!(
+                    dot,
+                    "        {} [label={}{}];",
+                    dot_id(&node.id),
+                    dot_id(&node.label),
+                    style
+                );
+            }
+            dot.push_str("    }\n");
+        }
+        for edge in &self.edges {
+            let mut label = edge.function.clone().unwrap_or_default();
+            if edge.msg_type == MessageType::Internal {
+                if !label.is_empty() {
+                    label.push('\n');
+                }
+                label.push_str(&edge.value.to_string());
+            }
+            let style = if edge.bounced { ", style=dashed" } else { "" };
+            let _ = writeln!(
+                dot,
+                "    {} -> {} [label={}{}];",
+                dot_id(&edge.from),
+                dot_id(&edge.to),
+                dot_id(&label),
+                style
+            );
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// JSON with `nodes` and `edges` arrays, suitable for graph libraries
+    pub fn to_graph_json(&self) -> Result<Value> {
+        Ok(serde_json::to_value(self)?)
+    }
+
+    fn parse_transaction(transaction: &TvmTransaction) -> Result<TransactionMessages> {
+        let in_msg = match transaction.read_in_msg()? {
+            Some(message) => Some(ParsedMessage::with_msg(&message, None)?),
+            None => None,
+        };
+        let mut out_msgs = Vec::new();
+        for index in 0..transaction.msg_count() {
+            if let Some(message) = transaction.get_out_msg(index)? {
+                out_msgs.push(ParsedMessage::with_msg(&message, None)?);
+            }
+        }
+        let account = in_msg
+            .as_ref()
+            .and_then(|message| message.dst.clone())
+            .unwrap_or_else(|| transaction.account_id().to_hex_string());
+        let hash = transaction.serialize()?.repr_hash().as_hex_string();
+        let node = GraphNode {
+            label: format!("{}\nlt {}", &hash[..8], transaction.logical_time()),
+            id: hash,
+            kind: NodeKind::Transaction,
+            account: Some(account),
+            lt: Some(transaction.logical_time()),
+            aborted: transaction.read_description()?.is_aborted(),
+        };
+        Ok(TransactionMessages { node, in_msg, out_msgs })
+    }
+
+    // Node for the end of the message outside of the set
+    fn endpoint(
+        nodes: &mut BTreeMap<String, GraphNode>,
+        message: &ParsedMessage,
+        address: Option<&str>,
+    ) -> String {
+        let (kind, id, label) = match (&message.msg_type, address) {
+            (MessageType::Internal, Some(address)) => {
+                (NodeKind::Account, address.to_owned(), address.to_owned())
+            }
+            (_, address) => {
+                let label = address.unwrap_or("external").to_owned();
+                (NodeKind::External, format!("ext:{}", label), label)
+            }
+        };
+        nodes.entry(id.clone()).or_insert(GraphNode {
+            id: id.clone(),
+            kind,
+            label,
+            account: None,
+            lt: None,
+            aborted: false,
+        });
+        id
+    }
+
+    fn edge(
+        from: String,
+        to: String,
+        message: &ParsedMessage,
+        abis: &HashMap<String, String>,
+    ) -> GraphEdge {
+        GraphEdge {
+            from,
+            to,
+            message_id: message.id.to_string(),
+            msg_type: message.msg_type.clone(),
+            value: message.value,
+            bounced: message.bounced,
+            function: decode_function(message, abis),
+        }
+    }
+}
+
+// Calls are decoded by the destination ABI, answers and events by the source
+// one
+fn decode_function(message: &ParsedMessage, abis: &HashMap<String, String>) -> Option<String> {
+    let body = SliceData::load_cell(message.body.clone()?).ok()?;
+    [&message.dst, &message.src]
+        .into_iter()
+        .filter_map(|address| abis.get(address.as_deref()?))
+        .find_map(|abi| decode_body(abi, body.clone(), &message.msg_type))
+        .map(|decoded| decoded.function_name)
+}
+
+fn dot_id(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n"))
+}
@@ -0,0 +1,101 @@
+// Copyright 2018-2021 TON Labs LTD.
+//
+// Licensed under the SOFTWARE EVALUATION License (the "License"); you may not
+// use this file except in compliance with the License.
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific TON DEV software governing permissions and
+// limitations under the License.
+
+use std::str::FromStr;
+
+use tvm_block::CurrencyCollection;
+use tvm_block::Grams;
+use tvm_block::VarUInteger32;
+use tvm_types::Result;
+use tvm_types::error;
+use tvm_types::fail;
+
+use crate::error::SdkError;
+
+const TOKEN_DECIMALS: usize = 9;
+
+/// Value of the internal message: native tokens and extra currencies (ECC).
+/// Amounts are checked when added, so the value always converts to
+/// `CurrencyCollection`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MessageValue {
+    value: CurrencyCollection,
+}
+
+impl MessageValue {
+    pub fn nanotokens(value: u128) -> Result<Self> {
+        Ok(Self { value: CurrencyCollection::from_grams(Grams::new(value)?) })
+    }
+
+    /// Parses nanotokens given as decimal or `0x` prefixed hex string
+    pub fn parse_nanotokens(value: &str) -> Result<Self> {
+        Self::nanotokens(Grams::from_str(value.trim())?.as_u128())
+    }
+
+    /// Parses tokens given as decimal string with up to 9 fractional digits,
+    /// e.g. `1.5`
+    pub fn parse_tokens(value: &str) -> Result<Self> {
+        let invalid = || {
+            error!(SdkError::InvalidData { msg: format!("Invalid amount of tokens: {}", value) })
+        };
+        let (int, frac) = value.trim().split_once('.').unwrap_or((value.trim(), ""));
+        let digits = |part: &str| part.bytes().all(|byte| byte.is_ascii_digit());
+        if (int.is_empty() && frac.is_empty())
+            || !digits(int)
+            || !digits(frac)
+            || frac.len() > TOKEN_DECIMALS
+        {
+            return Err(invalid());
+        }
+        let nanotokens = format!("{}{:0<width$}", int, frac, width = TOKEN_DECIMALS);
+        Self::nanotokens(nanotokens.parse().map_err(|_| invalid())?)
+    }
+
+    /// Adds the amount of the extra currency, replacing the previous one
+    pub fn with_extra(mut self, currency_id: u32, amount: u128) -> Result<Self> {
+        self.value.set_other(currency_id, amount)?;
+        Ok(self)
+    }
+
+    /// Adds the amount of the extra currency given as decimal or `0x`
+    /// prefixed hex string, up to 248 bits
+    pub fn parse_extra(mut self, currency_id: u32, amount: &str) -> Result<Self> {
+        let amount = VarUInteger32::from_str(amount.trim())?;
+        if amount.is_zero() {
+            fail!(SdkError::InvalidData {
+                msg: format!("Zero amount of extra currency {}", currency_id)
+            })
+        }
+        self.value.set_other_ex(currency_id, &amount)?;
+        Ok(self)
+    }
+
+    /// Adds amounts of extra currencies, e.g. parsed from JSON object
+    pub fn parse_extras<'a>(
+        mut self,
+        extras: impl IntoIterator<Item = (u32, &'a str)>,
+    ) -> Result<Self> {
+        for (currency_id, amount) in extras {
+            self = self.parse_extra(currency_id, amount)?;
+        }
+        Ok(self)
+    }
+
+    pub fn as_currency_collection(&self) -> &CurrencyCollection {
+        &self.value
+    }
+}
+
+impl From<MessageValue> for CurrencyCollection {
+    fn from(value: MessageValue) -> Self {
+        value.value
+    }
+}
@@ -0,0 +1,217 @@
+// Copyright 2018-2021 TON Labs LTD.
+//
+// Licensed under the SOFTWARE EVALUATION License (the "License"); you may not
+// use this file except in compliance with the License.
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific TON DEV software governing permissions and
+// limitations under the License.
+
+//! Middleware around transports for cross-cutting concerns: logging,
+//! policies, metrics and mutation of outgoing messages.
+//!
+//! Layers are called in order of registration before sending and in reverse
+//! order after receiving and on errors, like layers of an onion. A layer
+//! failing `pre_send` stops the message from being sent.
+
+use std::sync::Arc;
+
+use tvm_block::Account;
+use tvm_block::MsgAddressInt;
+use tvm_types::Result;
+
+use crate::MessageId;
+use crate::SdkMessage;
+use crate::Transaction;
+use crate::proofs::AccountProof;
+use crate::transport::Transport;
+
+/// Operation of the transport reported to `Middleware::on_error`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransportOperation {
+    SendMessage,
+    WaitForTransaction,
+    QueryAccount,
+    QueryAccounts,
+}
+
+#[async_trait::async_trait]
+pub trait Middleware: Send + Sync {
+    /// Called before the message is sent. The message may be replaced, its
+    /// `id` must stay the hash of `serialized_message`, e.g. rebuilt by
+    /// `Contract::serialize_message`.
+    async fn pre_send(&self, _message: &mut SdkMessage) -> Result<()> {
+        Ok(())
+    }
+
+    /// Called with the transaction processing the message. Failing it fails
+    /// the operation.
+    async fn post_receive(
+        &self,
+        _message_id: &MessageId,
+        _transaction: &tvm_block::Transaction,
+    ) -> Result<()> {
+        Ok(())
+    }
+
+    /// Called when the operation of the inner transport or of a layer fails
+    async fn on_error(&self, _operation: TransportOperation, _error: &tvm_types::Error) {}
+}
+
+/// Transport running the operations of the inner one through the layers
+pub struct MiddlewareTransport<T: Transport> {
+    inner: T,
+    layers: Vec<Arc<dyn Middleware>>,
+}
+
+impl<T: Transport> MiddlewareTransport<T> {
+    pub fn new(inner: T) -> Self {
+        Self { inner, layers: Vec::new() }
+    }
+
+    pub fn with_layer(mut self, layer: Arc<dyn Middleware>) -> Self {
+        self.layers.push(layer);
+        self
+    }
+
+    pub fn push_layer(&mut self, layer: Arc<dyn Middleware>) {
+        self.layers.push(layer);
+    }
+
+    pub fn layers(&self) -> &[Arc<dyn Middleware>] {
+        &self.layers
+    }
+
+    pub fn inner(&self) -> &T {
+        &self.inner
+    }
+
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+
+    async fn prepare(&self, message: &SdkMessage) -> Result<SdkMessage> {
+        let mut message = message.clone();
+        for layer in &self.layers {
+            if let Err(err) = layer.pre_send(&mut message).await {
+                return Err(self.report(TransportOperation::SendMessage, err).await);
+            }
+        }
+        Ok(message)
+    }
+
+    async fn send(&self, message: &SdkMessage) -> Result<()> {
+        if self.layers.is_empty() {
+            return self.inner.send_message(message).await;
+        }
+        let message = self.prepare(message).await?;
+        self.observe(TransportOperation::SendMessage, self.inner.send_message(&message).await).await
+    }
+
+    async fn receive(
+        &self,
+        message_id: &MessageId,
+        timeout_ms: u32,
+    ) -> Result<tvm_block::Transaction> {
+        let operation = TransportOperation::WaitForTransaction;
+        let transaction = self
+            .observe(operation, self.inner.wait_for_transaction(message_id, timeout_ms).await)
+            .await?;
+        for layer in self.layers.iter().rev() {
+            if let Err(err) = layer.post_receive(message_id, &transaction).await {
+                return Err(self.report(operation, err).await);
+            }
+        }
+        Ok(transaction)
+    }
+
+    async fn observe<R>(&self, operation: TransportOperation, result: Result<R>) -> Result<R> {
+        match result {
+            Ok(value) => Ok(value),
+            Err(err) => Err(self.report(operation, err).await),
+        }
+    }
+
+    async fn report(
+        &self,
+        operation: TransportOperation,
+        err: tvm_types::Error,
+    ) -> tvm_types::Error {
+        for layer in self.layers.iter().rev() {
+            layer.on_error(operation, &err).await;
+        }
+        err
+    }
+}
+
+#[async_trait::async_trait]
+impl<T: Transport> Transport for MiddlewareTransport<T> {
+    async fn send_message(&self, message: &SdkMessage) -> Result<()> {
+        self.send(message).await
+    }
+
+    async fn wait_for_transaction(
+        &self,
+        message_id: &MessageId,
+        timeout_ms: u32,
+    ) -> Result<tvm_block::Transaction> {
+        self.receive(message_id, timeout_ms).await
+    }
+
+    async fn query_account(&self, address: &MsgAddressInt) -> Result<Option<Account>> {
+        self.observe(TransportOperation::QueryAccount, self.inner.query_account(address).await)
+            .await
+    }
+
+    async fn query_account_with_proof(
+        &self,
+        address: &MsgAddressInt,
+    ) -> Result<Option<(Account, AccountProof)>> {
+        let result = self.inner.query_account_with_proof(address).await;
+        self.observe(TransportOperation::QueryAccount, result).await
+    }
+
+    async fn query_accounts(&self, addresses: &[MsgAddressInt]) -> Result<Vec<Option<Account>>> {
+        self.observe(TransportOperation::QueryAccounts, self.inner.query_accounts(addresses).await)
+            .await
+    }
+
+    // Waits for the message as mutated by the layers
+    async fn process_message(&self, message: &SdkMessage, timeout_ms: u32) -> Result<Transaction> {
+        let message = self.prepare(message).await?;
+        self.observe(TransportOperation::SendMessage, self.inner.send_message(&message).await)
+            .await?;
+        let transaction = self.receive(&message.id, timeout_ms).await?;
+        Transaction::try_from(&transaction)
+    }
+}
+
+/// Logs messages, transactions and errors with the `log` crate
+pub struct LogMiddleware;
+
+#[async_trait::async_trait]
+impl Middleware for LogMiddleware {
+    async fn pre_send(&self, message: &mut SdkMessage) -> Result<()> {
+        log::debug!("Sending message {} to {}", message.id, message.address);
+        Ok(())
+    }
+
+    async fn post_receive(
+        &self,
+        message_id: &MessageId,
+        transaction: &tvm_block::Transaction,
+    ) -> Result<()> {
+        log::debug!(
+            "Message {} processed by transaction at lt {}",
+            message_id,
+            transaction.logical_time()
+        );
+        Ok(())
+    }
+
+    async fn on_error(&self, operation: TransportOperation, error: &tvm_types::Error) {
+        log::warn!("{:?} failed: {}", operation, error);
+    }
+}
@@ -0,0 +1,212 @@
+// Copyright 2018-2021 TON Labs LTD.
+//
+// Licensed under the SOFTWARE EVALUATION License (the "License"); you may not
+// use this file except in compliance with the License.
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific TON DEV software governing permissions and
+// limitations under the License.
+
+//! Message helpers of the standard SafeMultisig and SetcodeMultisig wallets.
+
+use serde_json::json;
+use tvm_abi::PublicKeyData;
+use tvm_abi::SignatureData;
+use tvm_block::MsgAddressExt;
+use tvm_block::MsgAddressInt;
+use tvm_types::Cell;
+use tvm_types::Result;
+use tvm_types::base64_encode;
+use tvm_types::ed25519_verify;
+use tvm_types::error;
+use tvm_types::fail;
+
+use crate::Contract;
+use crate::FunctionCallSet;
+use crate::MessageToSign;
+use crate::SdkMessage;
+use crate::Signer;
+use crate::error::SdkError;
+
+/// Parameters of `submitTransaction`
+#[derive(Clone, Debug)]
+pub struct SubmitTransaction {
+    pub dest: MsgAddressInt,
+    pub value: u128,
+    pub bounce: bool,
+    pub all_balance: bool,
+    pub payload: Option<Cell>,
+}
+
+/// Multisig wallet deployed at `address` with the given ABI
+#[derive(Clone, Debug)]
+pub struct Multisig {
+    address: MsgAddressInt,
+    abi: String,
+}
+
+impl Multisig {
+    pub fn new(address: MsgAddressInt, abi: impl Into<String>) -> Self {
+        Self { address, abi: abi.into() }
+    }
+
+    pub fn address(&self) -> &MsgAddressInt {
+        &self.address
+    }
+
+    pub fn abi(&self) -> &str {
+        &self.abi
+    }
+
+    /// Call set of `submitTransaction`. `signer` goes to the `pubkey` header,
+    /// the wallet checks it against the custodians.
+    pub fn submit_transaction_call(
+        &self,
+        transaction: &SubmitTransaction,
+        signer: Option<&PublicKeyData>,
+        expire: Option<u32>,
+    ) -> Result<FunctionCallSet> {
+        let payload = match &transaction.payload {
+            Some(payload) => tvm_types::boc::write_boc(payload)?,
+            None => tvm_types::boc::write_boc(&Cell::default())?,
+        };
+        let input = json!({
+            "dest": transaction.dest.to_string(),
+            "value": transaction.value.to_string(),
+            "bounce": transaction.bounce,
+            "allBalance": transaction.all_balance,
+            "payload": base64_encode(payload),
+        });
+        Ok(self.call("submitTransaction", input, signer, expire))
+    }
+
+    /// Call set of `confirmTransaction`
+    pub fn confirm_transaction_call(
+        &self,
+        transaction_id: u64,
+        signer: Option<&PublicKeyData>,
+        expire: Option<u32>,
+    ) -> FunctionCallSet {
+        let input = json!({ "transactionId": transaction_id.to_string() });
+        self.call("confirmTransaction", input, signer, expire)
+    }
+
+    /// Builds external message with the call signed by the custodian
+    pub fn signed_message(
+        &self,
+        call: &FunctionCallSet,
+        signer: &dyn Signer,
+    ) -> Result<SdkMessage> {
+        Contract::construct_call_ext_in_message_json(
+            self.address.clone(),
+            MsgAddressExt::default(),
+            call,
+            Some(signer),
+        )
+    }
+
+    /// Builds unsigned message with the call and data to sign, for keys kept
+    /// outside of the process
+    pub fn message_to_sign(&self, call: &FunctionCallSet) -> Result<MessageToSign> {
+        Contract::get_call_message_bytes_for_signing(
+            self.address.clone(),
+            MsgAddressExt::default(),
+            call,
+        )
+    }
+
+    fn call(
+        &self,
+        func: &str,
+        input: serde_json::Value,
+        signer: Option<&PublicKeyData>,
+        expire: Option<u32>,
+    ) -> FunctionCallSet {
+        let mut header = serde_json::Map::new();
+        if let Some(signer) = signer {
+            header.insert("pubkey".to_owned(), hex::encode(signer).into());
+        }
+        if let Some(expire) = expire {
+            header.insert("expire".to_owned(), expire.into());
+        }
+        FunctionCallSet {
+            func: func.to_owned(),
+            header: (!header.is_empty()).then(|| serde_json::Value::Object(header).to_string()),
+            input: input.to_string(),
+            abi: self.abi.clone(),
+        }
+    }
+}
+
+/// Collects signatures of several keys over the same `MessageToSign` and
+/// turns each of them into a signed message.
+///
+/// With the `pubkey` header (ABI 2.x) the signer key is a part of the signed
+/// data, so a shared message fits only calls built without `signer`. For the
+/// wallets reading custodian from the header every custodian signs own message
+/// built with `Multisig::message_to_sign`.
+pub struct SignatureCollector {
+    abi: String,
+    message: MessageToSign,
+    signatures: Vec<(PublicKeyData, SignatureData)>,
+}
+
+impl SignatureCollector {
+    pub fn new(abi: impl Into<String>, message: MessageToSign) -> Self {
+        Self { abi: abi.into(), message, signatures: Vec::new() }
+    }
+
+    pub fn data_to_sign(&self) -> &[u8] {
+        &self.message.data_to_sign
+    }
+
+    /// Verifies signature and stores it. Fails on wrong signature and on the
+    /// second signature of the same key.
+    pub fn add_signature(
+        &mut self,
+        public_key: &PublicKeyData,
+        signature: &SignatureData,
+    ) -> Result<()> {
+        if self.signatures.iter().any(|(key, _)| key == public_key) {
+            fail!(SdkError::InvalidData {
+                msg: format!("Message is already signed by {}", hex::encode(public_key))
+            })
+        }
+        ed25519_verify(public_key, &self.message.data_to_sign, signature).map_err(|err| {
+            SdkError::InvalidData {
+                msg: format!("Invalid signature of {}: {}", hex::encode(public_key), err),
+            }
+        })?;
+        self.signatures.push((*public_key, *signature));
+        Ok(())
+    }
+
+    pub fn len(&self) -> usize {
+        self.signatures.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.signatures.is_empty()
+    }
+
+    pub fn signers(&self) -> impl Iterator<Item = &PublicKeyData> {
+        self.signatures.iter().map(|(key, _)| key)
+    }
+
+    /// Signed messages in the order signatures were added
+    pub fn signed_messages(&self) -> Result<Vec<SdkMessage>> {
+        self.signatures
+            .iter()
+            .map(|(key, signature)| {
+                Contract::add_sign_to_message(
+                    &self.abi,
+                    signature,
+                    Some(key.as_slice()),
+                    &self.message.message,
+                )
+            })
+            .collect()
+    }
+}
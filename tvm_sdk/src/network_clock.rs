@@ -0,0 +1,107 @@
+// Copyright 2018-2021 TON Labs LTD.
+//
+// Licensed under the SOFTWARE EVALUATION License (the "License"); you may not
+// use this file except in compliance with the License.
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific TON DEV software governing permissions and
+// limitations under the License.
+
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::time::Instant;
+
+use tvm_block::Block;
+use tvm_types::Result;
+use tvm_types::error;
+use tvm_types::fail;
+
+use crate::Clock;
+use crate::error::SdkError;
+
+#[derive(Clone, Copy, Debug)]
+struct Observation {
+    gen_utime_ms: u64,
+    seen_at: Instant,
+}
+
+/// Network time derived from the latest seen masterchain block.
+///
+/// The time is the block generation time plus the time elapsed since the
+/// block was seen, measured by the monotonic clock, so the local wall clock
+/// is not used at all. Clones share the state: one clone is fed with blocks,
+/// e.g. from the blocks subscription, while others are given to
+/// `ExpirationPolicy` or `SessionKey` as their clock. Until the first block is
+/// observed the time is zero, so messages built too early are already expired.
+#[derive(Clone, Debug, Default)]
+pub struct NetworkClock {
+    last: Arc<Mutex<Option<Observation>>>,
+}
+
+impl NetworkClock {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Takes the generation time of the masterchain block, UNIX time in
+    /// milliseconds. Times older than the current network time are ignored,
+    /// so the clock never goes backwards.
+    pub fn observe(&self, gen_utime_ms: u64) {
+        let now = Instant::now();
+        let mut last = self.last.lock().unwrap();
+        if last.is_some_and(|last| Self::extrapolate(&last, now) >= gen_utime_ms) {
+            return;
+        }
+        *last = Some(Observation { gen_utime_ms, seen_at: now });
+    }
+
+    /// Takes the generation time of the block, which must be a masterchain
+    /// one: shardchain blocks may lag behind the masterchain.
+    pub fn observe_block(&self, block: &Block) -> Result<()> {
+        let info = block.read_info()?;
+        if !info.shard().is_masterchain() {
+            fail!(SdkError::InvalidData {
+                msg: format!("Block of shard {} is not a masterchain block", info.shard())
+            })
+        }
+        self.observe(info.gen_utime_ms());
+        Ok(())
+    }
+
+    /// Fetches the latest masterchain block time from the transport
+    #[cfg(feature = "transport")]
+    pub async fn sync(&self, transport: &crate::transport::GraphQlTransport) -> Result<()> {
+        match transport.query_last_masterchain_time().await? {
+            Some(gen_utime) => self.observe(gen_utime as u64 * 1000),
+            None => fail!(SdkError::InvalidData { msg: "No masterchain blocks found".to_owned() }),
+        }
+        Ok(())
+    }
+
+    /// Whether any block was observed
+    pub fn is_synced(&self) -> bool {
+        self.last.lock().unwrap().is_some()
+    }
+
+    /// Generation time of the latest observed block, UNIX time in
+    /// milliseconds
+    pub fn last_block_time_ms(&self) -> Option<u64> {
+        self.last.lock().unwrap().map(|last| last.gen_utime_ms)
+    }
+
+    fn extrapolate(last: &Observation, now: Instant) -> u64 {
+        let elapsed = now.saturating_duration_since(last.seen_at).as_millis() as u64;
+        last.gen_utime_ms.saturating_add(elapsed)
+    }
+}
+
+impl Clock for NetworkClock {
+    fn now_ms(&self) -> u64 {
+        match *self.last.lock().unwrap() {
+            Some(last) => Self::extrapolate(&last, Instant::now()),
+            None => 0,
+        }
+    }
+}
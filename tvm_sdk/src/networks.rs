@@ -0,0 +1,202 @@
+// Copyright 2018-2021 TON Labs LTD.
+//
+// Licensed under the SOFTWARE EVALUATION License (the "License"); you may not
+// use this file except in compliance with the License.
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific TON DEV software governing permissions and
+// limitations under the License.
+
+//! Registry of networks for tools working across environments, e.g. mainnet,
+//! testnet and a local node.
+//!
+//! Each network has its own transport and keystore, so keys of one network
+//! are never used to sign messages for another, and the same flow is run
+//! against the network chosen by name.
+
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::sync::Arc;
+
+use tvm_types::Result;
+use tvm_types::error;
+use tvm_types::fail;
+
+use crate::ExpirationPolicy;
+use crate::Keystore;
+use crate::Signer;
+#[cfg(feature = "executor")]
+use crate::deployed_contract::NetworkContract;
+use crate::error::SdkError;
+use crate::flows::Flows;
+use crate::middleware::Middleware;
+use crate::middleware::MiddlewareTransport;
+use crate::transport::GraphQlTransport;
+
+/// Transport and keys of a single network
+pub struct NetworkClient {
+    name: String,
+    transport: MiddlewareTransport<GraphQlTransport>,
+    keystore: Keystore,
+    expiration: Option<ExpirationPolicy>,
+    timeout_ms: Option<u32>,
+}
+
+impl NetworkClient {
+    pub fn new(name: impl Into<String>, transport: GraphQlTransport) -> Self {
+        Self {
+            name: name.into(),
+            transport: MiddlewareTransport::new(transport),
+            keystore: Keystore::new(),
+            expiration: None,
+            timeout_ms: None,
+        }
+    }
+
+    pub fn with_keystore(mut self, keystore: Keystore) -> Self {
+        self.keystore = keystore;
+        self
+    }
+
+    /// Adds the layer to the transport of the flows, see `middleware`
+    pub fn with_middleware(mut self, layer: Arc<dyn Middleware>) -> Self {
+        self.transport.push_layer(layer);
+        self
+    }
+
+    /// Expiration policy of the flows, e.g. with the network clock
+    pub fn with_expiration(mut self, policy: ExpirationPolicy) -> Self {
+        self.expiration = Some(policy);
+        self
+    }
+
+    /// Time to wait for transactions of the flows, in milliseconds
+    pub fn with_timeout(mut self, timeout_ms: u32) -> Self {
+        self.timeout_ms = Some(timeout_ms);
+        self
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Transport without the middleware
+    pub fn transport(&self) -> &GraphQlTransport {
+        self.transport.inner()
+    }
+
+    pub fn keystore(&self) -> &Keystore {
+        &self.keystore
+    }
+
+    pub fn keystore_mut(&mut self) -> &mut Keystore {
+        &mut self.keystore
+    }
+
+    /// Signer from the keystore of this network
+    pub fn signer(&self, key: &str) -> Result<&dyn Signer> {
+        self.keystore.get(key).ok_or_else(|| {
+            error!(SdkError::InvalidData {
+                msg: format!("Key {} is not in the keystore of network {}", key, self.name)
+            })
+        })
+    }
+
+    /// Flows over the transport of the network with the middleware
+    pub fn flows(&self) -> Flows<'_, MiddlewareTransport<GraphQlTransport>> {
+        let mut flows = Flows::new(&self.transport);
+        if let Some(policy) = &self.expiration {
+            flows = flows.with_expiration(policy.clone());
+        }
+        if let Some(timeout_ms) = self.timeout_ms {
+            flows = flows.with_timeout(timeout_ms);
+        }
+        flows
+    }
+
+    /// Contract of the network called over the transport with the
+    /// middleware
+    #[cfg(feature = "executor")]
+    pub fn contract(
+        &self,
+        address: tvm_block::MsgAddressInt,
+        abi: impl Into<String>,
+    ) -> NetworkContract<'_, MiddlewareTransport<GraphQlTransport>> {
+        let mut contract = NetworkContract::new(&self.transport, address, abi);
+        if let Some(policy) = &self.expiration {
+            contract = contract.with_expiration(policy.clone());
+        }
+        if let Some(timeout_ms) = self.timeout_ms {
+            contract = contract.with_timeout(timeout_ms);
+        }
+        contract
+    }
+}
+
+/// Networks keyed by name, one of them may be the default
+#[derive(Default)]
+pub struct Networks {
+    networks: BTreeMap<String, NetworkClient>,
+    default: Option<String>,
+}
+
+impl Networks {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the network, returns the one replaced by name
+    pub fn add(&mut self, network: NetworkClient) -> Option<NetworkClient> {
+        self.networks.insert(network.name.clone(), network)
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<NetworkClient> {
+        if self.default.as_deref() == Some(name) {
+            self.default = None;
+        }
+        self.networks.remove(name)
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.networks.keys().map(String::as_str)
+    }
+
+    pub fn get(&self, name: &str) -> Result<&NetworkClient> {
+        self.networks.get(name).ok_or_else(|| unknown(name))
+    }
+
+    pub fn get_mut(&mut self, name: &str) -> Result<&mut NetworkClient> {
+        self.networks.get_mut(name).ok_or_else(|| unknown(name))
+    }
+
+    pub fn set_default(&mut self, name: &str) -> Result<()> {
+        if !self.networks.contains_key(name) {
+            return Err(unknown(name));
+        }
+        self.default = Some(name.to_owned());
+        Ok(())
+    }
+
+    pub fn default_network(&self) -> Result<&NetworkClient> {
+        match &self.default {
+            Some(name) => self.get(name),
+            None => fail!(SdkError::InvalidData { msg: "No default network is set".to_owned() }),
+        }
+    }
+
+    /// Runs the flow against the network, e.g.
+    /// `networks.run("testnet", |net| async move { ... }).await`
+    pub async fn run<'a, F, Fut, R>(&'a self, name: &str, flow: F) -> Result<R>
+    where
+        F: FnOnce(&'a NetworkClient) -> Fut,
+        Fut: Future<Output = Result<R>> + 'a,
+    {
+        flow(self.get(name)?).await
+    }
+}
+
+fn unknown(name: &str) -> tvm_types::Error {
+    error!(SdkError::InvalidData { msg: format!("Unknown network {}", name) })
+}
@@ -0,0 +1,92 @@
+// Copyright 2018-2021 TON Labs LTD.
+//
+// Licensed under the SOFTWARE EVALUATION License (the "License"); you may not
+// use this file except in compliance with the License.
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific TON DEV software governing permissions and
+// limitations under the License.
+
+use crate::error::SdkError;
+
+/// Reason why the node didn't accept a message, classified from the node
+/// error code and message
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NodeRejection {
+    /// Message with the same hash was already received by the node
+    AlreadyExists,
+    /// Destination account doesn't exist
+    AccountNotFound,
+    /// Endpoint rate limit or message queue limit is exceeded
+    LimitExceeded,
+    /// Message must be resent to the active block producer
+    WrongProducer,
+    /// Message must be resent with the other thread identifier
+    ThreadMismatch,
+    /// Block manager token must be refreshed
+    TokenExpired,
+    /// Message expired before it was accepted
+    Expired,
+    /// Any other node error
+    Other,
+}
+
+impl NodeRejection {
+    /// Maps node error code and message. Codes are checked first, message
+    /// patterns cover endpoints which return only a text.
+    pub fn classify(code: &str, message: &str) -> Self {
+        match code {
+            "WRONG_PRODUCER" => return Self::WrongProducer,
+            "THREAD_MISMATCH" => return Self::ThreadMismatch,
+            "TOKEN_EXPIRED" => return Self::TokenExpired,
+            "MESSAGE_EXPIRED" => return Self::Expired,
+            "DUPLICATE_MESSAGE" | "MESSAGE_ALREADY_EXISTS" => return Self::AlreadyExists,
+            "ACCOUNT_NOT_FOUND" => return Self::AccountNotFound,
+            "LIMIT_EXCEEDED" | "TOO_MANY_REQUESTS" => return Self::LimitExceeded,
+            _ => {}
+        }
+        let lowercase = message.to_lowercase();
+        if lowercase.contains("already exists") || lowercase.contains("duplicate message") {
+            Self::AlreadyExists
+        } else if lowercase.contains("account not found")
+            || lowercase.contains("account does not exist")
+        {
+            Self::AccountNotFound
+        } else if lowercase.contains("limit exceeded") || lowercase.contains("too many requests") {
+            Self::LimitExceeded
+        } else if lowercase.contains("message expired") {
+            Self::Expired
+        } else {
+            Self::Other
+        }
+    }
+
+    /// Rejection reported by the error, `SdkError::NodeRejected` of
+    /// transports
+    pub fn of(err: &tvm_types::Error) -> Option<Self> {
+        match err.downcast_ref::<SdkError>() {
+            Some(SdkError::NodeRejected { code, msg }) => Some(Self::classify(code, msg)),
+            _ => None,
+        }
+    }
+
+    /// The same message may be accepted if sent again
+    pub fn is_retriable(&self) -> bool {
+        matches!(
+            self,
+            Self::LimitExceeded | Self::WrongProducer | Self::ThreadMismatch | Self::TokenExpired
+        )
+    }
+
+    /// Message must be resent to another endpoint or with updated producer
+    /// data
+    pub fn is_redirect(&self) -> bool {
+        matches!(self, Self::WrongProducer | Self::ThreadMismatch | Self::TokenExpired)
+    }
+}
+
+#[cfg(test)]
+#[path = "tests/test_node_rejection.rs"]
+mod tests;
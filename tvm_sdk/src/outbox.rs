@@ -0,0 +1,322 @@
+// Copyright 2018-2021 TON Labs LTD.
+//
+// Licensed under the SOFTWARE EVALUATION License (the "License"); you may not
+// use this file except in compliance with the License.
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific TON DEV software governing permissions and
+// limitations under the License.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use serde_json::Value;
+use tvm_abi::AbiCache;
+use tvm_abi::Token;
+use tvm_abi::token::Detokenizer;
+use tvm_abi::token::Tokenizer;
+use tvm_block::MsgAddressExt;
+use tvm_block::MsgAddressInt;
+use tvm_types::Result;
+use tvm_types::error;
+
+use crate::Contract;
+use crate::ExpirationPolicy;
+use crate::FunctionCallSet;
+use crate::NodeRejection;
+use crate::SdkMessage;
+use crate::Signer;
+use crate::SigningOptions;
+use crate::error::SdkError;
+use crate::journal::Journal;
+
+/// Failed delivery attempt
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct FailedAttempt {
+    /// UNIX time in seconds
+    pub at: u32,
+    pub error: String,
+}
+
+/// External call queued for sending
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct OutboxEntry {
+    pub id: u64,
+    #[serde(with = "crate::json_helper::address")]
+    pub address: MsgAddressInt,
+    pub call: FunctionCallSet,
+    /// Failed attempts since the call was queued last time
+    pub attempts: u32,
+    /// All failed attempts, including ones before requeueing
+    pub history: Vec<FailedAttempt>,
+}
+
+/// Dead letter with the call input decoded by the ABI
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct OutboxPreview {
+    pub id: u64,
+    pub address: String,
+    pub function: String,
+    pub header: Option<Value>,
+    pub input: Value,
+    pub history: Vec<FailedAttempt>,
+}
+
+#[derive(Serialize, Deserialize)]
+enum JournalRecord {
+    NextId(u64),
+    Queued(Box<OutboxEntry>),
+    DeadLetter(Box<OutboxEntry>),
+    Delivered(u64),
+    Failed { id: u64, attempt: FailedAttempt },
+    Rejected { id: u64, attempt: FailedAttempt },
+    Edited { id: u64, header: Option<String>, input: String },
+    Requeued(u64),
+    Discarded(u64),
+}
+
+/// Queue of external calls with retries. Calls failing `max_attempts` times
+/// move to the dead-letter queue, where they can be inspected, edited and
+/// requeued. The outbox opened by `open` appends its changes to a JSON lines
+/// journal file, replayed on the next `open`, as `MessageScheduler` does.
+pub struct Outbox {
+    max_attempts: u32,
+    next_id: u64,
+    pending: BTreeMap<u64, OutboxEntry>,
+    dead_letters: BTreeMap<u64, OutboxEntry>,
+    journal: Option<Journal<JournalRecord>>,
+}
+
+impl Outbox {
+    /// Outbox kept in memory only
+    pub fn new(max_attempts: u32) -> Self {
+        Self {
+            max_attempts: max_attempts.max(1),
+            next_id: 1,
+            pending: BTreeMap::new(),
+            dead_letters: BTreeMap::new(),
+            journal: None,
+        }
+    }
+
+    /// Opens the journal, creating it if needed, and compacts it to queued
+    /// calls and dead letters
+    pub fn open(path: impl AsRef<Path>, max_attempts: u32) -> Result<Self> {
+        let (journal, records) = Journal::open(path)?;
+        let mut outbox = Self::new(max_attempts);
+        for record in records {
+            outbox.apply(record)?;
+        }
+        let records: Vec<_> = std::iter::once(JournalRecord::NextId(outbox.next_id))
+            .chain(outbox.pending.values().cloned().map(Box::new).map(JournalRecord::Queued))
+            .chain(
+                outbox.dead_letters.values().cloned().map(Box::new).map(JournalRecord::DeadLetter),
+            )
+            .collect();
+        let mut journal = journal;
+        journal.compact(&records)?;
+        outbox.journal = Some(journal);
+        Ok(outbox)
+    }
+
+    /// Queues the call, returns its id
+    pub fn push(&mut self, address: MsgAddressInt, call: FunctionCallSet) -> Result<u64> {
+        let id = self.next_id;
+        let entry = OutboxEntry { id, address, call, attempts: 0, history: vec![] };
+        self.record(JournalRecord::Queued(Box::new(entry)))?;
+        Ok(id)
+    }
+
+    pub fn pending(&self) -> impl Iterator<Item = &OutboxEntry> {
+        self.pending.values()
+    }
+
+    pub fn get(&self, id: u64) -> Option<&OutboxEntry> {
+        self.pending.get(&id)
+    }
+
+    /// Encodes the queued call with headers filled by the policy. Each
+    /// attempt gets a new message, as the previous one may be expired.
+    pub fn build(
+        &self,
+        id: u64,
+        policy: &ExpirationPolicy,
+        signer: Option<&dyn Signer>,
+        options: SigningOptions,
+    ) -> Result<SdkMessage> {
+        let entry = Self::find(&self.pending, id)?;
+        Contract::construct_call_ext_in_message_with_policy(
+            entry.address.clone(),
+            MsgAddressExt::default(),
+            &entry.call,
+            signer,
+            policy,
+            options,
+        )
+    }
+
+    /// Removes the delivered call. Returns `false` for unknown ids.
+    pub fn delivered(&mut self, id: u64) -> Result<bool> {
+        if !self.pending.contains_key(&id) {
+            return Ok(false);
+        }
+        self.record(JournalRecord::Delivered(id))?;
+        Ok(true)
+    }
+
+    /// Records failed attempt of the call at the UNIX time. Returns `true` if
+    /// the call ran out of attempts and was moved to the dead-letter queue.
+    pub fn failed(&mut self, id: u64, at: u32, error: impl ToString) -> Result<bool> {
+        Self::find(&self.pending, id)?;
+        let attempt = FailedAttempt { at, error: error.to_string() };
+        self.record(JournalRecord::Failed { id, attempt })?;
+        Ok(self.dead_letters.contains_key(&id))
+    }
+
+    /// Records failed sending of the call like `failed`, classified by the
+    /// `NodeRejection` of the error: the call the node already has is
+    /// delivered, the call rejected for a reason a resend can't fix moves to
+    /// the dead-letter queue at once. Expired calls are rebuilt by `build`,
+    /// so they are retried. Returns `true` if the call left the queue.
+    pub fn send_failed(&mut self, id: u64, at: u32, err: &tvm_types::Error) -> Result<bool> {
+        match NodeRejection::of(err) {
+            Some(NodeRejection::AlreadyExists) => self.delivered(id),
+            Some(rejection) if !rejection.is_retriable() && rejection != NodeRejection::Expired => {
+                Self::find(&self.pending, id)?;
+                let attempt = FailedAttempt { at, error: err.to_string() };
+                self.record(JournalRecord::Rejected { id, attempt })?;
+                Ok(true)
+            }
+            _ => self.failed(id, at, err),
+        }
+    }
+
+    pub fn dead_letters(&self) -> impl Iterator<Item = &OutboxEntry> {
+        self.dead_letters.values()
+    }
+
+    pub fn dead_letter(&self, id: u64) -> Option<&OutboxEntry> {
+        self.dead_letters.get(&id)
+    }
+
+    /// Dead letter with header and input decoded by the ABI of the call
+    pub fn inspect(&self, id: u64) -> Result<OutboxPreview> {
+        let entry = Self::find(&self.dead_letters, id)?;
+        let input = Self::tokenize_input(&entry.call)?;
+        Ok(OutboxPreview {
+            id,
+            address: entry.address.to_string(),
+            function: entry.call.func.clone(),
+            header: entry.call.header.as_deref().map(serde_json::from_str).transpose()?,
+            input: Detokenizer::detokenize_to_json_value(&input)?,
+            history: entry.history.clone(),
+        })
+    }
+
+    /// Replaces header and input of the dead letter. The input is checked
+    /// against the ABI of the call.
+    pub fn edit(&mut self, id: u64, header: Option<String>, input: String) -> Result<()> {
+        let entry = Self::find(&self.dead_letters, id)?;
+        Self::tokenize_input(&FunctionCallSet {
+            header: header.clone(),
+            input: input.clone(),
+            ..entry.call.clone()
+        })?;
+        self.record(JournalRecord::Edited { id, header, input })
+    }
+
+    /// Moves the dead letter back to the queue with full set of attempts.
+    /// The failure history is kept.
+    pub fn requeue(&mut self, id: u64) -> Result<()> {
+        Self::find(&self.dead_letters, id)?;
+        self.record(JournalRecord::Requeued(id))
+    }
+
+    /// Drops the dead letter. Returns `false` for unknown ids.
+    pub fn discard(&mut self, id: u64) -> Result<bool> {
+        if !self.dead_letters.contains_key(&id) {
+            return Ok(false);
+        }
+        self.record(JournalRecord::Discarded(id))?;
+        Ok(true)
+    }
+
+    // Changes are journaled before they are applied
+    fn record(&mut self, record: JournalRecord) -> Result<()> {
+        if let Some(journal) = &mut self.journal {
+            journal.append(&record)?;
+        }
+        self.apply(record)
+    }
+
+    fn apply(&mut self, record: JournalRecord) -> Result<()> {
+        match record {
+            JournalRecord::NextId(id) => self.next_id = self.next_id.max(id),
+            JournalRecord::Queued(entry) => {
+                self.next_id = self.next_id.max(entry.id + 1);
+                self.pending.insert(entry.id, *entry);
+            }
+            JournalRecord::DeadLetter(entry) => {
+                self.next_id = self.next_id.max(entry.id + 1);
+                self.dead_letters.insert(entry.id, *entry);
+            }
+            JournalRecord::Delivered(id) => {
+                self.pending.remove(&id);
+            }
+            JournalRecord::Failed { id, attempt } => {
+                let entry = Self::find_mut(&mut self.pending, id)?;
+                entry.attempts += 1;
+                entry.history.push(attempt);
+                if entry.attempts >= self.max_attempts {
+                    if let Some(entry) = self.pending.remove(&id) {
+                        self.dead_letters.insert(id, entry);
+                    }
+                }
+            }
+            JournalRecord::Rejected { id, attempt } => {
+                let mut entry = self.pending.remove(&id).ok_or_else(|| Self::not_found(id))?;
+                entry.attempts += 1;
+                entry.history.push(attempt);
+                self.dead_letters.insert(id, entry);
+            }
+            JournalRecord::Edited { id, header, input } => {
+                let entry = Self::find_mut(&mut self.dead_letters, id)?;
+                entry.call = FunctionCallSet { header, input, ..entry.call.clone() };
+            }
+            JournalRecord::Requeued(id) => {
+                let mut entry = self.dead_letters.remove(&id).ok_or_else(|| Self::not_found(id))?;
+                entry.attempts = 0;
+                self.pending.insert(id, entry);
+            }
+            JournalRecord::Discarded(id) => {
+                self.dead_letters.remove(&id);
+            }
+        }
+        Ok(())
+    }
+
+    fn tokenize_input(call: &FunctionCallSet) -> Result<Vec<Token>> {
+        let contract = AbiCache::global().load(&call.abi)?;
+        let function = contract.function(&call.func)?;
+        let input: Value = serde_json::from_str(&call.input)?;
+        Tokenizer::tokenize_all_params(function.input_params(), &input)
+    }
+
+    fn find(entries: &BTreeMap<u64, OutboxEntry>, id: u64) -> Result<&OutboxEntry> {
+        entries.get(&id).ok_or_else(|| Self::not_found(id))
+    }
+
+    fn find_mut(entries: &mut BTreeMap<u64, OutboxEntry>, id: u64) -> Result<&mut OutboxEntry> {
+        entries.get_mut(&id).ok_or_else(|| Self::not_found(id))
+    }
+
+    fn not_found(id: u64) -> tvm_types::Error {
+        error!(SdkError::InvalidData { msg: format!("No outbox message {}", id) })
+    }
+}
+
+#[cfg(test)]
+#[path = "tests/test_outbox.rs"]
+mod tests;
@@ -0,0 +1,228 @@
+// Copyright 2018-2021 TON Labs LTD.
+//
+// Licensed under the SOFTWARE EVALUATION License (the "License"); you may not
+// use this file except in compliance with the License.
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific TON DEV software governing permissions and
+// limitations under the License.
+
+//! Merkle proofs that messages and account states belong to blocks.
+//!
+//! Proofs are built by a party having the full block or shard state, e.g. a
+//! node or an indexer, and checked by light clients against a block hash they
+//! trust, so data returned by untrusted endpoints can be validated. All
+//! proofs and data are BOCs.
+//!
+//! Account proof consists of two proofs: the block proof with the hash of the
+//! shard state after the block and the state proof with the account.
+//!
+//! `TrustStore` keeps the hashes of blocks the client trusts, e.g. checked by
+//! `block_signatures`, and checks account proofs against them.
+
+use std::collections::HashSet;
+use std::sync::RwLock;
+
+use tvm_block::Account;
+use tvm_block::Block;
+use tvm_block::BlockSeqNoAndShard;
+use tvm_block::Deserializable;
+use tvm_block::GetRepresentationHash;
+use tvm_block::HashmapAugType;
+use tvm_block::MerkleProof;
+use tvm_block::Message;
+use tvm_block::Serializable;
+use tvm_block::ShardStateUnsplit;
+use tvm_block::check_account_proof;
+use tvm_types::Cell;
+use tvm_types::Result;
+use tvm_types::UInt256;
+use tvm_types::UsageTree;
+use tvm_types::error;
+use tvm_types::fail;
+use tvm_types::read_single_root_boc;
+use tvm_types::write_boc;
+
+use crate::error::SdkError;
+
+/// Proof that the account state belongs to the shard state after the block
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AccountProof {
+    pub block_proof: Vec<u8>,
+    pub state_proof: Vec<u8>,
+}
+
+impl AccountProof {
+    /// Root hash of the block the proof is built for
+    pub fn block_id(&self) -> Result<UInt256> {
+        Ok(MerkleProof::construct_from_cell(read_single_root_boc(&self.block_proof)?)?.hash)
+    }
+}
+
+/// Builds the proof that the message is imported or created by the block
+pub fn build_message_proof(block_boc: &[u8], message_boc: &[u8]) -> Result<Vec<u8>> {
+    let block_root = read_single_root_boc(block_boc)?;
+    let message = Message::construct_from_bytes(message_boc)?;
+    let proof = message
+        .prepare_proof(true, &block_root)
+        .or_else(|_| message.prepare_proof(false, &block_root))
+        .map_err(|_| {
+            error!(SdkError::InvalidData {
+                msg: format!(
+                    "Message {:x} doesn't belong to the block",
+                    message.hash().unwrap_or_default()
+                )
+            })
+        })?;
+    write_boc(&proof)
+}
+
+/// Checks the proof that the message belongs to the block with the root hash
+pub fn verify_message_proof(
+    proof_boc: &[u8],
+    message_boc: &[u8],
+    block_id: &UInt256,
+) -> Result<()> {
+    let block: Block = read_proof(proof_boc, block_id)?.virtualize()?;
+    block.read_info()?;
+    let message_hash = Message::construct_from_bytes(message_boc)?.hash()?;
+    let extra = block.read_extra()?;
+    let found = match extra.read_in_msg_descr()?.get(&message_hash) {
+        Ok(Some(in_msg)) => Some(in_msg.message_cell()?.repr_hash()),
+        _ => match extra.read_out_msg_descr()?.get(&message_hash) {
+            Ok(Some(out_msg)) => Some(out_msg.read_message_hash()?),
+            _ => None,
+        },
+    };
+    match found {
+        Some(hash) if hash == message_hash => Ok(()),
+        Some(_) => {
+            fail!(SdkError::InvalidData { msg: "Wrong message hash in the proof".to_owned() })
+        }
+        None => fail!(SdkError::InvalidData { msg: "No message in the proof".to_owned() }),
+    }
+}
+
+/// Builds the proof that the account state belongs to the shard state of the
+/// block. `state_boc` is the shard state after the block.
+pub fn build_account_proof(
+    block_boc: &[u8],
+    state_boc: &[u8],
+    account_boc: &[u8],
+) -> Result<AccountProof> {
+    let state_root = read_single_root_boc(state_boc)?;
+    let account = Account::construct_from_bytes(account_boc)?;
+    let (block_proof, state_hash) = prove_block_state(read_single_root_boc(block_boc)?)?;
+    if state_hash != state_root.repr_hash() {
+        fail!(SdkError::InvalidData { msg: "Shard state doesn't follow the block".to_owned() })
+    }
+    let state_proof = account.prepare_proof(&state_root).map_err(|_| {
+        error!(SdkError::InvalidData {
+            msg: "Account doesn't belong to the shard state".to_owned()
+        })
+    })?;
+    Ok(AccountProof {
+        block_proof: write_boc(&block_proof)?,
+        state_proof: write_boc(&state_proof)?,
+    })
+}
+
+/// Builds the block part of the account proof: the proof of the hash of the
+/// shard state after the block. Used with the state proof built by a party
+/// having the shard state, e.g. returned by the endpoint with the account.
+pub fn build_block_state_proof(block_boc: &[u8]) -> Result<Vec<u8>> {
+    write_boc(&prove_block_state(read_single_root_boc(block_boc)?)?.0)
+}
+
+/// Sequence number and shard of the shard state in the state proof, i.e. of
+/// the block to fetch for `build_block_state_proof`. Not checked until the
+/// proof is verified.
+pub fn state_proof_block(state_proof_boc: &[u8]) -> Result<BlockSeqNoAndShard> {
+    let proof = MerkleProof::construct_from_cell(read_single_root_boc(state_proof_boc)?)?;
+    let state: ShardStateUnsplit = proof.virtualize()?;
+    Ok(BlockSeqNoAndShard {
+        seq_no: state.seq_no(),
+        vert_seq_no: state.vert_seq_no(),
+        shard_id: state.shard().clone(),
+    })
+}
+
+/// Checks the proof that the account state belongs to the shard state after
+/// the block with the root hash, returns the block sequence number and shard
+pub fn verify_account_proof(
+    proof: &AccountProof,
+    account_boc: &[u8],
+    block_id: &UInt256,
+) -> Result<BlockSeqNoAndShard> {
+    let block: Block = read_proof(&proof.block_proof, block_id)?.virtualize()?;
+    let info = block.read_info()?;
+    let state_hash = block.read_state_update()?.new_hash;
+    let state_proof = read_proof(&proof.state_proof, &state_hash)?;
+    let account = Account::construct_from_bytes(account_boc)?;
+    let state_block = check_account_proof(&state_proof, &account)?;
+    if state_block.seq_no != info.seq_no() || &state_block.shard_id != info.shard() {
+        fail!(SdkError::InvalidData { msg: "Shard state doesn't match the block".to_owned() })
+    }
+    Ok(state_block)
+}
+
+/// Hashes of the trusted blocks, account proofs are accepted only if they are
+/// built for one of them
+#[derive(Default)]
+pub struct TrustStore {
+    blocks: RwLock<HashSet<UInt256>>,
+}
+
+impl TrustStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the block root hash, e.g. of a block checked by `block_signatures`
+    pub fn trust_block(&self, block_id: UInt256) {
+        self.blocks.write().unwrap().insert(block_id);
+    }
+
+    pub fn is_trusted(&self, block_id: &UInt256) -> bool {
+        self.blocks.read().unwrap().contains(block_id)
+    }
+
+    /// Checks the proof that the account state belongs to the shard state
+    /// after one of the trusted blocks
+    pub fn verify_account(
+        &self,
+        proof: &AccountProof,
+        account_boc: &[u8],
+    ) -> Result<BlockSeqNoAndShard> {
+        let block_id = proof.block_id()?;
+        if !self.is_trusted(&block_id) {
+            fail!(SdkError::InvalidData {
+                msg: format!("Account proof is built for not trusted block {:x}", block_id)
+            })
+        }
+        verify_account_proof(proof, account_boc, &block_id)
+    }
+}
+
+// Proof of the block info and state update, returns it with the hash of the
+// shard state after the block
+fn prove_block_state(block_root: Cell) -> Result<(Cell, UInt256)> {
+    let usage_tree = UsageTree::with_root(block_root.clone());
+    let block = Block::construct_from_cell(usage_tree.root_cell())?;
+    block.read_info()?;
+    let state_hash = block.read_state_update()?.new_hash;
+    let proof = MerkleProof::create_by_usage_tree(&block_root, usage_tree)?.serialize()?;
+    Ok((proof, state_hash))
+}
+
+fn read_proof(proof_boc: &[u8], root_hash: &UInt256) -> Result<MerkleProof> {
+    let proof = MerkleProof::construct_from_cell(read_single_root_boc(proof_boc)?)?;
+    if &proof.hash != root_hash {
+        fail!(SdkError::InvalidData {
+            msg: format!("Proof is built for {:x}, not for {:x}", proof.hash, root_hash)
+        })
+    }
+    Ok(proof)
+}
@@ -0,0 +1,201 @@
+// Copyright 2018-2021 TON Labs LTD.
+//
+// Licensed under the SOFTWARE EVALUATION License (the "License"); you may not
+// use this file except in compliance with the License.
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific TON DEV software governing permissions and
+// limitations under the License.
+
+//! Detection of replayed external messages.
+//!
+//! The `expire` header makes the contract reject stale messages, but a
+//! message may still be delivered again until it expires, e.g. resubmitted
+//! to a deposit pipeline. `ReplayGuard` remembers hashes of processed
+//! messages until their expiration, so services reject duplicates before
+//! processing. Messages are keyed by the normalized hash, see
+//! `MessageIdKind::Normalized`, as anyone may resend the signed body with
+//! another source address, import fee or state init. Records of expired
+//! messages are useless, the contract rejects such messages anyway, so they are
+//! dropped.
+
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::Mutex;
+
+use tvm_abi::Function;
+use tvm_abi::TokenValue;
+use tvm_block::Message as TvmMessage;
+use tvm_types::Result;
+use tvm_types::UInt256;
+use tvm_types::error;
+use tvm_types::fail;
+
+use crate::AbiSource;
+use crate::Contract;
+use crate::MessageIdKind;
+use crate::error::SdkError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReplayStatus {
+    /// Message is seen for the first time and is recorded
+    Accepted,
+    /// Message was recorded before
+    Duplicate,
+    /// Message is expired and must be rejected regardless of the records
+    Expired,
+}
+
+/// Store of processed message hashes, e.g. in memory or in a database shared
+/// by service instances
+pub trait ReplayGuard: Send + Sync {
+    /// Records the message expiring at `expire_at` unless it is expired at
+    /// `now` or already recorded. Times are UNIX time in seconds.
+    fn check(&self, hash: &UInt256, expire_at: u32, now: u32) -> Result<ReplayStatus>;
+
+    /// Drops records of messages expired at `now`, returns their number
+    fn purge(&self, now: u32) -> Result<usize>;
+}
+
+/// In-memory `ReplayGuard` keeping at most `capacity` records. Expired
+/// records are dropped first, and if there are still too many, the least
+/// recently seen ones are evicted, so the capacity must cover all messages
+/// received within the expiration window to catch every duplicate.
+pub struct MemoryReplayGuard {
+    capacity: usize,
+    records: Mutex<Records>,
+}
+
+#[derive(Default)]
+struct Records {
+    // Expiration time and use sequence number of each record
+    entries: HashMap<UInt256, (u32, u64)>,
+    by_expiration: BTreeSet<(u32, UInt256)>,
+    by_use: BTreeMap<u64, UInt256>,
+    next_use: u64,
+}
+
+impl MemoryReplayGuard {
+    pub fn new(capacity: usize) -> Self {
+        Self { capacity: capacity.max(1), records: Mutex::new(Records::default()) }
+    }
+
+    pub fn len(&self) -> usize {
+        self.records.lock().unwrap().entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl ReplayGuard for MemoryReplayGuard {
+    fn check(&self, hash: &UInt256, expire_at: u32, now: u32) -> Result<ReplayStatus> {
+        if expire_at <= now {
+            return Ok(ReplayStatus::Expired);
+        }
+        let mut records = self.records.lock().unwrap();
+        if records.touch(hash) {
+            return Ok(ReplayStatus::Duplicate);
+        }
+        if records.entries.len() >= self.capacity {
+            records.purge(now);
+        }
+        while records.entries.len() >= self.capacity {
+            records.evict_least_recent();
+        }
+        records.insert(hash.clone(), expire_at);
+        Ok(ReplayStatus::Accepted)
+    }
+
+    fn purge(&self, now: u32) -> Result<usize> {
+        Ok(self.records.lock().unwrap().purge(now))
+    }
+}
+
+impl Records {
+    fn touch(&mut self, hash: &UInt256) -> bool {
+        let next_use = self.next_use;
+        let Some((_, last_use)) = self.entries.get_mut(hash) else {
+            return false;
+        };
+        self.by_use.remove(last_use);
+        *last_use = next_use;
+        self.by_use.insert(next_use, hash.clone());
+        self.next_use += 1;
+        true
+    }
+
+    fn insert(&mut self, hash: UInt256, expire_at: u32) {
+        self.entries.insert(hash.clone(), (expire_at, self.next_use));
+        self.by_expiration.insert((expire_at, hash.clone()));
+        self.by_use.insert(self.next_use, hash);
+        self.next_use += 1;
+    }
+
+    fn remove(&mut self, hash: &UInt256) {
+        if let Some((expire_at, last_use)) = self.entries.remove(hash) {
+            self.by_expiration.remove(&(expire_at, hash.clone()));
+            self.by_use.remove(&last_use);
+        }
+    }
+
+    fn purge(&mut self, now: u32) -> usize {
+        let mut count = 0;
+        while let Some((expire_at, hash)) = self.by_expiration.first().cloned() {
+            if expire_at > now {
+                break;
+            }
+            self.remove(&hash);
+            count += 1;
+        }
+        count
+    }
+
+    fn evict_least_recent(&mut self) {
+        if let Some((_, hash)) = self.by_use.pop_first() {
+            self.remove(&hash);
+        }
+    }
+}
+
+/// Value of the `expire` header of the external call, `None` if the ABI has
+/// no such header or the message has no body
+pub fn message_expiration(
+    abi: &(impl AbiSource + ?Sized),
+    message: &TvmMessage,
+) -> Result<Option<u32>> {
+    let Some(body) = message.body() else {
+        return Ok(None);
+    };
+    let abi = abi.abi_contract()?;
+    let (header, _, _) = Function::decode_header(abi.version(), body, abi.header(), false)?;
+    Ok(header.into_iter().find_map(|token| match token.value {
+        TokenValue::Expire(expire_at) => Some(expire_at),
+        _ => None,
+    }))
+}
+
+/// Checks the external call by its normalized hash and the `expire` header
+pub fn check_message(
+    guard: &dyn ReplayGuard,
+    abi: &(impl AbiSource + ?Sized),
+    message: &TvmMessage,
+    now: u32,
+) -> Result<ReplayStatus> {
+    let Some(expire_at) = message_expiration(abi, message)? else {
+        fail!(SdkError::InvalidData {
+            msg: "Message without `expire` header can't be checked for replay".to_owned()
+        })
+    };
+    let id = Contract::message_id(message, MessageIdKind::Normalized)?;
+    guard.check(&UInt256::from_str(&id.to_string())?, expire_at, now)
+}
+
+#[cfg(test)]
+#[path = "tests/test_replay.rs"]
+mod tests;
@@ -0,0 +1,160 @@
+// Copyright 2018-2021 TON Labs LTD.
+//
+// Licensed under the SOFTWARE EVALUATION License (the "License"); you may not
+// use this file except in compliance with the License.
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific TON DEV software governing permissions and
+// limitations under the License.
+
+//! Resending external messages expired unprocessed.
+//!
+//! An external message is dropped by validators once its `expire` header is
+//! in the past, so sending the same message again is pointless. The call is
+//! rebuilt instead, with fresh `time` and `expire` headers from the
+//! expiration policy, signed again and sent after a backoff delay.
+//!
+//! A message is resent only if the transport reports no transaction within
+//! the timeout, `SdkError::WaitTimeout`, and the destination account has no
+//! transactions since the message was sent. Otherwise the message may have
+//! been processed, e.g. the transaction was missed by the transport, and
+//! the resent call would be executed twice.
+//!
+//! Messages the node didn't accept are rebuilt and sent again as well, if
+//! the `NodeRejection` may be fixed by a resend or the message expired. A
+//! message the node already has is waited for as if it was just sent.
+
+use std::time::Duration;
+
+use tvm_block::MsgAddressExt;
+use tvm_block::MsgAddressInt;
+use tvm_types::Result;
+use tvm_types::error;
+use tvm_types::fail;
+
+use crate::Contract;
+use crate::ExpirationPolicy;
+use crate::FunctionCallSet;
+use crate::NodeRejection;
+use crate::Signer;
+use crate::SigningOptions;
+use crate::Transaction;
+use crate::error::SdkError;
+use crate::transport::Transport;
+
+/// Time to wait for the transaction past the expiration of the message, in
+/// milliseconds, as blocks with the message may still be produced
+const EXPIRATION_GRACE_MS: u64 = 5_000;
+/// Time to wait for the transaction of messages never expiring
+const DEFAULT_TIMEOUT_MS: u32 = 60_000;
+
+/// Exponential backoff between attempts
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Backoff {
+    /// Attempts including the first one
+    pub max_attempts: u32,
+    pub initial_delay_ms: u64,
+    pub max_delay_ms: u64,
+    pub factor: u32,
+}
+
+impl Default for Backoff {
+    fn default() -> Self {
+        Self { max_attempts: 3, initial_delay_ms: 1_000, max_delay_ms: 30_000, factor: 2 }
+    }
+}
+
+impl Backoff {
+    /// Delay before the retry, counted from 0 for the second attempt
+    pub fn delay_ms(&self, retry: u32) -> u64 {
+        let factor = (self.factor as u64).saturating_pow(retry);
+        self.initial_delay_ms.saturating_mul(factor).min(self.max_delay_ms)
+    }
+}
+
+/// Builds the call with headers of the policy, sends it and waits for the
+/// transaction until the message expires. Expired messages are rebuilt with
+/// a fresh `expire` header, signed again and sent after the backoff delay,
+/// if the account is not changed since the message was sent, and so are
+/// messages rejected by the node for a reason a resend may fix. Other errors
+/// are returned without retries, as are calls of functions without `expire`
+/// header, which never expire. Messages are signed with the
+/// options, e.g. with the signature id of the network.
+pub async fn send_with_retries<T: Transport + ?Sized>(
+    transport: &T,
+    address: &MsgAddressInt,
+    call: &FunctionCallSet,
+    signer: Option<&dyn Signer>,
+    policy: &ExpirationPolicy,
+    options: SigningOptions,
+    backoff: &Backoff,
+) -> Result<Transaction> {
+    for attempt in 0..backoff.max_attempts.max(1) {
+        if attempt > 0 {
+            tokio::time::sleep(Duration::from_millis(backoff.delay_ms(attempt - 1))).await;
+        }
+        let (call, expire) = policy.apply_with_expire(call)?;
+        let message = Contract::construct_call_ext_in_message_with_options(
+            address.clone(),
+            MsgAddressExt::default(),
+            &call,
+            signer,
+            options,
+        )?;
+        let Some(expire) = expire else {
+            return transport.process_message(&message, DEFAULT_TIMEOUT_MS).await;
+        };
+        let last_lt = last_transaction_lt(transport, address).await?;
+        if let Err(err) = transport.send_message(&message).await {
+            match NodeRejection::of(&err) {
+                Some(NodeRejection::AlreadyExists) => {}
+                Some(rejection) if rejection.is_retriable() => continue,
+                Some(NodeRejection::Expired) => continue,
+                _ => return Err(err),
+            }
+        }
+        let timeout_ms = (expire.saturating_sub(policy.now()) as u64 * 1000 + EXPIRATION_GRACE_MS)
+            .min(u32::MAX as u64) as u32;
+        match transport.wait_for_transaction(&message.id, timeout_ms).await {
+            Ok(transaction) => return Transaction::try_from(&transaction),
+            Err(err) if is_wait_timeout(&err) && policy.now() >= expire => {
+                if last_transaction_lt(transport, address).await? != last_lt {
+                    fail!(SdkError::InvalidData {
+                        msg: format!(
+                            "Message {} expired without transaction found, but account {} \
+                             has new transactions, the message may be processed",
+                            message.id, address
+                        )
+                    })
+                }
+            }
+            Err(err) => return Err(err),
+        }
+    }
+    fail!(SdkError::InvalidData {
+        msg: format!(
+            "Message to {} was rejected or expired unprocessed {} times",
+            address,
+            backoff.max_attempts.max(1)
+        )
+    })
+}
+
+// Logical time of the last transaction of the account, `None` if it doesn't
+// exist
+async fn last_transaction_lt<T: Transport + ?Sized>(
+    transport: &T,
+    address: &MsgAddressInt,
+) -> Result<Option<u64>> {
+    Ok(transport.query_account(address).await?.and_then(|account| account.last_tr_time()))
+}
+
+fn is_wait_timeout(err: &tvm_types::Error) -> bool {
+    matches!(err.downcast_ref::<SdkError>(), Some(SdkError::WaitTimeout { .. }))
+}
+
+#[cfg(test)]
+#[path = "tests/test_retry.rs"]
+mod tests;
@@ -0,0 +1,210 @@
+// Copyright 2018-2021 TON Labs LTD.
+//
+// Licensed under the SOFTWARE EVALUATION License (the "License"); you may not
+// use this file except in compliance with the License.
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific TON DEV software governing permissions and
+// limitations under the License.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use serde_json::Map;
+use serde_json::Value;
+use tvm_block::MsgAddressExt;
+use tvm_block::MsgAddressInt;
+use tvm_types::Result;
+use tvm_types::error;
+
+use crate::Contract;
+use crate::ExpirationPolicy;
+use crate::FunctionCallSet;
+use crate::SdkMessage;
+use crate::Signer;
+use crate::SigningOptions;
+use crate::error::SdkError;
+use crate::journal::Journal;
+
+/// Condition for sending of the scheduled message
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum ScheduleCondition {
+    /// Not before the UNIX time in seconds
+    At(u32),
+    /// When the balance of the destination account reaches the value, in
+    /// nanotokens
+    BalanceAtLeast(u64),
+    /// When the masterchain block with the seqno is produced
+    SeqnoAtLeast(u32),
+}
+
+/// External call waiting for its condition
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ScheduledMessage {
+    pub id: u64,
+    #[serde(with = "crate::json_helper::address")]
+    pub address: MsgAddressInt,
+    pub call: FunctionCallSet,
+    pub condition: ScheduleCondition,
+}
+
+impl ScheduledMessage {
+    pub fn is_due(&self, now: u32, seqno: u32, balance: Option<u64>) -> bool {
+        match self.condition {
+            ScheduleCondition::At(time) => now >= time,
+            ScheduleCondition::BalanceAtLeast(value) => balance.is_some_and(|b| b >= value),
+            ScheduleCondition::SeqnoAtLeast(target) => seqno >= target,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+enum JournalRecord {
+    Scheduled(Box<ScheduledMessage>),
+    Completed(u64),
+    /// Id of the next scheduled message, kept by compaction so ids of
+    /// completed messages are not reused
+    NextId(u64),
+}
+
+/// Holds external calls until their conditions are met.
+///
+/// Messages are encoded only when sent, with `time` and `expire` headers
+/// filled by the expiration policy, so calls scheduled long before are not
+/// expired. Schedule changes are appended to a JSON lines journal file,
+/// replayed on `open`, so pending messages survive restarts. A message stays
+/// pending until `complete` is called, i.e. it is sent at least once.
+pub struct MessageScheduler {
+    journal: Journal<JournalRecord>,
+    pending: BTreeMap<u64, ScheduledMessage>,
+    next_id: u64,
+}
+
+impl MessageScheduler {
+    /// Opens the journal, creating it if needed, and compacts it to pending
+    /// messages
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let (journal, records) = Journal::open(path)?;
+        let mut pending = BTreeMap::new();
+        let mut next_id = 1;
+        for record in records {
+            match record {
+                JournalRecord::Scheduled(message) => {
+                    next_id = next_id.max(message.id + 1);
+                    pending.insert(message.id, *message);
+                }
+                JournalRecord::Completed(id) => {
+                    pending.remove(&id);
+                }
+                JournalRecord::NextId(id) => next_id = next_id.max(id),
+            }
+        }
+        let mut scheduler = Self { journal, pending, next_id };
+        let records: Vec<_> = std::iter::once(JournalRecord::NextId(next_id))
+            .chain(scheduler.pending.values().cloned().map(Box::new).map(JournalRecord::Scheduled))
+            .collect();
+        scheduler.journal.compact(&records)?;
+        Ok(scheduler)
+    }
+
+    /// Adds the call to the schedule, returns its id
+    pub fn schedule(
+        &mut self,
+        address: MsgAddressInt,
+        call: FunctionCallSet,
+        condition: ScheduleCondition,
+    ) -> Result<u64> {
+        let message = ScheduledMessage { id: self.next_id, address, call, condition };
+        self.journal.append(&JournalRecord::Scheduled(Box::new(message.clone())))?;
+        self.next_id += 1;
+        self.pending.insert(message.id, message);
+        Ok(self.next_id - 1)
+    }
+
+    /// Removes the message from the schedule after it was sent. Also used to
+    /// cancel the message. Returns `false` for unknown ids.
+    pub fn complete(&mut self, id: u64) -> Result<bool> {
+        if !self.pending.contains_key(&id) {
+            return Ok(false);
+        }
+        self.journal.append(&JournalRecord::Completed(id))?;
+        self.pending.remove(&id);
+        Ok(true)
+    }
+
+    pub fn path(&self) -> &Path {
+        self.journal.path()
+    }
+
+    pub fn get(&self, id: u64) -> Option<&ScheduledMessage> {
+        self.pending.get(&id)
+    }
+
+    pub fn pending(&self) -> impl Iterator<Item = &ScheduledMessage> {
+        self.pending.values()
+    }
+
+    /// Ids of messages ready to be sent at the time and masterchain seqno.
+    /// `balance` returns the known balance of the account.
+    pub fn due(
+        &self,
+        now: u32,
+        seqno: u32,
+        balance: impl Fn(&MsgAddressInt) -> Option<u64>,
+    ) -> Vec<u64> {
+        self.pending
+            .values()
+            .filter(|message| message.is_due(now, seqno, balance(&message.address)))
+            .map(|message| message.id)
+            .collect()
+    }
+
+    /// Encodes the scheduled message. `time` and `expire` headers set when
+    /// the call was scheduled are dropped once expired and filled again by
+    /// the policy.
+    pub fn build(
+        &self,
+        id: u64,
+        policy: &ExpirationPolicy,
+        signer: Option<&dyn Signer>,
+        options: SigningOptions,
+    ) -> Result<SdkMessage> {
+        let message = self.pending.get(&id).ok_or_else(|| {
+            error!(SdkError::InvalidData { msg: format!("No scheduled message {}", id) })
+        })?;
+        let call = Self::refresh_header(&message.call, policy.now())?;
+        Contract::construct_call_ext_in_message_with_policy(
+            message.address.clone(),
+            MsgAddressExt::default(),
+            &call,
+            signer,
+            policy,
+            options,
+        )
+    }
+
+    fn refresh_header(call: &FunctionCallSet, now: u32) -> Result<FunctionCallSet> {
+        let Some(header) = call.header.as_deref() else {
+            return Ok(call.clone());
+        };
+        let mut header: Map<String, Value> = serde_json::from_str(header)?;
+        let expired = match header.get("expire") {
+            Some(expire) => expire.as_u64().is_some_and(|expire| expire <= now as u64),
+            None => false,
+        };
+        if expired {
+            header.remove("expire");
+            header.remove("time");
+        }
+        Ok(FunctionCallSet {
+            header: (!header.is_empty()).then(|| Value::Object(header).to_string()),
+            ..call.clone()
+        })
+    }
+}
+
+#[cfg(test)]
+#[path = "tests/test_scheduler.rs"]
+mod tests;
@@ -0,0 +1,168 @@
+// Copyright 2018-2021 TON Labs LTD.
+//
+// Licensed under the SOFTWARE EVALUATION License (the "License"); you may not
+// use this file except in compliance with the License.
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific TON DEV software governing permissions and
+// limitations under the License.
+
+//! Sending from seqno-based wallets by concurrent senders.
+//!
+//! Messages of such wallets carry the seqno stored in the wallet data and are
+//! rejected with exit code 33 if it doesn't match. `SeqnoSender` predicts the
+//! next seqno from the last message sent, so the account is not fetched
+//! before every message. Messages of the same wallet are sent one by one
+//! under a lock per address, and on a seqno mismatch the seqno is refreshed
+//! from the account and the message is rebuilt and sent again. So is the
+//! message rejected by the node for a reason a resend may fix, see
+//! `NodeRejection::is_retriable`.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::Mutex;
+
+use tvm_block::Account;
+use tvm_block::MsgAddressInt;
+use tvm_types::Result;
+use tvm_types::SliceData;
+use tvm_types::error;
+use tvm_types::fail;
+
+use crate::NodeRejection;
+use crate::SdkMessage;
+use crate::Transaction;
+use crate::error::SdkError;
+use crate::transport::Transport;
+
+/// Exit code of simple, v3 and v4 wallets rejecting a message with wrong
+/// seqno
+pub const SEQNO_MISMATCH_EXIT_CODE: i32 = 33;
+
+const DEFAULT_MAX_RETRIES: u32 = 3;
+const DEFAULT_TIMEOUT_MS: u32 = 60_000;
+
+/// Decides whether the error of sending or simulation is a seqno mismatch
+pub type SeqnoMismatchDetector = Arc<dyn Fn(&tvm_types::Error) -> bool + Send + Sync>;
+
+/// Seqno stored in the first 32 bits of the wallet data, zero for wallets
+/// not deployed yet
+pub fn read_seqno(account: Option<&Account>) -> Result<u32> {
+    match account.and_then(Account::get_data) {
+        Some(data) => SliceData::load_cell(data)?.get_next_u32(),
+        None => Ok(0),
+    }
+}
+
+/// Default detector: the error reports exit code 33, e.g.
+/// `SdkError::MessageRejected` of transports or errors of the local executor
+pub fn is_seqno_mismatch_error(err: &tvm_types::Error) -> bool {
+    SdkError::exit_code_of(err) == Some(SEQNO_MISMATCH_EXIT_CODE)
+}
+
+/// Sender of messages of seqno-based wallets with optimistic seqno
+/// prediction
+pub struct SeqnoSender<'a, T: Transport + ?Sized> {
+    transport: &'a T,
+    wallets: Mutex<HashMap<String, Arc<tokio::sync::Mutex<Option<u32>>>>>,
+    max_retries: u32,
+    timeout_ms: u32,
+    detector: SeqnoMismatchDetector,
+}
+
+impl<'a, T: Transport + ?Sized> SeqnoSender<'a, T> {
+    pub fn new(transport: &'a T) -> Self {
+        Self {
+            transport,
+            wallets: Mutex::new(HashMap::new()),
+            max_retries: DEFAULT_MAX_RETRIES,
+            timeout_ms: DEFAULT_TIMEOUT_MS,
+            detector: Arc::new(is_seqno_mismatch_error),
+        }
+    }
+
+    /// Number of resends after seqno mismatches and retriable node
+    /// rejections
+    pub fn with_max_retries(mut self, max_retries: u32) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    /// Time to wait for transactions, in milliseconds
+    pub fn with_timeout(mut self, timeout_ms: u32) -> Self {
+        self.timeout_ms = timeout_ms;
+        self
+    }
+
+    pub fn with_mismatch_detector(mut self, detector: SeqnoMismatchDetector) -> Self {
+        self.detector = detector;
+        self
+    }
+
+    /// Seqno the next message of the wallet is built with, `None` if it is
+    /// to be fetched
+    pub async fn predicted_seqno(&self, wallet: &MsgAddressInt) -> Option<u32> {
+        *self.slot(wallet).lock().await
+    }
+
+    /// Drops the prediction, e.g. after the wallet is used by other sender
+    pub async fn invalidate(&self, wallet: &MsgAddressInt) {
+        *self.slot(wallet).lock().await = None;
+    }
+
+    /// Builds the message with the predicted seqno, sends it and waits for
+    /// the transaction. The message is rebuilt with the refreshed seqno on
+    /// mismatch or retriable rejection by the node. Aborted transactions and
+    /// other errors drop the prediction, as the seqno is unknown after
+    /// them.
+    pub async fn send<F>(&self, wallet: &MsgAddressInt, build: F) -> Result<Transaction>
+    where
+        F: Fn(u32) -> Result<SdkMessage> + Send + Sync,
+    {
+        let slot = self.slot(wallet);
+        let mut predicted = slot.lock().await;
+        for _ in 0..=self.max_retries {
+            let seqno = match *predicted {
+                Some(seqno) => seqno,
+                None => read_seqno(self.transport.query_account(wallet).await?.as_ref())?,
+            };
+            *predicted = None;
+            let message = build(seqno)?;
+            match self.transport.process_message(&message, self.timeout_ms).await {
+                Ok(transaction) if is_seqno_mismatch(&transaction) => {}
+                Ok(transaction) => {
+                    // the seqno is not stored if the transaction is aborted
+                    if !transaction.aborted {
+                        *predicted = Some(seqno.wrapping_add(1));
+                    }
+                    return Ok(transaction);
+                }
+                Err(err) if (self.detector)(&err) => {}
+                Err(err) if NodeRejection::of(&err).is_some_and(|r| r.is_retriable()) => {}
+                Err(err) => return Err(err),
+            }
+        }
+        fail!(SdkError::InvalidData {
+            msg: format!(
+                "Message of wallet {} was rejected or its seqno mismatched {} times",
+                wallet,
+                self.max_retries as u64 + 1
+            )
+        })
+    }
+
+    fn slot(&self, wallet: &MsgAddressInt) -> Arc<tokio::sync::Mutex<Option<u32>>> {
+        let mut wallets = self.wallets.lock().unwrap();
+        wallets.entry(wallet.to_string()).or_default().clone()
+    }
+}
+
+fn is_seqno_mismatch(transaction: &Transaction) -> bool {
+    transaction.compute.exit_code == Some(SEQNO_MISMATCH_EXIT_CODE)
+}
+
+#[cfg(test)]
+#[path = "tests/test_seqno.rs"]
+mod tests;
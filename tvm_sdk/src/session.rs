@@ -0,0 +1,158 @@
+// Copyright 2018-2021 TON Labs LTD.
+//
+// Licensed under the SOFTWARE EVALUATION License (the "License"); you may not
+// use this file except in compliance with the License.
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific TON DEV software governing permissions and
+// limitations under the License.
+
+//! Session keys delegated by the account owner.
+//!
+//! A dApp generates `SessionKey`, the owner registers it in the account with
+//! the call from `SessionRegistry::register_call`, and later calls are signed
+//! by the session key, which stops signing when the session expires. The
+//! account contract is expected to have functions
+//! `addSessionKey(uint256 pubkey, uint32 validUntil[, uint128 limit])` and
+//! `removeSessionKey(uint256 pubkey)` and to accept external calls with the
+//! session key in the `pubkey` header. Function names can be changed with
+//! `SessionRegistry::with_functions`.
+
+use std::sync::Arc;
+
+use serde_json::json;
+use tvm_abi::PublicKeyData;
+use tvm_abi::SignatureData;
+use tvm_types::Ed25519PrivateKey;
+use tvm_types::Result;
+use tvm_types::ed25519_generate_private_key;
+use tvm_types::error;
+use tvm_types::fail;
+
+use crate::Clock;
+use crate::FunctionCallSet;
+use crate::Signer;
+use crate::SystemClock;
+use crate::error::SdkError;
+
+/// Restrictions of the session registered in the account
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SessionLimits {
+    /// UNIX time in seconds the session expires at
+    pub valid_until: u32,
+    /// Total value the session key may spend, if the contract limits it
+    pub spending_limit: Option<u128>,
+}
+
+/// Ephemeral key of the session. Signs only until the session expires.
+pub struct SessionKey {
+    key: Ed25519PrivateKey,
+    limits: SessionLimits,
+    clock: Arc<dyn Clock>,
+}
+
+impl SessionKey {
+    /// Generates new random key
+    pub fn generate(limits: SessionLimits) -> Result<Self> {
+        Ok(Self::from_key(ed25519_generate_private_key()?, limits))
+    }
+
+    /// Restores session from the stored key
+    pub fn from_key(key: Ed25519PrivateKey, limits: SessionLimits) -> Self {
+        Self { key, limits, clock: Arc::new(SystemClock) }
+    }
+
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Private key, to keep the session between dApp restarts
+    pub fn key(&self) -> &Ed25519PrivateKey {
+        &self.key
+    }
+
+    pub fn public_key(&self) -> PublicKeyData {
+        self.key.verifying_key()
+    }
+
+    pub fn limits(&self) -> &SessionLimits {
+        &self.limits
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.clock.now_ms() / 1000 >= self.limits.valid_until as u64
+    }
+}
+
+impl Signer for SessionKey {
+    fn public_key(&self) -> Result<PublicKeyData> {
+        Ok(SessionKey::public_key(self))
+    }
+
+    fn sign(&self, data: &[u8]) -> Result<SignatureData> {
+        if self.is_expired() {
+            fail!(SdkError::InvalidData {
+                msg: format!("Session key expired at {}", self.limits.valid_until)
+            })
+        }
+        Ok(self.key.sign(data))
+    }
+}
+
+/// Builds calls managing session keys of the account with the given ABI
+#[derive(Clone, Debug)]
+pub struct SessionRegistry {
+    abi: String,
+    add_function: String,
+    remove_function: String,
+}
+
+impl SessionRegistry {
+    pub fn new(abi: impl Into<String>) -> Self {
+        Self {
+            abi: abi.into(),
+            add_function: "addSessionKey".to_owned(),
+            remove_function: "removeSessionKey".to_owned(),
+        }
+    }
+
+    pub fn with_functions(mut self, add: impl Into<String>, remove: impl Into<String>) -> Self {
+        self.add_function = add.into();
+        self.remove_function = remove.into();
+        self
+    }
+
+    /// Call registering the session key, to be signed by the owner
+    pub fn register_call(&self, session: &SessionKey) -> Result<FunctionCallSet> {
+        let mut input = json!({
+            "pubkey": format!("0x{}", hex::encode(session.public_key())),
+            "validUntil": session.limits.valid_until,
+        });
+        if let Some(limit) = session.limits.spending_limit {
+            input["limit"] = limit.to_string().into();
+        }
+        self.call(&self.add_function, input)
+    }
+
+    /// Call revoking the session key before it expires
+    pub fn revoke_call(&self, public_key: &PublicKeyData) -> Result<FunctionCallSet> {
+        let input = json!({ "pubkey": format!("0x{}", hex::encode(public_key)) });
+        self.call(&self.remove_function, input)
+    }
+
+    fn call(&self, func: &str, input: serde_json::Value) -> Result<FunctionCallSet> {
+        let contract = tvm_abi::AbiCache::global().load(&self.abi)?;
+        contract
+            .function(func)
+            .map_err(|err| error!("Account doesn't support session keys: {}", err))?;
+        Ok(FunctionCallSet {
+            func: func.to_owned(),
+            header: None,
+            input: input.to_string(),
+            abi: self.abi.clone(),
+        })
+    }
+}
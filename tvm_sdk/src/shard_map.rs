@@ -0,0 +1,226 @@
+// Copyright 2018-2021 TON Labs LTD.
+//
+// Licensed under the SOFTWARE EVALUATION License (the "License"); you may not
+// use this file except in compliance with the License.
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific TON DEV software governing permissions and
+// limitations under the License.
+
+use std::collections::HashMap;
+
+use serde_json::Value;
+use tvm_block::AccountIdPrefixFull;
+use tvm_block::McShardRecord;
+use tvm_block::MsgAddressInt;
+use tvm_block::ShardHashes;
+use tvm_block::ShardIdent;
+use tvm_types::Result;
+use tvm_types::error;
+
+use crate::contract::ShardDescr;
+
+/// Shards deeper than this are matched one by one instead of by the table
+const MAX_TABLE_DEPTH: u8 = 16;
+
+/// Values bound to shards, looked up by account address. When shards
+/// overlap, the most specific one (longest prefix) wins, so a map may hold
+/// a workchain-wide default together with split shards.
+#[derive(Clone, Debug, Default)]
+pub struct ShardMap<T> {
+    shards: Vec<(ShardIdent, T)>,
+}
+
+impl<T> ShardMap<T> {
+    pub fn new() -> Self {
+        Self { shards: Vec::new() }
+    }
+
+    /// Binds value to the shard, replacing the previous value of the same
+    /// shard
+    pub fn insert(&mut self, shard: ShardIdent, value: T) {
+        match self.shards.iter_mut().find(|(ident, _)| *ident == shard) {
+            Some((_, old)) => *old = value,
+            None => self.shards.push((shard, value)),
+        }
+    }
+
+    /// Binds value to the shard given as `workchain:prefix` with the prefix in
+    /// hex with tag, e.g. `0:8000000000000000`
+    pub fn insert_str(&mut self, shard: &str, value: T) -> Result<()> {
+        self.insert(parse_shard(shard)?, value);
+        Ok(())
+    }
+
+    pub fn find_by_prefix(&self, prefix: &AccountIdPrefixFull) -> Option<(&ShardIdent, &T)> {
+        self.shards
+            .iter()
+            .filter(|(shard, _)| shard.contains_full_prefix(prefix))
+            .max_by_key(|(shard, _)| shard.prefix_len())
+            .map(|(shard, value)| (shard, value))
+    }
+
+    /// Returns shard containing the address and its value
+    pub fn find(&self, address: &MsgAddressInt) -> Result<Option<(&ShardIdent, &T)>> {
+        Ok(self.find_by_prefix(&AccountIdPrefixFull::prefix(address)?))
+    }
+
+    pub fn len(&self) -> usize {
+        self.shards.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.shards.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&ShardIdent, &T)> {
+        self.shards.iter().map(|(shard, value)| (shard, value))
+    }
+}
+
+/// Shard of the account in the shard hashes tree with its ancestors
+#[derive(Clone, Debug)]
+pub struct ShardChain {
+    /// Leaf shard with its last block. Its description tells whether the
+    /// shard is going to split or merge.
+    pub record: McShardRecord,
+    /// Ancestors of the shard from the workchain root down to its parent,
+    /// empty for a not split workchain
+    pub ancestors: Vec<ShardIdent>,
+}
+
+impl ShardChain {
+    /// Finds the shard containing the address in the shard hashes tree of
+    /// the masterchain block
+    pub fn find(shard_hashes: &ShardHashes, address: &MsgAddressInt) -> Result<Option<Self>> {
+        let prefix = AccountIdPrefixFull::prefix(address)?;
+        let Some(record) = shard_hashes.find_shard_by_prefix(&prefix)? else {
+            return Ok(None);
+        };
+        let mut ancestors = Vec::new();
+        let mut shard = record.shard().clone();
+        while shard.prefix_len() > 0 {
+            shard = shard.merge()?;
+            ancestors.push(shard.clone());
+        }
+        ancestors.reverse();
+        Ok(Some(Self { record, ancestors }))
+    }
+
+    pub fn shard(&self) -> &ShardIdent {
+        self.record.shard()
+    }
+}
+
+/// Shard list prepared for matching of many addresses, e.g. all accounts
+/// watched by the service against shards of each new block. Shards of a
+/// workchain are spread into a table indexed by the leading bits of the
+/// account id, so the match takes constant time. As in `ShardMap` the
+/// longest prefix wins for overlapping shards.
+#[derive(Clone, Debug, Default)]
+pub struct ShardMatcher {
+    shards: Vec<ShardIdent>,
+    workchains: HashMap<i32, ShardTable>,
+}
+
+#[derive(Clone, Debug, Default)]
+struct ShardTable {
+    depth: u8,
+    // Index of the shard for each value of `depth` leading bits
+    slots: Vec<Option<usize>>,
+    // Shards deeper than `MAX_TABLE_DEPTH`, the longest first
+    deep: Vec<usize>,
+}
+
+impl ShardMatcher {
+    pub fn new(shards: impl IntoIterator<Item = ShardIdent>) -> Self {
+        let shards = shards.into_iter().collect::<Vec<_>>();
+        let mut order = (0..shards.len()).collect::<Vec<_>>();
+        order.sort_by_key(|&index| shards[index].prefix_len());
+
+        let mut workchains: HashMap<i32, ShardTable> = HashMap::new();
+        for &index in &order {
+            let shard = &shards[index];
+            let table = workchains.entry(shard.workchain_id()).or_default();
+            if shard.prefix_len() > MAX_TABLE_DEPTH {
+                table.deep.insert(0, index);
+            } else {
+                table.depth = table.depth.max(shard.prefix_len());
+            }
+        }
+        for table in workchains.values_mut() {
+            table.slots = vec![None; 1 << table.depth];
+        }
+        for &index in &order {
+            let shard = &shards[index];
+            if shard.prefix_len() > MAX_TABLE_DEPTH {
+                continue;
+            }
+            let table = workchains.get_mut(&shard.workchain_id()).unwrap();
+            let first = table.slot(shard.shard_prefix_without_tag());
+            let count = 1 << (table.depth - shard.prefix_len());
+            table.slots[first..first + count].fill(Some(index));
+        }
+        Self { shards, workchains }
+    }
+
+    /// Parses shard descriptions in the format of `Contract::check_shard_match`
+    pub fn from_descrs(shard_descrs: &[Value]) -> Result<Self> {
+        let mut shards = Vec::with_capacity(shard_descrs.len());
+        for descr in shard_descrs {
+            let descr: ShardDescr = serde_json::from_value(descr.clone())?;
+            shards.push(ShardIdent::with_tagged_prefix(descr.workchain_id, descr.shard)?);
+        }
+        Ok(Self::new(shards))
+    }
+
+    pub fn shards(&self) -> &[ShardIdent] {
+        &self.shards
+    }
+
+    /// Index of the shard containing the prefix in the list the matcher was
+    /// created from
+    pub fn find_by_prefix(&self, prefix: &AccountIdPrefixFull) -> Option<usize> {
+        let table = self.workchains.get(&prefix.workchain_id)?;
+        table
+            .deep
+            .iter()
+            .copied()
+            .find(|&index| self.shards[index].contains_full_prefix(prefix))
+            .or_else(|| table.slots[table.slot(prefix.prefix)])
+    }
+
+    pub fn find(&self, address: &MsgAddressInt) -> Result<Option<usize>> {
+        Ok(self.find_by_prefix(&AccountIdPrefixFull::prefix(address)?))
+    }
+
+    /// Whether any shard contains the prefix. Prefixes of addresses matched
+    /// repeatedly may be computed once with `AccountIdPrefixFull::prefix`.
+    pub fn matches_prefix(&self, prefix: &AccountIdPrefixFull) -> bool {
+        self.find_by_prefix(prefix).is_some()
+    }
+
+    pub fn matches(&self, address: &MsgAddressInt) -> Result<bool> {
+        Ok(self.matches_prefix(&AccountIdPrefixFull::prefix(address)?))
+    }
+}
+
+impl ShardTable {
+    fn slot(&self, prefix: u64) -> usize {
+        prefix.checked_shr(64 - self.depth as u32).unwrap_or(0) as usize
+    }
+}
+
+fn parse_shard(shard: &str) -> Result<ShardIdent> {
+    let (workchain_id, prefix) =
+        shard.split_once(':').ok_or_else(|| error!("Can't read shard ident from {}", shard))?;
+    let workchain_id = workchain_id
+        .trim()
+        .parse()
+        .map_err(|e| error!("Can't read workchain_id from {}: {}", shard, e))?;
+    let prefix = u64::from_str_radix(prefix.trim(), 16)
+        .map_err(|e| error!("Can't read shard from {}: {}", shard, e))?;
+    ShardIdent::with_tagged_prefix(workchain_id, prefix)
+}
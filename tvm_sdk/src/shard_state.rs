@@ -0,0 +1,130 @@
+// Copyright 2018-2021 TON Labs LTD.
+//
+// Licensed under the SOFTWARE EVALUATION License (the "License"); you may not
+// use this file except in compliance with the License.
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific TON DEV software governing permissions and
+// limitations under the License.
+
+use tvm_block::Account;
+use tvm_block::CurrencyCollection;
+use tvm_block::Deserializable;
+use tvm_block::MsgAddressInt;
+use tvm_block::ShardAccount;
+use tvm_block::ShardAccounts;
+use tvm_block::ShardIdent;
+use tvm_block::ShardState as TvmShardState;
+use tvm_block::ShardStateUnsplit;
+use tvm_types::Cell;
+use tvm_types::Result;
+use tvm_types::UInt256;
+use tvm_types::error;
+use tvm_types::fail;
+
+use crate::contract::address_in_workchain;
+use crate::error::SdkError;
+use crate::types::grams_to_u64;
+
+/// Full state of a single shard, as downloaded from a node
+#[derive(Debug, Clone)]
+pub struct ShardState {
+    state: ShardStateUnsplit,
+    accounts: ShardAccounts,
+}
+
+impl ShardState {
+    pub fn from_boc(bytes: &[u8]) -> Result<Self> {
+        Self::from_cell(tvm_types::boc::read_single_root_boc(bytes)?)
+    }
+
+    pub fn from_cell(cell: Cell) -> Result<Self> {
+        match TvmShardState::construct_from_cell(cell)? {
+            TvmShardState::UnsplitState(state) => Self::with_state(state),
+            TvmShardState::SplitState(_) => fail!(SdkError::InvalidData {
+                msg: "Split shard state is not supported, load its halves separately".to_owned()
+            }),
+        }
+    }
+
+    pub fn with_state(state: ShardStateUnsplit) -> Result<Self> {
+        let accounts = state.read_accounts()?;
+        Ok(Self { state, accounts })
+    }
+
+    pub fn state(&self) -> &ShardStateUnsplit {
+        &self.state
+    }
+
+    pub fn shard(&self) -> &ShardIdent {
+        self.state.shard()
+    }
+
+    pub fn seq_no(&self) -> u32 {
+        self.state.seq_no()
+    }
+
+    pub fn gen_time(&self) -> u32 {
+        self.state.gen_time()
+    }
+
+    /// Returns the account stored in the state. Accounts of other shards are
+    /// reported as absent.
+    pub fn get_account(&self, address: &MsgAddressInt) -> Result<Option<Account>> {
+        self.get_shard_account(address)?
+            .map(|shard_account| shard_account.read_account()?.as_struct())
+            .transpose()
+    }
+
+    /// Returns the account with its last transaction info
+    pub fn get_shard_account(&self, address: &MsgAddressInt) -> Result<Option<ShardAccount>> {
+        if address.workchain_id() != self.shard().workchain_id()
+            || !self.shard().contains_account(address.address())?
+        {
+            return Ok(None);
+        }
+        self.accounts.account(&address.address())
+    }
+
+    /// Returns serialized account, ready to be stored in a local cache
+    pub fn get_account_boc(&self, address: &MsgAddressInt) -> Result<Option<Vec<u8>>> {
+        self.get_shard_account(address)?
+            .map(|shard_account| tvm_types::boc::write_boc(&shard_account.account_cell()))
+            .transpose()
+    }
+
+    /// Calls `f` for accounts of the state in the address order, while it
+    /// returns `true`. Accounts are read from the dictionary one by one, so
+    /// large states are not loaded into memory at once. Returns `false` if
+    /// the iteration was stopped by `f`.
+    pub fn iterate_accounts<F>(&self, mut f: F) -> Result<bool>
+    where
+        F: FnMut(MsgAddressInt, ShardAccount) -> Result<bool>,
+    {
+        let workchain_id = self.shard().workchain_id();
+        self.accounts.iterate_accounts(|account_id: UInt256, shard_account, _| {
+            f(address_in_workchain(None, workchain_id, account_id.into())?, shard_account)
+        })
+    }
+
+    /// Total balance of the shard as declared in the state
+    pub fn total_balance(&self) -> &CurrencyCollection {
+        self.state.total_balance()
+    }
+
+    /// Sum of the account balances, aggregated in the accounts dictionary
+    pub fn accounts_balance(&self) -> &CurrencyCollection {
+        self.accounts.full_balance()
+    }
+
+    /// Total balance of the shard in nanotokens
+    pub fn total_balance_grams(&self) -> Result<u64> {
+        grams_to_u64(&self.total_balance().grams)
+    }
+}
+
+#[cfg(test)]
+#[path = "tests/test_shard_state.rs"]
+mod tests;
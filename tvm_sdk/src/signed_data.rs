@@ -0,0 +1,226 @@
+// Copyright 2018-2021 TON Labs LTD.
+//
+// Licensed under the SOFTWARE EVALUATION License (the "License"); you may not
+// use this file except in compliance with the License.
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific TON DEV software governing permissions and
+// limitations under the License.
+
+//! Signing of off-chain data by wallet keys, for logins and API
+//! authentication.
+//!
+//! The signed hash covers the wallet address, the domain of the service, the
+//! signing time and the payload hash. It is calculated over bytes starting
+//! with `0xffff`, which never start a cell representation, so a signed data
+//! hash can't be a hash of a message body and can't be used as a transaction
+//! signature. Checking that the key belongs to the wallet is left to the
+//! verifier, e.g. by running the wallet getter or reading its data.
+//!
+//! Address ownership proofs are signatures of the challenge given by the
+//! verifier, checked against the key stored in the account data at the
+//! position of the wallet layout.
+
+use tvm_abi::PublicKeyData;
+use tvm_abi::SignatureData;
+use tvm_block::Account;
+use tvm_block::Deserializable;
+use tvm_block::MsgAddressInt;
+use tvm_types::Result;
+use tvm_types::SliceData;
+use tvm_types::ed25519_verify;
+use tvm_types::error;
+use tvm_types::fail;
+use tvm_types::sha256_digest;
+use tvm_types::sha256_digest_slices;
+
+use crate::AbiContract;
+use crate::AsyncSigner;
+use crate::Signer;
+use crate::error::SdkError;
+
+const SIGNED_DATA_PREFIX: &[u8] = b"\xff\xfftvm-signed-data";
+const OWNERSHIP_PREFIX: &[u8] = b"\xff\xfftvm-address-ownership";
+
+/// Payload signed by the wallet key for the domain
+#[derive(Clone, Debug, PartialEq)]
+pub struct SignedData {
+    pub address: MsgAddressInt,
+    pub domain: String,
+    /// UNIX time of signing in seconds
+    pub timestamp: u32,
+    pub payload: Vec<u8>,
+    pub public_key: PublicKeyData,
+    pub signature: SignatureData,
+}
+
+impl SignedData {
+    /// Hash signed for the payload
+    pub fn data_to_sign(
+        address: &MsgAddressInt,
+        domain: &str,
+        timestamp: u32,
+        payload: &[u8],
+    ) -> [u8; 32] {
+        let address = address.to_string();
+        sha256_digest_slices(&[
+            SIGNED_DATA_PREFIX,
+            &(address.len() as u32).to_be_bytes(),
+            address.as_bytes(),
+            &(domain.len() as u32).to_be_bytes(),
+            domain.as_bytes(),
+            &timestamp.to_be_bytes(),
+            &sha256_digest(payload),
+        ])
+    }
+
+    pub fn sign(
+        signer: &dyn Signer,
+        address: MsgAddressInt,
+        domain: impl Into<String>,
+        timestamp: u32,
+        payload: Vec<u8>,
+    ) -> Result<Self> {
+        let domain = domain.into();
+        let data = Self::data_to_sign(&address, &domain, timestamp, &payload);
+        Ok(Self {
+            public_key: signer.public_key()?,
+            signature: signer.sign(&data)?,
+            address,
+            domain,
+            timestamp,
+            payload,
+        })
+    }
+
+    pub async fn sign_async(
+        signer: &dyn AsyncSigner,
+        address: MsgAddressInt,
+        domain: impl Into<String>,
+        timestamp: u32,
+        payload: Vec<u8>,
+    ) -> Result<Self> {
+        let domain = domain.into();
+        let data = Self::data_to_sign(&address, &domain, timestamp, &payload);
+        Ok(Self {
+            public_key: signer.public_key().await?,
+            signature: signer.sign(&data).await?,
+            address,
+            domain,
+            timestamp,
+            payload,
+        })
+    }
+
+    /// Checks the signature, the domain and that the data was signed not
+    /// earlier than `max_age` seconds before `now` and not after it.
+    pub fn verify(&self, domain: &str, now: u32, max_age: u32) -> Result<()> {
+        if self.domain != domain {
+            fail!(SdkError::InvalidData {
+                msg: format!("Data is signed for domain {}, expected {}", self.domain, domain)
+            })
+        }
+        if self.timestamp > now || now - self.timestamp > max_age {
+            fail!(SdkError::InvalidData {
+                msg: format!("Data signing time {} is out of range at {}", self.timestamp, now)
+            })
+        }
+        let data = Self::data_to_sign(&self.address, &self.domain, self.timestamp, &self.payload);
+        ed25519_verify(&self.public_key, &data, &self.signature).map_err(|err| {
+            SdkError::InvalidData { msg: format!("Invalid data signature: {}", err) }
+        })?;
+        Ok(())
+    }
+}
+
+/// Where the wallet keeps its public key
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WalletLayout {
+    /// Key 0 of the ABI data dictionary, as read by `AbiContract::get_pubkey`
+    AbiDataMap,
+    /// First 256 bits of data, as in contracts compiled for ABI 2.x
+    Leading,
+    /// After 32-bit seqno, as in simple wallets
+    AfterSeqno,
+    /// After 32-bit seqno and 32-bit wallet id, as in v3 and v4 wallets
+    AfterWalletId,
+}
+
+impl WalletLayout {
+    /// Public key stored in the account data in this layout
+    pub fn read_public_key(self, data: &SliceData) -> Result<Option<PublicKeyData>> {
+        let offset = match self {
+            WalletLayout::AbiDataMap => return AbiContract::get_pubkey(data),
+            WalletLayout::Leading => 0,
+            WalletLayout::AfterSeqno => 32,
+            WalletLayout::AfterWalletId => 64,
+        };
+        if data.remaining_bits() < offset + 256 {
+            return Ok(None);
+        }
+        let mut data = data.clone();
+        data.move_by(offset)?;
+        Ok(Some(data.get_next_bytes(32)?.as_slice().try_into()?))
+    }
+}
+
+/// Hash signed to prove ownership of the address
+pub fn ownership_data_to_sign(address: &MsgAddressInt, challenge: &[u8]) -> [u8; 32] {
+    let address = address.to_string();
+    sha256_digest_slices(&[
+        OWNERSHIP_PREFIX,
+        &(address.len() as u32).to_be_bytes(),
+        address.as_bytes(),
+        &sha256_digest(challenge),
+    ])
+}
+
+/// Signs the challenge of the verifier with the wallet key
+pub fn sign_address_ownership(
+    signer: &dyn Signer,
+    address: &MsgAddressInt,
+    challenge: &[u8],
+) -> Result<SignatureData> {
+    signer.sign(&ownership_data_to_sign(address, challenge))
+}
+
+/// Checks that the challenge is signed by the key stored in the data of the
+/// account given as BOC. The key is read only at the position of the layout,
+/// which must be known for the wallet, e.g. by its code hash: other
+/// positions may hold keys chosen by anybody, like keys of counterparties.
+pub fn verify_address_ownership(
+    account_boc: &[u8],
+    address: &MsgAddressInt,
+    layout: WalletLayout,
+    public_key: &PublicKeyData,
+    signature: &SignatureData,
+    challenge: &[u8],
+) -> Result<()> {
+    let account = Account::construct_from_bytes(account_boc)?;
+    if account.get_addr() != Some(address) {
+        fail!(SdkError::InvalidData { msg: format!("Account state is not of {}", address) })
+    }
+    let data = account.get_data().ok_or_else(|| {
+        error!(SdkError::InvalidData { msg: format!("Account {} has no data", address) })
+    })?;
+    if layout.read_public_key(&SliceData::load_cell(data)?)?.as_ref() != Some(public_key) {
+        fail!(SdkError::InvalidData {
+            msg: format!(
+                "Key {} is not stored by {} as {:?}",
+                hex::encode(public_key),
+                address,
+                layout
+            )
+        })
+    }
+    ed25519_verify(public_key, &ownership_data_to_sign(address, challenge), signature).map_err(
+        |err| SdkError::InvalidData { msg: format!("Invalid ownership signature: {}", err) },
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+#[path = "tests/test_signed_data.rs"]
+mod tests;
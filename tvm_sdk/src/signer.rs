@@ -0,0 +1,240 @@
+// Copyright 2018-2021 TON Labs LTD.
+//
+// Licensed under the SOFTWARE EVALUATION License (the "License"); you may not
+// use this file except in compliance with the License.
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific TON DEV software governing permissions and
+// limitations under the License.
+
+use tvm_abi::PublicKeyData;
+use tvm_abi::SignatureData;
+use tvm_types::BLS_SECRET_KEY_LEN;
+use tvm_types::Ed25519PrivateKey;
+use tvm_types::Result;
+use tvm_types::error;
+use tvm_types::fail;
+
+use crate::error::SdkError;
+
+/// Signature scheme of a key
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SignatureScheme {
+    Ed25519,
+    Secp256k1,
+    Bls,
+}
+
+/// Source of signatures for external messages. Implemented by `LocalSigner`
+/// for keys kept in memory, and can be implemented for hardware wallets,
+/// HSMs and remote signing services.
+pub trait Signer {
+    /// Key for the `pubkey` header, asked only from Ed25519 signers
+    fn public_key(&self) -> Result<PublicKeyData>;
+
+    /// Signs the data. For message bodies the data is the hash of the
+    /// unsigned body.
+    fn sign(&self, data: &[u8]) -> Result<SignatureData>;
+
+    /// Contracts checking other signatures than Ed25519 have to know or
+    /// recover the key, so it is not put into the `pubkey` header
+    fn scheme(&self) -> SignatureScheme {
+        SignatureScheme::Ed25519
+    }
+}
+
+/// Signer which has to wait for a device or a remote service
+#[async_trait::async_trait]
+pub trait AsyncSigner: Send + Sync {
+    async fn public_key(&self) -> Result<PublicKeyData>;
+
+    async fn sign(&self, data: &[u8]) -> Result<SignatureData>;
+
+    fn scheme(&self) -> SignatureScheme {
+        SignatureScheme::Ed25519
+    }
+}
+
+#[async_trait::async_trait]
+impl<T: Signer + Send + Sync> AsyncSigner for T {
+    async fn public_key(&self) -> Result<PublicKeyData> {
+        Signer::public_key(self)
+    }
+
+    async fn sign(&self, data: &[u8]) -> Result<SignatureData> {
+        Signer::sign(self, data)
+    }
+
+    fn scheme(&self) -> SignatureScheme {
+        Signer::scheme(self)
+    }
+}
+
+/// Signer with the private key in memory
+pub struct LocalSigner {
+    key: SigningKey,
+}
+
+impl LocalSigner {
+    pub fn new(key: impl Into<SigningKey>) -> Self {
+        Self { key: key.into() }
+    }
+
+    pub fn key(&self) -> &SigningKey {
+        &self.key
+    }
+}
+
+impl From<Ed25519PrivateKey> for LocalSigner {
+    fn from(key: Ed25519PrivateKey) -> Self {
+        Self::new(key)
+    }
+}
+
+impl From<SigningKey> for LocalSigner {
+    fn from(key: SigningKey) -> Self {
+        Self::new(key)
+    }
+}
+
+impl Signer for LocalSigner {
+    fn public_key(&self) -> Result<PublicKeyData> {
+        Signer::public_key(&self.key)
+    }
+
+    fn sign(&self, data: &[u8]) -> Result<SignatureData> {
+        Signer::sign(&self.key, data)
+    }
+
+    fn scheme(&self) -> SignatureScheme {
+        self.key.scheme()
+    }
+}
+
+/// Private key of any of the supported schemes, for contracts verifying
+/// other signatures than Ed25519. Ed25519 and secp256k1 signatures are 64
+/// bytes and fit the ABI signature of external messages, 96 bytes BLS
+/// signatures can be passed only in body parameters, see
+/// `Contract::construct_call_ext_in_message_signed_in_body`.
+pub enum SigningKey {
+    Ed25519(Ed25519PrivateKey),
+    /// Signatures are compact, `r` and `s` without recovery id, of the
+    /// SHA-256 hash of the data. The data is always hashed, even if it is a
+    /// 32 bytes hash itself, as of message bodies, so contracts verify the
+    /// signature of `sha256(data)`.
+    #[cfg(feature = "secp256k1")]
+    Secp256k1(secp256k1::SecretKey),
+    /// Minimal public key mode: 48 bytes public keys, 96 bytes signatures
+    Bls([u8; BLS_SECRET_KEY_LEN]),
+}
+
+impl SigningKey {
+    pub fn scheme(&self) -> SignatureScheme {
+        match self {
+            SigningKey::Ed25519(_) => SignatureScheme::Ed25519,
+            #[cfg(feature = "secp256k1")]
+            SigningKey::Secp256k1(_) => SignatureScheme::Secp256k1,
+            SigningKey::Bls(_) => SignatureScheme::Bls,
+        }
+    }
+
+    /// Public key, compressed for secp256k1
+    pub fn public_key(&self) -> Result<Vec<u8>> {
+        match self {
+            SigningKey::Ed25519(key) => Ok(key.verifying_key().to_vec()),
+            #[cfg(feature = "secp256k1")]
+            SigningKey::Secp256k1(key) => {
+                let secp = secp256k1::Secp256k1::signing_only();
+                Ok(key.public_key(&secp).serialize().to_vec())
+            }
+            SigningKey::Bls(key) => {
+                Ok(tvm_types::gen_public_key_based_on_secret_key(key)?.to_vec())
+            }
+        }
+    }
+
+    pub fn sign(&self, data: &[u8]) -> Result<Vec<u8>> {
+        match self {
+            SigningKey::Ed25519(key) => Ok(key.sign(data).to_vec()),
+            #[cfg(feature = "secp256k1")]
+            SigningKey::Secp256k1(key) => {
+                let digest = tvm_types::sha256_digest(data);
+                let message = secp256k1::Message::from_slice(&digest)?;
+                let secp = secp256k1::Secp256k1::signing_only();
+                Ok(secp.sign_ecdsa(&message, key).serialize_compact().to_vec())
+            }
+            SigningKey::Bls(key) => Ok(tvm_types::bls::sign(key, data)?.to_vec()),
+        }
+    }
+}
+
+impl From<Ed25519PrivateKey> for SigningKey {
+    fn from(key: Ed25519PrivateKey) -> Self {
+        SigningKey::Ed25519(key)
+    }
+}
+
+impl Signer for SigningKey {
+    fn public_key(&self) -> Result<PublicKeyData> {
+        match self {
+            SigningKey::Ed25519(key) => Ok(key.verifying_key()),
+            _ => fail!(SdkError::InvalidData {
+                msg: format!("{:?} public key doesn't fit `pubkey` header", self.scheme())
+            }),
+        }
+    }
+
+    fn sign(&self, data: &[u8]) -> Result<SignatureData> {
+        let signature = SigningKey::sign(self, data)?;
+        signature.as_slice().try_into().map_err(|_| {
+            error!(SdkError::InvalidData {
+                msg: format!(
+                    "{:?} signature doesn't fit ABI signature, pass it in body parameters",
+                    self.scheme()
+                )
+            })
+        })
+    }
+
+    fn scheme(&self) -> SignatureScheme {
+        SigningKey::scheme(self)
+    }
+}
+
+/// Named signers to choose from
+#[derive(Default)]
+pub struct Keystore {
+    pub(crate) signers: Vec<(String, Box<dyn Signer>)>,
+}
+
+impl Keystore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&mut self, name: impl Into<String>, signer: Box<dyn Signer>) {
+        self.signers.push((name.into(), signer));
+    }
+
+    pub fn len(&self) -> usize {
+        self.signers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.signers.is_empty()
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.signers.iter().map(|(name, _)| name.as_str())
+    }
+
+    pub fn get(&self, name: &str) -> Option<&dyn Signer> {
+        self.signers.iter().find(|(n, _)| n == name).map(|(_, signer)| signer.as_ref())
+    }
+}
+
+#[cfg(test)]
+#[path = "tests/test_signer.rs"]
+mod tests;
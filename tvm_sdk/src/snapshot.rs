@@ -0,0 +1,95 @@
+// Copyright 2018-2021 TON Labs LTD.
+//
+// Licensed under the SOFTWARE EVALUATION License (the "License"); you may not
+// use this file except in compliance with the License.
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific TON DEV software governing permissions and
+// limitations under the License.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use tvm_block::Account;
+use tvm_block::ConfigParams;
+use tvm_block::Deserializable;
+use tvm_block::Serializable;
+use tvm_types::Cell;
+use tvm_types::Result;
+use tvm_types::base64_decode;
+use tvm_types::base64_encode;
+use tvm_types::error;
+use tvm_types::fail;
+
+use crate::error::SdkError;
+
+/// Reproducible state of a local chain: accounts, blockchain config and time.
+/// Stored as JSON with account states and config encoded as base64 BOCs.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct ChainSnapshot {
+    /// Chain time, UNIX seconds
+    pub now: u32,
+    #[serde(with = "crate::json_helper::opt_cell")]
+    pub config: Option<Cell>,
+    /// Account BOCs keyed by account address
+    pub accounts: BTreeMap<String, String>,
+}
+
+impl ChainSnapshot {
+    pub fn new(now: u32) -> Self {
+        Self { now, ..Default::default() }
+    }
+
+    pub fn export_snapshot(&self, path: impl AsRef<Path>) -> Result<()> {
+        let json = serde_json::to_vec_pretty(self)?;
+        std::fs::write(path, json)?;
+        Ok(())
+    }
+
+    pub fn import_snapshot(path: impl AsRef<Path>) -> Result<Self> {
+        let json = std::fs::read(path)?;
+        Ok(serde_json::from_slice(&json)?)
+    }
+
+    pub fn set_config(&mut self, config: &ConfigParams) -> Result<()> {
+        self.config = Some(config.serialize()?);
+        Ok(())
+    }
+
+    pub fn read_config(&self) -> Result<Option<ConfigParams>> {
+        self.config.clone().map(ConfigParams::construct_from_cell).transpose()
+    }
+
+    /// Adds account state, replacing the previous state of the same account
+    pub fn add_account(&mut self, account: &Account) -> Result<()> {
+        let Some(address) = account.get_addr() else {
+            fail!(SdkError::InvalidData { msg: "Can't add account without address".to_owned() })
+        };
+        let boc = account.write_to_bytes()?;
+        self.accounts.insert(address.to_string(), base64_encode(boc));
+        Ok(())
+    }
+
+    pub fn read_account(&self, address: &str) -> Result<Option<Account>> {
+        self.accounts
+            .get(address)
+            .map(|boc| Account::construct_from_bytes(&base64_decode(boc)?))
+            .transpose()
+    }
+
+    /// Decodes all account states of the snapshot
+    pub fn read_accounts(&self) -> Result<Vec<Account>> {
+        self.accounts
+            .iter()
+            .map(|(address, boc)| {
+                Account::construct_from_bytes(&base64_decode(boc)?).map_err(|err| {
+                    error!(SdkError::InvalidData {
+                        msg: format!("Invalid state of account {} in snapshot: {}", address, err)
+                    })
+                })
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,227 @@
+// Copyright 2018-2021 TON Labs LTD.
+//
+// Licensed under the SOFTWARE EVALUATION License (the "License"); you may not
+// use this file except in compliance with the License.
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific TON DEV software governing permissions and
+// limitations under the License.
+
+//! Staking accounting for dashboards: stakes sent to the elector and payouts
+//! returned by it, completed DePool rounds and yield over them.
+
+use num_traits::ToPrimitive;
+use tvm_abi::DecodedEvent;
+use tvm_abi::EventDecoder;
+use tvm_abi::Token;
+use tvm_abi::TokenValue;
+use tvm_block::Message as TvmMessage;
+use tvm_block::MsgAddressInt;
+use tvm_types::Result;
+use tvm_types::error;
+use tvm_types::fail;
+
+use crate::error::SdkError;
+use crate::types::grams_to_u64;
+
+/// Operation code of the new stake message to the elector ("NstK")
+pub const ELECTOR_NEW_STAKE_OP: u32 = 0x4e73744b;
+/// Operation code of the elector answer returning the stake with rewards
+pub const ELECTOR_RECOVER_STAKE_OK: u32 = 0xf96f7324;
+
+const SECONDS_PER_YEAR: f64 = 365.0 * 24.0 * 3600.0;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ElectorTransferKind {
+    /// Stake sent to the elector
+    Stake,
+    /// Stake with rewards returned by the elector
+    Payout,
+}
+
+/// Value exchanged between the validator wallet and the elector
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ElectorTransfer {
+    pub kind: ElectorTransferKind,
+    /// Validator wallet or DePool address
+    pub wallet: String,
+    pub query_id: u64,
+    /// Value of the message, in nanotokens
+    pub value: u64,
+    pub created_at: u32,
+    pub created_lt: u64,
+}
+
+impl ElectorTransfer {
+    /// Reads the stake or payout from the internal message between a wallet
+    /// and the elector. Returns `None` for other messages.
+    pub fn from_message(message: &TvmMessage, elector: &MsgAddressInt) -> Result<Option<Self>> {
+        let Some(header) = message.int_header() else {
+            return Ok(None);
+        };
+        let (kind, wallet, op) = if header.dst == *elector {
+            (ElectorTransferKind::Stake, header.src_ref(), ELECTOR_NEW_STAKE_OP)
+        } else if header.src_ref() == Some(elector) {
+            (ElectorTransferKind::Payout, Some(&header.dst), ELECTOR_RECOVER_STAKE_OK)
+        } else {
+            return Ok(None);
+        };
+        let (Some(wallet), Some(mut body)) = (wallet, message.body()) else {
+            return Ok(None);
+        };
+        if body.remaining_bits() < 96 || body.get_next_u32()? != op {
+            return Ok(None);
+        }
+        Ok(Some(Self {
+            kind,
+            wallet: wallet.to_string(),
+            query_id: body.get_next_u64()?,
+            value: grams_to_u64(&header.value.grams)?,
+            created_at: header.created_at.as_u32(),
+            created_lt: header.created_lt,
+        }))
+    }
+
+    /// Reads elector transfers of all wallets from the messages
+    pub fn collect<'a>(
+        elector: &MsgAddressInt,
+        messages: impl IntoIterator<Item = &'a TvmMessage>,
+    ) -> Result<Vec<Self>> {
+        let mut transfers = Vec::new();
+        for message in messages {
+            if let Some(transfer) = Self::from_message(message, elector)? {
+                transfers.push(transfer);
+            }
+        }
+        Ok(transfers)
+    }
+}
+
+/// Completed DePool round, from the `RoundCompleted` event
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct DePoolRound {
+    pub id: u64,
+    pub supposed_elected_at: u32,
+    pub unfreeze: u32,
+    pub stake: u64,
+    pub recovered_stake: u64,
+    pub rewards: u64,
+    pub participant_qty: u32,
+    pub validator_stake: u64,
+    pub completion_reason: u8,
+}
+
+impl DePoolRound {
+    /// Reads rounds from `RoundCompleted` events of the DePool with the ABI.
+    /// Other messages and events are skipped.
+    pub fn collect<'a>(
+        abi: &str,
+        messages: impl IntoIterator<Item = &'a TvmMessage>,
+    ) -> Result<Vec<Self>> {
+        let decoder = EventDecoder::load(abi)?;
+        let mut event = DecodedEvent::default();
+        let mut rounds = Vec::new();
+        for message in messages {
+            if message.ext_out_header().is_none() {
+                continue;
+            }
+            let Some(body) = message.body() else {
+                continue;
+            };
+            match decoder.event(&body) {
+                Ok(found) if found.name == "RoundCompleted" => {}
+                _ => continue,
+            }
+            decoder.decode_into(body, &mut event)?;
+            rounds.push(Self::from_tokens(&event.tokens)?);
+        }
+        Ok(rounds)
+    }
+
+    fn from_tokens(tokens: &[Token]) -> Result<Self> {
+        let round = match find(tokens, "round")? {
+            TokenValue::Tuple(round) => round,
+            _ => fail!(SdkError::InvalidData { msg: "Invalid DePool round".to_owned() }),
+        };
+        Ok(Self {
+            id: uint(round, "id")?,
+            supposed_elected_at: uint(round, "supposedElectedAt")? as u32,
+            unfreeze: uint(round, "unfreeze")? as u32,
+            stake: uint(round, "stake")?,
+            recovered_stake: uint(round, "recoveredStake")?,
+            rewards: uint(round, "rewards")?,
+            participant_qty: uint(round, "participantQty")? as u32,
+            validator_stake: uint(round, "validatorStake")?,
+            completion_reason: uint(round, "completionReason")? as u8,
+        })
+    }
+
+    /// Rewards per staked token
+    pub fn reward_rate(&self) -> f64 {
+        if self.stake == 0 { 0.0 } else { self.rewards as f64 / self.stake as f64 }
+    }
+
+    /// Seconds the stake was held by the elector
+    pub fn duration(&self) -> u32 {
+        self.unfreeze.saturating_sub(self.supposed_elected_at)
+    }
+}
+
+/// Totals of completed rounds, the inputs of the yield calculation
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct StakingSummary {
+    pub rounds: u64,
+    pub total_stake: u128,
+    pub total_rewards: u128,
+    /// Sum of round durations, in seconds
+    pub total_duration: u64,
+}
+
+impl StakingSummary {
+    pub fn from_rounds<'a>(rounds: impl IntoIterator<Item = &'a DePoolRound>) -> Self {
+        let mut summary = Self::default();
+        for round in rounds {
+            summary.rounds += 1;
+            summary.total_stake += round.stake as u128;
+            summary.total_rewards += round.rewards as u128;
+            summary.total_duration += round.duration() as u64;
+        }
+        summary
+    }
+
+    /// Stake weighted rewards per staked token per round
+    pub fn reward_rate(&self) -> f64 {
+        if self.total_stake == 0 {
+            0.0
+        } else {
+            self.total_rewards as f64 / self.total_stake as f64
+        }
+    }
+
+    /// Annual yield with rewards reinvested every round of the average
+    /// duration. `None` if there are no rounds with known duration.
+    pub fn apy(&self) -> Option<f64> {
+        if self.total_duration == 0 {
+            return None;
+        }
+        let round_duration = self.total_duration as f64 / self.rounds as f64;
+        Some((1.0 + self.reward_rate()).powf(SECONDS_PER_YEAR / round_duration) - 1.0)
+    }
+}
+
+fn find<'a>(tokens: &'a [Token], name: &str) -> Result<&'a TokenValue> {
+    tokens.iter().find(|token| token.name == name).map(|token| &token.value).ok_or_else(|| {
+        error!(SdkError::InvalidData { msg: format!("No {} in DePool event", name) })
+    })
+}
+
+fn uint(tokens: &[Token], name: &str) -> Result<u64> {
+    match find(tokens, name)? {
+        TokenValue::Uint(value) => value.number.to_u64().ok_or_else(|| {
+            error!(SdkError::InvalidData { msg: format!("{} is out of range", name) })
+        }),
+        _ => fail!(SdkError::InvalidData { msg: format!("{} is not an integer", name) }),
+    }
+}
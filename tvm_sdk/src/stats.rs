@@ -0,0 +1,130 @@
+// Copyright 2018-2021 TON Labs LTD.
+//
+// Licensed under the SOFTWARE EVALUATION License (the "License"); you may not
+// use this file except in compliance with the License.
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific TON DEV software governing permissions and
+// limitations under the License.
+
+use std::collections::BTreeSet;
+
+use tvm_block::Message as TvmMessage;
+use tvm_block::MsgAddressInt;
+use tvm_block::Transaction as TvmTransaction;
+use tvm_types::Result;
+
+use crate::types::grams_to_u64;
+
+/// Time window `[start, end)` in UNIX seconds
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimeWindow {
+    pub start: u32,
+    pub end: u32,
+}
+
+impl TimeWindow {
+    pub fn contains(&self, time: u32) -> bool {
+        self.start <= time && time < self.end
+    }
+}
+
+/// Activity report of a single account over a time window
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct AccountStats {
+    pub address: String,
+    pub window: TimeWindow,
+    /// Number of account transactions in the window
+    pub transactions: u64,
+    /// Number of aborted transactions
+    pub aborted: u64,
+    pub in_messages: u64,
+    pub out_messages: u64,
+    /// Total value of inbound internal messages, in nanotokens
+    pub value_in: u64,
+    /// Total value of outbound internal messages, in nanotokens
+    pub value_out: u64,
+    /// Total fees collected from the account
+    pub fees: u64,
+    /// Addresses the account exchanged internal messages with
+    pub counterparties: BTreeSet<String>,
+}
+
+impl AccountStats {
+    /// Collects statistics from account transactions. Transactions of other
+    /// accounts and transactions outside of the window are skipped, so the
+    /// result of any transaction scan can be passed as is.
+    pub fn collect<'a>(
+        address: &MsgAddressInt,
+        window: TimeWindow,
+        transactions: impl IntoIterator<Item = &'a TvmTransaction>,
+    ) -> Result<Self> {
+        let mut stats = Self {
+            address: address.to_string(),
+            window,
+            transactions: 0,
+            aborted: 0,
+            in_messages: 0,
+            out_messages: 0,
+            value_in: 0,
+            value_out: 0,
+            fees: 0,
+            counterparties: BTreeSet::new(),
+        };
+        let account_id = address.address();
+
+        for transaction in transactions {
+            if *transaction.account_id() != account_id || !window.contains(transaction.now()) {
+                continue;
+            }
+            stats.add_transaction(transaction)?;
+        }
+
+        Ok(stats)
+    }
+
+    /// Number of distinct counterparties
+    pub fn unique_counterparties(&self) -> usize {
+        self.counterparties.len()
+    }
+
+    fn add_transaction(&mut self, transaction: &TvmTransaction) -> Result<()> {
+        self.transactions += 1;
+        if transaction.read_description()?.is_aborted() {
+            self.aborted += 1;
+        }
+        self.fees = self.fees.saturating_add(grams_to_u64(&transaction.total_fees().grams)?);
+
+        if let Some(msg) = transaction.read_in_msg()? {
+            self.in_messages += 1;
+            self.value_in = self.value_in.saturating_add(Self::message_value(&msg)?);
+            if let Some(src) = msg.src_ref() {
+                self.counterparties.insert(src.to_string());
+            }
+        }
+
+        let mut out_msgs = vec![];
+        transaction.iterate_out_msgs(|msg| {
+            out_msgs.push(msg);
+            Ok(true)
+        })?;
+        for msg in out_msgs {
+            self.out_messages += 1;
+            self.value_out = self.value_out.saturating_add(Self::message_value(&msg)?);
+            if let Some(dst) = msg.dst_ref() {
+                self.counterparties.insert(dst.to_string());
+            }
+        }
+
+        Ok(())
+    }
+
+    fn message_value(msg: &TvmMessage) -> Result<u64> {
+        msg.get_value()
+            .map(|value| grams_to_u64(&value.grams))
+            .transpose()
+            .map(Option::unwrap_or_default)
+    }
+}
@@ -0,0 +1,96 @@
+// Copyright 2018-2021 TON Labs LTD.
+//
+// Licensed under the SOFTWARE EVALUATION License (the "License"); you may not
+// use this file except in compliance with the License.
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific TON DEV software governing permissions and
+// limitations under the License.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use tvm_types::Result;
+
+/// Storage of binary values by string keys
+pub trait KeyValueStorage: Send + Sync {
+    /// Value stored by the key, `None` if there is none
+    fn get(&self, key: &str) -> Result<Option<Vec<u8>>>;
+
+    /// Stores the value replacing the previous one
+    fn put(&self, key: &str, value: &[u8]) -> Result<()>;
+
+    /// Removes the value, does nothing if there is none
+    fn remove(&self, key: &str) -> Result<()>;
+}
+
+/// Storage keeping values in files named after the keys in the directory.
+/// Values are written to temporary files renamed over the stored ones, so
+/// readers never see partial values.
+pub struct FileStorage {
+    dir: PathBuf,
+}
+
+impl FileStorage {
+    pub fn open(dir: impl AsRef<Path>) -> Result<Self> {
+        std::fs::create_dir_all(dir.as_ref())?;
+        Ok(Self { dir: dir.as_ref().to_path_buf() })
+    }
+
+    fn path(&self, key: &str) -> PathBuf {
+        self.dir.join(key.replace([':', '/', '\\'], "_"))
+    }
+}
+
+impl KeyValueStorage for FileStorage {
+    fn get(&self, key: &str) -> Result<Option<Vec<u8>>> {
+        match std::fs::read(self.path(key)) {
+            Ok(value) => Ok(Some(value)),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    fn put(&self, key: &str, value: &[u8]) -> Result<()> {
+        let path = self.path(key);
+        let mut tmp_name = path.file_name().unwrap_or_default().to_os_string();
+        tmp_name.push(".tmp");
+        let tmp_path = path.with_file_name(tmp_name);
+        std::fs::write(&tmp_path, value)?;
+        std::fs::rename(&tmp_path, &path)?;
+        Ok(())
+    }
+
+    fn remove(&self, key: &str) -> Result<()> {
+        match std::fs::remove_file(self.path(key)) {
+            Err(err) if err.kind() != std::io::ErrorKind::NotFound => Err(err.into()),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Storage keeping values in memory
+#[derive(Default)]
+pub struct MemoryStorage {
+    values: Mutex<HashMap<String, Vec<u8>>>,
+}
+
+impl KeyValueStorage for MemoryStorage {
+    fn get(&self, key: &str) -> Result<Option<Vec<u8>>> {
+        Ok(self.values.lock().unwrap().get(key).cloned())
+    }
+
+    fn put(&self, key: &str, value: &[u8]) -> Result<()> {
+        self.values.lock().unwrap().insert(key.to_owned(), value.to_vec());
+        Ok(())
+    }
+
+    fn remove(&self, key: &str) -> Result<()> {
+        self.values.lock().unwrap().remove(key);
+        Ok(())
+    }
+}
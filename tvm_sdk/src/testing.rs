@@ -0,0 +1,97 @@
+// Copyright 2018-2021 TON Labs LTD.
+//
+// Licensed under the SOFTWARE EVALUATION License (the "License"); you may not
+// use this file except in compliance with the License.
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific TON DEV software governing permissions and
+// limitations under the License.
+
+//! Helpers for test fixtures. Keys produced here are derived from public
+//! labels and must never hold real funds. With the `executor` feature
+//! `LocalChain` runs contracts in memory, its contracts implement
+//! `DeployedContract` as network ones do.
+
+use tvm_block::CurrencyCollection;
+use tvm_block::MsgAddressExt;
+use tvm_block::MsgAddressInt;
+use tvm_types::Result;
+
+use crate::Contract;
+use crate::ContractImage;
+use crate::FunctionCallSet;
+use crate::LocalSigner;
+use crate::SdkMessage;
+
+#[cfg(feature = "executor")]
+mod local_chain;
+#[cfg(feature = "executor")]
+pub use local_chain::LocalChain;
+#[cfg(feature = "executor")]
+pub use local_chain::LocalContract;
+
+pub mod keys {
+    use tvm_types::Ed25519PrivateKey;
+    use tvm_types::Result;
+    use tvm_types::ed25519_create_private_key;
+    use tvm_types::sha256_digest_slices;
+
+    const SEED_DOMAIN: &[u8] = b"tvm-sdk/testing/keys";
+
+    /// Returns Ed25519 key derived from the label. The same label always
+    /// gives the same key.
+    pub fn from_seed(label: &str) -> Result<Ed25519PrivateKey> {
+        ed25519_create_private_key(&sha256_digest_slices(&[SEED_DOMAIN, label.as_bytes()]))
+    }
+
+    /// Returns public key of the key derived from the label
+    pub fn public_from_seed(label: &str) -> Result<[u8; 32]> {
+        Ok(from_seed(label)?.verifying_key())
+    }
+}
+
+/// Contract image with the public key of the key derived from the label
+pub fn wallet_image(label: &str, tvc: &[u8]) -> Result<ContractImage> {
+    ContractImage::from_state_init_and_key(
+        &mut std::io::Cursor::new(tvc),
+        &keys::public_from_seed(label)?,
+    )
+}
+
+/// Address of the wallet deployed with `wallet_deploy_message`
+pub fn wallet_address(label: &str, tvc: &[u8], workchain_id: i32) -> Result<MsgAddressInt> {
+    Ok(wallet_image(label, tvc)?.msg_address(workchain_id))
+}
+
+/// Builds deploy message of the wallet owned by the key derived from the
+/// label. `constructor` holds the constructor call parameters.
+pub fn wallet_deploy_message(
+    label: &str,
+    tvc: &[u8],
+    constructor: &FunctionCallSet,
+    workchain_id: i32,
+) -> Result<SdkMessage> {
+    let signer = LocalSigner::new(keys::from_seed(label)?);
+    Contract::construct_deploy_message_json(
+        constructor,
+        wallet_image(label, tvc)?,
+        Some(&signer),
+        workchain_id,
+        MsgAddressExt::default(),
+    )
+}
+
+/// Builds internal message without body transferring `value` to the address,
+/// to be applied to a local chain as a funding message
+pub fn fund_message(address: MsgAddressInt, value: u64) -> Result<SdkMessage> {
+    Contract::construct_int_message_with_body(
+        address,
+        None,
+        true,
+        false,
+        CurrencyCollection::with_grams(value),
+        None,
+    )
+}
@@ -0,0 +1,329 @@
+// Copyright 2018-2021 TON Labs LTD.
+//
+// Licensed under the SOFTWARE EVALUATION License (the "License"); you may not
+// use this file except in compliance with the License.
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific TON DEV software governing permissions and
+// limitations under the License.
+
+use std::collections::HashMap;
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+
+use tvm_abi::json_abi::DecodedMessage;
+use tvm_block::Account;
+use tvm_block::ConfigParams;
+use tvm_block::CurrencyCollection;
+use tvm_block::Deserializable;
+use tvm_block::MsgAddressExt;
+use tvm_block::MsgAddressInt;
+use tvm_block::Serializable;
+use tvm_block::Transaction as TvmTransaction;
+use tvm_types::Cell;
+use tvm_types::Result;
+use tvm_types::error;
+use tvm_types::fail;
+
+use crate::ChainSnapshot;
+use crate::Clock;
+use crate::Contract;
+use crate::ExpirationPolicy;
+use crate::SdkMessage;
+use crate::Signer;
+use crate::SigningOptions;
+use crate::SystemClock;
+use crate::Transaction;
+use crate::deployed_contract::CallResult;
+use crate::deployed_contract::DeployedContract;
+use crate::deployed_contract::decode_call_result;
+use crate::deployed_contract::function_call;
+use crate::deployed_contract::run_getter;
+use crate::error::SdkError;
+use crate::executor::LocalExecutor;
+
+// Offline config of the client library
+const DEFAULT_CONFIG: &[u8] = include_bytes!("default_config.boc");
+const MAX_CASCADE_TRANSACTIONS: usize = 10_000;
+const LT_STEP: u64 = 1_000;
+const DEFAULT_TTL: u32 = 60;
+
+/// Time not fixed, the chain follows the system clock
+const LIVE_TIME: u64 = u64::MAX;
+
+/// Clock of the chain time in milliseconds, increased by a millisecond on
+/// every reading not moved forward, so messages built in a row get
+/// increasing `time` headers as replay protection requires
+struct ChainClock {
+    now: AtomicU64,
+    last_ms: AtomicU64,
+}
+
+impl ChainClock {
+    fn now(&self) -> u32 {
+        match self.now.load(Ordering::Relaxed) {
+            LIVE_TIME => (SystemClock.now_ms() / 1000) as u32,
+            now => now as u32,
+        }
+    }
+
+    // Time after all `time` headers issued, so messages built with the
+    // restored clock pass replay protection of contracts called before
+    fn snapshot_time(&self) -> u32 {
+        let issued = self.last_ms.load(Ordering::Relaxed);
+        match self.now.load(Ordering::Relaxed) {
+            LIVE_TIME => (SystemClock.now_ms().max(issued) / 1000 + 1) as u32,
+            now if issued >= now * 1000 => (issued / 1000 + 1) as u32,
+            now => now as u32,
+        }
+    }
+}
+
+impl Clock for ChainClock {
+    fn now_ms(&self) -> u64 {
+        let now_ms = match self.now.load(Ordering::Relaxed) {
+            LIVE_TIME => SystemClock.now_ms(),
+            now => now * 1000,
+        };
+        let last = self
+            .last_ms
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |last| Some(now_ms.max(last + 1)));
+        now_ms.max(last.unwrap_or_default() + 1)
+    }
+}
+
+/// In-memory chain of tests. Messages are executed by the local executor
+/// one at a time, internal messages sent by the transactions are delivered
+/// in order of sending until the cascade is over. The chain time follows the
+/// system clock until it is set.
+pub struct LocalChain {
+    executor: LocalExecutor,
+    config: Option<Cell>,
+    clock: Arc<ChainClock>,
+    state: Mutex<ChainState>,
+}
+
+struct ChainState {
+    lt: u64,
+    accounts: HashMap<String, Account>,
+}
+
+impl LocalChain {
+    /// Chain with the default config
+    pub fn new() -> Result<Self> {
+        Self::with_config(ConfigParams::construct_from_bytes(DEFAULT_CONFIG)?)
+    }
+
+    pub fn with_executor(executor: LocalExecutor) -> Self {
+        Self {
+            executor,
+            config: None,
+            clock: Arc::new(ChainClock {
+                now: AtomicU64::new(LIVE_TIME),
+                last_ms: AtomicU64::new(0),
+            }),
+            state: Mutex::new(ChainState { lt: LT_STEP, accounts: HashMap::new() }),
+        }
+    }
+
+    pub fn with_config(config: ConfigParams) -> Result<Self> {
+        let mut chain = Self::with_executor(LocalExecutor::with_config_params(config.clone())?);
+        chain.config = Some(config.serialize()?);
+        Ok(chain)
+    }
+
+    /// Chain with the accounts, config and time of the snapshot
+    pub fn from_snapshot(snapshot: &ChainSnapshot) -> Result<Self> {
+        let chain = match snapshot.read_config()? {
+            Some(config) => Self::with_config(config)?,
+            None => Self::new()?,
+        };
+        chain.set_now(snapshot.now);
+        for account in snapshot.read_accounts()? {
+            chain.set_account(account)?;
+        }
+        Ok(chain)
+    }
+
+    /// Snapshot of the accounts and config. Its time is past the time of the
+    /// messages built by the chain clock, fixed by `from_snapshot`.
+    pub fn snapshot(&self) -> Result<ChainSnapshot> {
+        let mut snapshot = ChainSnapshot::new(self.clock.snapshot_time());
+        snapshot.config = self.config.clone();
+        for account in self.lock().accounts.values() {
+            snapshot.add_account(account)?;
+        }
+        Ok(snapshot)
+    }
+
+    /// Chain time, UNIX seconds
+    pub fn now(&self) -> u32 {
+        self.clock.now()
+    }
+
+    /// Fixes the chain time, e.g. to test expiration
+    pub fn set_now(&self, now: u32) {
+        self.clock.now.store(now as u64, Ordering::Relaxed);
+    }
+
+    /// Clock of the chain time, for expiration policies of messages sent to
+    /// the chain
+    pub fn clock(&self) -> Arc<dyn Clock> {
+        self.clock.clone()
+    }
+
+    pub fn account(&self, address: &MsgAddressInt) -> Option<Account> {
+        self.lock().accounts.get(&address.to_string()).cloned()
+    }
+
+    /// Puts the account state, replacing the previous state of the same
+    /// account
+    pub fn set_account(&self, account: Account) -> Result<()> {
+        let Some(address) = account.get_addr() else {
+            fail!(SdkError::InvalidData { msg: "Can't add account without address".to_owned() })
+        };
+        self.lock().accounts.insert(address.to_string(), account);
+        Ok(())
+    }
+
+    /// Credits the account with `value` nanotokens without a transaction.
+    /// Missing accounts are created uninitialized, ready to be deployed.
+    pub fn fund(&self, address: &MsgAddressInt, value: u64) -> Result<()> {
+        let funds = CurrencyCollection::with_grams(value);
+        let mut state = self.lock();
+        match state.accounts.get_mut(&address.to_string()) {
+            Some(account) => account.add_funds(&funds)?,
+            None => {
+                let account = Account::uninit(address.clone(), 0, self.now(), funds);
+                state.accounts.insert(address.to_string(), account);
+            }
+        }
+        Ok(())
+    }
+
+    /// Processes the message and its cascade, returns the transaction of the
+    /// message
+    pub fn process_message(&self, message: &SdkMessage) -> Result<Transaction> {
+        let transactions = self.process_cascade(message)?;
+        Transaction::try_from(&transactions[0])
+    }
+
+    /// Processes the message and the internal messages sent by the
+    /// transactions, returns the transactions in order of execution. Fails
+    /// if the external message is not accepted.
+    pub fn process_cascade(&self, message: &SdkMessage) -> Result<Vec<TvmTransaction>> {
+        let mut state = self.lock();
+        let now = self.now();
+        let mut queue = VecDeque::from([message.clone()]);
+        let mut transactions = Vec::new();
+        while let Some(message) = queue.pop_front() {
+            if transactions.len() == MAX_CASCADE_TRANSACTIONS {
+                fail!(SdkError::InvalidData {
+                    msg: format!("Cascade exceeded {} transactions", MAX_CASCADE_TRANSACTIONS)
+                })
+            }
+            let address = message.address.to_string();
+            let account = state.accounts.get(&address).cloned().unwrap_or_default();
+            let result =
+                self.executor.execute(&message, &account.write_to_bytes()?, now, state.lt)?;
+            let account = Account::construct_from_bytes(&result.account)?;
+            if account.is_none() {
+                state.accounts.remove(&address);
+            } else {
+                state.accounts.insert(address, account);
+            }
+            state.lt += LT_STEP;
+
+            let transaction = result.transaction;
+            for index in 0..transaction.msg_count() {
+                let Some(out_msg) = transaction.get_out_msg(index)? else {
+                    continue;
+                };
+                let Some(dst) = out_msg.int_header().map(|header| header.dst.clone()) else {
+                    continue;
+                };
+                let (serialized_message, id) = Contract::serialize_message(&out_msg)?;
+                queue.push_back(SdkMessage {
+                    id,
+                    serialized_message,
+                    message: out_msg,
+                    address: dst,
+                });
+            }
+            transactions.push(transaction);
+        }
+        Ok(transactions)
+    }
+
+    /// Contract deployed to the chain, called with the chain time
+    pub fn contract(&self, address: MsgAddressInt, abi: impl Into<String>) -> LocalContract<'_> {
+        LocalContract {
+            chain: self,
+            address,
+            abi: abi.into(),
+            signer: None,
+            policy: ExpirationPolicy::new(DEFAULT_TTL).with_clock(self.clock()),
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, ChainState> {
+        self.state.lock().unwrap_or_else(|err| err.into_inner())
+    }
+}
+
+/// Contract called on the local chain
+pub struct LocalContract<'a> {
+    chain: &'a LocalChain,
+    address: MsgAddressInt,
+    abi: String,
+    signer: Option<Arc<dyn Signer + Send + Sync>>,
+    policy: ExpirationPolicy,
+}
+
+impl LocalContract<'_> {
+    /// Signer of the calls, calls are unsigned without it
+    pub fn with_signer(mut self, signer: Arc<dyn Signer + Send + Sync>) -> Self {
+        self.signer = Some(signer);
+        self
+    }
+
+    pub fn with_expiration(mut self, policy: ExpirationPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+}
+
+#[async_trait::async_trait]
+impl DeployedContract for LocalContract<'_> {
+    fn address(&self) -> &MsgAddressInt {
+        &self.address
+    }
+
+    fn abi(&self) -> &str {
+        &self.abi
+    }
+
+    async fn call(&self, func: &str, input: &str) -> Result<CallResult> {
+        let message = Contract::construct_call_ext_in_message_with_policy(
+            self.address.clone(),
+            MsgAddressExt::default(),
+            &function_call(self, func, input),
+            self.signer.as_deref().map(|signer| signer as &dyn Signer),
+            &self.policy,
+            SigningOptions::default(),
+        )?;
+        decode_call_result(self.abi.as_str(), self.chain.process_message(&message)?)
+    }
+
+    async fn getter(&self, func: &str, input: &str) -> Result<DecodedMessage> {
+        let Some(account) = self.chain.account(&self.address) else {
+            fail!(SdkError::InvalidData { msg: format!("Account {} doesn't exist", self.address) })
+        };
+        run_getter(&account, &function_call(self, func, input), &self.policy)
+    }
+}
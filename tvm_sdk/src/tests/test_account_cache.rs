@@ -0,0 +1,222 @@
+// Copyright 2018-2021 TON Labs LTD.
+//
+// Licensed under the SOFTWARE EVALUATION License (the "License"); you may not
+// use this file except in compliance with the License.
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific TON DEV software governing permissions and
+// limitations under the License.
+
+use tvm_block::Block;
+use tvm_block::BlockInfo;
+use tvm_block::CurrencyCollection;
+use tvm_block::GetRepresentationHash;
+use tvm_block::MerkleUpdate;
+use tvm_block::Serializable;
+use tvm_block::ShardAccount;
+use tvm_block::ShardIdent;
+use tvm_block::ShardStateUnsplit;
+use tvm_types::write_boc;
+
+use super::*;
+use crate::proofs::build_account_proof;
+use crate::proofs::build_block_state_proof;
+use crate::proofs::state_proof_block;
+use crate::storage::MemoryStorage;
+#[cfg(feature = "transport")]
+use crate::transport::AccountVerification;
+
+fn account(balance: u64, last_lt: u64) -> Account {
+    let address = MsgAddressInt::with_standart(None, 0, [0x11; 32].into()).unwrap();
+    let mut account =
+        Account::with_address_and_ballance(&address, &CurrencyCollection::with_grams(balance));
+    account.set_last_tr_time(last_lt);
+    account
+}
+
+// Block and the shard state after it holding the account, as BOCs
+fn block_with_state(account: &Account, seq_no: u32) -> (Vec<u8>, Vec<u8>) {
+    let shard = ShardIdent::with_workchain_id(0).unwrap();
+    let old_state = ShardStateUnsplit::with_ident(shard.clone()).serialize().unwrap();
+    let mut state = ShardStateUnsplit::with_ident(shard.clone());
+    state.set_seq_no(seq_no);
+    let shard_account = ShardAccount::with_params(account, UInt256::default(), 0, None).unwrap();
+    state.insert_account(&UInt256::from([0x11; 32]), &shard_account).unwrap();
+    let state = state.serialize().unwrap();
+
+    let mut info = BlockInfo::new();
+    info.set_shard(shard);
+    info.set_seq_no(seq_no).unwrap();
+    let mut block = Block::default();
+    block.write_info(&info).unwrap();
+    block.write_state_update(&MerkleUpdate::create(&old_state, &state).unwrap()).unwrap();
+    (block.write_to_bytes().unwrap(), write_boc(&state).unwrap())
+}
+
+// Block with the shard state holding the account, returns the block root
+// hash and the proof of the account
+fn block_with_account(account: &Account, seq_no: u32) -> (UInt256, AccountProof) {
+    let (block, state) = block_with_state(account, seq_no);
+    let proof = build_account_proof(&block, &state, &account.write_to_bytes().unwrap()).unwrap();
+    (Block::construct_from_bytes(&block).unwrap().hash().unwrap(), proof)
+}
+
+#[test]
+fn test_only_newer_states_stored() {
+    let cache = AccountCache::with_storage(Box::<MemoryStorage>::default());
+    let address = account(0, 0).get_addr().unwrap().clone();
+    assert!(cache.update(&account(100, 10).write_to_bytes().unwrap()).unwrap());
+    assert!(!cache.update(&account(200, 10).write_to_bytes().unwrap()).unwrap());
+    assert!(!cache.update(&account(200, 5).write_to_bytes().unwrap()).unwrap());
+    assert!(cache.update(&account(300, 20).write_to_bytes().unwrap()).unwrap());
+    assert_eq!(cache.get(&address).unwrap(), Some(account(300, 20)));
+
+    cache.remove(&address).unwrap();
+    assert!(cache.get_boc(&address).unwrap().is_none());
+}
+
+#[test]
+fn test_trust_store_accepts_only_proven_updates() {
+    let cache = AccountCache::with_storage(Box::<MemoryStorage>::default());
+    let state = account(100, 10);
+    let boc = state.write_to_bytes().unwrap();
+    let (block_id, proof) = block_with_account(&state, 1);
+
+    // proofs are not checked until the store is enabled
+    assert!(cache.update_with_proof(&boc, &proof).unwrap());
+
+    let cache = AccountCache::with_storage(Box::<MemoryStorage>::default());
+    cache.trust_block(UInt256::from([0x22; 32]));
+    assert!(cache.update(&boc).is_err());
+    assert!(cache.update_with_proof(&boc, &proof).is_err());
+
+    cache.trust_block(block_id);
+    let forged = account(1_000_000, 20).write_to_bytes().unwrap();
+    assert!(cache.update_with_proof(&forged, &proof).is_err());
+    assert!(cache.update_with_proof(&boc, &proof).unwrap());
+    assert_eq!(cache.get(state.get_addr().unwrap()).unwrap(), Some(state));
+}
+
+#[test]
+fn test_proof_assembled_from_state_proof_and_block() {
+    let state = account(100, 10);
+    let boc = state.write_to_bytes().unwrap();
+    let (block, _) = block_with_state(&state, 7);
+    let (block_id, proof) = block_with_account(&state, 7);
+
+    // the endpoint gives the state proof, the block is found by its seq_no
+    let block_ref = state_proof_block(&proof.state_proof).unwrap();
+    assert_eq!(block_ref.seq_no, 7);
+    assert_eq!(block_ref.shard_id, ShardIdent::with_workchain_id(0).unwrap());
+    let assembled = AccountProof {
+        block_proof: build_block_state_proof(&block).unwrap(),
+        state_proof: proof.state_proof.clone(),
+    };
+
+    let cache = AccountCache::with_storage(Box::<MemoryStorage>::default());
+    cache.trust_block(block_id);
+    assert!(cache.update_with_proof(&boc, &assembled).unwrap());
+}
+
+// Serves the account with the proof and the transaction at its last lt
+#[cfg(feature = "transport")]
+struct TestTransport {
+    account: Account,
+    proof: AccountProof,
+}
+
+#[cfg(feature = "transport")]
+#[async_trait::async_trait]
+impl Transport for TestTransport {
+    async fn send_message(&self, _message: &crate::SdkMessage) -> Result<()> {
+        unreachable!()
+    }
+
+    async fn wait_for_transaction(
+        &self,
+        _message_id: &crate::MessageId,
+        _timeout_ms: u32,
+    ) -> Result<tvm_block::Transaction> {
+        unreachable!()
+    }
+
+    async fn query_account(&self, _address: &MsgAddressInt) -> Result<Option<Account>> {
+        Ok(Some(self.account.clone()))
+    }
+
+    async fn query_account_with_proof(
+        &self,
+        _address: &MsgAddressInt,
+    ) -> Result<Option<(Account, AccountProof)>> {
+        Ok(Some((self.account.clone(), self.proof.clone())))
+    }
+
+    async fn query_account_transactions(
+        &self,
+        _address: &MsgAddressInt,
+        after_lt: u64,
+        _limit: u32,
+    ) -> Result<Vec<tvm_block::Transaction>> {
+        let lt = self.account.last_tr_time().unwrap_or_default();
+        if after_lt >= lt {
+            return Ok(Vec::new());
+        }
+        let mut transaction = tvm_block::Transaction::default();
+        transaction.set_logical_time(lt);
+        transaction
+            .write_description(&tvm_block::TransactionDescr::Ordinary(Default::default()))?;
+        Ok(vec![transaction])
+    }
+}
+
+#[cfg(feature = "transport")]
+#[tokio::test]
+async fn test_refreshed_on_subscription_events() {
+    let state = account(100, 10);
+    let address = state.get_addr().unwrap().clone();
+    let (block_id, proof) = block_with_account(&state, 1);
+    let transport = TestTransport { account: state.clone(), proof };
+    let cache = AccountCache::with_storage(Box::<MemoryStorage>::default());
+    cache.trust_block(block_id);
+
+    let flows = crate::flows::Flows::new(&transport);
+    let abi = include_str!("data/SafeMultisigWallet.abi.json");
+    let mut subscription = flows.subscribe_events(&address, abi, 0);
+    assert!(cache.refresh_on_events(&mut subscription).await.unwrap().is_empty());
+    assert_eq!(cache.get(&address).unwrap(), Some(state.clone()));
+
+    // the state older than the seen transactions is refreshed without events
+    cache.remove(&address).unwrap();
+    assert!(cache.refresh_on_events(&mut subscription).await.unwrap().is_empty());
+    assert_eq!(cache.get(&address).unwrap(), Some(state));
+}
+
+#[cfg(feature = "transport")]
+#[tokio::test]
+async fn test_accounts_verified_against_trust_store() {
+    let state = account(100, 10);
+    let addresses = [state.get_addr().unwrap().clone()];
+    let (block_id, proof) = block_with_account(&state, 1);
+    let trust_store = TrustStore::new();
+    trust_store.trust_block(block_id);
+
+    let transport = TestTransport { account: state.clone(), proof: proof.clone() };
+    let verified = transport.get_accounts_verified(&addresses, None).await.unwrap();
+    assert_eq!(verified[0].verification, AccountVerification::Skipped);
+    let verified = transport.get_accounts_verified(&addresses, Some(&trust_store)).await.unwrap();
+    assert_eq!(verified[0].verification, AccountVerification::Verified);
+    assert_eq!(verified[0].account, Some(state));
+
+    // the endpoint returns the tampered state with the proof of the real one
+    let tampered = TestTransport { account: account(1_000_000, 10), proof };
+    let verified = tampered.get_accounts_verified(&addresses, Some(&trust_store)).await.unwrap();
+    assert!(matches!(verified[0].verification, AccountVerification::Failed(_)));
+
+    // proofs of blocks out of the trust store are not accepted
+    let (_, proof) = block_with_account(&account(100, 10), 2);
+    let untrusted = TestTransport { account: account(100, 10), proof };
+    let verified = untrusted.get_accounts_verified(&addresses, Some(&trust_store)).await.unwrap();
+    assert!(matches!(verified[0].verification, AccountVerification::Failed(_)));
+}
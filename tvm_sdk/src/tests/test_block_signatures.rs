@@ -0,0 +1,110 @@
+// Copyright 2018-2021 TON Labs LTD.
+//
+// Licensed under the SOFTWARE EVALUATION License (the "License"); you may not
+// use this file except in compliance with the License.
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific TON DEV software governing permissions and
+// limitations under the License.
+
+use tvm_block::BlockInfo;
+use tvm_block::BlockSignatures;
+use tvm_block::CryptoSignature;
+use tvm_block::Serializable;
+use tvm_block::ShardIdent;
+use tvm_block::SigPubKey;
+use tvm_block::ValidatorBaseInfo;
+use tvm_types::Ed25519PrivateKey;
+use tvm_types::ed25519_create_private_key;
+
+use super::*;
+
+const GEN_UTIME: u32 = 1_700_000_000;
+const CC_SEQNO: u32 = 7;
+
+fn validator_keys() -> Vec<Ed25519PrivateKey> {
+    (1..=4).map(|seed| ed25519_create_private_key(&[seed; 32]).unwrap()).collect()
+}
+
+fn validator_set(keys: &[Ed25519PrivateKey]) -> ValidatorSet {
+    let list = keys
+        .iter()
+        .map(|key| {
+            let public_key = SigPubKey::from_bytes(&key.verifying_key()).unwrap();
+            ValidatorDescr::with_params(public_key, 1, None, None)
+        })
+        .collect();
+    ValidatorSet::new(GEN_UTIME - 100, GEN_UTIME + 100, 4, list).unwrap()
+}
+
+// Proof of a masterchain block signed by `signers`, indexes of `keys`
+fn block_proof(keys: &[Ed25519PrivateKey], signers: &[usize], weight: u64) -> Vec<u8> {
+    let mut info = BlockInfo::new();
+    info.set_gen_utime(GEN_UTIME.into());
+    let mut block = Block::default();
+    block.write_info(&info).unwrap();
+    let cell = block.serialize().unwrap();
+    let info_hash = block.info_cell().repr_hash();
+    let root = MerkleProof::create(&cell, |hash| hash == &cell.repr_hash() || hash == &info_hash)
+        .unwrap()
+        .serialize()
+        .unwrap();
+    let block_id = BlockIdExt::with_params(
+        ShardIdent::masterchain(),
+        1,
+        cell.repr_hash(),
+        UInt256::from([0x33; 32]),
+    );
+
+    let (_, hash_short) = validator_set(keys)
+        .calc_subset(
+            &CatchainConfig::default(),
+            block_id.shard().shard_prefix_with_tag(),
+            block_id.shard().workchain_id(),
+            CC_SEQNO,
+            GEN_UTIME.into(),
+        )
+        .unwrap();
+    let data = Block::build_data_for_sign(block_id.root_hash(), block_id.file_hash());
+    let mut pure = BlockSignaturesPure::with_weight(weight);
+    for &index in signers {
+        let public_key = SigPubKey::from_bytes(&keys[index].verifying_key()).unwrap();
+        let node_id_short =
+            ValidatorDescr::with_params(public_key, 1, None, None).compute_node_id_short();
+        let sign = CryptoSignature::from_bytes(&keys[index].sign(&data)).unwrap();
+        pure.add_sigpair(CryptoSignaturePair::with_params(node_id_short, sign));
+    }
+    let signatures =
+        BlockSignatures::with_params(ValidatorBaseInfo::with_params(hash_short, CC_SEQNO), pure);
+    BlockProof::with_params(block_id, root, Some(signatures)).write_to_bytes().unwrap()
+}
+
+#[test]
+fn test_valid_proof() {
+    let keys = validator_keys();
+    let check =
+        verify_block_signatures(&block_proof(&keys, &[0, 1, 2], 3), &validator_set(&keys)).unwrap();
+    assert_eq!(check.signatures, 3);
+    assert_eq!(check.signed_weight, 3);
+    assert_eq!(check.total_weight, 4);
+
+    // 2 of 4 is not enough
+    assert!(
+        verify_block_signatures(&block_proof(&keys, &[0, 1], 2), &validator_set(&keys)).is_err()
+    );
+}
+
+#[test]
+fn test_duplicated_signature_counted_once() {
+    let keys = validator_keys();
+    let proof = block_proof(&keys, &[0, 1, 1], 3);
+    assert!(verify_block_signatures(&proof, &validator_set(&keys)).is_err());
+
+    let check =
+        verify_block_signatures(&block_proof(&keys, &[0, 1, 2, 1], 3), &validator_set(&keys))
+            .unwrap();
+    assert_eq!(check.signatures, 4);
+    assert_eq!(check.signed_weight, 3);
+}
@@ -0,0 +1,268 @@
+// Copyright 2018-2021 TON Labs LTD.
+//
+// Licensed under the SOFTWARE EVALUATION License (the "License"); you may not
+// use this file except in compliance with the License.
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific TON DEV software governing permissions and
+// limitations under the License.
+
+use serde_json::json;
+use tvm_types::ed25519_create_private_key;
+use tvm_types::ed25519_verify;
+
+use super::*;
+use crate::LocalSigner;
+
+const MULTISIG_ABI: &str = include_str!("data/SafeMultisigWallet.abi.json");
+const SIGNATURE_ID: i32 = -239;
+
+fn signer() -> LocalSigner {
+    LocalSigner::new(ed25519_create_private_key(&[1; 32]).unwrap())
+}
+
+fn address() -> MsgAddressInt {
+    MsgAddressInt::with_standart(None, 0, [0x11; 32].into()).unwrap()
+}
+
+// Call with all header parameters set, so messages are reproducible
+fn call(func: &str, input: &str) -> FunctionCallSet {
+    let header = json!({
+        "pubkey": hex::encode(Signer::public_key(&signer()).unwrap()),
+        "time": 1_700_000_000_000u64,
+        "expire": 1_700_000_060,
+    });
+    FunctionCallSet {
+        func: func.to_owned(),
+        header: Some(header.to_string()),
+        input: input.to_owned(),
+        abi: MULTISIG_ABI.to_owned(),
+    }
+}
+
+fn signature(message: &SdkMessage) -> Vec<u8> {
+    let mut body = message.message.body().unwrap();
+    assert!(body.get_next_bit().unwrap());
+    body.get_next_bytes(64).unwrap()
+}
+
+#[test]
+fn test_data_to_sign_prefixed_with_signature_id() {
+    let options = SigningOptions { signature_id: Some(SIGNATURE_ID) };
+    assert_eq!(options.data_to_sign(vec![1, 2]), vec![0xFF, 0xFF, 0xFF, 0x11, 1, 2]);
+    assert_eq!(SigningOptions::default().data_to_sign(vec![1, 2]), vec![1, 2]);
+
+    let call = call("acceptTransfer", r#"{"payload": ""}"#);
+    let plain =
+        Contract::get_call_message_bytes_for_signing(address(), MsgAddressExt::default(), &call)
+            .unwrap();
+    let prefixed = Contract::get_call_message_bytes_for_signing_with_options(
+        address(),
+        MsgAddressExt::default(),
+        &call,
+        options,
+    )
+    .unwrap();
+    assert_eq!(prefixed.message, plain.message);
+    assert_eq!(prefixed.data_to_sign, options.data_to_sign(plain.data_to_sign));
+
+    let constructor = call_constructor();
+    let image = ContractImage::new().unwrap();
+    let plain = Contract::get_deploy_message_bytes_for_signing_ref(
+        &constructor,
+        &image,
+        0,
+        MsgAddressExt::default(),
+    )
+    .unwrap();
+    let prefixed = Contract::get_deploy_message_bytes_for_signing_with_options(
+        &constructor,
+        &image,
+        0,
+        MsgAddressExt::default(),
+        options,
+    )
+    .unwrap();
+    assert_eq!(prefixed.message, plain.message);
+    assert_eq!(prefixed.data_to_sign, options.data_to_sign(plain.data_to_sign));
+}
+
+fn call_constructor() -> FunctionCallSet {
+    let owners = format!(r#"{{"owners": ["0x{}"], "reqConfirms": 1}}"#, hex::encode([7; 32]));
+    call("constructor", &owners)
+}
+
+#[test]
+fn test_messages_signed_with_signature_id() {
+    let options = SigningOptions { signature_id: Some(SIGNATURE_ID) };
+    let signer = signer();
+    let public_key = Signer::public_key(&signer).unwrap();
+
+    let call = call("acceptTransfer", r#"{"payload": ""}"#);
+    let to_sign = Contract::get_call_message_bytes_for_signing_with_options(
+        address(),
+        MsgAddressExt::default(),
+        &call,
+        options,
+    )
+    .unwrap();
+    let message = Contract::construct_call_ext_in_message_with_policy(
+        address(),
+        MsgAddressExt::default(),
+        &call,
+        Some(&signer),
+        &ExpirationPolicy::new(60),
+        options,
+    )
+    .unwrap();
+    ed25519_verify(&public_key, &to_sign.data_to_sign, &signature(&message)).unwrap();
+
+    let constructor = call_constructor();
+    let image = ContractImage::new().unwrap();
+    let to_sign = Contract::get_deploy_message_bytes_for_signing_with_options(
+        &constructor,
+        &image,
+        0,
+        MsgAddressExt::default(),
+        options,
+    )
+    .unwrap();
+    let message = Contract::construct_deploy_message_with_options(
+        &constructor,
+        &image,
+        Some(&signer),
+        0,
+        MsgAddressExt::default(),
+        options,
+    )
+    .unwrap();
+    ed25519_verify(&public_key, &to_sign.data_to_sign, &signature(&message)).unwrap();
+    // the signature without the id doesn't match
+    let unprefixed = &to_sign.data_to_sign[4..];
+    assert!(ed25519_verify(&public_key, unprefixed, &signature(&message)).is_err());
+}
+
+#[test]
+fn test_builder_signs_as_constructors() {
+    let options = SigningOptions { signature_id: Some(SIGNATURE_ID) };
+    let signer = signer();
+    let call = call("acceptTransfer", r#"{"payload": ""}"#);
+    let built = MessageBuilder::new()
+        .dst(address())
+        .body(call.clone())
+        .sign_with(&signer)
+        .signing_options(options)
+        .build()
+        .unwrap();
+    let constructed = Contract::construct_call_ext_in_message_with_options(
+        address(),
+        MsgAddressExt::default(),
+        &call,
+        Some(&signer),
+        options,
+    )
+    .unwrap();
+    assert_eq!(built.id, constructed.id);
+}
+
+#[cfg(feature = "secp256k1")]
+#[test]
+fn test_message_signed_by_secp256k1_key() {
+    let key = SigningKey::Secp256k1(secp256k1::SecretKey::from_slice(&[1; 32]).unwrap());
+    let call = FunctionCallSet {
+        header: Some(r#"{"time": 1700000000000, "expire": 1700000060}"#.to_owned()),
+        ..call("acceptTransfer", r#"{"payload": ""}"#)
+    };
+    let to_sign =
+        Contract::get_call_message_bytes_for_signing(address(), MsgAddressExt::default(), &call)
+            .unwrap();
+    let message = Contract::construct_call_ext_in_message_json(
+        address(),
+        MsgAddressExt::default(),
+        &call,
+        Some(&key),
+    )
+    .unwrap();
+
+    let secp = secp256k1::Secp256k1::verification_only();
+    let public_key = secp256k1::PublicKey::from_slice(&key.public_key().unwrap()).unwrap();
+    let signature = secp256k1::ecdsa::Signature::from_compact(&signature(&message)).unwrap();
+    let digest =
+        secp256k1::Message::from_slice(&tvm_types::sha256_digest(&to_sign.data_to_sign)).unwrap();
+    secp.verify_ecdsa(&digest, &signature, &public_key).unwrap();
+}
+
+#[test]
+fn test_message_signed_in_body_by_bls_key() {
+    let options = SigningOptions { signature_id: Some(SIGNATURE_ID) };
+    let (public_key, secret_key) = tvm_types::bls::gen_bls_key_pair().unwrap();
+    let key = SigningKey::Bls(secret_key);
+    let call = call("acceptTransfer", r#"{"payload": "0102"}"#);
+    let message = Contract::construct_call_ext_in_message_signed_in_body(
+        address(),
+        MsgAddressExt::default(),
+        &call,
+        &key,
+        "payload",
+        options,
+    )
+    .unwrap();
+
+    let unsigned = FunctionCallSet { input: r#"{"payload": ""}"#.to_owned(), ..call.clone() };
+    let to_sign = Contract::get_call_message_bytes_for_signing_with_options(
+        address(),
+        MsgAddressExt::default(),
+        &unsigned,
+        options,
+    )
+    .unwrap();
+    let signature = key.sign(&to_sign.data_to_sign).unwrap();
+    assert!(
+        tvm_types::bls::verify(
+            &signature.clone().try_into().unwrap(),
+            &to_sign.data_to_sign,
+            &public_key
+        )
+        .unwrap()
+    );
+    let signed = FunctionCallSet {
+        input: json!({ "payload": hex::encode(signature) }).to_string(),
+        ..call.clone()
+    };
+    let expected = Contract::construct_call_ext_in_message_json(
+        address(),
+        MsgAddressExt::default(),
+        &signed,
+        None,
+    )
+    .unwrap();
+    assert_eq!(message.id, expected.id);
+    // BLS signature doesn't fit ABI signature
+    assert!(
+        Contract::construct_call_ext_in_message_json(
+            address(),
+            MsgAddressExt::default(),
+            &call,
+            Some(&key),
+        )
+        .is_err()
+    );
+}
+
+#[test]
+fn test_image_from_state_init_limited() {
+    let code = BuilderData::with_raw(vec![0xAB; 4], 32).unwrap().into_cell().unwrap();
+    let data = BuilderData::with_raw(vec![0x01; 8], 64).unwrap().into_cell().unwrap();
+    let state_init = StateInit { code: Some(code), data: Some(data), ..Default::default() };
+    let boc = state_init.write_to_bytes().unwrap();
+    let read = |max_cells, max_size| {
+        ContractImage::from_state_init_limited(&mut std::io::Cursor::new(&boc), max_cells, max_size)
+    };
+
+    let image = read(3, boc.len() as u64).unwrap();
+    assert_eq!(image.state_init(), state_init);
+    assert!(read(2, boc.len() as u64).is_err());
+    assert!(read(3, boc.len() as u64 - 1).is_err());
+}
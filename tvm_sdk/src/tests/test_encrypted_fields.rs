@@ -0,0 +1,60 @@
+// Copyright 2018-2021 TON Labs LTD.
+//
+// Licensed under the SOFTWARE EVALUATION License (the "License"); you may not
+// use this file except in compliance with the License.
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific TON DEV software governing permissions and
+// limitations under the License.
+
+use serde_json::json;
+use tvm_types::ed25519_create_private_key;
+
+use super::*;
+
+const MULTISIG_ABI: &str = include_str!("data/SafeMultisigWallet.abi.json");
+
+fn owner_key(seed: u8) -> Ed25519PrivateKey {
+    ed25519_create_private_key(&[seed; 32]).unwrap()
+}
+
+#[test]
+fn test_round_trip() {
+    let owner = Arc::new(SealedBoxCipher::for_owner(owner_key(1)));
+    let sender = SealedBoxCipher::for_recipient(owner_key(1).verifying_key());
+    let fields = EncryptedFields::new(owner).field("payload");
+
+    let call = FunctionCallSet {
+        func: "acceptTransfer".to_owned(),
+        header: None,
+        input: json!({"payload": {"bid": 100}}).to_string(),
+        abi: MULTISIG_ABI.to_owned(),
+    };
+    let encrypted = fields.encrypt_call(&call).unwrap();
+    assert!(!encrypted.input.contains("bid"));
+    let params = fields.decrypt_params(&encrypted.input).unwrap();
+    assert_eq!(serde_json::from_str::<Value>(&params).unwrap(), json!({"payload": {"bid": 100}}));
+
+    let ciphertext = sender.encrypt("payload", b"100").unwrap();
+    assert_eq!(fields.cipher.decrypt("payload", &ciphertext).unwrap(), b"100");
+    assert!(sender.decrypt("payload", &ciphertext).is_err());
+}
+
+#[test]
+fn test_tampered_ciphertext_rejected() {
+    let cipher = SealedBoxCipher::for_owner(owner_key(1));
+    let ciphertext = cipher.encrypt("payload", b"{\"bid\":100}").unwrap();
+
+    for index in [0, PUBLIC_KEY_LEN, PUBLIC_KEY_LEN + TAG_LEN, ciphertext.len() - 1] {
+        let mut tampered = ciphertext.clone();
+        tampered[index] ^= 1;
+        assert!(cipher.decrypt("payload", &tampered).is_err());
+    }
+    assert!(cipher.decrypt("payload", &ciphertext[..PUBLIC_KEY_LEN + TAG_LEN - 1]).is_err());
+    // the tag is bound to the field and to the recipient key
+    assert!(cipher.decrypt("other", &ciphertext).is_err());
+    assert!(SealedBoxCipher::for_owner(owner_key(2)).decrypt("payload", &ciphertext).is_err());
+    assert_eq!(cipher.decrypt("payload", &ciphertext).unwrap(), b"{\"bid\":100}");
+}
@@ -0,0 +1,206 @@
+// Copyright 2018-2021 TON Labs LTD.
+//
+// Licensed under the SOFTWARE EVALUATION License (the "License"); you may not
+// use this file except in compliance with the License.
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific TON DEV software governing permissions and
+// limitations under the License.
+
+use tvm_block::CurrencyCollection;
+use tvm_block::ExternalInboundMessageHeader;
+use tvm_block::InternalMessageHeader;
+use tvm_block::MsgAddressInt;
+use tvm_block::StateInit;
+
+use super::*;
+use crate::Contract;
+
+const BLOCK_TIME: u32 = 1_700_000_000;
+
+fn config() -> BlockchainConfig {
+    let config = ConfigParams::construct_from_bytes(include_bytes!("data/config.boc")).unwrap();
+    BlockchainConfig::with_config(config).unwrap()
+}
+
+fn address() -> MsgAddressInt {
+    MsgAddressInt::with_standart(None, 0, [0x11; 32].into()).unwrap()
+}
+
+fn code(hex: &str) -> Cell {
+    let bytes = hex::decode(hex).unwrap();
+    BuilderData::with_raw(bytes.clone(), bytes.len() * 8).unwrap().into_cell().unwrap()
+}
+
+fn account_boc(code: Cell) -> Vec<u8> {
+    let state_init =
+        StateInit { code: Some(code), data: Some(Cell::default()), ..Default::default() };
+    let balance = CurrencyCollection::with_grams(10_000_000_000);
+    let account =
+        Account::active_by_init_code_hash(address(), balance, 0, state_init, false).unwrap();
+    account.write_to_bytes().unwrap()
+}
+
+fn internal_message(value: u64) -> TvmMessage {
+    let source = MsgAddressInt::with_standart(None, 0, [0x22; 32].into()).unwrap();
+    let header = InternalMessageHeader::with_addresses(
+        source,
+        address(),
+        CurrencyCollection::with_grams(value),
+    );
+    TvmMessage::with_int_header(header)
+}
+
+fn external_message() -> SdkMessage {
+    let message = TvmMessage::with_ext_in_header(ExternalInboundMessageHeader {
+        dst: address(),
+        ..Default::default()
+    });
+    sdk_message(message)
+}
+
+fn sdk_message(message: TvmMessage) -> SdkMessage {
+    let (serialized_message, id) = Contract::serialize_message(&message).unwrap();
+    SdkMessage { id, serialized_message, message, address: address() }
+}
+
+fn balance(account_boc: &[u8]) -> u128 {
+    Account::construct_from_bytes(account_boc).unwrap().balance().unwrap().grams.as_u128()
+}
+
+// ACCEPT
+const ACCEPT: &str = "f800";
+// DROP PUSHINT 42: drops the method id and returns 42
+const GET_42: &str = "30802a";
+// DROP PUSHINT 10 AGAINEND: loops forever
+const LOOP: &str = "307aeb";
+// NEWC, then PUSHINT 0 SWAP 256 STU four times: 1024 bits don't fit the cell
+const CELL_OVERFLOW: &str = "c87001cbff7001cbff7001cbff7001cbff";
+
+// Stores the random seed of the transaction into the account data:
+// RANDSEED NEWC 256 STU ENDC POPROOT
+const STORE_RAND_SEED: &str = "f826c8cbffc9ed54";
+
+fn context(block_lt: u64, seed: u8) -> ReplayContext {
+    ReplayContext { block_lt, seq_no: 1, rand_seed: UInt256::from([seed; 32]), config: config() }
+}
+
+// Executes the message the way the node does in the block of `context`
+fn record_transaction(
+    message: &TvmMessage,
+    account_boc: &[u8],
+    context: &ReplayContext,
+) -> TvmTransaction {
+    let params = ExecuteParams {
+        block_unixtime: BLOCK_TIME,
+        block_lt: context.block_lt,
+        seq_no: context.seq_no,
+        last_tr_lt: Arc::new(AtomicU64::new(context.block_lt)),
+        seed_block: context.rand_seed.clone(),
+        ..ExecuteParams::default()
+    };
+    let mut account = tvm_types::boc::read_single_root_boc(account_boc).unwrap();
+    let executor = OrdinaryTransactionExecutor::new(context.config.clone());
+    executor.execute_with_libs_and_params(Some(message), &mut account, params).unwrap().0
+}
+
+#[test]
+fn test_replay_recorded_transaction() {
+    let account = account_boc(code(STORE_RAND_SEED));
+    let recorded =
+        record_transaction(&internal_message(1_000_000_000), &account, &context(5_000, 7));
+    assert!(recorded.logical_time() >= 5_000);
+    let executor = LocalExecutor::new(config());
+
+    let report = executor.replay_transaction(&recorded, &account, &context(5_000, 7)).unwrap();
+    assert!(report.matches(), "{:?}", report.divergences);
+    assert_eq!(report.replayed.exit_code, Some(0));
+
+    let report = executor.replay_transaction(&recorded, &account, &context(5_000, 8)).unwrap();
+    let fields = report.divergences.iter().map(|d| d.field.as_str()).collect::<Vec<_>>();
+    assert_eq!(fields, ["new_state"]);
+
+    let report = executor.replay_transaction(&recorded, &account, &context(1_000, 7)).unwrap();
+    assert!(report.divergences.iter().any(|d| d.field == "lt"));
+
+    let other_account = account_boc(code("ed54"));
+    assert!(executor.replay_transaction(&recorded, &other_account, &context(5_000, 7)).is_err());
+}
+
+#[test]
+fn test_fees_estimated() {
+    let executor = LocalExecutor::new(config());
+    let account = account_boc(code(ACCEPT));
+    let result = executor.execute(&external_message(), &account, BLOCK_TIME, 1_000).unwrap();
+
+    assert_eq!(result.exit_code, Some(0));
+    assert!(!result.aborted);
+    assert!(result.exhausted.is_none());
+    assert!(result.gas_used > 0);
+    assert!(result.fees.gas_fee > 0);
+    assert_eq!(
+        balance(&account) - balance(&result.account),
+        result.fees.total_account_fees as u128
+    );
+    assert!(result.transaction.logical_time() >= 1_000);
+
+    // the external message is not accepted without ACCEPT
+    let result = executor.execute(&external_message(), &account_boc(code("")), BLOCK_TIME, 1_000);
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_get_method_run() {
+    let account = account_boc(code(GET_42));
+    assert_eq!(run_get_method(&account, "get", &[]).unwrap(), [json!("42")]);
+    assert_eq!(run_get_method(&account, "get", &[json!(-1)]).unwrap(), [json!("-1"), json!("42")]);
+
+    let failing = account_boc(code("f23c"));
+    assert!(run_get_method(&failing, "get", &[]).is_err());
+}
+
+#[test]
+fn test_get_method_gas_raised_and_partial() {
+    let gas = GetMethodGas { initial: 10, cap: 10_000, factor: 10, partial: false };
+    let output = run_get_method_with_gas(&account_boc(code(GET_42)), "get", &[], &gas).unwrap();
+    assert!(output.complete);
+    assert_eq!(output.gas_limit, 100);
+    assert_eq!(output.stack, [json!("42")]);
+
+    let account = account_boc(code(LOOP));
+    assert!(run_get_method_with_gas(&account, "loop", &[], &gas).is_err());
+    let partial = GetMethodGas { partial: true, ..gas };
+    let output = run_get_method_with_gas(&account, "loop", &[], &partial).unwrap();
+    assert!(!output.complete);
+    assert_eq!(output.gas_limit, 10_000);
+    assert!(output.gas_used >= 10_000);
+}
+
+#[test]
+fn test_resource_exhausted_classified() {
+    let executor = LocalExecutor::new(config()).with_trace(4);
+    let execute = |code_hex: &str| {
+        let message = sdk_message(internal_message(10_000_000));
+        executor.execute(&message, &account_boc(code(code_hex)), BLOCK_TIME, 1_000).unwrap()
+    };
+
+    let result = execute(LOOP);
+    assert!(result.aborted);
+    let exhausted = result.exhausted.unwrap();
+    assert_eq!(exhausted.resource, VmResource::Gas);
+    assert_eq!(exhausted.exit_code, result.exit_code.unwrap());
+    assert!(exhausted.limit.unwrap() > 0);
+    assert!(exhausted.reached.unwrap() >= exhausted.limit.unwrap());
+    assert_eq!(exhausted.trace.len(), 4);
+
+    let result = execute(CELL_OVERFLOW);
+    assert!(result.aborted);
+    let exhausted = result.exhausted.unwrap();
+    assert_eq!(exhausted.resource, VmResource::Cell);
+    assert_eq!(exhausted.exit_code, ExceptionCode::CellOverflow as i32);
+    assert!(exhausted.limit.is_none());
+
+    assert!(execute("").exhausted.is_none());
+}
@@ -0,0 +1,281 @@
+// Copyright 2018-2021 TON Labs LTD.
+//
+// Licensed under the SOFTWARE EVALUATION License (the "License"); you may not
+// use this file except in compliance with the License.
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific TON DEV software governing permissions and
+// limitations under the License.
+
+use std::path::Path;
+use std::path::PathBuf;
+
+use serde_json::json;
+
+use super::*;
+
+fn export_path(name: &str, extension: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!(
+        "tvm-sdk-export-{}-{}.{}",
+        name,
+        std::process::id(),
+        extension
+    ));
+    let _ = std::fs::remove_file(&path);
+    path
+}
+
+fn schema() -> ExportSchema {
+    ExportSchema {
+        columns: vec![
+            ExportColumn::new("name", ColumnType::Text),
+            ExportColumn::new("value", ColumnType::Int),
+            ExportColumn::new("active", ColumnType::Bool),
+            ExportColumn::new("params", ColumnType::Text),
+        ],
+    }
+}
+
+fn rows() -> Vec<ExportRow> {
+    let rows = [
+        json!({ "name": "first", "value": 1, "active": true, "params": { "a": 1 } }),
+        json!({ "name": "second", "value": "0x10", "active": false }),
+        json!({ "value": "-5", "params": "text" }),
+    ];
+    rows.into_iter().map(|row| row.as_object().unwrap().clone()).collect()
+}
+
+// Rows as they are read back: missing values are nulls, integers are
+// normalized and non-string text values are JSON encoded
+fn expected_rows() -> Vec<Value> {
+    vec![
+        json!({ "name": "first", "value": 1, "active": true, "params": "{\"a\":1}" }),
+        json!({ "name": "second", "value": 16, "active": false, "params": null }),
+        json!({ "name": null, "value": -5, "active": null, "params": "text" }),
+    ]
+}
+
+#[cfg(feature = "export-csv")]
+fn read_csv(path: &Path, schema: &ExportSchema) -> Vec<Value> {
+    let mut reader = csv::Reader::from_path(path).unwrap();
+    let headers = reader.headers().unwrap().clone();
+    assert_eq!(
+        headers.iter().collect::<Vec<_>>(),
+        schema.columns.iter().map(|column| column.name.as_str()).collect::<Vec<_>>()
+    );
+    let mut rows = vec![];
+    for record in reader.records() {
+        let record = record.unwrap();
+        let mut row = ExportRow::new();
+        for (column, cell) in schema.columns.iter().zip(record.iter()) {
+            let value = match column.kind {
+                _ if cell.is_empty() => Value::Null,
+                ColumnType::Bool => cell.parse::<bool>().unwrap().into(),
+                ColumnType::Int => cell.parse::<i64>().unwrap().into(),
+                ColumnType::Text => cell.into(),
+            };
+            row.insert(column.name.clone(), value);
+        }
+        rows.push(Value::Object(row));
+    }
+    rows
+}
+
+#[cfg(feature = "export-parquet")]
+fn read_parquet(path: &Path) -> Vec<Value> {
+    use parquet::file::reader::FileReader;
+    use parquet::file::reader::SerializedFileReader;
+    use parquet::record::Field;
+
+    let reader = SerializedFileReader::new(std::fs::File::open(path).unwrap()).unwrap();
+    let mut rows = vec![];
+    for row in reader.get_row_iter(None).unwrap() {
+        let mut values = ExportRow::new();
+        for (name, field) in row.unwrap().get_column_iter() {
+            let value = match field {
+                Field::Null => Value::Null,
+                Field::Bool(b) => (*b).into(),
+                Field::Long(int) => (*int).into(),
+                Field::Str(text) => text.as_str().into(),
+                other => panic!("Unexpected parquet field {}", other),
+            };
+            values.insert(name.clone(), value);
+        }
+        rows.push(Value::Object(values));
+    }
+    rows
+}
+
+#[cfg(feature = "export-csv")]
+#[test]
+fn test_csv_written_and_read_back() {
+    let path = export_path("rows", "csv");
+    let mut writer = ExportWriter::create(schema(), &path).unwrap();
+    for row in &rows() {
+        writer.write_row(row).unwrap();
+    }
+    assert_eq!(writer.rows(), 3);
+    writer.finish().unwrap();
+
+    assert_eq!(read_csv(&path, &schema()), expected_rows());
+
+    let mut writer = ExportWriter::create(schema(), &path).unwrap();
+    let invalid = json!({ "value": "many" }).as_object().unwrap().clone();
+    assert!(writer.write_row(&invalid).is_err());
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[cfg(feature = "export-parquet")]
+#[test]
+fn test_parquet_written_and_read_back() {
+    let path = export_path("rows", "parquet");
+    let mut writer = ExportWriter::create(schema(), &path).unwrap();
+    for row in &rows() {
+        writer.write_row(row).unwrap();
+    }
+    writer.finish().unwrap();
+
+    assert_eq!(read_parquet(&path), expected_rows());
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_unsupported_export_format() {
+    assert!(ExportWriter::create(schema(), export_path("rows", "xlsx")).is_err());
+}
+
+#[cfg(feature = "transport")]
+mod streams {
+    use tvm_block::ExtOutMessageHeader;
+    use tvm_block::MsgAddressExt;
+    use tvm_block::MsgAddressInt;
+    use tvm_block::TransactionDescr;
+    use tvm_block::TransactionDescrOrdinary;
+    use tvm_types::BuilderData;
+    use tvm_types::Cell;
+    use tvm_types::IBitstring;
+    use tvm_types::SliceData;
+
+    use super::*;
+    use crate::MessageId;
+    use crate::SdkMessage;
+    use crate::transport::Transport;
+
+    const MULTISIG_ABI: &str = include_str!("data/SafeMultisigWallet.abi.json");
+
+    // Serves account transactions from memory
+    struct TestTransport {
+        transactions: Vec<tvm_block::Transaction>,
+    }
+
+    #[async_trait::async_trait]
+    impl Transport for TestTransport {
+        async fn send_message(&self, _message: &SdkMessage) -> Result<()> {
+            unreachable!()
+        }
+
+        async fn wait_for_transaction(
+            &self,
+            _message_id: &MessageId,
+            _timeout_ms: u32,
+        ) -> Result<tvm_block::Transaction> {
+            unreachable!()
+        }
+
+        async fn query_account(
+            &self,
+            _address: &MsgAddressInt,
+        ) -> Result<Option<tvm_block::Account>> {
+            unreachable!()
+        }
+
+        async fn query_account_transactions(
+            &self,
+            _address: &MsgAddressInt,
+            after_lt: u64,
+            limit: u32,
+        ) -> Result<Vec<tvm_block::Transaction>> {
+            let transactions = self.transactions.iter().filter(|tr| tr.logical_time() > after_lt);
+            Ok(transactions.take(limit as usize).cloned().collect())
+        }
+    }
+
+    fn address() -> MsgAddressInt {
+        MsgAddressInt::with_standart(None, 0, [0x11; 32].into()).unwrap()
+    }
+
+    fn cell(bytes: &[u8]) -> Cell {
+        BuilderData::with_raw(bytes.to_vec(), bytes.len() * 8).unwrap().into_cell().unwrap()
+    }
+
+    fn transaction_with_event(lt: u64, payload: u8) -> tvm_block::Transaction {
+        let abi = AbiContract::load(MULTISIG_ABI.as_bytes()).unwrap();
+        let mut body = BuilderData::new();
+        body.append_u32(abi.event("TransferAccepted").unwrap().get_id()).unwrap();
+        body.checked_append_reference(cell(&[payload])).unwrap();
+        let header = ExtOutMessageHeader::with_addresses(address(), MsgAddressExt::default());
+        let mut event = tvm_block::Message::with_ext_out_header(header);
+        event.set_body(SliceData::load_builder(body).unwrap());
+
+        let mut transaction = tvm_block::Transaction::default();
+        transaction.set_logical_time(lt);
+        transaction.add_out_message(&event).unwrap();
+        let description = TransactionDescrOrdinary::default();
+        transaction.write_description(&TransactionDescr::Ordinary(description)).unwrap();
+        transaction
+    }
+
+    // More transactions than fit into a page of the transport
+    fn transport() -> TestTransport {
+        TestTransport {
+            transactions: (1..=60).map(|i| transaction_with_event(i * 10, i as u8)).collect(),
+        }
+    }
+
+    #[cfg(feature = "export-csv")]
+    #[tokio::test]
+    async fn test_account_transactions_exported() {
+        let transport = transport();
+        let path = export_path("transactions", "csv");
+        let schema = ExportSchema::transactions();
+        let mut writer = ExportWriter::create(schema.clone(), &path).unwrap();
+        let last_lt = writer.write_account_transactions(&transport, &address(), 0).await.unwrap();
+        assert_eq!(last_lt, 600);
+        assert_eq!(
+            writer.write_account_transactions(&transport, &address(), 600).await.unwrap(),
+            600
+        );
+        writer.finish().unwrap();
+
+        let rows = read_csv(&path, &schema);
+        assert_eq!(rows.len(), 60);
+        let first = Transaction::try_from(&transport.transactions[0]).unwrap();
+        assert_eq!(rows[0]["id"], first.id.to_string());
+        assert!(rows.iter().all(|row| row["out_msgs_count"] == 1));
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[cfg(feature = "export-parquet")]
+    #[tokio::test]
+    async fn test_events_exported() {
+        let transport = transport();
+        let abi = AbiContract::load(MULTISIG_ABI.as_bytes()).unwrap();
+        let path = export_path("events", "parquet");
+        let schema = ExportSchema::event(&abi, "TransferAccepted").unwrap();
+        let mut writer = ExportWriter::create(schema, &path).unwrap();
+        let mut subscription =
+            crate::flows::Flows::new(&transport).subscribe_events(&address(), MULTISIG_ABI, 0);
+        assert_eq!(writer.write_events(&mut subscription).await.unwrap(), 60);
+        assert_eq!(subscription.last_lt(), 600);
+        assert_eq!(writer.write_events(&mut subscription).await.unwrap(), 0);
+        writer.finish().unwrap();
+
+        let rows = read_parquet(&path);
+        assert_eq!(rows.len(), 60);
+        assert_eq!(rows[0], json!({ "event": "TransferAccepted", "payload": "01" }));
+        assert_eq!(rows[59]["payload"], "3c");
+        std::fs::remove_file(&path).unwrap();
+    }
+}
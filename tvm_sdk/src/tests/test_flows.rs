@@ -0,0 +1,273 @@
+// Copyright 2018-2021 TON Labs LTD.
+//
+// Licensed under the SOFTWARE EVALUATION License (the "License"); you may not
+// use this file except in compliance with the License.
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific TON DEV software governing permissions and
+// limitations under the License.
+
+use std::collections::HashMap;
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+use tvm_block::Account;
+use tvm_block::CurrencyCollection;
+use tvm_block::ExtOutMessageHeader;
+use tvm_block::Serializable;
+use tvm_block::StateInit;
+use tvm_block::TransactionDescr;
+use tvm_block::TransactionDescrOrdinary;
+use tvm_types::SliceData;
+
+use super::*;
+use crate::AbiContract;
+use crate::MessageId;
+use crate::SdkMessage;
+
+const MULTISIG_ABI: &str = include_str!("data/SafeMultisigWallet.abi.json");
+
+// Keeps accounts and transactions in memory, processed messages get
+// transactions aborted as listed in `aborted`, the rest succeed
+#[derive(Default)]
+struct TestTransport {
+    accounts: Mutex<HashMap<String, Account>>,
+    aborted: Mutex<VecDeque<bool>>,
+    processed: Mutex<Vec<SdkMessage>>,
+    transactions: Vec<tvm_block::Transaction>,
+}
+
+impl TestTransport {
+    fn add_account(&self, account: Account) {
+        let address = account.get_addr().unwrap().to_string();
+        self.accounts.lock().unwrap().insert(address, account);
+    }
+}
+
+#[async_trait::async_trait]
+impl Transport for TestTransport {
+    async fn send_message(&self, _message: &SdkMessage) -> Result<()> {
+        unreachable!()
+    }
+
+    async fn wait_for_transaction(
+        &self,
+        _message_id: &MessageId,
+        _timeout_ms: u32,
+    ) -> Result<tvm_block::Transaction> {
+        unreachable!()
+    }
+
+    async fn query_account(&self, address: &MsgAddressInt) -> Result<Option<Account>> {
+        Ok(self.accounts.lock().unwrap().get(&address.to_string()).cloned())
+    }
+
+    async fn query_account_transactions(
+        &self,
+        _address: &MsgAddressInt,
+        after_lt: u64,
+        limit: u32,
+    ) -> Result<Vec<tvm_block::Transaction>> {
+        let transactions = self.transactions.iter().filter(|tr| tr.logical_time() > after_lt);
+        Ok(transactions.take(limit as usize).cloned().collect())
+    }
+
+    async fn process_message(&self, message: &SdkMessage, _timeout_ms: u32) -> Result<Transaction> {
+        self.processed.lock().unwrap().push(message.clone());
+        let aborted = self.aborted.lock().unwrap().pop_front().unwrap_or_default();
+        Ok(Transaction { aborted, ..Default::default() })
+    }
+}
+
+fn address(byte: u8) -> MsgAddressInt {
+    MsgAddressInt::with_standart(None, 0, [byte; 32].into()).unwrap()
+}
+
+fn cell(bytes: &[u8]) -> Cell {
+    BuilderData::with_raw(bytes.to_vec(), bytes.len() * 8).unwrap().into_cell().unwrap()
+}
+
+fn state_init() -> StateInit {
+    StateInit { code: Some(cell(&[0xAB; 4])), data: Some(cell(&[0x01; 8])), ..Default::default() }
+}
+
+fn active_account(address: MsgAddressInt, state_init: StateInit) -> Account {
+    let balance = CurrencyCollection::with_grams(1_000_000_000);
+    Account::active_by_init_code_hash(address, balance, 0, state_init, false).unwrap()
+}
+
+fn transfer(dest: MsgAddressInt) -> Transfer {
+    Transfer { dest, value: 1_000, bounce: true, comment: None }
+}
+
+fn constructor() -> FunctionCallSet {
+    FunctionCallSet {
+        func: "constructor".to_owned(),
+        header: None,
+        input: json!({ "owners": [format!("0x{}", "11".repeat(32))], "reqConfirms": 1 })
+            .to_string(),
+        abi: MULTISIG_ABI.to_owned(),
+    }
+}
+
+#[tokio::test]
+async fn test_bounce_decided_by_destination_status() {
+    let transport = TestTransport::default();
+    transport.add_account(active_account(address(0x22), state_init()));
+    let flows = Flows::new(&transport);
+
+    assert!(flows.prepare_transfer(&transfer(address(0x22))).await.unwrap().bounce);
+    assert!(!flows.prepare_transfer(&transfer(address(0x33))).await.unwrap().bounce);
+    let unbounced = Transfer { bounce: false, ..transfer(address(0x22)) };
+    assert!(!flows.prepare_transfer(&unbounced).await.unwrap().bounce);
+
+    let flows = Flows::new(&transport).with_uninit_destination(UninitDestination::Reject);
+    assert!(flows.prepare_transfer(&transfer(address(0x22))).await.unwrap().bounce);
+    assert!(flows.prepare_transfer(&transfer(address(0x33))).await.is_err());
+
+    let flows = Flows::new(&transport).with_uninit_destination(UninitDestination::Unchecked);
+    assert!(flows.prepare_transfer(&transfer(address(0x33))).await.unwrap().bounce);
+}
+
+#[tokio::test]
+async fn test_deploy_raced_by_another_deploy() {
+    let image = ContractImage::from_cell(state_init().serialize().unwrap()).unwrap();
+    let code = state_init().code.unwrap();
+    let expected = Contract::address_from_code_hash(
+        &code.repr_hash(),
+        code.repr_depth(),
+        state_init().data.unwrap(),
+        0,
+    )
+    .unwrap();
+
+    // not deployed by anyone
+    let transport = TestTransport { aborted: Mutex::new([true].into()), ..Default::default() };
+    let flows = Flows::new(&transport);
+    assert!(flows.deploy_with_outcome(&image, &constructor(), None, 0).await.is_err());
+    assert_eq!(transport.processed.lock().unwrap()[0].address, expected);
+
+    let transport = TestTransport { aborted: Mutex::new([true].into()), ..Default::default() };
+    transport.add_account(active_account(expected.clone(), state_init()));
+    let flows = Flows::new(&transport);
+    match flows.deploy_with_outcome(&image, &constructor(), None, 0).await.unwrap() {
+        DeployOutcome::AlreadyDeployed { address } => assert_eq!(address, expected),
+        _ => panic!("deploy race is not detected"),
+    }
+    // plain deploy fails on the race
+    transport.aborted.lock().unwrap().push_back(true);
+    assert!(flows.deploy(&image, &constructor(), None, 0).await.is_err());
+    assert_eq!(transport.processed.lock().unwrap().len(), 2);
+
+    let transport =
+        TestTransport { aborted: Mutex::new([true, false].into()), ..Default::default() };
+    transport.add_account(active_account(expected.clone(), state_init()));
+    let flows = Flows::new(&transport).with_redeploy(RedeployHandling::ResendConstructor);
+    match flows.deploy_with_outcome(&image, &constructor(), None, 0).await.unwrap() {
+        DeployOutcome::ConstructorResent(deployed) => assert_eq!(deployed.address, expected),
+        _ => panic!("constructor is not resent"),
+    }
+    let processed = transport.processed.lock().unwrap();
+    assert!(processed[0].message.state_init().is_some());
+    assert!(processed[1].message.state_init().is_none());
+    assert_eq!(processed[1].address, expected);
+}
+
+fn transaction_with_event(lt: u64, payload: &[u8]) -> tvm_block::Transaction {
+    let abi = AbiContract::load(MULTISIG_ABI.as_bytes()).unwrap();
+    let mut body = BuilderData::new();
+    body.append_u32(abi.event("TransferAccepted").unwrap().get_id()).unwrap();
+    body.checked_append_reference(cell(payload)).unwrap();
+    let header = ExtOutMessageHeader::with_addresses(address(0x11), MsgAddressExt::default());
+    let mut event = tvm_block::Message::with_ext_out_header(header.clone());
+    event.set_body(SliceData::load_builder(body).unwrap());
+    let mut other = tvm_block::Message::with_ext_out_header(header);
+    other.set_body(SliceData::load_cell(cell(&[0xFF; 4])).unwrap());
+
+    let mut transaction = tvm_block::Transaction::default();
+    transaction.set_logical_time(lt);
+    transaction.add_out_message(&event).unwrap();
+    transaction.add_out_message(&other).unwrap();
+    let description = TransactionDescrOrdinary::default();
+    transaction.write_description(&TransactionDescr::Ordinary(description)).unwrap();
+    transaction
+}
+
+#[tokio::test]
+async fn test_events_decoded_from_new_transactions() {
+    let transport = TestTransport {
+        transactions: vec![transaction_with_event(10, &[1]), transaction_with_event(20, &[2])],
+        ..Default::default()
+    };
+    let flows = Flows::new(&transport);
+
+    let mut subscription = flows.subscribe_events(&address(0x11), MULTISIG_ABI, 0);
+    let events = subscription.next_events().await.unwrap();
+    assert_eq!(events.iter().map(|event| event.transaction_lt).collect::<Vec<_>>(), [10, 20]);
+    assert_eq!(events[1].event.function_name, "TransferAccepted");
+    assert_eq!(
+        serde_json::from_str::<serde_json::Value>(&events[1].event.params).unwrap(),
+        json!({ "payload": "02" })
+    );
+    assert_eq!(subscription.last_lt(), 20);
+    assert!(subscription.next_events().await.unwrap().is_empty());
+
+    let mut resumed = flows.subscribe_events(&address(0x11), MULTISIG_ABI, 10);
+    assert_eq!(resumed.next_events().await.unwrap().len(), 1);
+}
+
+const COUNTER_ABI: &str = r#"{
+    "ABI version": 2,
+    "version": "2.3",
+    "header": [],
+    "functions": [],
+    "events": [],
+    "fields": [{"name": "owner", "type": "uint32"}, {"name": "counter", "type": "uint32"}]
+}"#;
+
+fn counter_account(last_lt: u64, owner: u8, counter: u8) -> Account {
+    let data = cell(&[0, 0, 0, owner, 0, 0, 0, counter]);
+    let state_init = StateInit { data: Some(data), ..state_init() };
+    let mut account = active_account(address(0x44), state_init);
+    account.set_last_tr_time(last_lt);
+    account
+}
+
+#[tokio::test]
+async fn test_account_data_changes() {
+    let transport = TestTransport::default();
+    let flows = Flows::new(&transport);
+    let mut subscription = flows.subscribe_account(&address(0x44), COUNTER_ABI).unwrap();
+    assert!(subscription.next_changes().await.unwrap().is_empty());
+    assert!(subscription.last_data().unwrap().is_empty());
+
+    transport.add_account(counter_account(10, 1, 5));
+    let changes = subscription.next_changes().await.unwrap();
+    assert_eq!(
+        changes.iter().map(|change| change.name.as_str()).collect::<Vec<_>>(),
+        ["counter", "owner"]
+    );
+    assert!(changes.iter().all(|change| change.old_value.is_none()));
+
+    // the data is not decoded again without new transactions
+    transport.add_account(counter_account(10, 1, 6));
+    assert!(subscription.next_changes().await.unwrap().is_empty());
+
+    transport.add_account(counter_account(20, 1, 6));
+    assert_eq!(
+        subscription.next_changes().await.unwrap(),
+        [FieldChange {
+            name: "counter".to_owned(),
+            old_value: Some(json!("5")),
+            new_value: Some(json!("6")),
+        }]
+    );
+    assert!(subscription.next_changes().await.unwrap().is_empty());
+
+    transport.accounts.lock().unwrap().clear();
+    let changes = subscription.next_changes().await.unwrap();
+    assert_eq!(changes.len(), 2);
+    assert!(changes.iter().all(|change| change.new_value.is_none()));
+}
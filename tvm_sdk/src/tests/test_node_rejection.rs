@@ -0,0 +1,38 @@
+// Copyright 2018-2021 TON Labs LTD.
+//
+// Licensed under the SOFTWARE EVALUATION License (the "License"); you may not
+// use this file except in compliance with the License.
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific TON DEV software governing permissions and
+// limitations under the License.
+
+use tvm_types::error;
+
+use super::*;
+
+#[test]
+fn test_rejection_classified() {
+    assert_eq!(NodeRejection::classify("WRONG_PRODUCER", ""), NodeRejection::WrongProducer);
+    assert_eq!(
+        NodeRejection::classify("", "Message already exists in the pool"),
+        NodeRejection::AlreadyExists
+    );
+    assert_eq!(
+        NodeRejection::classify("ERROR", "Account not found: 0:00"),
+        NodeRejection::AccountNotFound
+    );
+    assert!(NodeRejection::classify("", "Rate limit exceeded").is_retriable());
+    assert!(!NodeRejection::classify("OTHER", "Invalid message").is_retriable());
+    assert!(NodeRejection::TokenExpired.is_redirect());
+
+    let rejected = error!(SdkError::NodeRejected {
+        code: "THREAD_MISMATCH".to_owned(),
+        msg: "Wrong thread".to_owned()
+    });
+    assert_eq!(NodeRejection::of(&rejected), Some(NodeRejection::ThreadMismatch));
+    let rejected = error!(SdkError::MessageRejected { exit_code: 52, msg: String::new() });
+    assert_eq!(NodeRejection::of(&rejected), None);
+}
@@ -0,0 +1,107 @@
+// Copyright 2018-2021 TON Labs LTD.
+//
+// Licensed under the SOFTWARE EVALUATION License (the "License"); you may not
+// use this file except in compliance with the License.
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific TON DEV software governing permissions and
+// limitations under the License.
+
+use std::path::PathBuf;
+
+use super::*;
+
+fn journal_path(name: &str) -> PathBuf {
+    let path =
+        std::env::temp_dir().join(format!("tvm-sdk-outbox-{}-{}.jsonl", name, std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    path
+}
+
+fn push(outbox: &mut Outbox) -> u64 {
+    let call = FunctionCallSet {
+        func: "touch".to_owned(),
+        header: None,
+        input: "{}".to_owned(),
+        abi: "{}".to_owned(),
+    };
+    outbox.push(MsgAddressInt::default(), call).unwrap()
+}
+
+#[test]
+fn test_state_restored_on_open() {
+    let path = journal_path("state");
+    let mut outbox = Outbox::open(&path, 2).unwrap();
+    let delivered = push(&mut outbox);
+    let failing = push(&mut outbox);
+    let dead = push(&mut outbox);
+    assert!(outbox.delivered(delivered).unwrap());
+    assert!(!outbox.failed(failing, 10, "timeout").unwrap());
+    assert!(!outbox.failed(dead, 10, "timeout").unwrap());
+    assert!(outbox.failed(dead, 20, "rejected").unwrap());
+    drop(outbox);
+
+    let mut outbox = Outbox::open(&path, 2).unwrap();
+    assert_eq!(outbox.pending().map(|entry| entry.id).collect::<Vec<_>>(), vec![failing]);
+    assert_eq!(outbox.get(failing).unwrap().attempts, 1);
+    let letter = outbox.dead_letter(dead).unwrap();
+    assert_eq!(letter.history.len(), 2);
+    assert_eq!(letter.history[1], FailedAttempt { at: 20, error: "rejected".to_owned() });
+
+    outbox.requeue(dead).unwrap();
+    drop(outbox);
+    let outbox = Outbox::open(&path, 2).unwrap();
+    assert_eq!(outbox.get(dead).unwrap().attempts, 0);
+    assert_eq!(outbox.get(dead).unwrap().history.len(), 2);
+    assert_eq!(outbox.dead_letters().count(), 0);
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_ids_not_reused_after_restarts() {
+    let path = journal_path("ids");
+    let mut outbox = Outbox::open(&path, 1).unwrap();
+    assert_eq!(push(&mut outbox), 1);
+    assert_eq!(push(&mut outbox), 2);
+    assert!(outbox.delivered(2).unwrap());
+    assert!(outbox.failed(1, 10, "timeout").unwrap());
+    assert!(outbox.discard(1).unwrap());
+    drop(outbox);
+
+    // the first restart compacts the journal to the next id only
+    drop(Outbox::open(&path, 1).unwrap());
+    let mut outbox = Outbox::open(&path, 1).unwrap();
+    assert_eq!(outbox.pending().count() + outbox.dead_letters().count(), 0);
+    assert_eq!(push(&mut outbox), 3);
+    assert!(!outbox.delivered(2).unwrap());
+    assert!(outbox.failed(1, 20, "stale").is_err());
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_send_failures_classified() {
+    let rejected = |code: &str| {
+        error!(SdkError::NodeRejected { code: code.to_owned(), msg: "Rejected".to_owned() })
+    };
+    let path = journal_path("rejections");
+    let mut outbox = Outbox::open(&path, 3).unwrap();
+    let duplicate = push(&mut outbox);
+    let limited = push(&mut outbox);
+    let missing = push(&mut outbox);
+    assert!(outbox.send_failed(duplicate, 10, &rejected("DUPLICATE_MESSAGE")).unwrap());
+    assert!(!outbox.send_failed(limited, 10, &rejected("LIMIT_EXCEEDED")).unwrap());
+    assert!(!outbox.send_failed(limited, 10, &rejected("MESSAGE_EXPIRED")).unwrap());
+    assert!(outbox.send_failed(missing, 10, &rejected("ACCOUNT_NOT_FOUND")).unwrap());
+    drop(outbox);
+
+    let outbox = Outbox::open(&path, 3).unwrap();
+    assert_eq!(outbox.pending().map(|entry| entry.id).collect::<Vec<_>>(), vec![limited]);
+    assert_eq!(outbox.get(limited).unwrap().attempts, 2);
+    let letter = outbox.dead_letter(missing).unwrap();
+    assert_eq!(letter.attempts, 1);
+    assert!(letter.history[0].error.contains("ACCOUNT_NOT_FOUND"));
+    assert!(outbox.dead_letter(duplicate).is_none());
+    std::fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,65 @@
+// Copyright 2018-2021 TON Labs LTD.
+//
+// Licensed under the SOFTWARE EVALUATION License (the "License"); you may not
+// use this file except in compliance with the License.
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific TON DEV software governing permissions and
+// limitations under the License.
+
+use tvm_block::MsgAddressExt;
+use tvm_block::MsgAddressInt;
+use tvm_block::StateInit;
+use tvm_types::SliceData;
+
+use super::*;
+use crate::FunctionCallSet;
+
+const MULTISIG_ABI: &str = include_str!("data/SafeMultisigWallet.abi.json");
+const EXPIRE: u32 = 1_700_000_060;
+
+fn call_message(input: &str) -> TvmMessage {
+    let call = FunctionCallSet {
+        func: "acceptTransfer".to_owned(),
+        header: Some(format!(r#"{{"time": 1700000000000, "expire": {}}}"#, EXPIRE)),
+        input: input.to_owned(),
+        abi: MULTISIG_ABI.to_owned(),
+    };
+    let address = MsgAddressInt::with_standart(None, 0, [0x11; 32].into()).unwrap();
+    Contract::construct_call_ext_in_message_json(address, MsgAddressExt::default(), &call, None)
+        .unwrap()
+        .message
+}
+
+#[test]
+fn test_resent_body_is_duplicate() {
+    let guard = MemoryReplayGuard::new(16);
+    let message = call_message(r#"{"payload": ""}"#);
+    assert_eq!(
+        check_message(&guard, MULTISIG_ABI, &message, EXPIRE - 60).unwrap(),
+        ReplayStatus::Accepted
+    );
+
+    // the same body with another source and state init has another repr hash
+    let mut resent = message.clone();
+    resent.ext_in_header_mut().unwrap().src =
+        MsgAddressExt::with_extern(SliceData::from_raw(vec![0x22; 32], 256)).unwrap();
+    resent.set_state_init(StateInit::default());
+    assert_ne!(
+        Contract::message_id(&resent, MessageIdKind::Repr).unwrap(),
+        Contract::message_id(&message, MessageIdKind::Repr).unwrap()
+    );
+    assert_eq!(
+        check_message(&guard, MULTISIG_ABI, &resent, EXPIRE - 30).unwrap(),
+        ReplayStatus::Duplicate
+    );
+
+    let other = call_message(r#"{"payload": "0102"}"#);
+    assert_eq!(
+        check_message(&guard, MULTISIG_ABI, &other, EXPIRE - 30).unwrap(),
+        ReplayStatus::Accepted
+    );
+    assert_eq!(check_message(&guard, MULTISIG_ABI, &other, EXPIRE).unwrap(), ReplayStatus::Expired);
+}
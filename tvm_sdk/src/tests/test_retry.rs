@@ -0,0 +1,145 @@
+// Copyright 2018-2021 TON Labs LTD.
+//
+// Licensed under the SOFTWARE EVALUATION License (the "License"); you may not
+// use this file except in compliance with the License.
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific TON DEV software governing permissions and
+// limitations under the License.
+
+use std::sync::atomic::AtomicU32;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+
+use tvm_block::Account;
+use tvm_block::CurrencyCollection;
+
+use super::*;
+use crate::MessageId;
+use crate::SdkMessage;
+
+const MULTISIG_ABI: &str = include_str!("data/SafeMultisigWallet.abi.json");
+
+#[derive(Clone, Copy, PartialEq)]
+enum Outcome {
+    Timeout,
+    // the transport fails, the message may be processed
+    Error,
+    // the message is processed, but the transaction is not found in time
+    TimeoutProcessed,
+    // the node doesn't accept the message with the error code
+    Rejected(&'static str),
+}
+
+struct TestTransport {
+    outcome: Outcome,
+    sent: AtomicU32,
+    last_lt: AtomicU64,
+}
+
+impl TestTransport {
+    fn new(outcome: Outcome) -> Self {
+        Self { outcome, sent: AtomicU32::new(0), last_lt: AtomicU64::new(1_000) }
+    }
+}
+
+#[async_trait::async_trait]
+impl Transport for TestTransport {
+    async fn send_message(&self, _message: &SdkMessage) -> Result<()> {
+        self.sent.fetch_add(1, Ordering::Relaxed);
+        match self.outcome {
+            Outcome::TimeoutProcessed => {
+                self.last_lt.fetch_add(1_000, Ordering::Relaxed);
+            }
+            Outcome::Rejected(code) => fail!(SdkError::NodeRejected {
+                code: code.to_owned(),
+                msg: "Message rejected".to_owned()
+            }),
+            _ => {}
+        }
+        Ok(())
+    }
+
+    async fn wait_for_transaction(
+        &self,
+        message_id: &MessageId,
+        timeout_ms: u32,
+    ) -> Result<tvm_block::Transaction> {
+        match self.outcome {
+            Outcome::Error => {
+                fail!(SdkError::InternalError { msg: "Connection reset".to_owned() })
+            }
+            _ => fail!(SdkError::WaitTimeout { message_id: message_id.to_string(), timeout_ms }),
+        }
+    }
+
+    async fn query_account(&self, address: &MsgAddressInt) -> Result<Option<tvm_block::Account>> {
+        let lt = self.last_lt.load(Ordering::Relaxed);
+        Ok(Some(Account::uninit(address.clone(), lt, 0, CurrencyCollection::with_grams(1))))
+    }
+}
+
+fn call() -> FunctionCallSet {
+    FunctionCallSet {
+        func: "acceptTransfer".to_owned(),
+        header: None,
+        input: r#"{"payload": ""}"#.to_owned(),
+        abi: MULTISIG_ABI.to_owned(),
+    }
+}
+
+async fn send(transport: &TestTransport) -> Result<Transaction> {
+    let address = MsgAddressInt::with_standart(None, 0, [0x11; 32].into()).unwrap();
+    // messages expire as soon as they are sent
+    let policy = ExpirationPolicy::new(0);
+    let backoff = Backoff { initial_delay_ms: 0, ..Default::default() };
+    send_with_retries(
+        transport,
+        &address,
+        &call(),
+        None,
+        &policy,
+        SigningOptions::default(),
+        &backoff,
+    )
+    .await
+}
+
+#[tokio::test]
+async fn test_resent_after_timeout() {
+    let transport = TestTransport::new(Outcome::Timeout);
+    let err = send(&transport).await.unwrap_err();
+    assert!(err.to_string().contains("expired unprocessed 3 times"), "{}", err);
+    assert_eq!(transport.sent.load(Ordering::Relaxed), 3);
+}
+
+#[tokio::test]
+async fn test_not_resent_on_transport_error() {
+    let transport = TestTransport::new(Outcome::Error);
+    let err = send(&transport).await.unwrap_err();
+    assert!(err.to_string().contains("Connection reset"), "{}", err);
+    assert_eq!(transport.sent.load(Ordering::Relaxed), 1);
+}
+
+#[tokio::test]
+async fn test_not_resent_when_account_changed() {
+    let transport = TestTransport::new(Outcome::TimeoutProcessed);
+    let err = send(&transport).await.unwrap_err();
+    assert!(err.to_string().contains("may be processed"), "{}", err);
+    assert_eq!(transport.sent.load(Ordering::Relaxed), 1);
+}
+
+#[tokio::test]
+async fn test_resent_after_retriable_rejection() {
+    let transport = TestTransport::new(Outcome::Rejected("LIMIT_EXCEEDED"));
+    let err = send(&transport).await.unwrap_err();
+    assert!(err.to_string().contains("rejected or expired unprocessed 3 times"), "{}", err);
+    assert_eq!(transport.sent.load(Ordering::Relaxed), 3);
+
+    let transport = TestTransport::new(Outcome::Rejected("ACCOUNT_NOT_FOUND"));
+    let err = send(&transport).await.unwrap_err();
+    assert_eq!(NodeRejection::of(&err), Some(NodeRejection::AccountNotFound));
+    assert_eq!(transport.sent.load(Ordering::Relaxed), 1);
+}
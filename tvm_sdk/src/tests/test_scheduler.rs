@@ -0,0 +1,79 @@
+// Copyright 2018-2021 TON Labs LTD.
+//
+// Licensed under the SOFTWARE EVALUATION License (the "License"); you may not
+// use this file except in compliance with the License.
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific TON DEV software governing permissions and
+// limitations under the License.
+
+use std::io::Write;
+use std::path::PathBuf;
+
+use super::*;
+
+fn journal_path(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!(
+        "tvm-sdk-scheduler-{}-{}.jsonl",
+        name,
+        std::process::id()
+    ));
+    let _ = std::fs::remove_file(&path);
+    path
+}
+
+fn schedule(scheduler: &mut MessageScheduler) -> u64 {
+    let call = FunctionCallSet {
+        func: "touch".to_owned(),
+        header: None,
+        input: "{}".to_owned(),
+        abi: "{}".to_owned(),
+    };
+    scheduler.schedule(MsgAddressInt::default(), call, ScheduleCondition::At(0)).unwrap()
+}
+
+#[test]
+fn test_ids_not_reused_after_restarts() {
+    let path = journal_path("ids");
+    let mut scheduler = MessageScheduler::open(&path).unwrap();
+    assert_eq!(schedule(&mut scheduler), 1);
+    assert_eq!(schedule(&mut scheduler), 2);
+    assert!(scheduler.complete(1).unwrap());
+    assert!(scheduler.complete(2).unwrap());
+    drop(scheduler);
+
+    // the first restart compacts the journal to no pending messages
+    drop(MessageScheduler::open(&path).unwrap());
+    let mut scheduler = MessageScheduler::open(&path).unwrap();
+    assert_eq!(scheduler.pending().count(), 0);
+    assert_eq!(schedule(&mut scheduler), 3);
+    // stale completion of the message sent before
+    assert!(!scheduler.complete(1).unwrap());
+    assert_eq!(scheduler.pending().count(), 1);
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_torn_last_line_dropped() {
+    let path = journal_path("torn");
+    let mut scheduler = MessageScheduler::open(&path).unwrap();
+    schedule(&mut scheduler);
+    drop(scheduler);
+    // crash in the middle of appending
+    let mut file = std::fs::OpenOptions::new().append(true).open(&path).unwrap();
+    file.write_all(br#"{"Completed":"#).unwrap();
+    drop(file);
+
+    let mut scheduler = MessageScheduler::open(&path).unwrap();
+    assert_eq!(scheduler.pending().map(|message| message.id).collect::<Vec<_>>(), vec![1]);
+    assert_eq!(schedule(&mut scheduler), 2);
+    drop(scheduler);
+    assert_eq!(MessageScheduler::open(&path).unwrap().pending().count(), 2);
+
+    // broken records before the last line are not recoverable
+    std::fs::write(&path, "{\n{\"NextId\":3}\n").unwrap();
+    assert!(MessageScheduler::open(&path).is_err());
+    std::fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,138 @@
+// Copyright 2018-2021 TON Labs LTD.
+//
+// Licensed under the SOFTWARE EVALUATION License (the "License"); you may not
+// use this file except in compliance with the License.
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific TON DEV software governing permissions and
+// limitations under the License.
+
+use std::collections::VecDeque;
+
+use super::*;
+use crate::MessageId;
+use crate::transaction::ComputePhase;
+
+enum Outcome {
+    Processed { exit_code: i32, aborted: bool },
+    Rejected(i32),
+    NodeRejected(&'static str),
+}
+
+// Processes messages with the scripted outcomes, the wallet is not deployed
+#[derive(Default)]
+struct TestTransport {
+    outcomes: Mutex<VecDeque<Outcome>>,
+}
+
+impl TestTransport {
+    fn with_outcomes(outcomes: impl IntoIterator<Item = Outcome>) -> Self {
+        Self { outcomes: Mutex::new(outcomes.into_iter().collect()) }
+    }
+}
+
+#[async_trait::async_trait]
+impl Transport for TestTransport {
+    async fn send_message(&self, _message: &SdkMessage) -> Result<()> {
+        Ok(())
+    }
+
+    async fn wait_for_transaction(
+        &self,
+        _message_id: &MessageId,
+        _timeout_ms: u32,
+    ) -> Result<tvm_block::Transaction> {
+        unreachable!()
+    }
+
+    async fn query_account(&self, _address: &MsgAddressInt) -> Result<Option<Account>> {
+        Ok(None)
+    }
+
+    async fn process_message(
+        &self,
+        _message: &SdkMessage,
+        _timeout_ms: u32,
+    ) -> Result<Transaction> {
+        match self.outcomes.lock().unwrap().pop_front().unwrap() {
+            Outcome::Processed { exit_code, aborted } => Ok(Transaction {
+                aborted,
+                compute: ComputePhase { exit_code: Some(exit_code), ..Default::default() },
+                ..Default::default()
+            }),
+            Outcome::Rejected(exit_code) => {
+                fail!(SdkError::MessageRejected { exit_code, msg: "rejected".to_owned() })
+            }
+            Outcome::NodeRejected(code) => {
+                fail!(SdkError::NodeRejected { code: code.to_owned(), msg: "rejected".to_owned() })
+            }
+        }
+    }
+}
+
+fn wallet() -> MsgAddressInt {
+    MsgAddressInt::with_standart(None, 0, [0x11; 32].into()).unwrap()
+}
+
+#[test]
+fn test_mismatch_detected_by_exit_code() {
+    let rejected = |exit_code| error!(SdkError::MessageRejected { exit_code, msg: String::new() });
+    assert!(is_seqno_mismatch_error(&rejected(SEQNO_MISMATCH_EXIT_CODE)));
+    assert!(!is_seqno_mismatch_error(&rejected(133)));
+    assert!(!is_seqno_mismatch_error(&error!(SdkError::InvalidData {
+        msg: "exit code 33".to_owned()
+    })));
+}
+
+#[tokio::test]
+async fn test_seqno_advanced_only_after_committed_transaction() {
+    let transport = TestTransport::with_outcomes([
+        Outcome::Rejected(SEQNO_MISMATCH_EXIT_CODE),
+        Outcome::Processed { exit_code: 0, aborted: false },
+        Outcome::Processed { exit_code: 100, aborted: true },
+    ]);
+    let sender = SeqnoSender::new(&transport);
+    let built = Mutex::new(Vec::new());
+    let build = |seqno| {
+        built.lock().unwrap().push(seqno);
+        Ok(SdkMessage {
+            id: MessageId::default(),
+            serialized_message: Vec::new(),
+            message: tvm_block::Message::default(),
+            address: wallet(),
+        })
+    };
+
+    sender.send(&wallet(), build).await.unwrap();
+    assert_eq!(*built.lock().unwrap(), vec![0, 0]);
+    assert_eq!(sender.predicted_seqno(&wallet()).await, Some(1));
+
+    let transaction = sender.send(&wallet(), build).await.unwrap();
+    assert!(transaction.aborted);
+    assert_eq!(*built.lock().unwrap(), vec![0, 0, 1]);
+    assert_eq!(sender.predicted_seqno(&wallet()).await, None);
+}
+
+#[tokio::test]
+async fn test_resent_after_retriable_node_rejection() {
+    let transport = TestTransport::with_outcomes([
+        Outcome::NodeRejected("LIMIT_EXCEEDED"),
+        Outcome::Processed { exit_code: 0, aborted: false },
+        Outcome::NodeRejected("ACCOUNT_NOT_FOUND"),
+    ]);
+    let sender = SeqnoSender::new(&transport);
+    let build = |_| {
+        Ok(SdkMessage {
+            id: MessageId::default(),
+            serialized_message: Vec::new(),
+            message: tvm_block::Message::default(),
+            address: wallet(),
+        })
+    };
+
+    assert!(!sender.send(&wallet(), build).await.unwrap().aborted);
+    let err = sender.send(&wallet(), build).await.unwrap_err();
+    assert_eq!(NodeRejection::of(&err), Some(NodeRejection::AccountNotFound));
+}
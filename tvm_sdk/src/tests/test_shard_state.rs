@@ -0,0 +1,57 @@
+// Copyright 2018-2021 TON Labs LTD.
+//
+// Licensed under the SOFTWARE EVALUATION License (the "License"); you may not
+// use this file except in compliance with the License.
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific TON DEV software governing permissions and
+// limitations under the License.
+
+use super::*;
+
+fn shard_state(workchain_id: i32, ids: &[u8]) -> ShardState {
+    let mut state =
+        ShardStateUnsplit::with_ident(ShardIdent::with_workchain_id(workchain_id).unwrap());
+    for &id in ids {
+        let address = address_in_workchain(None, workchain_id, [id; 32].into()).unwrap();
+        let account = Account::with_address(address);
+        let shard_account =
+            ShardAccount::with_params(&account, UInt256::default(), 0, None).unwrap();
+        state.insert_account(&UInt256::from([id; 32]), &shard_account).unwrap();
+    }
+    ShardState::with_state(state).unwrap()
+}
+
+fn account_addresses(state: &ShardState, limit: usize) -> Vec<MsgAddressInt> {
+    let mut addresses = Vec::new();
+    state
+        .iterate_accounts(|address, shard_account| {
+            assert_eq!(shard_account.read_account()?.as_struct()?.get_addr(), Some(&address));
+            addresses.push(address);
+            Ok(addresses.len() < limit)
+        })
+        .unwrap();
+    addresses
+}
+
+#[test]
+fn test_accounts_iterated_in_address_order() {
+    let state = shard_state(0, &[0x33, 0x11, 0x22]);
+    let addresses = account_addresses(&state, usize::MAX);
+    let expected = [0x11, 0x22, 0x33]
+        .map(|id| MsgAddressInt::with_standart(None, 0, [id; 32].into()).unwrap());
+    assert_eq!(addresses, expected);
+
+    // stopped by the callback
+    assert_eq!(account_addresses(&state, 2), expected[..2]);
+}
+
+#[test]
+fn test_accounts_of_wide_workchain_have_var_addresses() {
+    let state = shard_state(1000, &[0x11]);
+    let addresses = account_addresses(&state, usize::MAX);
+    assert_eq!(addresses, [MsgAddressInt::with_variant(None, 1000, [0x11; 32].into()).unwrap()]);
+    assert!(state.get_account(&addresses[0]).unwrap().is_some());
+}
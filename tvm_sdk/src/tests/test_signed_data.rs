@@ -0,0 +1,121 @@
+// Copyright 2018-2021 TON Labs LTD.
+//
+// Licensed under the SOFTWARE EVALUATION License (the "License"); you may not
+// use this file except in compliance with the License.
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific TON DEV software governing permissions and
+// limitations under the License.
+
+use tvm_block::CurrencyCollection;
+use tvm_block::Serializable;
+use tvm_block::StateInit;
+use tvm_types::BuilderData;
+use tvm_types::ed25519_create_private_key;
+
+use super::*;
+use crate::LocalSigner;
+
+const CHALLENGE: &[u8] = b"challenge";
+
+fn signer(seed: u8) -> LocalSigner {
+    LocalSigner::new(ed25519_create_private_key(&[seed; 32]).unwrap())
+}
+
+fn key(signer: &LocalSigner) -> PublicKeyData {
+    Signer::public_key(signer).unwrap()
+}
+
+fn address() -> MsgAddressInt {
+    MsgAddressInt::with_standart(None, 0, [0x11; 32].into()).unwrap()
+}
+
+// Account with the data of 32-bit values followed by the keys
+fn account(prefix: &[u32], keys: &[PublicKeyData]) -> Vec<u8> {
+    let mut data = BuilderData::new();
+    for value in prefix {
+        data.append_raw(&value.to_be_bytes(), 32).unwrap();
+    }
+    for key in keys {
+        data.append_raw(key, 256).unwrap();
+    }
+    let state_init = StateInit {
+        code: Some(Default::default()),
+        data: Some(data.into_cell().unwrap()),
+        ..Default::default()
+    };
+    Account::active_by_init_code_hash(
+        address(),
+        CurrencyCollection::with_grams(1),
+        0,
+        state_init,
+        false,
+    )
+    .unwrap()
+    .write_to_bytes()
+    .unwrap()
+}
+
+#[test]
+fn test_ownership_accepted_in_wallet_layout() {
+    let owner = signer(1);
+    let key = key(&owner);
+    let account = account(&[7, 42], &[key]);
+    let signature = sign_address_ownership(&owner, &address(), CHALLENGE).unwrap();
+
+    verify_address_ownership(
+        &account,
+        &address(),
+        WalletLayout::AfterWalletId,
+        &key,
+        &signature,
+        CHALLENGE,
+    )
+    .unwrap();
+    // other challenge
+    assert!(
+        verify_address_ownership(
+            &account,
+            &address(),
+            WalletLayout::AfterWalletId,
+            &key,
+            &signature,
+            b"other",
+        )
+        .is_err()
+    );
+}
+
+#[test]
+fn test_ownership_spoofed_by_key_at_other_position() {
+    let attacker = signer(2);
+    let attacker_key = key(&attacker);
+    // the contract stores the key chosen by the attacker first, e.g. as a
+    // beneficiary, while its owner key is after the seqno
+    let account = account(&[], &[attacker_key]);
+    let signature = sign_address_ownership(&attacker, &address(), CHALLENGE).unwrap();
+
+    assert_eq!(
+        WalletLayout::Leading
+            .read_public_key(
+                &SliceData::load_cell(
+                    Account::construct_from_bytes(&account).unwrap().get_data().unwrap()
+                )
+                .unwrap()
+            )
+            .unwrap(),
+        Some(attacker_key)
+    );
+    let err = verify_address_ownership(
+        &account,
+        &address(),
+        WalletLayout::AfterSeqno,
+        &attacker_key,
+        &signature,
+        CHALLENGE,
+    )
+    .unwrap_err();
+    assert!(err.to_string().contains("is not stored by"), "{}", err);
+}
@@ -0,0 +1,70 @@
+// Copyright 2018-2021 TON Labs LTD.
+//
+// Licensed under the SOFTWARE EVALUATION License (the "License"); you may not
+// use this file except in compliance with the License.
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific TON DEV software governing permissions and
+// limitations under the License.
+
+use tvm_types::BLS_PUBLIC_KEY_LEN;
+use tvm_types::BLS_SIG_LEN;
+use tvm_types::ed25519_create_private_key;
+use tvm_types::ed25519_verify;
+
+use super::*;
+
+const DATA: &[u8] = b"data to sign";
+
+#[test]
+fn test_ed25519_key() {
+    let key = SigningKey::from(ed25519_create_private_key(&[1; 32]).unwrap());
+    assert_eq!(key.scheme(), SignatureScheme::Ed25519);
+    let public_key = Signer::public_key(&key).unwrap();
+    assert_eq!(key.public_key().unwrap(), public_key.to_vec());
+
+    let signature = Signer::sign(&key, DATA).unwrap();
+    assert_eq!(key.sign(DATA).unwrap(), signature.to_vec());
+    ed25519_verify(&public_key, DATA, &signature).unwrap();
+
+    let signer = LocalSigner::new(key);
+    assert_eq!(Signer::scheme(&signer), SignatureScheme::Ed25519);
+    assert_eq!(Signer::sign(&signer, DATA).unwrap(), signature);
+}
+
+#[cfg(feature = "secp256k1")]
+#[test]
+fn test_secp256k1_key() {
+    let key = SigningKey::Secp256k1(secp256k1::SecretKey::from_slice(&[1; 32]).unwrap());
+    assert_eq!(key.scheme(), SignatureScheme::Secp256k1);
+    let public_key = secp256k1::PublicKey::from_slice(&key.public_key().unwrap()).unwrap();
+    assert!(Signer::public_key(&key).is_err());
+
+    // data is hashed regardless of its length
+    let secp = secp256k1::Secp256k1::verification_only();
+    for data in [DATA, &[7; 32]] {
+        let signature = Signer::sign(&key, data).unwrap();
+        let signature = secp256k1::ecdsa::Signature::from_compact(&signature).unwrap();
+        let digest = secp256k1::Message::from_slice(&tvm_types::sha256_digest(data)).unwrap();
+        secp.verify_ecdsa(&digest, &signature, &public_key).unwrap();
+    }
+}
+
+#[test]
+fn test_bls_key() {
+    let (public_key, secret_key) = tvm_types::bls::gen_bls_key_pair().unwrap();
+    let key = SigningKey::Bls(secret_key);
+    assert_eq!(key.scheme(), SignatureScheme::Bls);
+    assert_eq!(key.public_key().unwrap(), public_key.to_vec());
+
+    let signature: [u8; BLS_SIG_LEN] = key.sign(DATA).unwrap().try_into().unwrap();
+    let public_key: &[u8; BLS_PUBLIC_KEY_LEN] = &public_key;
+    assert!(tvm_types::bls::verify(&signature, DATA, public_key).unwrap());
+    assert!(!tvm_types::bls::verify(&signature, b"other data", public_key).unwrap());
+
+    // doesn't fit ABI signature and `pubkey` header
+    assert!(Signer::sign(&key, DATA).is_err());
+    assert!(Signer::public_key(&key).is_err());
+}
@@ -0,0 +1,152 @@
+// Copyright 2018-2021 TON Labs LTD.
+//
+// Licensed under the SOFTWARE EVALUATION License (the "License"); you may not
+// use this file except in compliance with the License.
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific TON DEV software governing permissions and
+// limitations under the License.
+
+use std::path::PathBuf;
+use std::sync::atomic::AtomicU32;
+use std::sync::atomic::Ordering;
+
+use tvm_block::TransactionDescr;
+use tvm_block::TransactionDescrOrdinary;
+use tvm_types::ed25519_create_private_key;
+use tvm_types::error;
+use tvm_types::fail;
+
+use super::*;
+use crate::LocalSigner;
+use crate::MessageId;
+use crate::SdkMessage;
+use crate::error::SdkError;
+
+const MULTISIG_ABI: &str = include_str!("data/SafeMultisigWallet.abi.json");
+const NOW: u32 = 1_700_000_000;
+
+struct FixedClock;
+
+impl Clock for FixedClock {
+    fn now_ms(&self) -> u64 {
+        NOW as u64 * 1000
+    }
+}
+
+// Sends messages, the treasury transaction is aborted if `aborted` is set,
+// otherwise the transaction is never found
+#[derive(Default)]
+struct TestTransport {
+    aborted: bool,
+    sent: AtomicU32,
+}
+
+#[async_trait::async_trait]
+impl Transport for TestTransport {
+    async fn send_message(&self, _message: &SdkMessage) -> Result<()> {
+        self.sent.fetch_add(1, Ordering::Relaxed);
+        Ok(())
+    }
+
+    async fn wait_for_transaction(
+        &self,
+        message_id: &MessageId,
+        timeout_ms: u32,
+    ) -> Result<tvm_block::Transaction> {
+        if !self.aborted {
+            fail!(SdkError::WaitTimeout { message_id: message_id.to_string(), timeout_ms })
+        }
+        let mut transaction = tvm_block::Transaction::default();
+        transaction.write_description(&TransactionDescr::Ordinary(TransactionDescrOrdinary {
+            aborted: true,
+            ..Default::default()
+        }))?;
+        Ok(transaction)
+    }
+
+    async fn query_account(&self, _address: &MsgAddressInt) -> Result<Option<Account>> {
+        Ok(None)
+    }
+}
+
+fn address(byte: u8) -> MsgAddressInt {
+    MsgAddressInt::with_standart(None, 0, [byte; 32].into()).unwrap()
+}
+
+fn journal_path(name: &str) -> PathBuf {
+    let path =
+        std::env::temp_dir().join(format!("tvm-sdk-topup-{}-{}.jsonl", name, std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    path
+}
+
+fn topup_manager<'a>(
+    transport: &'a TestTransport,
+    signer: &'a LocalSigner,
+) -> TopupManager<'a, TestTransport> {
+    let policy =
+        TopupPolicy { max_transfer: 1_000, budget: 10_000, budget_window: 3_600, cooldown: 600 };
+    let mut manager =
+        TopupManager::new(transport, Multisig::new(address(0x11), MULTISIG_ABI), signer, policy)
+            .with_clock(Arc::new(FixedClock));
+    manager.manage(ManagedAccount { address: address(0x22), threshold: 100, target: 500 });
+    manager
+}
+
+#[tokio::test]
+async fn test_transfer_with_unknown_outcome_counted() {
+    let transport = TestTransport::default();
+    let signer = LocalSigner::new(ed25519_create_private_key(&[1; 32]).unwrap());
+    let mut manager = topup_manager(&transport, &signer);
+
+    assert!(manager.on_balance(&address(0x22), 50).await.is_err());
+    assert_eq!(transport.sent.load(Ordering::Relaxed), 1);
+    assert_eq!(manager.history().len(), 1);
+    assert_eq!(manager.remaining_budget(NOW), 10_000 - 450);
+
+    // the transfer may still be processed, so the account is not funded again
+    let (decision, result) = manager.on_balance(&address(0x22), 50).await.unwrap();
+    assert!(matches!(decision, TopupDecision::Denied(_)));
+    assert!(result.is_none());
+    assert_eq!(transport.sent.load(Ordering::Relaxed), 1);
+}
+
+#[tokio::test]
+async fn test_history_restored_on_restart() {
+    let path = journal_path("restart");
+    let transport = TestTransport::default();
+    let signer = LocalSigner::new(ed25519_create_private_key(&[1; 32]).unwrap());
+    let mut manager = topup_manager(&transport, &signer).with_history(&path).unwrap();
+    assert!(manager.on_balance(&address(0x22), 50).await.is_err());
+    drop(manager);
+
+    let manager = topup_manager(&transport, &signer).with_history(&path).unwrap();
+    assert_eq!(
+        manager.history(),
+        &[TopupRecord { address: address(0x22), value: 450, sent_at: NOW }]
+    );
+    assert!(matches!(manager.decide(&address(0x22), 50, NOW), TopupDecision::Denied(_)));
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[tokio::test]
+async fn test_rejected_transfer_not_counted() {
+    let path = journal_path("rejected");
+    let transport = TestTransport { aborted: true, ..Default::default() };
+    let signer = LocalSigner::new(ed25519_create_private_key(&[1; 32]).unwrap());
+    let mut manager = topup_manager(&transport, &signer).with_history(&path).unwrap();
+
+    let (decision, result) = manager.on_balance(&address(0x22), 50).await.unwrap();
+    assert_eq!(decision, TopupDecision::Transfer(450));
+    assert!(result.unwrap().transaction.is_aborted());
+    assert!(manager.history().is_empty());
+    drop(manager);
+
+    let manager = topup_manager(&transport, &signer).with_history(&path).unwrap();
+    assert!(manager.history().is_empty());
+    assert_eq!(manager.decide(&address(0x22), 50, NOW), TopupDecision::Transfer(450));
+    std::fs::remove_file(&path).unwrap();
+}
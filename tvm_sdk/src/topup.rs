@@ -0,0 +1,293 @@
+// Copyright 2018-2021 TON Labs LTD.
+//
+// Licensed under the SOFTWARE EVALUATION License (the "License"); you may not
+// use this file except in compliance with the License.
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific TON DEV software governing permissions and
+// limitations under the License.
+
+//! Automatic funding of service contracts from a treasury wallet.
+//!
+//! `TopupManager` gets balances of managed accounts either pushed by the
+//! caller, e.g. from an accounts subscription, or by polling the transport,
+//! and sends transfers from the treasury multisig to accounts below their
+//! threshold. Transfers are limited by `TopupPolicy`: size of a single
+//! transfer, total spent within a time window and a cooldown per account,
+//! which also keeps the account from being funded twice while the balance
+//! update of the first transfer is on its way. Transfers are recorded
+//! before they are sent and are dropped only when the treasury transaction
+//! is aborted, as a transfer timed out may still be processed. The records
+//! are kept in a journal file given to `with_history`, so limits hold across
+//! restarts.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+
+use tvm_block::Account;
+use tvm_block::MsgAddressInt;
+use tvm_types::Result;
+
+use crate::Clock;
+use crate::Signer;
+use crate::SystemClock;
+use crate::flows::CallResult;
+use crate::flows::Flows;
+use crate::flows::Transfer;
+use crate::journal::Journal;
+use crate::multisig::Multisig;
+use crate::transport::Transport;
+
+/// Account funded by the manager. When the balance falls below `threshold`,
+/// it is topped up to `target`.
+#[derive(Clone, Debug)]
+pub struct ManagedAccount {
+    pub address: MsgAddressInt,
+    pub threshold: u128,
+    pub target: u128,
+}
+
+/// Limits of the treasury spending, values in nanotokens, times in seconds
+#[derive(Clone, Debug)]
+pub struct TopupPolicy {
+    /// Maximal value of a single transfer
+    pub max_transfer: u128,
+    /// Maximal total value of transfers within `budget_window`
+    pub budget: u128,
+    pub budget_window: u32,
+    /// Minimal time between transfers to the same account
+    pub cooldown: u32,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TopupDecision {
+    /// Account is not managed or its balance is above the threshold
+    NotNeeded,
+    /// Value to transfer
+    Transfer(u128),
+    /// Top-up is needed but not allowed by the policy
+    Denied(String),
+}
+
+/// Transfer sent by the manager
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct TopupRecord {
+    #[serde(with = "crate::json_helper::address")]
+    pub address: MsgAddressInt,
+    pub value: u128,
+    /// UNIX time in seconds
+    pub sent_at: u32,
+}
+
+#[derive(Serialize, Deserialize)]
+enum JournalRecord {
+    Sent(TopupRecord),
+    /// Transfer rejected by the treasury
+    Rejected(TopupRecord),
+}
+
+pub struct TopupManager<'a, T: Transport + ?Sized> {
+    transport: &'a T,
+    flows: Flows<'a, T>,
+    treasury: Multisig,
+    signer: &'a dyn Signer,
+    policy: TopupPolicy,
+    accounts: HashMap<MsgAddressInt, ManagedAccount>,
+    history: Vec<TopupRecord>,
+    journal: Option<Journal<JournalRecord>>,
+    clock: Arc<dyn Clock>,
+}
+
+impl<'a, T: Transport + ?Sized> TopupManager<'a, T> {
+    /// Manager sending transfers from the treasury signed by its custodian
+    pub fn new(
+        transport: &'a T,
+        treasury: Multisig,
+        signer: &'a dyn Signer,
+        policy: TopupPolicy,
+    ) -> Self {
+        Self {
+            transport,
+            flows: Flows::new(transport),
+            treasury,
+            signer,
+            policy,
+            accounts: HashMap::new(),
+            history: Vec::new(),
+            journal: None,
+            clock: Arc::new(SystemClock),
+        }
+    }
+
+    /// Flows used for transfers, e.g. with custom expiration or timeout
+    pub fn with_flows(mut self, flows: Flows<'a, T>) -> Self {
+        self.flows = flows;
+        self
+    }
+
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Keeps the transfer history in the journal file, creating it if
+    /// needed, and restores the history recorded before
+    pub fn with_history(mut self, path: impl AsRef<Path>) -> Result<Self> {
+        let (mut journal, records) = Journal::open(path)?;
+        let mut history = Vec::new();
+        for record in records {
+            match record {
+                JournalRecord::Sent(record) => history.push(record),
+                JournalRecord::Rejected(record) => history.retain(|sent| sent != &record),
+            }
+        }
+        journal.compact(&history.iter().cloned().map(JournalRecord::Sent).collect::<Vec<_>>())?;
+        self.history = history;
+        self.journal = Some(journal);
+        Ok(self)
+    }
+
+    pub fn manage(&mut self, account: ManagedAccount) {
+        self.accounts.insert(account.address.clone(), account);
+    }
+
+    /// Stops funding the account, returns `false` if it was not managed
+    pub fn unmanage(&mut self, address: &MsgAddressInt) -> bool {
+        self.accounts.remove(address).is_some()
+    }
+
+    pub fn accounts(&self) -> impl Iterator<Item = &ManagedAccount> {
+        self.accounts.values()
+    }
+
+    /// Transfers sent within the budget window
+    pub fn history(&self) -> &[TopupRecord] {
+        &self.history
+    }
+
+    /// Value the treasury may still spend within the budget window
+    pub fn remaining_budget(&self, now: u32) -> u128 {
+        let spent: u128 = self
+            .history
+            .iter()
+            .filter(|record| record.sent_at.saturating_add(self.policy.budget_window) > now)
+            .map(|record| record.value)
+            .sum();
+        self.policy.budget.saturating_sub(spent)
+    }
+
+    /// Decides on the top-up of the account with the balance, without
+    /// sending anything
+    pub fn decide(&self, address: &MsgAddressInt, balance: u128, now: u32) -> TopupDecision {
+        let Some(account) = self.accounts.get(address) else {
+            return TopupDecision::NotNeeded;
+        };
+        if balance >= account.threshold {
+            return TopupDecision::NotNeeded;
+        }
+        let last = self.history.iter().rev().find(|record| &record.address == address);
+        if let Some(last) = last {
+            if last.sent_at.saturating_add(self.policy.cooldown) > now {
+                return TopupDecision::Denied(format!(
+                    "Account was topped up at {}, cooldown is {} s",
+                    last.sent_at, self.policy.cooldown
+                ));
+            }
+        }
+        let remaining = self.remaining_budget(now);
+        if remaining == 0 {
+            return TopupDecision::Denied("Budget of the window is exhausted".to_owned());
+        }
+        let value =
+            account.target.saturating_sub(balance).min(self.policy.max_transfer).min(remaining);
+        if value == 0 {
+            return TopupDecision::NotNeeded;
+        }
+        TopupDecision::Transfer(value)
+    }
+
+    /// Handles the balance update of the account, e.g. from a subscription,
+    /// and sends the transfer if the policy allows it
+    pub async fn on_balance(
+        &mut self,
+        address: &MsgAddressInt,
+        balance: u128,
+    ) -> Result<(TopupDecision, Option<CallResult>)> {
+        let now = self.now();
+        let decision = self.decide(address, balance, now);
+        let TopupDecision::Transfer(value) = decision else {
+            return Ok((decision, None));
+        };
+        self.prune(now)?;
+        // recorded before sending, so the transfer counts even if its outcome
+        // is unknown, e.g. on timeout or restart
+        let record = TopupRecord { address: address.clone(), value, sent_at: now };
+        if let Some(journal) = &mut self.journal {
+            journal.append(&JournalRecord::Sent(record.clone()))?;
+        }
+        self.history.push(record.clone());
+        let transfer = Transfer { dest: address.clone(), value, bounce: true, comment: None };
+        let result = self.flows.transfer(&self.treasury, &transfer, self.signer).await?;
+        if result.transaction.is_aborted() {
+            if let Some(journal) = &mut self.journal {
+                journal.append(&JournalRecord::Rejected(record.clone()))?;
+            }
+            self.history.retain(|sent| sent != &record);
+        }
+        Ok((decision, Some(result)))
+    }
+
+    // Drops records past the budget window and the cooldown
+    fn prune(&mut self, now: u32) -> Result<()> {
+        let window = self.policy.budget_window.max(self.policy.cooldown);
+        let len = self.history.len();
+        self.history.retain(|record| record.sent_at.saturating_add(window) > now);
+        if let Some(journal) = &mut self.journal {
+            if self.history.len() != len {
+                let records: Vec<_> =
+                    self.history.iter().cloned().map(JournalRecord::Sent).collect();
+                journal.compact(&records)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Handles the account state update, e.g. from a subscription
+    pub async fn on_account(
+        &mut self,
+        account: &Account,
+    ) -> Result<Option<(TopupDecision, Option<CallResult>)>> {
+        let Some(address) = account.get_addr().cloned() else {
+            return Ok(None);
+        };
+        let balance = account.balance().map(|balance| balance.grams.as_u128()).unwrap_or(0);
+        Ok(Some(self.on_balance(&address, balance).await?))
+    }
+
+    /// Fetches balances of all managed accounts and tops them up. Accounts
+    /// not existing are denied, as the address may be mistaken.
+    pub async fn check_all(&mut self) -> Result<Vec<(MsgAddressInt, TopupDecision)>> {
+        let addresses: Vec<_> = self.accounts.keys().cloned().collect();
+        let accounts = self.transport.query_accounts(&addresses).await?;
+        let mut decisions = Vec::with_capacity(addresses.len());
+        for (address, account) in addresses.into_iter().zip(accounts) {
+            let decision = match account.and_then(|account| account.balance().cloned()) {
+                Some(balance) => self.on_balance(&address, balance.grams.as_u128()).await?.0,
+                None => TopupDecision::Denied("Account doesn't exist".to_owned()),
+            };
+            decisions.push((address, decision));
+        }
+        Ok(decisions)
+    }
+
+    fn now(&self) -> u32 {
+        (self.clock.now_ms() / 1000).min(u32::MAX as u64) as u32
+    }
+}
+
+#[cfg(test)]
+#[path = "tests/test_topup.rs"]
+mod tests;
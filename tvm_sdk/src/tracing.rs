@@ -0,0 +1,322 @@
+// Copyright 2018-2021 TON Labs LTD.
+//
+// Licensed under the SOFTWARE EVALUATION License (the "License"); you may not
+// use this file except in compliance with the License.
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific TON DEV software governing permissions and
+// limitations under the License.
+
+//! Call trees of transaction cascades decoded by ABIs.
+//!
+//! The tree is reconstructed from the root transaction and its fetched or
+//! simulated descendants: each outbound message is linked with the
+//! transaction consuming it. Calls are decoded by the ABI of the destination,
+//! answers and events by the ABI of the source. ABIs are found by account
+//! address or by code hash, the latter known from the caller or from state
+//! inits of deploy messages.
+
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use serde_json::Value;
+use tvm_block::Serializable;
+use tvm_block::TrComputePhase;
+use tvm_block::Transaction as TvmTransaction;
+use tvm_types::Result;
+use tvm_types::SliceData;
+
+use crate::MessageType;
+use crate::ParsedMessage;
+use crate::message::decode_body;
+
+/// ABIs of the traced accounts
+#[derive(Debug, Clone, Default)]
+pub struct TraceAbis {
+    by_address: HashMap<String, String>,
+    by_code_hash: HashMap<String, String>,
+    code_hashes: HashMap<String, String>,
+}
+
+impl TraceAbis {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_address(mut self, address: impl Into<String>, abi: impl Into<String>) -> Self {
+        self.by_address.insert(address.into(), abi.into());
+        self
+    }
+
+    /// ABI of all accounts with the code, e.g. wallets of the same kind
+    pub fn with_code_hash(mut self, code_hash: impl Into<String>, abi: impl Into<String>) -> Self {
+        self.by_code_hash.insert(code_hash.into().to_lowercase(), abi.into());
+        self
+    }
+
+    /// Code hash of the account, for accounts not deployed within the trace
+    pub fn with_account_code(
+        mut self,
+        address: impl Into<String>,
+        code_hash: impl Into<String>,
+    ) -> Self {
+        self.code_hashes.insert(address.into(), code_hash.into().to_lowercase());
+        self
+    }
+
+    /// ABI of the account, the one set by address takes precedence
+    pub fn abi(&self, address: &str) -> Option<&str> {
+        self.by_address
+            .get(address)
+            .or_else(|| self.by_code_hash.get(self.code_hashes.get(address)?))
+            .map(String::as_str)
+    }
+
+    fn learn_code(&mut self, message: &ParsedMessage) {
+        let code = message.state_init.as_ref().and_then(|state_init| state_init.code.as_ref());
+        if let (Some(dst), Some(code)) = (&message.dst, code) {
+            self.code_hashes.entry(dst.clone()).or_insert_with(|| code.repr_hash().as_hex_string());
+        }
+    }
+}
+
+/// Message body decoded by ABI
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TracedCall {
+    pub function_name: String,
+    pub params: Value,
+    /// Answer or event rather than a call
+    pub output: bool,
+}
+
+/// Outbound message of the traced transaction
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TraceMessage {
+    pub message_id: String,
+    pub msg_type: MessageType,
+    pub dst: Option<String>,
+    /// In nanotokens
+    pub value: u64,
+    pub bounced: bool,
+    pub call: Option<TracedCall>,
+    /// Transaction consuming the message, `None` for external messages and
+    /// for messages which transactions are not given, e.g. still in flight
+    pub transaction: Option<Box<TraceNode>>,
+}
+
+/// Transaction of the call tree
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TraceNode {
+    pub transaction_id: String,
+    pub account: String,
+    pub lt: u64,
+    pub aborted: bool,
+    /// `None` if the compute phase is skipped
+    pub exit_code: Option<i32>,
+    pub in_msg: Option<String>,
+    /// Inbound message decoded by ABI
+    pub call: Option<TracedCall>,
+    pub out_msgs: Vec<TraceMessage>,
+}
+
+impl TraceNode {
+    /// Number of transactions in the subtree including this one
+    pub fn transaction_count(&self) -> usize {
+        1 + self.children().map(TraceNode::transaction_count).sum::<usize>()
+    }
+
+    /// Transactions consuming outbound messages of this one
+    pub fn children(&self) -> impl Iterator<Item = &TraceNode> {
+        self.out_msgs.iter().filter_map(|message| message.transaction.as_deref())
+    }
+
+    fn render(&self, text: &mut String, depth: usize) {
+        let indent = "  ".repeat(depth);
+        let _ = write!(text, "{}{} lt {}", indent, self.account, self.lt);
+        if let Some(call) = &self.call {
+            let _ = write!(text, " {}", format_call(call));
+        }
+        match self.exit_code {
+            Some(exit_code) => {
+                let _ = write!(text, " exit {}", exit_code);
+            }
+            None => text.push_str(" skipped"),
+        }
+        if self.aborted {
+            text.push_str(" aborted");
+        }
+        text.push('\n');
+        for message in &self.out_msgs {
+            let _ = write!(text, "{}  -> {}", indent, message.dst.as_deref().unwrap_or("external"));
+            if message.msg_type == MessageType::Internal {
+                let _ = write!(text, " {}", message.value);
+            }
+            if message.bounced {
+                text.push_str(" bounced");
+            }
+            if let Some(call) = &message.call {
+                let _ = write!(text, " {}", format_call(call));
+            }
+            text.push('\n');
+            if let Some(transaction) = &message.transaction {
+                transaction.render(text, depth + 2);
+            }
+        }
+    }
+}
+
+/// Call tree of a cascade of transactions
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct CallTrace {
+    pub root: TraceNode,
+    /// Given transactions not reached from the root
+    pub unlinked: Vec<String>,
+}
+
+struct ParsedTransaction {
+    id: String,
+    account: String,
+    lt: u64,
+    aborted: bool,
+    exit_code: Option<i32>,
+    in_msg: Option<ParsedMessage>,
+    out_msgs: Vec<ParsedMessage>,
+}
+
+impl CallTrace {
+    /// Builds the tree from the root transaction and its descendants given in
+    /// any order
+    pub fn from_transactions<'a>(
+        root: &TvmTransaction,
+        transactions: impl IntoIterator<Item = &'a TvmTransaction>,
+        abis: &TraceAbis,
+    ) -> Result<Self> {
+        let root = parse_transaction(root)?;
+        let mut consumers = HashMap::new();
+        let mut abis = abis.clone();
+        for message in root.in_msg.iter().chain(&root.out_msgs) {
+            abis.learn_code(message);
+        }
+        for transaction in transactions {
+            let transaction = parse_transaction(transaction)?;
+            if transaction.id == root.id {
+                continue;
+            }
+            for message in transaction.in_msg.iter().chain(&transaction.out_msgs) {
+                abis.learn_code(message);
+            }
+            let key = match &transaction.in_msg {
+                Some(message) => message.id.to_string(),
+                None => format!("tx:{}", transaction.id),
+            };
+            consumers.insert(key, transaction);
+        }
+
+        let root = build_node(root, &mut consumers, &abis)?;
+        let mut unlinked: Vec<_> = consumers.into_values().map(|tx| (tx.lt, tx.id)).collect();
+        unlinked.sort();
+        Ok(Self { root, unlinked: unlinked.into_iter().map(|(_, id)| id).collect() })
+    }
+
+    /// Indented text of the tree, one line per transaction and message
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        self.root.render(&mut text, 0);
+        text
+    }
+}
+
+fn build_node(
+    transaction: ParsedTransaction,
+    consumers: &mut HashMap<String, ParsedTransaction>,
+    abis: &TraceAbis,
+) -> Result<TraceNode> {
+    let mut out_msgs = Vec::new();
+    for message in &transaction.out_msgs {
+        let child = match consumers.remove(&message.id.to_string()) {
+            Some(child) => Some(Box::new(build_node(child, consumers, abis)?)),
+            None => None,
+        };
+        out_msgs.push(TraceMessage {
+            message_id: message.id.to_string(),
+            msg_type: message.msg_type.clone(),
+            dst: message.dst.clone(),
+            value: message.value,
+            bounced: message.bounced,
+            call: decode_call(message, abis)?,
+            transaction: child,
+        });
+    }
+    let call = match &transaction.in_msg {
+        Some(message) => decode_call(message, abis)?,
+        None => None,
+    };
+    Ok(TraceNode {
+        transaction_id: transaction.id,
+        account: transaction.account,
+        lt: transaction.lt,
+        aborted: transaction.aborted,
+        exit_code: transaction.exit_code,
+        in_msg: transaction.in_msg.map(|message| message.id.to_string()),
+        call,
+        out_msgs,
+    })
+}
+
+fn parse_transaction(transaction: &TvmTransaction) -> Result<ParsedTransaction> {
+    let in_msg = match transaction.read_in_msg()? {
+        Some(message) => Some(ParsedMessage::with_msg(&message, None)?),
+        None => None,
+    };
+    let mut out_msgs = Vec::new();
+    for index in 0..transaction.msg_count() {
+        if let Some(message) = transaction.get_out_msg(index)? {
+            out_msgs.push(ParsedMessage::with_msg(&message, None)?);
+        }
+    }
+    let account = in_msg
+        .as_ref()
+        .and_then(|message| message.dst.clone())
+        .unwrap_or_else(|| transaction.account_id().to_hex_string());
+    let description = transaction.read_description()?;
+    let exit_code = match description.compute_phase_ref() {
+        Some(TrComputePhase::Vm(vm)) => Some(vm.exit_code),
+        _ => None,
+    };
+    Ok(ParsedTransaction {
+        id: transaction.serialize()?.repr_hash().as_hex_string(),
+        account,
+        lt: transaction.logical_time(),
+        aborted: description.is_aborted(),
+        exit_code,
+        in_msg,
+        out_msgs,
+    })
+}
+
+// Calls are decoded by the destination ABI, answers and events by the source
+// one
+fn decode_call(message: &ParsedMessage, abis: &TraceAbis) -> Result<Option<TracedCall>> {
+    let Some(body) = message.body.clone() else {
+        return Ok(None);
+    };
+    let body = SliceData::load_cell(body)?;
+    let decoded = [&message.dst, &message.src]
+        .into_iter()
+        .filter_map(|address| abis.abi(address.as_deref()?))
+        .find_map(|abi| decode_body(abi, body.clone(), &message.msg_type));
+    match decoded {
+        Some(decoded) => Ok(Some(TracedCall {
+            function_name: decoded.function_name,
+            params: serde_json::from_str(&decoded.params)?,
+            output: decoded.output,
+        })),
+        None => Ok(None),
+    }
+}
+
+fn format_call(call: &TracedCall) -> String {
+    format!("{}{}", call.function_name, call.params)
+}
@@ -84,13 +84,22 @@ impl TryFrom<&tvm_block::Transaction> for Transaction {
     type Error = tvm_types::Error;
 
     fn try_from(transaction: &tvm_block::Transaction) -> Result<Self> {
-        let descr = if let TransactionDescr::Ordinary(descr) = transaction.read_description()? {
-            descr
-        } else {
-            return Err(SdkError::InvalidData { msg: "Invalid transaction type".to_owned() }.into());
+        let (storage_ph, compute_ph, action, aborted) = match transaction.read_description()? {
+            TransactionDescr::Ordinary(descr) => {
+                (descr.storage_ph, Some(descr.compute_ph), descr.action, descr.aborted)
+            }
+            TransactionDescr::TickTock(descr) => {
+                (Some(descr.storage), Some(descr.compute_ph), descr.action, descr.aborted)
+            }
+            TransactionDescr::Storage(storage_ph) => (Some(storage_ph), None, None, false),
+            _ => {
+                return Err(
+                    SdkError::InvalidData { msg: "Invalid transaction type".to_owned() }.into()
+                );
+            }
         };
 
-        let storage_phase = if let Some(phase) = descr.storage_ph {
+        let storage_phase = if let Some(phase) = storage_ph {
             Some(StoragePhase {
                 status_change: phase.status_change,
                 storage_fees_collected: grams_to_u64(&phase.storage_fees_collected)?,
@@ -99,8 +108,9 @@ impl TryFrom<&tvm_block::Transaction> for Transaction {
             None
         };
 
-        let compute_phase = match descr.compute_ph {
-            TrComputePhase::Skipped(ph) => ComputePhase {
+        let compute_phase = match compute_ph {
+            None => ComputePhase::default(),
+            Some(TrComputePhase::Skipped(ph)) => ComputePhase {
                 skipped_reason: Some(ph.reason),
                 exit_code: None,
                 exit_arg: None,
@@ -108,7 +118,7 @@ impl TryFrom<&tvm_block::Transaction> for Transaction {
                 gas_fees: 0,
                 gas_used: 0,
             },
-            TrComputePhase::Vm(ph) => ComputePhase {
+            Some(TrComputePhase::Vm(ph)) => ComputePhase {
                 skipped_reason: None,
                 exit_code: Some(ph.exit_code),
                 exit_arg: ph.exit_arg,
@@ -118,7 +128,7 @@ impl TryFrom<&tvm_block::Transaction> for Transaction {
             },
         };
 
-        let action_phase = if let Some(phase) = descr.action {
+        let action_phase = if let Some(phase) = action {
             Some(ActionPhase {
                 success: phase.success,
                 valid: phase.valid,
@@ -152,7 +162,7 @@ impl TryFrom<&tvm_block::Transaction> for Transaction {
             in_msg,
             out_msgs,
             out_messages,
-            aborted: descr.aborted,
+            aborted,
             total_fees: grams_to_u64(&transaction.total_fees().grams)?,
             storage: storage_phase,
             compute: compute_phase,
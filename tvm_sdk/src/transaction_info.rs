@@ -0,0 +1,321 @@
+// Copyright 2018-2021 TON Labs LTD.
+//
+// Licensed under the SOFTWARE EVALUATION License (the "License"); you may not
+// use this file except in compliance with the License.
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific TON DEV software governing permissions and
+// limitations under the License.
+
+use tvm_block::AccountStatus;
+use tvm_block::Deserializable;
+use tvm_block::GetRepresentationHash;
+use tvm_block::TrActionPhase;
+use tvm_block::TrBouncePhase;
+use tvm_block::TrComputePhase;
+use tvm_block::TrCreditPhase;
+use tvm_block::TrStoragePhase;
+use tvm_block::Transaction as TvmTransaction;
+use tvm_block::TransactionDescr;
+use tvm_types::Result;
+
+use crate::MessageId;
+use crate::Transaction;
+use crate::TransactionFees;
+use crate::TransactionId;
+use crate::json_helper;
+use crate::types::grams_to_u64;
+
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct StoragePhaseInfo {
+    #[serde(with = "json_helper::uint")]
+    pub storage_fees_collected: u64,
+    /// Storage fee debt left after the phase, zero if there is none
+    #[serde(with = "json_helper::uint")]
+    pub storage_fees_due: u64,
+    /// `Unchanged`, `Frozen` or `Deleted`
+    pub status_change_name: String,
+}
+
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct CreditPhaseInfo {
+    #[serde(with = "json_helper::uint")]
+    pub due_fees_collected: u64,
+    #[serde(with = "json_helper::uint")]
+    pub credit: u64,
+}
+
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct ComputePhaseInfo {
+    /// `Skipped` or `Vm`
+    pub compute_type_name: String,
+    /// `NoState`, `BadState`, `NoGas` or `Suspended` for skipped phase
+    pub skipped_reason_name: Option<String>,
+    pub success: bool,
+    pub msg_state_used: bool,
+    pub account_activated: bool,
+    #[serde(with = "json_helper::uint")]
+    pub gas_fees: u64,
+    #[serde(with = "json_helper::uint")]
+    pub gas_used: u64,
+    #[serde(with = "json_helper::uint")]
+    pub gas_limit: u64,
+    pub gas_credit: Option<u32>,
+    pub mode: i8,
+    pub exit_code: i32,
+    pub exit_arg: Option<i32>,
+    pub vm_steps: u32,
+}
+
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct ActionPhaseInfo {
+    pub success: bool,
+    pub valid: bool,
+    pub no_funds: bool,
+    pub status_change_name: String,
+    #[serde(with = "json_helper::uint")]
+    pub total_fwd_fees: u64,
+    #[serde(with = "json_helper::uint")]
+    pub total_action_fees: u64,
+    pub result_code: i32,
+    pub result_arg: Option<i32>,
+    pub tot_actions: i16,
+    pub spec_actions: i16,
+    pub skipped_actions: i16,
+    pub msgs_created: i16,
+}
+
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct BouncePhaseInfo {
+    /// `NegFunds`, `NoFunds` or `Ok`
+    pub bounce_type_name: String,
+    #[serde(with = "json_helper::uint")]
+    pub msg_size_cells: u64,
+    #[serde(with = "json_helper::uint")]
+    pub msg_size_bits: u64,
+    /// Fees required to bounce, for `NoFunds`
+    #[serde(with = "json_helper::uint")]
+    pub req_fwd_fees: u64,
+    #[serde(with = "json_helper::uint")]
+    pub msg_fees: u64,
+    #[serde(with = "json_helper::uint")]
+    pub fwd_fees: u64,
+}
+
+/// Transaction decoded from BOC. Field names and formats follow the
+/// `transactions` collection of the GraphQL API.
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct TransactionInfo {
+    pub id: TransactionId,
+    /// `Ordinary`, `Storage`, `Tick`, `Tock`, `SplitPrepare`, `SplitInstall`,
+    /// `MergePrepare` or `MergeInstall`
+    pub tr_type_name: String,
+    /// Full address if the transaction has inbound message, account id in
+    /// hex otherwise
+    pub account_addr: String,
+    #[serde(with = "json_helper::uint")]
+    pub lt: u64,
+    pub prev_trans_hash: String,
+    #[serde(with = "json_helper::uint")]
+    pub prev_trans_lt: u64,
+    pub now: u32,
+    #[serde(with = "json_helper::account_status")]
+    pub orig_status: AccountStatus,
+    #[serde(with = "json_helper::account_status")]
+    pub end_status: AccountStatus,
+    pub in_msg: Option<MessageId>,
+    pub out_msgs: Vec<MessageId>,
+    pub aborted: bool,
+    pub destroyed: bool,
+    pub credit_first: bool,
+    #[serde(with = "json_helper::uint")]
+    pub total_fees: u64,
+    pub storage: Option<StoragePhaseInfo>,
+    pub credit: Option<CreditPhaseInfo>,
+    pub compute: Option<ComputePhaseInfo>,
+    pub action: Option<ActionPhaseInfo>,
+    pub bounce: Option<BouncePhaseInfo>,
+    /// Fees paid by the account, zero for split and merge transactions
+    pub fees: TransactionFees,
+}
+
+impl TransactionInfo {
+    pub fn from_boc(data: &[u8]) -> Result<Self> {
+        Self::try_from(&TvmTransaction::construct_from_bytes(data)?)
+    }
+}
+
+impl TryFrom<&TvmTransaction> for TransactionInfo {
+    type Error = tvm_types::Error;
+
+    fn try_from(transaction: &TvmTransaction) -> Result<Self> {
+        let mut info = Self {
+            id: transaction.hash()?.into(),
+            lt: transaction.logical_time(),
+            prev_trans_hash: transaction.prev_trans_hash().as_hex_string(),
+            prev_trans_lt: transaction.prev_trans_lt(),
+            now: transaction.now(),
+            orig_status: transaction.orig_status.clone(),
+            end_status: transaction.end_status.clone(),
+            total_fees: grams_to_u64(&transaction.total_fees().grams)?,
+            ..Default::default()
+        };
+
+        let in_msg = transaction.read_in_msg()?;
+        info.account_addr = match in_msg.as_ref().and_then(|message| message.dst()) {
+            Some(address) => address.to_string(),
+            None => transaction.account_id().to_hex_string(),
+        };
+        info.in_msg = transaction.in_msg.as_ref().map(|message| message.hash().into());
+        transaction.out_msgs.iterate_slices(|slice| {
+            info.out_msgs.push(slice.reference(0)?.repr_hash().into());
+            Ok(true)
+        })?;
+
+        let (tr_type, fees_known) = match transaction.read_description()? {
+            TransactionDescr::Ordinary(descr) => {
+                info.storage = descr.storage_ph.as_ref().map(storage_phase).transpose()?;
+                info.credit = descr.credit_ph.as_ref().map(credit_phase).transpose()?;
+                info.compute = Some(compute_phase(&descr.compute_ph)?);
+                info.action = descr.action.as_ref().map(action_phase).transpose()?;
+                info.bounce = descr.bounce.as_ref().map(bounce_phase).transpose()?;
+                info.aborted = descr.aborted;
+                info.destroyed = descr.destroyed;
+                info.credit_first = descr.credit_first;
+                ("Ordinary", true)
+            }
+            TransactionDescr::TickTock(descr) => {
+                info.storage = Some(storage_phase(&descr.storage)?);
+                info.compute = Some(compute_phase(&descr.compute_ph)?);
+                info.action = descr.action.as_ref().map(action_phase).transpose()?;
+                info.aborted = descr.aborted;
+                info.destroyed = descr.destroyed;
+                (if descr.tt.is_tick() { "Tick" } else { "Tock" }, true)
+            }
+            TransactionDescr::Storage(descr) => {
+                info.storage = Some(storage_phase(&descr)?);
+                ("Storage", true)
+            }
+            TransactionDescr::SplitPrepare(descr) => {
+                info.compute = Some(compute_phase(&descr.compute_ph)?);
+                info.action = descr.action.as_ref().map(action_phase).transpose()?;
+                info.aborted = descr.aborted;
+                info.destroyed = descr.destroyed;
+                ("SplitPrepare", false)
+            }
+            TransactionDescr::SplitInstall(_) => ("SplitInstall", false),
+            TransactionDescr::MergePrepare(descr) => {
+                info.storage = Some(storage_phase(&descr.storage_ph)?);
+                info.aborted = descr.aborted;
+                ("MergePrepare", false)
+            }
+            TransactionDescr::MergeInstall(descr) => {
+                info.credit = descr.credit_ph.as_ref().map(credit_phase).transpose()?;
+                info.compute = Some(compute_phase(&descr.compute_ph)?);
+                info.action = descr.action.as_ref().map(action_phase).transpose()?;
+                info.aborted = descr.aborted;
+                info.destroyed = descr.destroyed;
+                ("MergeInstall", false)
+            }
+        };
+        info.tr_type_name = tr_type.to_owned();
+        if fees_known {
+            info.fees = Transaction::try_from(transaction)?.calc_fees();
+        }
+        Ok(info)
+    }
+}
+
+fn storage_phase(phase: &TrStoragePhase) -> Result<StoragePhaseInfo> {
+    Ok(StoragePhaseInfo {
+        storage_fees_collected: grams_to_u64(&phase.storage_fees_collected)?,
+        storage_fees_due: match &phase.storage_fees_due {
+            Some(due) => grams_to_u64(due)?,
+            None => 0,
+        },
+        status_change_name: format!("{:?}", phase.status_change),
+    })
+}
+
+fn credit_phase(phase: &TrCreditPhase) -> Result<CreditPhaseInfo> {
+    Ok(CreditPhaseInfo {
+        due_fees_collected: match &phase.due_fees_collected {
+            Some(due) => grams_to_u64(due)?,
+            None => 0,
+        },
+        credit: grams_to_u64(&phase.credit.grams)?,
+    })
+}
+
+fn compute_phase(phase: &TrComputePhase) -> Result<ComputePhaseInfo> {
+    Ok(match phase {
+        TrComputePhase::Skipped(skipped) => ComputePhaseInfo {
+            compute_type_name: "Skipped".to_owned(),
+            skipped_reason_name: Some(format!("{:?}", skipped.reason)),
+            ..Default::default()
+        },
+        TrComputePhase::Vm(vm) => ComputePhaseInfo {
+            compute_type_name: "Vm".to_owned(),
+            skipped_reason_name: None,
+            success: vm.success,
+            msg_state_used: vm.msg_state_used,
+            account_activated: vm.account_activated,
+            gas_fees: grams_to_u64(&vm.gas_fees)?,
+            gas_used: vm.gas_used.as_u64(),
+            gas_limit: vm.gas_limit.as_u64(),
+            gas_credit: vm.gas_credit.map(|credit| credit.as_u32()),
+            mode: vm.mode,
+            exit_code: vm.exit_code,
+            exit_arg: vm.exit_arg,
+            vm_steps: vm.vm_steps,
+        },
+    })
+}
+
+fn action_phase(phase: &TrActionPhase) -> Result<ActionPhaseInfo> {
+    Ok(ActionPhaseInfo {
+        success: phase.success,
+        valid: phase.valid,
+        no_funds: phase.no_funds,
+        status_change_name: format!("{:?}", phase.status_change),
+        total_fwd_fees: grams_to_u64(&phase.total_fwd_fees.unwrap_or_default())?,
+        total_action_fees: grams_to_u64(&phase.total_action_fees.unwrap_or_default())?,
+        result_code: phase.result_code,
+        result_arg: phase.result_arg,
+        tot_actions: phase.tot_actions,
+        spec_actions: phase.spec_actions,
+        skipped_actions: phase.skipped_actions,
+        msgs_created: phase.msgs_created,
+    })
+}
+
+fn bounce_phase(phase: &TrBouncePhase) -> Result<BouncePhaseInfo> {
+    Ok(match phase {
+        TrBouncePhase::Negfunds => {
+            BouncePhaseInfo { bounce_type_name: "NegFunds".to_owned(), ..Default::default() }
+        }
+        TrBouncePhase::Nofunds(nofunds) => BouncePhaseInfo {
+            bounce_type_name: "NoFunds".to_owned(),
+            msg_size_cells: nofunds.msg_size.cells(),
+            msg_size_bits: nofunds.msg_size.bits(),
+            req_fwd_fees: grams_to_u64(&nofunds.req_fwd_fees)?,
+            ..Default::default()
+        },
+        TrBouncePhase::Ok(ok) => BouncePhaseInfo {
+            bounce_type_name: "Ok".to_owned(),
+            msg_size_cells: ok.msg_size.cells(),
+            msg_size_bits: ok.msg_size.bits(),
+            msg_fees: grams_to_u64(&ok.msg_fees)?,
+            fwd_fees: grams_to_u64(&ok.fwd_fees)?,
+            ..Default::default()
+        },
+    })
+}
@@ -0,0 +1,595 @@
+// Copyright 2018-2021 TON Labs LTD.
+//
+// Licensed under the SOFTWARE EVALUATION License (the "License"); you may not
+// use this file except in compliance with the License.
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific TON DEV software governing permissions and
+// limitations under the License.
+
+//! Sending of messages built by `Contract` and fetching of their results.
+//!
+//! `Transport` abstracts the node API, `GraphQlTransport` implements it over
+//! the GraphQL API of the block explorer (`postRequests` mutation and
+//! `transactions` and `accounts` queries with server side waiting).
+//! `StalenessWatchdog` keeps it from reading state of an endpoint lagging
+//! behind the masterchain.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use serde_json::Value;
+use serde_json::json;
+use tvm_block::Account;
+use tvm_block::Deserializable;
+use tvm_block::MsgAddressInt;
+use tvm_block::Serializable;
+use tvm_types::Result;
+use tvm_types::base64_decode;
+use tvm_types::base64_encode;
+use tvm_types::error;
+use tvm_types::fail;
+
+use crate::Clock;
+use crate::MessageId;
+use crate::NodeRejection;
+use crate::SdkMessage;
+use crate::SystemClock;
+use crate::Transaction;
+use crate::TransactionId;
+use crate::error::SdkError;
+use crate::proofs;
+use crate::proofs::AccountProof;
+use crate::proofs::TrustStore;
+
+/// Verification status of the fetched account
+#[derive(Debug, Clone, PartialEq)]
+pub enum AccountVerification {
+    /// Proofs are not requested
+    Skipped,
+    /// The state is proven by a block of the trust store
+    Verified,
+    /// The proof is missing, wrong or built for a not trusted block
+    Failed(String),
+}
+
+/// Account fetched by `Transport::get_accounts_verified`
+pub struct VerifiedAccount {
+    pub address: MsgAddressInt,
+    /// `None` for accounts not existing
+    pub account: Option<Account>,
+    pub verification: AccountVerification,
+}
+
+/// Extra time given to HTTP request over the server side waiting timeout
+const REQUEST_TIMEOUT_MARGIN_MS: u64 = 5000;
+const DEFAULT_STALENESS_CHECK_INTERVAL_MS: u64 = 5000;
+
+/// Latest masterchain block known to the endpoint
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MasterchainHead {
+    pub seq_no: u32,
+    pub gen_utime: u32,
+}
+
+/// Called with the endpoint, its head and the lag in seconds
+pub type StaleEndpointCallback = Arc<dyn Fn(&str, &MasterchainHead, u32) + Send + Sync>;
+
+#[derive(Clone)]
+pub enum StaleEndpointAction {
+    /// Reads from the lagging endpoint fail
+    Refuse,
+    /// Reads are served, the callback is called on each of them
+    Warn(StaleEndpointCallback),
+}
+
+/// Tracks the latest masterchain block of the endpoint and guards reads of
+/// `GraphQlTransport` from the endpoint lagging more than `max_lag` seconds
+/// behind the clock. The head is queried at most once per check interval,
+/// reads in between use the lag seen by the last check.
+pub struct StalenessWatchdog {
+    max_lag: u32,
+    action: StaleEndpointAction,
+    check_interval_ms: u64,
+    clock: Arc<dyn Clock>,
+    // Head, its lag and the check time in milliseconds
+    last: Mutex<Option<(MasterchainHead, u32, u64)>>,
+}
+
+impl StalenessWatchdog {
+    pub fn new(max_lag: u32, action: StaleEndpointAction) -> Self {
+        Self {
+            max_lag,
+            action,
+            check_interval_ms: DEFAULT_STALENESS_CHECK_INTERVAL_MS,
+            clock: Arc::new(SystemClock),
+            last: Mutex::new(None),
+        }
+    }
+
+    pub fn with_check_interval(mut self, interval_ms: u64) -> Self {
+        self.check_interval_ms = interval_ms;
+        self
+    }
+
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Head seen by the last check
+    pub fn last_head(&self) -> Option<MasterchainHead> {
+        self.last.lock().unwrap().map(|(head, _, _)| head)
+    }
+
+    /// Lag seen by the last check, in seconds
+    pub fn last_lag(&self) -> Option<u32> {
+        self.last.lock().unwrap().map(|(_, lag, _)| lag)
+    }
+
+    // Lag of the last check if it is recent enough
+    fn cached(&self, now_ms: u64) -> Option<(MasterchainHead, u32)> {
+        match *self.last.lock().unwrap() {
+            Some((head, lag, checked_at)) if checked_at + self.check_interval_ms > now_ms => {
+                Some((head, lag))
+            }
+            _ => None,
+        }
+    }
+
+    fn record(&self, head: MasterchainHead, now_ms: u64) -> u32 {
+        let lag = ((now_ms / 1000) as u32).saturating_sub(head.gen_utime);
+        *self.last.lock().unwrap() = Some((head, lag, now_ms));
+        lag
+    }
+
+    fn judge(&self, endpoint: &str, head: &MasterchainHead, lag: u32) -> Result<()> {
+        if lag <= self.max_lag {
+            return Ok(());
+        }
+        match &self.action {
+            StaleEndpointAction::Refuse => fail!(SdkError::InvalidData {
+                msg: format!(
+                    "Endpoint {} is stale: masterchain block {} is {} s old, limit is {} s",
+                    endpoint, head.seq_no, lag, self.max_lag
+                )
+            }),
+            StaleEndpointAction::Warn(callback) => {
+                callback(endpoint, head, lag);
+                Ok(())
+            }
+        }
+    }
+}
+
+#[async_trait::async_trait]
+pub trait Transport: Send + Sync {
+    async fn send_message(&self, message: &SdkMessage) -> Result<()>;
+
+    /// Waits for the transaction processing the inbound message. Fails with
+    /// `SdkError::WaitTimeout` if there is none within the timeout.
+    async fn wait_for_transaction(
+        &self,
+        message_id: &MessageId,
+        timeout_ms: u32,
+    ) -> Result<tvm_block::Transaction>;
+
+    /// Account state, `None` for accounts not existing
+    async fn query_account(&self, address: &MsgAddressInt) -> Result<Option<Account>>;
+
+    /// Transactions of the account with logical time greater than
+    /// `after_lt`, in the logical time order, at most `limit` of them.
+    /// Transports not listing transactions fail.
+    async fn query_account_transactions(
+        &self,
+        address: &MsgAddressInt,
+        after_lt: u64,
+        limit: u32,
+    ) -> Result<Vec<tvm_block::Transaction>> {
+        let _ = (after_lt, limit);
+        fail!(SdkError::InternalError {
+            msg: format!("Transport doesn't list transactions of account {}", address)
+        })
+    }
+
+    /// Account state with the proof that it belongs to the shard state after
+    /// a block, `None` for accounts not existing. Transports not providing
+    /// proofs fail.
+    async fn query_account_with_proof(
+        &self,
+        address: &MsgAddressInt,
+    ) -> Result<Option<(Account, AccountProof)>> {
+        fail!(SdkError::InternalError {
+            msg: format!("Transport doesn't provide proofs of account {}", address)
+        })
+    }
+
+    /// States of the accounts in order of the addresses. Implementations
+    /// should fetch them in one request.
+    async fn query_accounts(&self, addresses: &[MsgAddressInt]) -> Result<Vec<Option<Account>>> {
+        let mut accounts = Vec::with_capacity(addresses.len());
+        for address in addresses {
+            accounts.push(self.query_account(address).await?);
+        }
+        Ok(accounts)
+    }
+
+    /// Fetches the accounts, with the proofs checked against the trust store
+    /// if it is given. Failed verification is reported per account and
+    /// doesn't fail the batch. Absence of an account can't be proven, so
+    /// accounts not found are reported as failed when proofs are requested.
+    async fn get_accounts_verified(
+        &self,
+        addresses: &[MsgAddressInt],
+        trust_store: Option<&TrustStore>,
+    ) -> Result<Vec<VerifiedAccount>> {
+        let Some(trust_store) = trust_store else {
+            let accounts = self.query_accounts(addresses).await?;
+            return Ok(addresses
+                .iter()
+                .zip(accounts)
+                .map(|(address, account)| VerifiedAccount {
+                    address: address.clone(),
+                    account,
+                    verification: AccountVerification::Skipped,
+                })
+                .collect());
+        };
+        let mut result = Vec::with_capacity(addresses.len());
+        for address in addresses {
+            let (account, verification) = match self.query_account_with_proof(address).await {
+                Ok(Some((account, proof))) => {
+                    let verification =
+                        match trust_store.verify_account(&proof, &account.write_to_bytes()?) {
+                            Ok(_) => AccountVerification::Verified,
+                            Err(err) => AccountVerification::Failed(err.to_string()),
+                        };
+                    (Some(account), verification)
+                }
+                Ok(None) => (
+                    None,
+                    AccountVerification::Failed("Account is not found, no proof given".to_owned()),
+                ),
+                Err(err) => (None, AccountVerification::Failed(err.to_string())),
+            };
+            result.push(VerifiedAccount { address: address.clone(), account, verification });
+        }
+        Ok(result)
+    }
+
+    /// Sends the message and waits for the transaction processing it. The
+    /// message already received by the node is waited for as well.
+    async fn process_message(&self, message: &SdkMessage, timeout_ms: u32) -> Result<Transaction> {
+        if let Err(err) = self.send_message(message).await {
+            if NodeRejection::of(&err) != Some(NodeRejection::AlreadyExists) {
+                return Err(err);
+            }
+        }
+        let transaction = self.wait_for_transaction(&message.id, timeout_ms).await?;
+        Transaction::try_from(&transaction)
+    }
+}
+
+/// Transport over the GraphQL API
+pub struct GraphQlTransport {
+    endpoint: String,
+    client: reqwest::Client,
+    watchdog: Option<StalenessWatchdog>,
+}
+
+impl GraphQlTransport {
+    /// Transport for the GraphQL endpoint URL, e.g. `https://host/graphql`
+    pub fn new(endpoint: impl Into<String>) -> Result<Self> {
+        Ok(Self::with_client(endpoint, reqwest::Client::builder().build()?))
+    }
+
+    /// Transport using the preconfigured HTTP client
+    pub fn with_client(endpoint: impl Into<String>, client: reqwest::Client) -> Self {
+        Self { endpoint: endpoint.into(), client, watchdog: None }
+    }
+
+    /// Guards reads of accounts and transactions from the stale endpoint
+    pub fn with_watchdog(mut self, watchdog: StalenessWatchdog) -> Self {
+        self.watchdog = Some(watchdog);
+        self
+    }
+
+    pub fn watchdog(&self) -> Option<&StalenessWatchdog> {
+        self.watchdog.as_ref()
+    }
+
+    pub fn endpoint(&self) -> &str {
+        &self.endpoint
+    }
+
+    /// Performs GraphQL query and returns its `data`
+    pub async fn query(&self, query: &str, variables: Value, timeout: Duration) -> Result<Value> {
+        let response = self
+            .client
+            .post(&self.endpoint)
+            .timeout(timeout)
+            .json(&json!({ "query": query, "variables": variables }))
+            .send()
+            .await?;
+        let status = response.status();
+        let mut body: Value = response.json().await.map_err(|err| {
+            error!(SdkError::InvalidData {
+                msg: format!("Invalid GraphQL response with status {}: {}", status, err)
+            })
+        })?;
+        if let Some(errors) = body.get("errors").and_then(Value::as_array) {
+            if !errors.is_empty() {
+                let messages = errors
+                    .iter()
+                    .map(|error| error["message"].as_str().unwrap_or("unknown error"))
+                    .collect::<Vec<_>>();
+                let msg = format!("GraphQL query failed: {}", messages.join(", "));
+                if let Some(exit_code) = errors.iter().find_map(error_exit_code) {
+                    fail!(SdkError::MessageRejected { exit_code, msg })
+                }
+                if let Some(code) = errors.iter().find_map(node_error_code) {
+                    fail!(SdkError::NodeRejected { code: code.to_owned(), msg })
+                }
+                fail!(SdkError::InvalidData { msg })
+            }
+        }
+        if !status.is_success() {
+            fail!(SdkError::InvalidData {
+                msg: format!("GraphQL endpoint responded with status {}", status)
+            })
+        }
+        Ok(body["data"].take())
+    }
+
+    /// Transaction by its id, e.g. to replay it locally
+    pub async fn query_transaction(
+        &self,
+        id: &TransactionId,
+    ) -> Result<Option<tvm_block::Transaction>> {
+        self.check_staleness().await?;
+        let data = self
+            .query(
+                "query transactions($filter:TransactionFilter){transactions(filter:$filter){boc}}",
+                json!({ "filter": { "id": { "eq": id.to_string() } } }),
+                Duration::from_millis(REQUEST_TIMEOUT_MARGIN_MS),
+            )
+            .await?;
+        match data["transactions"].as_array().and_then(|list| list.first()) {
+            Some(transaction) => Self::read_boc(transaction),
+            None => Ok(None),
+        }
+    }
+
+    /// Generation time of the latest masterchain block, UNIX time in seconds
+    pub async fn query_last_masterchain_time(&self) -> Result<Option<u32>> {
+        Ok(self.query_masterchain_head().await?.map(|head| head.gen_utime))
+    }
+
+    /// Latest masterchain block known to the endpoint
+    pub async fn query_masterchain_head(&self) -> Result<Option<MasterchainHead>> {
+        let data = self
+            .query(
+                "query blocks($filter:BlockFilter){blocks(filter:$filter,orderBy:[{path:\"seq_no\",direction:DESC}],limit:1){seq_no gen_utime}}",
+                json!({ "filter": { "workchain_id": { "eq": -1 } } }),
+                Duration::from_millis(REQUEST_TIMEOUT_MARGIN_MS),
+            )
+            .await?;
+        let Some(block) = data["blocks"].as_array().and_then(|list| list.first()) else {
+            return Ok(None);
+        };
+        match (block["seq_no"].as_u64(), block["gen_utime"].as_u64()) {
+            (Some(seq_no), Some(gen_utime)) => {
+                Ok(Some(MasterchainHead { seq_no: seq_no as u32, gen_utime: gen_utime as u32 }))
+            }
+            _ => fail!(SdkError::InvalidData { msg: format!("Invalid block {}", block) }),
+        }
+    }
+
+    /// Checks the endpoint with the watchdog, if any, and returns its lag in
+    /// seconds. Fails if the endpoint is stale and the watchdog refuses it.
+    pub async fn check_staleness(&self) -> Result<Option<u32>> {
+        let Some(watchdog) = &self.watchdog else {
+            return Ok(None);
+        };
+        let now_ms = watchdog.clock.now_ms();
+        let (head, lag) = match watchdog.cached(now_ms) {
+            Some(cached) => cached,
+            None => {
+                let Some(head) = self.query_masterchain_head().await? else {
+                    fail!(SdkError::InvalidData {
+                        msg: format!("Endpoint {} has no masterchain blocks", self.endpoint)
+                    })
+                };
+                (head, watchdog.record(head, now_ms))
+            }
+        };
+        watchdog.judge(&self.endpoint, &head, lag)?;
+        Ok(Some(lag))
+    }
+
+    fn read_boc<T: Deserializable>(object: &Value) -> Result<Option<T>> {
+        match object["boc"].as_str() {
+            Some(boc) => Ok(Some(T::construct_from_base64(boc)?)),
+            None => Ok(None),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Transport for GraphQlTransport {
+    async fn send_message(&self, message: &SdkMessage) -> Result<()> {
+        let id = hex::decode(message.id.to_string())?;
+        let requests = json!([{
+            "id": base64_encode(id),
+            "body": base64_encode(&message.serialized_message),
+        }]);
+        self.query(
+            "mutation postRequests($requests:[Request]){postRequests(requests:$requests)}",
+            json!({ "requests": requests }),
+            Duration::from_millis(REQUEST_TIMEOUT_MARGIN_MS),
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn wait_for_transaction(
+        &self,
+        message_id: &MessageId,
+        timeout_ms: u32,
+    ) -> Result<tvm_block::Transaction> {
+        let data = self
+            .query(
+                "query transactions($filter:TransactionFilter,$timeout:Float){transactions(filter:$filter,timeout:$timeout){boc}}",
+                json!({
+                    "filter": { "in_msg": { "eq": message_id.to_string() } },
+                    "timeout": timeout_ms,
+                }),
+                Duration::from_millis(timeout_ms as u64 + REQUEST_TIMEOUT_MARGIN_MS),
+            )
+            .await?;
+        let transaction = match data["transactions"].as_array().and_then(|list| list.first()) {
+            Some(transaction) => Self::read_boc(transaction)?,
+            None => None,
+        };
+        transaction.ok_or_else(|| {
+            error!(SdkError::WaitTimeout { message_id: message_id.to_string(), timeout_ms })
+        })
+    }
+
+    async fn query_account(&self, address: &MsgAddressInt) -> Result<Option<Account>> {
+        self.check_staleness().await?;
+        let data = self
+            .query(
+                "query accounts($filter:AccountFilter){accounts(filter:$filter){boc}}",
+                json!({ "filter": { "id": { "eq": address.to_string() } } }),
+                Duration::from_millis(REQUEST_TIMEOUT_MARGIN_MS),
+            )
+            .await?;
+        match data["accounts"].as_array().and_then(|list| list.first()) {
+            Some(account) => Self::read_boc(account),
+            None => Ok(None),
+        }
+    }
+
+    // The endpoint returns the account with the proof of it in the shard state,
+    // the block proof is built from the block the state belongs to
+    async fn query_account_with_proof(
+        &self,
+        address: &MsgAddressInt,
+    ) -> Result<Option<(Account, AccountProof)>> {
+        self.check_staleness().await?;
+        let data = self
+            .query(
+                "query accounts($filter:AccountFilter){accounts(filter:$filter){boc proof}}",
+                json!({ "filter": { "id": { "eq": address.to_string() } } }),
+                Duration::from_millis(REQUEST_TIMEOUT_MARGIN_MS),
+            )
+            .await?;
+        let Some(object) = data["accounts"].as_array().and_then(|list| list.first()) else {
+            return Ok(None);
+        };
+        let (Some(account), Some(state_proof)) =
+            (Self::read_boc::<Account>(object)?, object["proof"].as_str())
+        else {
+            fail!(SdkError::InvalidData {
+                msg: format!("Endpoint returned account {} without proof", address)
+            })
+        };
+        let state_proof = base64_decode(state_proof)?;
+        let block = proofs::state_proof_block(&state_proof)?;
+        let data = self
+            .query(
+                "query blocks($filter:BlockFilter){blocks(filter:$filter){boc}}",
+                json!({ "filter": {
+                    "workchain_id": { "eq": block.shard_id.workchain_id() },
+                    "shard": { "eq": block.shard_id.shard_prefix_as_str_with_tag() },
+                    "seq_no": { "eq": block.seq_no },
+                } }),
+                Duration::from_millis(REQUEST_TIMEOUT_MARGIN_MS),
+            )
+            .await?;
+        let Some(block_boc) = data["blocks"]
+            .as_array()
+            .and_then(|list| list.first())
+            .and_then(|block| block["boc"].as_str())
+        else {
+            fail!(SdkError::InvalidData {
+                msg: format!(
+                    "Endpoint has no block {} of shard {} with account {}",
+                    block.seq_no, block.shard_id, address
+                )
+            })
+        };
+        let block_proof = proofs::build_block_state_proof(&base64_decode(block_boc)?)?;
+        Ok(Some((account, AccountProof { block_proof, state_proof })))
+    }
+
+    async fn query_account_transactions(
+        &self,
+        address: &MsgAddressInt,
+        after_lt: u64,
+        limit: u32,
+    ) -> Result<Vec<tvm_block::Transaction>> {
+        self.check_staleness().await?;
+        let data = self
+            .query(
+                "query transactions($filter:TransactionFilter,$limit:Int){transactions(filter:$filter,orderBy:[{path:\"lt\",direction:ASC}],limit:$limit){boc}}",
+                json!({
+                    "filter": {
+                        "account_addr": { "eq": address.to_string() },
+                        "lt": { "gt": format!("0x{:x}", after_lt) },
+                    },
+                    "limit": limit,
+                }),
+                Duration::from_millis(REQUEST_TIMEOUT_MARGIN_MS),
+            )
+            .await?;
+        let mut transactions = Vec::new();
+        for transaction in data["transactions"].as_array().into_iter().flatten() {
+            transactions.extend(Self::read_boc(transaction)?);
+        }
+        Ok(transactions)
+    }
+
+    async fn query_accounts(&self, addresses: &[MsgAddressInt]) -> Result<Vec<Option<Account>>> {
+        self.check_staleness().await?;
+        let ids: Vec<String> = addresses.iter().map(ToString::to_string).collect();
+        let data = self
+            .query(
+                "query accounts($filter:AccountFilter,$limit:Int){accounts(filter:$filter,limit:$limit){id boc}}",
+                json!({ "filter": { "id": { "in": ids } }, "limit": ids.len() }),
+                Duration::from_millis(REQUEST_TIMEOUT_MARGIN_MS),
+            )
+            .await?;
+        let mut found = HashMap::new();
+        for account in data["accounts"].as_array().into_iter().flatten() {
+            if let Some(id) = account["id"].as_str() {
+                found.insert(id.to_owned(), account.clone());
+            }
+        }
+        ids.iter()
+            .map(|id| match found.get(id) {
+                Some(account) => Self::read_boc(account),
+                None => Ok(None),
+            })
+            .collect()
+    }
+}
+
+// Exit code of the rejected message in the GraphQL error extensions
+// Code of the error returned by the node rather than by the endpoint itself,
+// which comes with the details object
+fn node_error_code(error: &Value) -> Option<&str> {
+    let extensions = &error["extensions"];
+    extensions["details"].is_object().then(|| extensions["code"].as_str()).flatten()
+}
+
+fn error_exit_code(error: &Value) -> Option<i32> {
+    let extensions = &error["extensions"];
+    let exit_code = extensions["exit_code"].as_i64();
+    exit_code.or_else(|| extensions["exception"]["exit_code"].as_i64())?.try_into().ok()
+}
@@ -0,0 +1,118 @@
+// Copyright 2018-2021 TON Labs LTD.
+//
+// Licensed under the SOFTWARE EVALUATION License (the "License"); you may not
+// use this file except in compliance with the License.
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific TON DEV software governing permissions and
+// limitations under the License.
+
+//! Reading and writing of .tvc files. Older compilers write plain state init
+//! BOC, newer ones write the TVC structure with contract code and compiler
+//! description, e.g. `sold 0.72.0`.
+
+use std::path::Path;
+
+use tvm_block::Deserializable;
+use tvm_block::Serializable;
+use tvm_block::StateInit;
+use tvm_struct::scheme::TVC;
+use tvm_types::Cell;
+use tvm_types::Result;
+use tvm_types::error;
+use tvm_types::fail;
+
+use crate::ContractImage;
+use crate::error::SdkError;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TvcFormat {
+    /// Plain state init BOC
+    StateInit,
+    /// TVC structure with code and description
+    V1,
+}
+
+/// Contents of the .tvc file
+#[derive(Clone, Debug)]
+pub struct Tvc {
+    format: TvcFormat,
+    state_init: StateInit,
+    description: Option<String>,
+}
+
+impl Tvc {
+    /// TVC of the new format
+    pub fn new(code: Cell, description: Option<String>) -> Self {
+        let mut state_init = StateInit::default();
+        state_init.set_code(code);
+        Self { format: TvcFormat::V1, state_init, description }
+    }
+
+    /// TVC of the old format
+    pub fn with_state_init(state_init: StateInit) -> Self {
+        Self { format: TvcFormat::StateInit, state_init, description: None }
+    }
+
+    pub fn from_bytes(data: &[u8]) -> Result<Self> {
+        let cell = tvm_types::boc::read_single_root_boc(data)?;
+        if let Ok(tvc) = TVC::construct_from_cell(cell.clone()) {
+            let Some(code) = tvc.code else {
+                fail!(SdkError::InvalidData { msg: "TVC has no code".to_owned() })
+            };
+            return Ok(Self::new(code, tvc.desc));
+        }
+        Ok(Self::with_state_init(StateInit::construct_from_cell(cell)?))
+    }
+
+    pub fn read_from(path: impl AsRef<Path>) -> Result<Self> {
+        Self::from_bytes(&std::fs::read(path)?)
+    }
+
+    pub fn format(&self) -> TvcFormat {
+        self.format
+    }
+
+    pub fn code(&self) -> Option<&Cell> {
+        self.state_init.code()
+    }
+
+    pub fn description(&self) -> Option<&str> {
+        self.description.as_deref()
+    }
+
+    /// Compiler version from the `sold <version>` description
+    pub fn sold_version(&self) -> Option<&str> {
+        self.description()?
+            .split(['\n', ',', ';'])
+            .find_map(|part| part.trim().strip_prefix("sold "))
+            .map(str::trim)
+    }
+
+    /// State init of the contract. TVC of the new format has code only.
+    pub fn state_init(&self) -> &StateInit {
+        &self.state_init
+    }
+
+    pub fn as_contract_image(&self) -> Result<ContractImage> {
+        ContractImage::from_cell(self.state_init.serialize()?)
+    }
+
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        let cell = match self.format {
+            TvcFormat::StateInit => self.state_init.serialize()?,
+            TvcFormat::V1 => {
+                TVC::new(self.state_init.code.clone(), self.description.clone()).serialize()?
+            }
+        };
+        tvm_types::boc::write_boc(&cell)
+    }
+
+    /// Writes TVC in its format
+    pub fn write_to(&self, path: impl AsRef<Path>) -> Result<()> {
+        std::fs::write(path, self.to_bytes()?)?;
+        Ok(())
+    }
+}
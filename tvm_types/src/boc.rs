@@ -129,6 +129,9 @@ pub struct BocWriter<'a, S: OrderedCellsStorage> {
     cells_count: usize,
     big_cells_count: usize,
     big_cells_size: usize,
+    // size of hashes and depths of the cells which don't store them
+    hashes_size: usize,
+    store_hashes: bool,
     abort: &'a dyn Fn() -> bool,
 }
 
@@ -430,6 +433,8 @@ impl<'a, S: OrderedCellsStorage> BocWriter<'a, S> {
             cells_count: 0,
             big_cells_count: 0,
             big_cells_size: 0,
+            hashes_size: 0,
+            store_hashes: false,
             abort,
         };
         let mut roots_set = HashSet::new();
@@ -461,12 +466,20 @@ impl<'a, S: OrderedCellsStorage> BocWriter<'a, S> {
         Ok(boc)
     }
 
+    /// Writes hashes and depths of every cell along with its data, so the
+    /// BOC can be read without hashes calculation (see
+    /// `BocReader::set_verify_hashes`)
+    pub fn set_store_hashes(mut self, store_hashes: bool) -> Self {
+        self.store_hashes = store_hashes;
+        self
+    }
+
     pub fn roots_count(&self) -> usize {
         self.roots_indexes_rev.len()
     }
 
     pub fn data_size(&self) -> usize {
-        self.data_size
+        if self.store_hashes { self.data_size + self.hashes_size } else { self.data_size }
     }
 
     pub fn references_count(&self) -> usize {
@@ -531,7 +544,7 @@ impl<'a, S: OrderedCellsStorage> BocWriter<'a, S> {
             debug_assert!(crs >= bytes_total_cells);
             std::cmp::max(crs, bytes_total_cells)
         });
-        let total_cells_size = self.data_size + self.references * ref_size;
+        let total_cells_size = self.data_size() + self.references * ref_size;
         let bytes_total_size = Self::number_of_bytes_to_fit(total_cells_size);
         let offset_size = custom_offset_size.map_or(bytes_total_size, |cos| {
             debug_assert!(cos >= bytes_total_size);
@@ -576,7 +589,7 @@ impl<'a, S: OrderedCellsStorage> BocWriter<'a, S> {
             for cell_index in (0..self.cells_count).rev() {
                 check_abort(self.abort)?;
                 let cell = &self.cells.get_cell_by_index(cell_index as u32)?;
-                total_size += full_len(cell.raw_data()?) + ref_size * cell.references_count();
+                total_size += self.cell_size(cell)? + ref_size * cell.references_count();
                 dest.write_all(&(total_size as u64).to_be_bytes()[(8 - offset_size)..8])?;
             }
         }
@@ -585,7 +598,7 @@ impl<'a, S: OrderedCellsStorage> BocWriter<'a, S> {
         for cell_rev_index in (0..self.cells_count).rev() {
             check_abort(self.abort)?;
             let cell = &self.cells.get_cell_by_index(cell_rev_index as u32)?;
-            dest.write_all(cell.raw_data()?)?;
+            self.write_cell_data(dest, cell)?;
             let cell_index = self.cells_count - 1 - cell_rev_index;
             for i in 0..cell.references_count() {
                 let child_hash = cell.reference_repr_hash(i).unwrap();
@@ -654,7 +667,45 @@ impl<'a, S: OrderedCellsStorage> BocWriter<'a, S> {
         if cell.cell_type() == CellType::Big {
             self.big_cells_count += 1;
             self.big_cells_size += cell_size;
+        } else if !cell.store_hashes() {
+            self.hashes_size += cell::hashes_count(cell.raw_data()?) * (SHA256_SIZE + DEPTH_SIZE);
+        }
+        Ok(())
+    }
+
+    fn adds_hashes(&self, cell: &Cell) -> bool {
+        self.store_hashes && !cell.store_hashes() && cell.cell_type() != CellType::Big
+    }
+
+    fn cell_size(&self, cell: &Cell) -> Result<usize> {
+        let raw_data = cell.raw_data()?;
+        if self.adds_hashes(cell) {
+            Ok(full_len(raw_data) + cell::hashes_count(raw_data) * (SHA256_SIZE + DEPTH_SIZE))
+        } else {
+            Ok(full_len(raw_data))
         }
+    }
+
+    fn write_cell_data<T: Write>(&self, dest: &mut T, cell: &Cell) -> Status {
+        let raw_data = cell.raw_data()?;
+        if !self.adds_hashes(cell) {
+            dest.write_all(raw_data)?;
+            return Ok(());
+        }
+        dest.write_all(&[raw_data[0] | cell::HASHES_D1_FLAG, raw_data[1]])?;
+        if cell.cell_type() == CellType::PrunedBranch {
+            dest.write_all(cell.repr_hash().as_slice())?;
+            dest.write_all(&cell.repr_depth().to_be_bytes())?;
+        } else {
+            let hashes_count = cell::hashes_count(raw_data);
+            for i in 0..hashes_count {
+                dest.write_all(cell.hash(i).as_slice())?;
+            }
+            for i in 0..hashes_count {
+                dest.write_all(&cell.depth(i).to_be_bytes())?;
+            }
+        }
+        dest.write_all(cell::cell_data(raw_data))?;
         Ok(())
     }
 
@@ -762,6 +813,9 @@ pub struct BocReader<'a> {
     indexed_cells: Box<dyn IndexedCellsStorage>,
     done_cells: Box<dyn DoneCellsStorage>,
     max_depth: u16,
+    verify_hashes: bool,
+    max_cells: Option<usize>,
+    max_size: Option<u64>,
 }
 
 impl Default for BocReader<'_> {
@@ -771,6 +825,9 @@ impl Default for BocReader<'_> {
             indexed_cells: Box::<HashMap<u32, RawCell>>::default(),
             done_cells: Box::<HashMap<u32, Cell>>::default(),
             max_depth: MAX_SAFE_DEPTH,
+            verify_hashes: true,
+            max_cells: None,
+            max_size: None,
         }
     }
 }
@@ -809,6 +866,30 @@ impl<'a> BocReader<'a> {
         self
     }
 
+    /// When disabled, hashes stored in cells are taken as is instead of being
+    /// recomputed, which saves most of the load time of big BOCs serialized
+    /// with hashes. Cells without stored hashes are hashed anyway. Cell
+    /// layout, stored depths and the max depth are checked in both modes.
+    /// Use only for BOCs from a trusted source.
+    pub fn set_verify_hashes(mut self, verify_hashes: bool) -> Self {
+        self.verify_hashes = verify_hashes;
+        self
+    }
+
+    /// Fails BOCs declaring more cells than `max_cells` before any cell is
+    /// read. Cells of BOC v3 are views into its buffer, so only the size
+    /// limit applies to them.
+    pub fn set_max_cells(mut self, max_cells: usize) -> Self {
+        self.max_cells = Some(max_cells);
+        self
+    }
+
+    /// Fails BOCs longer than `max_size` bytes before reading them
+    pub fn set_max_size(mut self, max_size: u64) -> Self {
+        self.max_size = Some(max_size);
+        self
+    }
+
     pub fn read<T: Read + Seek>(mut self, src: &mut T) -> Result<BocReaderResult> {
         #[cfg(not(target_family = "wasm"))]
         let now = std::time::Instant::now();
@@ -816,6 +897,7 @@ impl<'a> BocReader<'a> {
         let position = src.stream_position()?;
         let src_full_len = src.seek(SeekFrom::End(0))? - position;
         src.seek(SeekFrom::Start(position))?;
+        self.check_size(src_full_len)?;
 
         // TODO do not compute crc if header says crc isn't included
         let mut src = IoCrcFilter::new_reader(src);
@@ -828,6 +910,7 @@ impl<'a> BocReader<'a> {
             return Ok(BocReaderResult { header, roots: read_boc3_bytes(Arc::new(buf), 0)? });
         }
         let header_len = src.stream_position()? - position;
+        self.check_cells_count(&header)?;
 
         check_abort(self.abort)?;
 
@@ -872,7 +955,12 @@ impl<'a> BocReader<'a> {
             for i in 0..cell::refs_count(&raw_cell.data) {
                 refs.push(self.done_cells.get(raw_cell.refs[i])?)
             }
-            let cell = DataCell::with_raw_data(refs, raw_cell.data, Some(self.max_depth), true)?;
+            let cell = DataCell::with_raw_data(
+                refs,
+                raw_cell.data,
+                Some(self.max_depth),
+                self.verify_hashes,
+            )?;
             self.done_cells.insert(cell_index as u32, Cell::with_data(cell))?;
         }
         #[cfg(not(target_family = "wasm"))]
@@ -924,10 +1012,12 @@ impl<'a> BocReader<'a> {
         let now = std::time::Instant::now();
         let mut src = Cursor::new(data.deref());
 
+        self.check_size(data.len() as u64)?;
         let header = Self::read_header(&mut src)?;
         if header.magic == BOC_V3_TAG {
             return Ok(BocReaderResult { header, roots: read_boc3_bytes(data, 0)? });
         }
+        self.check_cells_count(&header)?;
 
         Self::precheck_cells_tree_len(&header, src.position(), data.len() as u64, false)?;
 
@@ -987,8 +1077,13 @@ impl<'a> BocReader<'a> {
                 refs.push(child.clone());
             }
 
-            let cell =
-                DataCell::with_external_data(refs, &data, offset, Some(self.max_depth), true)?;
+            let cell = DataCell::with_external_data(
+                refs,
+                &data,
+                offset,
+                Some(self.max_depth),
+                self.verify_hashes,
+            )?;
             if cell.cell_type() == CellType::Big {
                 if remaining_big_cells == 0 {
                     fail!("Big cell is not allowed");
@@ -1233,6 +1328,24 @@ impl<'a> BocReader<'a> {
         })
     }
 
+    fn check_size(&self, size: u64) -> Result<()> {
+        match self.max_size {
+            Some(max_size) if size > max_size => {
+                fail!("BOC size {} exceeds the limit {}", size, max_size)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn check_cells_count(&self, header: &BocHeader) -> Result<()> {
+        match self.max_cells {
+            Some(max_cells) if header.cells_count > max_cells => {
+                fail!("BOC cells count {} exceeds the limit {}", header.cells_count, max_cells)
+            }
+            _ => Ok(()),
+        }
+    }
+
     fn precheck_cells_tree_len(
         header: &BocHeader,
         header_len: u64,
@@ -0,0 +1,58 @@
+use crate::BuilderData;
+use crate::Cell;
+use crate::Result;
+
+/// Scope for transient cell graphs, e.g. cells of messages built in a loop.
+/// Cells created through the arena are kept alive until `release` (or drop of
+/// the arena), which frees all of them at once without recursion. Cells
+/// still referenced outside of the arena survive the release.
+#[derive(Default)]
+pub struct CellArena {
+    cells: Vec<Cell>,
+}
+
+impl CellArena {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self { cells: Vec::with_capacity(capacity) }
+    }
+
+    /// Finalizes builder into a cell owned by the arena
+    pub fn alloc(&mut self, builder: BuilderData) -> Result<Cell> {
+        Ok(self.track(builder.into_cell()?))
+    }
+
+    /// Makes the arena hold the cell until release
+    pub fn track(&mut self, cell: Cell) -> Cell {
+        self.cells.push(cell.clone());
+        cell
+    }
+
+    /// Count of cells held by the arena
+    pub fn len(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+
+    /// Frees all held cells. Subtrees owned only by the arena are torn down
+    /// iteratively, the arena keeps its buffers for the next round.
+    pub fn release(&mut self) {
+        let mut stack = std::mem::take(&mut self.cells);
+        while let Some(mut cell) = stack.pop() {
+            stack.append(&mut cell.take_unique_references());
+        }
+        self.cells = stack;
+    }
+}
+
+impl Drop for CellArena {
+    fn drop(&mut self) {
+        self.release();
+    }
+}
@@ -119,11 +119,9 @@ impl DataCell {
         Ok(cell)
     }
 
-    fn finalize(&mut self, force: bool, max_depth: Option<u16>) -> crate::Result<()> {
-        if !force && self.store_hashes() {
-            return Ok(());
-        }
-
+    // Checks the cell layout and depths and calculates hashes. Stored hashes
+    // are recalculated and compared only if `verify_hashes` is set.
+    fn finalize(&mut self, verify_hashes: bool, max_depth: Option<u16>) -> crate::Result<()> {
         // let now = std::time::Instant::now();
 
         // Check data size and references count
@@ -330,9 +328,33 @@ impl DataCell {
                 continue;
             }
 
-            let mut hasher = Sha256::new();
+            // depth
             let mut depth = 0;
+            if cell_type != CellType::Big {
+                for child in self.references.iter() {
+                    let child_depth = child.depth(i + is_merkle_cell as usize);
+                    depth = max(depth, child_depth + 1);
+                    let max_depth = max_depth.unwrap_or(MAX_DEPTH);
+                    if depth > max_depth {
+                        fail!("fail creating cell: depth {} > {}", depth, max_depth.min(MAX_DEPTH))
+                    }
+                }
+            }
+            if store_hashes {
+                let stored_depth = self.cell_data.depth(i);
+                if depth != stored_depth {
+                    fail!(
+                        "Calculated depth is not equal stored one ({} != {})",
+                        depth,
+                        stored_depth
+                    );
+                }
+                if !verify_hashes {
+                    continue;
+                }
+            }
 
+            let mut hasher = Sha256::new();
             if cell_type == CellType::Big {
                 // For big cell representation hash is calculated only from data
                 hasher.update(self.data());
@@ -351,14 +373,9 @@ impl DataCell {
                     hasher.update(self.cell_data.raw_hash(i - 1));
                 }
 
-                // depth
+                // depths
                 for child in self.references.iter() {
                     let child_depth = child.depth(i + is_merkle_cell as usize);
-                    depth = max(depth, child_depth + 1);
-                    let max_depth = max_depth.unwrap_or(MAX_DEPTH);
-                    if depth > max_depth {
-                        fail!("fail creating cell: depth {} > {}", depth, max_depth.min(MAX_DEPTH))
-                    }
                     hasher.update(child_depth.to_be_bytes());
                 }
 
@@ -371,14 +388,6 @@ impl DataCell {
 
             let hash = hasher.finalize();
             if store_hashes {
-                let stored_depth = self.cell_data.depth(i);
-                if depth != stored_depth {
-                    fail!(
-                        "Calculated depth is not equal stored one ({} != {})",
-                        depth,
-                        stored_depth
-                    );
-                }
                 let stored_hash = self.cell_data.raw_hash(i);
                 if hash.as_slice() != stored_hash {
                     fail!("Calculated hash is not equal stored one");
@@ -398,6 +407,11 @@ impl DataCell {
     pub fn cell_data(&self) -> &CellData {
         &self.cell_data
     }
+
+    /// Detaches references, used to release cell graphs without recursion
+    pub(crate) fn take_references(&mut self) -> Vec<Cell> {
+        std::mem::take(&mut self.references)
+    }
 }
 
 impl CellImpl for DataCell {
@@ -24,8 +24,10 @@ use crate::fail;
 use crate::types::Result;
 use crate::types::UInt256;
 
+mod arena;
 mod boc3_cell;
 mod usage_cell;
+pub use arena::CellArena;
 pub use boc3_cell::Boc3Cell;
 pub use boc3_cell::read_boc3_bytes;
 pub use boc3_cell::write_boc3;
@@ -382,6 +384,19 @@ impl Cell {
         CELL_COUNT.load(Ordering::Relaxed)
     }
 
+    /// Detaches references of the cell if nobody else holds it
+    pub(crate) fn take_unique_references(&mut self) -> Vec<Cell> {
+        #[cfg(not(feature = "dyn_cell"))]
+        match self {
+            Cell::Data(cell) => {
+                Arc::get_mut(cell).map(DataCell::take_references).unwrap_or_default()
+            }
+            _ => Vec::new(),
+        }
+        #[cfg(feature = "dyn_cell")]
+        Vec::new()
+    }
+
     // pub fn finalization_nanos() -> u64 {
     //     FINALIZATION_NANOS.load(Ordering::Relaxed)
     // }
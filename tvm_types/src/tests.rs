@@ -9,8 +9,17 @@ use std::time::Instant;
 
 use crate::BocReader;
 use crate::BocWriter;
+use crate::BuilderData;
 use crate::Cell;
+use crate::CellArena;
+use crate::CellType;
+use crate::DataCell;
+use crate::UInt256;
+use crate::cell::DEPTH_SIZE;
+use crate::cell::MAX_SAFE_DEPTH;
+use crate::cell::SHA256_SIZE;
 use crate::read_boc3_bytes;
+use crate::write_boc;
 use crate::write_boc3_to_bytes;
 
 struct Stat {
@@ -46,6 +55,135 @@ impl Stats {
     }
 }
 
+#[test]
+fn test_boc_reader_without_hash_verification() {
+    let child = BuilderData::with_raw(vec![1, 2, 3], 24).unwrap().into_cell().unwrap();
+    let mut builder = BuilderData::with_raw(vec![0xAB; 16], 128).unwrap();
+    builder.checked_append_reference(child).unwrap();
+    let cell = builder.into_cell().unwrap();
+    let mut boc = Vec::new();
+    BocWriter::with_root(&cell).unwrap().set_store_hashes(true).write(&mut boc).unwrap();
+    assert!(boc.windows(32).any(|window| window == cell.repr_hash().as_slice()));
+    let hashes_size = 2 * (SHA256_SIZE + DEPTH_SIZE);
+    assert_eq!(boc.len(), write_boc(&cell).unwrap().len() + hashes_size);
+
+    let root = BocReader::new()
+        .set_verify_hashes(false)
+        .read(&mut Cursor::new(&boc))
+        .unwrap()
+        .withdraw_single_root()
+        .unwrap();
+    assert_eq!(root.repr_hash(), cell.repr_hash());
+    let root = BocReader::new()
+        .set_verify_hashes(false)
+        .read_inmem(Arc::new(boc))
+        .unwrap()
+        .withdraw_single_root()
+        .unwrap();
+    assert_eq!(root.repr_hash(), cell.repr_hash());
+}
+
+#[test]
+fn test_boc_reader_limits() {
+    let child = BuilderData::with_raw(vec![1, 2, 3], 24).unwrap().into_cell().unwrap();
+    let mut builder = BuilderData::with_raw(vec![0xAB; 16], 128).unwrap();
+    builder.checked_append_reference(child).unwrap();
+    let cell = builder.into_cell().unwrap();
+    let boc = write_boc(&cell).unwrap();
+
+    let read = |reader: BocReader| reader.read(&mut Cursor::new(&boc));
+    assert!(read(BocReader::new().set_max_cells(1)).is_err());
+    assert!(read(BocReader::new().set_max_size(boc.len() as u64 - 1)).is_err());
+    let root = read(BocReader::new().set_max_cells(2).set_max_size(boc.len() as u64))
+        .unwrap()
+        .withdraw_single_root()
+        .unwrap();
+    assert_eq!(root.repr_hash(), cell.repr_hash());
+    assert!(BocReader::new().set_max_cells(1).read_inmem(Arc::new(boc.clone())).is_err());
+}
+
+#[test]
+fn test_cell_arena_release() {
+    let mut arena = CellArena::new();
+    let shared = arena.alloc(BuilderData::with_raw(vec![7], 8).unwrap()).unwrap();
+    let mut cell = shared.clone();
+    for i in 0..1000u32 {
+        let mut builder = BuilderData::with_raw(i.to_be_bytes().to_vec(), 32).unwrap();
+        builder.checked_append_reference(cell).unwrap();
+        cell = arena.alloc(builder).unwrap();
+    }
+    assert_eq!(arena.len(), 1001);
+    assert_eq!(cell.tree_cell_count(), 1001);
+    drop(cell);
+
+    arena.release();
+    assert!(arena.is_empty());
+    assert_eq!(shared.data(), &[7]);
+}
+
+// Same cell with hashes and depths stored in its data
+fn with_stored_hashes(cell: &Cell) -> Cell {
+    let refs = (0..cell.references_count()).map(|i| cell.reference(i).unwrap()).collect();
+    let hashes = [cell.repr_hash(), UInt256::default(), UInt256::default(), UInt256::default()];
+    let depths = [cell.repr_depth(), 0, 0, 0];
+    let data = DataCell::with_params(
+        refs,
+        // data of the test cells is byte aligned, so the tag is a whole byte
+        &[cell.data(), &[0x80]].concat(),
+        CellType::Ordinary,
+        0,
+        None,
+        Some(hashes),
+        Some(depths),
+    )
+    .unwrap();
+    Cell::with_data(data)
+}
+
+fn read_unverified(boc: &[u8], max_depth: u16) -> crate::Result<Cell> {
+    BocReader::new()
+        .set_verify_hashes(false)
+        .set_max_cell_depth(max_depth)
+        .read(&mut Cursor::new(boc))?
+        .withdraw_single_root()
+}
+
+#[test]
+fn test_boc_reader_without_hash_verification_checks_depth() {
+    let mut cell =
+        with_stored_hashes(&BuilderData::with_raw(vec![1], 8).unwrap().into_cell().unwrap());
+    for _ in 0..2 {
+        let mut builder = BuilderData::with_raw(vec![2], 8).unwrap();
+        builder.checked_append_reference(cell).unwrap();
+        cell = with_stored_hashes(&builder.into_cell().unwrap());
+    }
+    assert!(cell.store_hashes());
+    let boc = write_boc(&cell).unwrap();
+    assert_eq!(read_unverified(&boc, 2).unwrap().repr_hash(), cell.repr_hash());
+    assert!(read_unverified(&boc, 1).is_err());
+}
+
+#[test]
+fn test_boc_reader_without_hash_verification_skips_only_hashes() {
+    let cell = BuilderData::with_raw(vec![0x02; 8], 64).unwrap().into_cell().unwrap();
+    let cell = with_stored_hashes(&cell);
+    let boc = write_boc(&cell).unwrap();
+    let hash_offset =
+        boc.windows(32).position(|window| window == cell.repr_hash().as_slice()).unwrap();
+
+    // wrong stored hash is taken as is
+    let mut corrupted = boc.clone();
+    corrupted[hash_offset] ^= 1;
+    let root = read_unverified(&corrupted, MAX_SAFE_DEPTH).unwrap();
+    assert_ne!(root.repr_hash(), cell.repr_hash());
+    assert!(BocReader::new().read(&mut Cursor::new(&corrupted)).is_err());
+
+    // library reference cell of wrong length is rejected anyway
+    let mut exotic = boc.clone();
+    exotic[hash_offset - 2] |= 0x08;
+    assert!(read_unverified(&exotic, MAX_SAFE_DEPTH).is_err());
+}
+
 #[test]
 fn test_boc_reader_writer() {
     let repo_dir = Path::new(env!("CARGO_MANIFEST_DIR")).parent().unwrap();